@@ -0,0 +1,107 @@
+//! A compiler driver running the analysis along with a custom check.
+//!
+//! The check enforces a policy of deprecating items only in minor releases, by reporting newly
+//! deprecated items as non-breaking changes. The driver is invoked just like `rust-semverver`:
+//!
+//! ```text
+//! custom_check --crate-type=lib --extern old=libold.rlib --extern new=libnew.rlib test.rs
+//! ```
+//!
+//! where `test.rs` declares the two crates using `extern crate old;` and `extern crate new;`.
+
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate rustc_middle;
+
+use rustc_driver::{Callbacks, Compilation, RunCompiler};
+use rustc_interface::{interface, Queries};
+use rustc_middle::ty::TyCtxt;
+use semverver::{
+    run_analysis_with_checks, ChangeCategory, Config, Findings, IdMapping, SemverCheck,
+};
+use std::{env, process::Command};
+
+/// A check reporting items that have been deprecated.
+struct Deprecations;
+
+impl SemverCheck for Deprecations {
+    fn name(&self) -> &str {
+        "deprecations"
+    }
+
+    fn check<'tcx>(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        id_mapping: &IdMapping,
+        findings: &mut Findings<'_, 'tcx>,
+    ) {
+        for (old, new) in id_mapping.items() {
+            let (old, new) = (old.def_id(), new.def_id());
+
+            if findings.is_reported(old)
+                && tcx.lookup_deprecation(old).is_none()
+                && tcx.lookup_deprecation(new).is_some()
+            {
+                findings.add(
+                    old,
+                    "item deprecated".to_owned(),
+                    ChangeCategory::NonBreaking,
+                    None,
+                );
+            }
+        }
+    }
+}
+
+struct CheckCallbacks;
+
+impl Callbacks for CheckCallbacks {
+    fn after_analysis<'tcx>(
+        &mut self,
+        _compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
+            // the crates are found by name here, as opposed to their order in `rust-semverver`
+            let find = |name| {
+                tcx.crates(())
+                    .iter()
+                    .find(|&&krate| tcx.crate_name(krate).as_str() == name)
+                    .map(|krate| krate.as_def_id())
+            };
+
+            if let (Some(old), Some(new)) = (find("old"), find("new")) {
+                let config = Config {
+                    version: "1.0.0".to_owned(),
+                    ..Config::default()
+                };
+
+                let changes = run_analysis_with_checks(tcx, old, new, &config, &[&Deprecations]);
+                changes.output(tcx.sess, &config);
+            } else {
+                tcx.sess.err("could not find `old` and `new` crates");
+            }
+        });
+
+        Compilation::Stop
+    }
+}
+
+fn main() {
+    let sysroot = Command::new("rustc")
+        .args(&["--print", "sysroot"])
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|s| s.trim().to_owned())
+        .expect("could not find the sysroot");
+
+    let args: Vec<String> = env::args()
+        .chain(vec!["--sysroot".to_owned(), sysroot])
+        .collect();
+
+    let result = RunCompiler::new(&args, &mut CheckCallbacks).run();
+    std::process::exit(result.map_or(1, |_| 0));
+}
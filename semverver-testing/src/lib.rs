@@ -0,0 +1,84 @@
+//! Support for checking a crate against a baseline version from its own test suite.
+//!
+//! The check runs `cargo semver` on the crate the test belongs to, so it has to be installed
+//! alongside the toolchain running the tests. A test failing the check reports the breaking
+//! changes found, using the same human-readable output as a regular run.
+//!
+//! This crate doesn't depend on `semverver` itself, which needs a specific nightly toolchain to
+//! build, so it can be added as a dev-dependency to crates built with any toolchain.
+
+use std::{env, path::Path, process::Command};
+
+/// The version of a crate the current version is checked against.
+#[derive(Clone, Copy, Debug)]
+pub enum Baseline<'a> {
+    /// A published version of the crate, fetched from crates.io.
+    Version(&'a str),
+    /// A local copy of the crate, such as a git worktree checked out at a release tag.
+    Path(&'a Path),
+}
+
+/// Check the crate in the given directory for breaking changes against a baseline version.
+///
+/// Returns the rendered report if any breaking changes are found, or `cargo semver` fails.
+pub fn check_compatible(manifest_dir: &str, baseline: Baseline) -> Result<(), String> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut cmd = Command::new(cargo);
+    cmd.current_dir(manifest_dir)
+        .arg("semver")
+        .args(["--fail-on", "breaking"]);
+
+    match baseline {
+        Baseline::Version(version) => cmd.args(["-S", version]),
+        Baseline::Path(path) => cmd.arg("-s").arg(path),
+    };
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("could not run `cargo semver`: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
+
+/// Assert that the crate whose tests invoke the macro has no breaking changes against a
+/// baseline version, panicking with the rendered report otherwise.
+///
+/// The baseline is either a published version of the crate, or a path to a local copy:
+///
+/// ```ignore
+/// #[test]
+/// fn semver() {
+///     semverver_testing::assert_semver_compatible!("1.2.0");
+///     semverver_testing::assert_semver_compatible!(path = "../baseline");
+/// }
+/// ```
+#[macro_export]
+macro_rules! assert_semver_compatible {
+    (@check $baseline:expr, $desc:expr) => {
+        if let Err(report) =
+            $crate::check_compatible(env!("CARGO_MANIFEST_DIR"), $baseline)
+        {
+            panic!("breaking changes against `{}`:\n{}", $desc, report);
+        }
+    };
+    (path = $path:expr) => {
+        $crate::assert_semver_compatible!(@check
+            $crate::Baseline::Path(::std::path::Path::new($path)),
+            $path
+        )
+    };
+    ($version:expr) => {
+        $crate::assert_semver_compatible!(@check
+            $crate::Baseline::Version($version),
+            $version
+        )
+    };
+}
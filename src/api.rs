@@ -0,0 +1,343 @@
+//! A textual listing of a crate's public API.
+//!
+//! Every public item reachable from the crate root is rendered on a line of its own, along with
+//! its generics and signature or type. The lines are sorted, so that listings of different
+//! versions can be committed and compared using ordinary diffing tools.
+
+use rustc_hir::def::{DefKind, Res::Def};
+use rustc_hir::def_id::DefId;
+use rustc_hir::Unsafety;
+use rustc_metadata::creader::CStore;
+use rustc_middle::ty::{AssocKind, GenericParamDefKind, TyCtxt, Visibility::Public};
+use rustc_span::symbol::kw;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+/// Render the public API of the given crate root as a sorted list of lines.
+pub fn dump_api(tcx: TyCtxt, krate: DefId) -> Vec<String> {
+    let mut dumper = ApiDumper {
+        tcx,
+        crate_prefix: format!("{}::", tcx.def_path_str(krate)),
+        lines: BTreeSet::new(),
+    };
+
+    dumper.dump_modules(krate);
+    dumper.dump_trait_impls(krate);
+
+    dumper.lines.into_iter().collect()
+}
+
+/// Render the lines of the public API contributed by a single item, named by its definition path.
+pub fn dump_item(tcx: TyCtxt, def_id: DefId) -> Vec<String> {
+    let mut dumper = ApiDumper {
+        tcx,
+        crate_prefix: format!("{}::", tcx.crate_name(def_id.krate)),
+        lines: BTreeSet::new(),
+    };
+
+    let path = dumper.strip(tcx.def_path_str(def_id));
+    dumper.dump_item(tcx.def_kind(def_id), def_id, &path);
+
+    dumper.lines.into_iter().collect()
+}
+
+/// Strip a crate prefix from all paths in a rendered type or signature.
+///
+/// Only the leading segment of each path is stripped, so that modules and foreign crates sharing
+/// the crate's name are left alone.
+fn strip_crate_prefix(rendered: &str, prefix: &str) -> String {
+    let mut res = String::with_capacity(rendered.len());
+    let mut last = 0;
+
+    for (idx, _) in rendered.match_indices(prefix) {
+        let starts_path =
+            !rendered[..idx].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':');
+
+        if starts_path {
+            res.push_str(&rendered[last..idx]);
+            last = idx + prefix.len();
+        }
+    }
+
+    res.push_str(&rendered[last..]);
+    res
+}
+
+/// The state needed to render a crate's public API.
+struct ApiDumper<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// The prefix of paths into the crate, stripped from rendered types.
+    crate_prefix: String,
+    /// The rendered lines, kept sorted and free of duplicates.
+    lines: BTreeSet<String>,
+}
+
+impl<'tcx> ApiDumper<'tcx> {
+    /// Strip the crate prefix from a rendered type or signature.
+    fn strip(&self, rendered: String) -> String {
+        strip_crate_prefix(&rendered, &self.crate_prefix)
+    }
+
+    /// Render the generic parameters of an item, e.g. `<'a, T>`.
+    fn generics(&self, def_id: DefId) -> String {
+        let params: Vec<_> = self
+            .tcx
+            .generics_of(def_id)
+            .params
+            .iter()
+            .filter(|param| match param.kind {
+                GenericParamDefKind::Type { synthetic, .. } => {
+                    !synthetic && param.name != kw::SelfUpper
+                }
+                _ => true,
+            })
+            .map(|param| match param.kind {
+                GenericParamDefKind::Const { .. } => format!(
+                    "const {}: {}",
+                    param.name,
+                    self.strip(self.tcx.type_of(param.def_id).to_string())
+                ),
+                _ => param.name.to_string(),
+            })
+            .collect();
+
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", params.join(", "))
+        }
+    }
+
+    /// Render the where clause of an item, listing the predicates it declares.
+    fn where_clause(&self, def_id: DefId) -> String {
+        let preds: BTreeSet<_> = self
+            .tcx
+            .explicit_predicates_of(def_id)
+            .predicates
+            .iter()
+            .map(|(pred, _)| self.strip(pred.to_string()))
+            .collect();
+
+        if preds.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " where {}",
+                preds.into_iter().collect::<Vec<_>>().join(", ")
+            )
+        }
+    }
+
+    /// Render the signature of a function or method.
+    fn fn_sig(&self, def_id: DefId) -> String {
+        self.strip(self.tcx.fn_sig(def_id).to_string())
+    }
+
+    /// Render the type of an item.
+    fn ty(&self, def_id: DefId) -> String {
+        self.strip(self.tcx.type_of(def_id).to_string())
+    }
+
+    /// Walk the module hierarchy, rendering all public items.
+    fn dump_modules(&mut self, krate: DefId) {
+        let mut visited = HashSet::new();
+        let mut mod_queue = VecDeque::new();
+
+        mod_queue.push_back((krate, Vec::new(), Public));
+
+        while let Some((mod_def_id, idents, mod_vis)) = mod_queue.pop_front() {
+            for item in self.tcx.module_children(mod_def_id).iter() {
+                let vis = if mod_vis == Public { item.vis } else { mod_vis };
+
+                if vis != Public {
+                    continue;
+                }
+
+                let mut idents = idents.clone();
+                idents.push(item.ident.to_string());
+                let path = idents.join("::");
+
+                match item.res {
+                    Def(DefKind::Mod, def_id) => {
+                        self.lines.insert(format!("mod {}", path));
+
+                        if visited.insert(def_id) {
+                            mod_queue.push_back((def_id, idents, vis));
+                        }
+                    }
+                    Def(kind, def_id) => self.dump_item(kind, def_id, &path),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Render a public item found at the given path.
+    fn dump_item(&mut self, kind: DefKind, def_id: DefId, path: &str) {
+        let tcx = self.tcx;
+
+        let line = match kind {
+            DefKind::Struct | DefKind::Union | DefKind::Enum => {
+                self.dump_adt(def_id, path);
+                self.dump_inherent_impls(def_id, path);
+
+                let kind = match kind {
+                    DefKind::Struct => "struct",
+                    DefKind::Union => "union",
+                    _ => "enum",
+                };
+
+                format!(
+                    "{} {}{}{}",
+                    kind,
+                    path,
+                    self.generics(def_id),
+                    self.where_clause(def_id)
+                )
+            }
+            DefKind::Trait => {
+                self.dump_assoc_items(def_id, path, true);
+
+                let unsafety = if tcx.trait_def(def_id).unsafety == Unsafety::Unsafe {
+                    "unsafe "
+                } else {
+                    ""
+                };
+
+                format!(
+                    "{}trait {}{}{}",
+                    unsafety,
+                    path,
+                    self.generics(def_id),
+                    self.where_clause(def_id)
+                )
+            }
+            DefKind::TyAlias => format!(
+                "type {}{} = {}",
+                path,
+                self.generics(def_id),
+                self.ty(def_id)
+            ),
+            DefKind::Fn => format!(
+                "fn {}{}: {}{}",
+                path,
+                self.generics(def_id),
+                self.fn_sig(def_id),
+                self.where_clause(def_id)
+            ),
+            DefKind::Const => format!("const {}: {}", path, self.ty(def_id)),
+            DefKind::Static(mutbl) => {
+                format!("static {}{}: {}", mutbl.prefix_str(), path, self.ty(def_id))
+            }
+            DefKind::Macro(_) => format!("macro {}", path),
+            DefKind::TraitAlias => format!("trait alias {}", path),
+            _ => return,
+        };
+
+        self.lines.insert(line);
+    }
+
+    /// Render the public fields and variants of an ADT.
+    fn dump_adt(&mut self, def_id: DefId, path: &str) {
+        let adt_def = self.tcx.adt_def(def_id);
+
+        for variant in adt_def.variants() {
+            let variant_path = if adt_def.is_enum() {
+                let variant_path = format!("{}::{}", path, variant.ident(self.tcx));
+                self.lines.insert(format!("variant {}", variant_path));
+                variant_path
+            } else {
+                path.to_owned()
+            };
+
+            for field in &variant.fields {
+                if adt_def.is_enum() || field.vis == Public {
+                    self.lines.insert(format!(
+                        "field {}::{}: {}",
+                        variant_path,
+                        field.ident(self.tcx),
+                        self.ty(field.did)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Render the public associated items of the inherent impls of a type.
+    fn dump_inherent_impls(&mut self, def_id: DefId, path: &str) {
+        for impl_def_id in self.tcx.inherent_impls(def_id).iter() {
+            self.dump_assoc_items(*impl_def_id, path, false);
+        }
+    }
+
+    /// Render the associated items of a trait or inherent impl.
+    fn dump_assoc_items(&mut self, def_id: DefId, path: &str, is_trait: bool) {
+        for item in self.tcx.associated_items(def_id).in_definition_order() {
+            if !is_trait && self.tcx.visibility(item.def_id) != Public {
+                continue;
+            }
+
+            let item_path = format!("{}::{}", path, item.name);
+
+            let line = match item.kind {
+                AssocKind::Const => format!("const {}: {}", item_path, self.ty(item.def_id)),
+                AssocKind::Fn => format!(
+                    "fn {}{}: {}{}",
+                    item_path,
+                    self.generics(item.def_id),
+                    self.fn_sig(item.def_id),
+                    self.where_clause(item.def_id)
+                ),
+                AssocKind::Type if is_trait => format!(
+                    "type {}{}{}",
+                    item_path,
+                    self.generics(item.def_id),
+                    self.where_clause(item.def_id)
+                ),
+                AssocKind::Type => format!("type {} = {}", item_path, self.ty(item.def_id)),
+            };
+
+            self.lines.insert(line);
+        }
+    }
+
+    /// Render the trait impls defined in the crate.
+    fn dump_trait_impls(&mut self, krate: DefId) {
+        let cstore = CStore::from_tcx(self.tcx);
+
+        for (_, impl_def_id, _) in cstore.trait_impls_in_crate_untracked(krate.krate) {
+            if let Some(trait_ref) = self.tcx.impl_trait_ref(impl_def_id) {
+                let line = format!(
+                    "impl{} {} for {}{}",
+                    self.generics(impl_def_id),
+                    trait_ref.print_only_trait_path(),
+                    trait_ref.self_ty(),
+                    self.where_clause(impl_def_id)
+                );
+
+                self.lines.insert(self.strip(line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_segment_stripped() {
+        assert_eq!(strip_crate_prefix("new::Abc", "new::"), "Abc");
+        assert_eq!(
+            strip_crate_prefix("fn(new::Abc) -> &new::new::Bcd", "new::"),
+            "fn(Abc) -> &new::Bcd"
+        );
+        assert_eq!(
+            strip_crate_prefix("<new::Abc as renew::Cde>", "new::"),
+            "<Abc as renew::Cde>"
+        );
+        assert_eq!(
+            strip_crate_prefix("std::vec::Vec<other::new::Abc>", "new::"),
+            "std::vec::Vec<other::new::Abc>"
+        );
+    }
+}
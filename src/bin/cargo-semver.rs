@@ -3,14 +3,17 @@
 
 extern crate rustc_session;
 
+use cargo::core::dependency::DepKind;
 use cargo::core::{FeatureValue, Package, PackageId, Source, SourceId, Workspace};
 use cargo::sources::RegistrySource;
 use cargo::util::interning::InternedString;
+use cargo::util::Filesystem;
 use curl::easy::Easy;
 use log::debug;
 use rustc_session::getopts;
-use serde::Deserialize;
-use std::collections::HashSet;
+use semverver::{manifest::ManifestChange, ChangeCategory};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::{
     env, io,
     io::Write,
@@ -18,6 +21,7 @@ use std::{
     process::{Command, Stdio},
     rc::Rc,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
 pub type Result<T> = cargo::util::CargoResult<T>;
@@ -54,6 +58,11 @@ fn main() {
         Err(f) => cli::exit_with_error(&config, f.into()),
     };
 
+    let matches = match project_config::apply(&config, &opts, matches) {
+        Ok(m) => m,
+        Err(e) => cli::exit_with_error(&config, e),
+    };
+
     if matches.opt_present("h") {
         cli::print_help(&opts);
         return;
@@ -68,6 +77,27 @@ fn main() {
         cli::exit_with_error(&config, e);
     }
 
+    if let Some(cli::Subcommand::Explain(code)) = cli::subcommand(&matches) {
+        match semverver::explain(&code) {
+            Some(desc) => println!("{}", desc),
+            None => cli::exit_with_error(
+                &config,
+                anyhow::anyhow!("no explanation available for code `{}`", code),
+            ),
+        }
+
+        return;
+    }
+
+    // prebuilt documents are compared without building anything
+    if let Some(cli::Subcommand::DiffDoc(old, new)) = cli::subcommand(&matches) {
+        if let Err(e) = run_diff_doc(&old, &new) {
+            cli::exit_with_error(&config, e);
+        }
+
+        return;
+    }
+
     let config_res = config.configure(
         0,                        // verbose
         matches.opt_present("q"), // quiet
@@ -101,8 +131,25 @@ fn run(config: &cargo::Config, matches: &getopts::Matches) -> Result<()> {
     let compact = matches.opt_present("compact");
     let json = matches.opt_present("json");
 
+    // the members of the workspace are checked by separate invocations
+    if matches.opt_present("lockstep") {
+        return run_lockstep(config);
+    }
+
+    if let Some(path) = matches.opt_str("dashboard") {
+        return run_dashboard(config, Path::new(&path));
+    }
+
+    if matches.opt_present("next-versions") {
+        return run_next_versions(config);
+    }
+
+    if matches.opt_present("format") {
+        return run_release_plan(config);
+    }
+
     // Obtain WorkInfo for the "current"
-    let current = if let Some(name_and_version) = matches.opt_str("C") {
+    let mut current = if let Some(name_and_version) = matches.opt_str("C") {
         // -C "name:version" requires fetching the appropriate package:
         WorkInfo::remote(config, &PackageNameAndVersion::parse(&name_and_version)?)?
     } else if let Some(path) = matches.opt_str("c").map(PathBuf::from) {
@@ -122,59 +169,74 @@ fn run(config: &cargo::Config, matches: &getopts::Matches) -> Result<()> {
         ));
     }
 
-    // TODO: JSON output here
-    if matches.opt_present("show-public") {
-        let (current_rlib, current_deps_output) =
-            current.rlib_and_dep_output(config, &name, true, matches)?;
+    if let Some(count) = matches.opt_str("check-rdeps") {
+        // validated to be a number in `cli::validate_args`
+        let count = count.parse().unwrap();
 
-        let mut child = Command::new("rust-semver-public");
-        child
-            .arg("--crate-type=lib")
-            .args(&["--extern", &*format!("new={}", current_rlib.display())]);
+        return check_reverse_dependencies(config, &current, &name, count);
+    }
 
-        for link_path in current_deps_output {
-            child.args(&[format!("-L{}", link_path.display())]);
-        }
+    let baselines = matches.opt_strs("baselines");
+    if !baselines.is_empty() {
+        let verdict_path = matches
+            .opt_str("verdict-path")
+            .map(|path| config.cwd().join(path));
+        return run_baselines(config, &current, &name, &baselines, verdict_path);
+    }
 
-        if let Some(target) = matches.opt_str("target") {
-            child.args(&["--target", &target]);
-        }
+    // Both versions are built in separate directories, so that artifacts and build script
+    // outputs (`OUT_DIR`) of one version don't clobber those of the other, which would be the
+    // case for dependencies shared by both, as they are built with different `RUSTFLAGS`. The
+    // new version is built in the workspace's own target directory, reusing the artifacts of
+    // earlier builds, unless it needs flags of its own, which would invalidate them.
+    let semverver_target_dir = current
+        .workspace
+        .target_dir()
+        .into_path_unlocked()
+        .join("semverver");
+    if rustflags(true, matches).is_some() {
+        current.set_target_dir(semverver_target_dir.join("new"));
+    }
 
-        let mut child = child
-            .arg("-")
-            .stdin(Stdio::piped())
-            .spawn()
-            .map_err(|e| anyhow::Error::msg(format!("could not spawn rustc: {}", e)))?;
+    let subcommand = cli::subcommand(matches);
 
-        if let Some(ref mut stdin) = child.stdin {
-            stdin.write_fmt(format_args!(
-                "#[allow(unused_extern_crates)] \
-                 extern crate new;"
-            ))?;
-        } else {
-            return Err(anyhow::Error::msg(
-                "could not pipe to rustc (wtf?)".to_owned(),
-            ));
-        }
+    if let Some(cli::Subcommand::DumpApi(ref path)) = subcommand {
+        let api = run_public(config, matches, &current, &name, true, true)?;
+        let path = path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("public-api.txt"));
 
-        let exit_status = child
-            .wait()
-            .map_err(|e| anyhow::Error::msg(format!("failed to wait for rustc: {}", e)))?;
+        std::fs::write(&path, api)?;
+        config.shell().status(
+            "Dumped",
+            format!("public API of `{}` to {}", name, path.display()),
+        )?;
 
-        return if exit_status.success() {
-            Ok(())
-        } else {
-            Err(anyhow::Error::msg("rustc-semver-public errored".to_owned()))
-        };
+        return Ok(());
+    }
+
+    // TODO: JSON output here
+    if matches.opt_present("show-public") {
+        run_public(config, matches, &current, &name, true, false)?;
+        return Ok(());
     }
 
     // Obtain WorkInfo for the "stable" version
-    let (stable, stable_version) = if let Some(name_and_version) = matches.opt_str("S") {
+    let (mut stable, stable_version) = if let Some(name_and_version) = matches.opt_str("S") {
         // -S "name:version" requires fetching the appropriate package:
         let info = PackageNameAndVersion::parse(&name_and_version)?;
         let version = info.version.to_owned();
         let work_info = WorkInfo::remote(config, &info)?;
         (work_info, version)
+    } else if let Some(path) = matches.opt_str("base-crate") {
+        // --base-crate "path/to/foo-1.2.3.crate", unpacked next to the cached baselines:
+        let work_info = WorkInfo::packaged(
+            config,
+            &PathBuf::from(path),
+            &semverver_target_dir.join("packaged"),
+        )?;
+        let version = format!("{}", work_info.package.version());
+        (work_info, version)
     } else if let Some(path) = matches.opt_str("s") {
         // -s "local_path":
         let work_info = WorkInfo::local(config, &PathBuf::from(path))?;
@@ -192,10 +254,42 @@ fn run(config: &cargo::Config, matches: &getopts::Matches) -> Result<()> {
         (work_info, stable_crate.max_version.clone())
     };
 
+    // every baseline is cached in a directory of its own, which is kept across runs
+    stable.set_target_dir(semverver_target_dir.join("old").join(format!(
+        "{}-{}",
+        stable.package.name(),
+        stable.package.version()
+    )));
+
+    if let Some(cli::Subcommand::ApiDiff) = subcommand {
+        let old_api = run_public(config, matches, &stable, &name, false, true)?;
+        let new_api = run_public(config, matches, &current, &name, true, true)?;
+
+        print_api_diff(
+            &format!("{} {}", name, stable_version),
+            &String::from_utf8_lossy(&old_api),
+            &format!("{} {}", name, current.package.version()),
+            &String::from_utf8_lossy(&new_api),
+        );
+
+        return Ok(());
+    }
+
+    let timings = matches.opt_present("timings");
+
+    let start = Instant::now();
     let (current_rlib, current_deps_output) =
         current.rlib_and_dep_output(config, &name, true, matches)?;
+    if timings {
+        report_build_time(config, "new", start.elapsed())?;
+    }
+
+    let start = Instant::now();
     let (stable_rlib, stable_deps_output) =
         stable.rlib_and_dep_output(config, &name, false, matches)?;
+    if timings {
+        report_build_time(config, "old", start.elapsed())?;
+    }
 
     if matches.opt_present("d") {
         print!("--extern old={} ", stable_rlib.display());
@@ -235,7 +329,55 @@ fn run(config: &cargo::Config, matches: &getopts::Matches) -> Result<()> {
         .env("RUST_SEMVER_CRATE_VERSION", stable_version)
         .env("RUST_SEMVER_VERBOSE", format!("{}", explain))
         .env("RUST_SEMVER_COMPACT", format!("{}", compact))
+        .env("RUST_SEMVER_QUIET", format!("{}", matches.opt_present("q")))
+        .env(
+            "RUST_SEMVER_ZERO_SEMANTICS",
+            format!("{}", !matches.opt_present("no-zero-semantics")),
+        )
         .env("RUST_SEMVER_JSON", format!("{}", json))
+        .env("RUST_SEMVER_DOT", format!("{}", matches.opt_present("dot")))
+        .env(
+            "RUST_SEMVER_IMPACT",
+            format!("{}", matches.opt_present("impact")),
+        )
+        .env(
+            "RUST_SEMVER_MIR_DIFF",
+            format!("{}", matches.opt_present("mir-diff")),
+        )
+        .env(
+            "RUST_SEMVER_PANIC_SCAN",
+            format!("{}", matches.opt_present("panic-scan")),
+        )
+        .env(
+            "RUST_SEMVER_SERDE",
+            format!("{}", matches.opt_present("serde")),
+        )
+        .env(
+            "RUST_SEMVER_WASM_BINDGEN",
+            format!("{}", matches.opt_present("wasm-bindgen")),
+        )
+        .env("RUST_SEMVER_TIMINGS", format!("{}", timings))
+        .env(
+            "RUST_SEMVER_STREAM",
+            format!("{}", matches.opt_present("stream")),
+        )
+        .env("RUST_SEMVER_ONLY", matches.opt_strs("only").join(","))
+        .env("RUST_SEMVER_EXCLUDE", matches.opt_strs("exclude").join(","))
+        .env(
+            "RUST_SEMVER_UNSTABLE_FEATURES",
+            matches.opt_strs("unstable-feature").join(","),
+        )
+        .env("RUST_SEMVER_SEVERITIES", cli::severity_overrides(matches))
+        .env(
+            "RUST_SEMVER_NEW_VERSION",
+            current.package.version().to_string(),
+        )
+        .env(
+            "RUST_SEMVER_FAIL_ON",
+            matches
+                .opt_str("fail-on")
+                .unwrap_or_else(|| "denied".to_owned()),
+        )
         .env(
             "RUST_SEMVER_API_GUIDELINES",
             if matches.opt_present("a") {
@@ -245,8 +387,175 @@ fn run(config: &cargo::Config, matches: &getopts::Matches) -> Result<()> {
             },
         );
 
+    if let Some(max) = matches.opt_str("max-breaking") {
+        child.env("RUST_SEMVER_MAX_BREAKING", max);
+    }
+
+    if matches.opt_present("strict") {
+        child.env("RUST_SEMVER_STRICT", "true");
+    }
+
+    if let Some(path) = matches.opt_str("trace-item") {
+        child.env("RUST_SEMVER_TRACE_ITEM", path);
+    }
+
+    if let Some(secs) = matches.opt_str("timeout-per-item") {
+        child.env("RUST_SEMVER_TIMEOUT_PER_ITEM", secs);
+    }
+
+    if let Some(secs) = matches.opt_str("time-budget") {
+        child.env("RUST_SEMVER_TIME_BUDGET", secs);
+    }
+
+    if let Some(command) = matches.opt_str("external-rules") {
+        // the command is split like a shell would, so that arguments can contain spaces
+        let argv = shell_words::split(&command)
+            .map_err(|e| anyhow::anyhow!("invalid external rules command `{}`: {}", command, e))?;
+        child.env("RUST_SEMVER_EXTERNAL_RULES", serde_json::to_string(&argv)?);
+    }
+
+    if let Some(path) = matches.opt_str("verdict-path") {
+        child
+            .env("RUST_SEMVER_VERDICT_FILE", config.cwd().join(path))
+            .env(
+                "RUST_SEMVER_OLD_BASELINE",
+                stable.package.package_id().to_string(),
+            )
+            .env(
+                "RUST_SEMVER_NEW_BASELINE",
+                current.package.package_id().to_string(),
+            );
+    }
+
+    let manifest_changes = manifest_changes(&stable.package, &current.package);
+
+    if let Some(rust_version) = current.package.rust_version() {
+        child.env("RUST_SEMVER_RUST_VERSION", rust_version);
+    }
+
+    child.env(
+        "RUST_SEMVER_MANIFEST_CHANGES",
+        serde_json::to_string(&manifest_changes)?,
+    );
+
+    if let Some(path) = matches.opt_str("renames") {
+        // the file is created by `--interactive` if it doesn't exist yet
+        let renames = if matches.opt_present("interactive") && !Path::new(&path).exists() {
+            Vec::new()
+        } else {
+            read_renames(Path::new(&path))?
+        };
+
+        child.env("RUST_SEMVER_RENAMES", renames.join(","));
+    }
+
+    // the suggested version is passed back through a file, as stdout is left to the user
+    let version_file = if matches.opt_present("bump-write") {
+        let path = current
+            .workspace
+            .target_dir()
+            .into_path_unlocked()
+            .join("semverver-suggested-version");
+        let _ = std::fs::remove_file(&path);
+        child.env("RUST_SEMVER_VERSION_FILE", &path);
+        Some(path)
+    } else {
+        None
+    };
+
+    // ambiguous rename candidates are written along with the results, to ask about them
+    // afterwards
+    let candidates_file = if matches.opt_present("interactive") {
+        let path = current
+            .workspace
+            .target_dir()
+            .into_path_unlocked()
+            .join("semverver-rename-candidates");
+        let _ = std::fs::remove_file(&path);
+        child.env("RUST_SEMVER_CANDIDATES_FILE", &path);
+        Some(path)
+    } else {
+        None
+    };
+
     debug!("rust-semverver invocation: {:?}", child);
 
+    let mut exit_status = run_driver(&mut child)?;
+
+    if let Some(candidates_file) = candidates_file {
+        // `--renames` is required along with `--interactive`
+        let renames_file = PathBuf::from(matches.opt_str("renames").unwrap());
+        let candidates = std::fs::read_to_string(&candidates_file).unwrap_or_default();
+
+        let stdin = io::stdin();
+        let confirmed = ask_renames(&candidates, &renames_file, stdin.lock())?;
+
+        // the analysis only needs to be repeated if any of the items have been matched up
+        if confirmed > 0 {
+            config.shell().status(
+                "Recorded",
+                format!(
+                    "{} confirmed renames in {}, repeating the analysis",
+                    confirmed,
+                    renames_file.display()
+                ),
+            )?;
+
+            if let Some(path) = &version_file {
+                let _ = std::fs::remove_file(path);
+            }
+
+            child.env_remove("RUST_SEMVER_CANDIDATES_FILE");
+            child.env(
+                "RUST_SEMVER_RENAMES",
+                read_renames(&renames_file)?.join(","),
+            );
+            debug!(
+                "rust-semverver invocation with confirmed renames: {:?}",
+                child
+            );
+
+            exit_status = run_driver(&mut child)?;
+        }
+    }
+
+    // the file is only written if the analysis ran to completion
+    if let Some(suggested) = version_file.and_then(|path| std::fs::read_to_string(path).ok()) {
+        write_version(config, &current.package, suggested.trim())?;
+    }
+
+    let mut broken_usage = 0;
+
+    if matches.opt_present("check-doctests") || matches.opt_present("check-examples") {
+        let metadata = package_metadata(&stable.package)?;
+
+        if matches.opt_present("check-doctests") {
+            let doctests = extract_doctests(&stable.package)?;
+            broken_usage +=
+                check_usage_examples(config, &current, &stable, &metadata, "doctests", &doctests)?;
+        }
+
+        if matches.opt_present("check-examples") {
+            let examples = extract_examples(&stable.package, &metadata);
+            broken_usage +=
+                check_usage_examples(config, &current, &stable, &metadata, "examples", &examples)?;
+        }
+    }
+
+    if !exit_status.success() {
+        Err(anyhow::Error::msg("rustc-semverver errored".to_owned()))
+    } else if broken_usage > 0 {
+        Err(anyhow::anyhow!(
+            "{} usage examples of the stable version fail to compile against the current version",
+            broken_usage
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Run `rust-semverver` on the compiled crates, with the given invocation.
+fn run_driver(child: &mut Command) -> Result<std::process::ExitStatus> {
     let mut child = child
         .spawn()
         .map_err(|e| anyhow::Error::msg(format!("could not spawn rustc: {}", e)))?;
@@ -266,359 +575,4060 @@ fn run(config: &cargo::Config, matches: &getopts::Matches) -> Result<()> {
         ));
     }
 
-    let exit_status = child
+    child
         .wait()
-        .map_err(|e| anyhow::Error::msg(format!("failed to wait for rustc: {}", e)))?;
+        .map_err(|e| anyhow::Error::msg(format!("failed to wait for rustc: {}", e)))
+}
 
-    if exit_status.success() {
-        Ok(())
-    } else {
-        Err(anyhow::Error::msg("rustc-semverver errored".to_owned()))
+/// Group rename candidates by the removed item, leaving out the ones rejected before.
+///
+/// The candidates are given as `old::path = new::path` lines, and the rejected ones as
+/// `# not renamed: old::path = new::path` lines of the file listing renames.
+fn rename_candidates<'a>(candidates: &'a str, recorded: &str) -> BTreeMap<&'a str, Vec<&'a str>> {
+    let rejected: HashSet<_> = recorded
+        .lines()
+        .filter_map(|line| line.strip_prefix("# not renamed:"))
+        .map(str::trim)
+        .collect();
+
+    let mut candidates_by_old: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for line in candidates.lines().filter(|line| !rejected.contains(line)) {
+        if let Some((old, new)) = line.split_once(" = ") {
+            candidates_by_old.entry(old).or_default().push(new);
+        }
     }
-}
 
-/// CLI utils
-mod cli {
-    use cargo::util::CliError;
-    use rustc_session::getopts;
+    candidates_by_old
+}
 
-    /// CLI options
-    pub fn options() -> getopts::Options {
-        let mut opts = getopts::Options::new();
+/// Ask whether removed items have been renamed to any of the added items listed as candidates.
+///
+/// The answers are read from `input`. Confirmed renames are appended to the file listing
+/// renames, and rejected candidates as `# not renamed: old::path = new::path` comments, so that
+/// they aren't asked about again. Returns the number of confirmed renames.
+fn ask_renames(candidates: &str, renames_file: &Path, input: impl io::BufRead) -> Result<usize> {
+    let recorded = std::fs::read_to_string(renames_file).unwrap_or_default();
+    let candidates_by_old = rename_candidates(candidates, &recorded);
 
-        opts.optflag("h", "help", "print this message and exit");
-        opts.optflag("V", "version", "print version information and exit");
-        opts.optflag("e", "explain", "print detailed error explanations");
-        opts.optflag(
-            "q",
-            "quiet",
-            "surpress regular cargo output, print only important messages",
-        );
-        opts.optflag(
-            "",
-            "show-public",
-            "print the public types in the current crate given by -c or -C and exit",
-        );
-        opts.optflag("d", "debug", "print command to debug and exit");
-        opts.optflag(
-            "a",
-            "api-guidelines",
-            "report only changes that are breaking according to the API-guidelines",
-        );
-        opts.optopt(
-            "",
-            "features",
-            "Space-separated list of features to activate",
-            "FEATURES",
-        );
-        opts.optflag("", "all-features", "Activate all available features");
-        opts.optflag(
-            "",
-            "no-default-features",
-            "Do not activate the `default` feature",
-        );
-        opts.optflag(
-            "",
-            "compact",
-            "Only output the suggested version on stdout for further processing",
-        );
-        opts.optflag(
-            "j",
-            "json",
-            "Output a JSON-formatted description of all collected data on stdout.",
-        );
-        opts.optopt(
-            "s",
-            "stable-path",
-            "use local path as stable/old crate",
-            "PATH",
-        );
-        opts.optopt(
-            "c",
-            "current-path",
-            "use local path as current/new crate",
-            "PATH",
-        );
-        opts.optopt(
-            "S",
-            "stable-pkg",
-            "use a `name:version` string as stable/old crate",
-            "NAME:VERSION",
-        );
-        opts.optopt(
-            "C",
-            "current-pkg",
-            "use a `name:version` string as current/new crate",
-            "NAME:VERSION",
-        );
-        opts.optflag("", "offline", "Run without accessing the network.");
-        opts.optopt("", "target", "Build for the target triple", "<TRIPLE>");
-        opts
-    }
+    let mut answers = input.lines();
+    let mut taken = HashSet::new();
+    let mut decisions = String::new();
+    let mut confirmed = 0;
 
-    /// Parse CLI arguments
-    pub fn parse_args(opts: &getopts::Options) -> Result<getopts::Matches, getopts::Fail> {
-        let args: Vec<String> = std::env::args().skip(1).collect();
-        opts.parse(&args)
-    }
+    'items: for (old, candidates) in candidates_by_old {
+        // an item can only be the new version of a single removed item
+        let candidates: Vec<_> = candidates
+            .into_iter()
+            .filter(|new| !taken.contains(new))
+            .collect();
 
-    /// Validate CLI arguments
-    pub fn validate_args(matches: &getopts::Matches) -> Result<(), anyhow::Error> {
-        if (matches.opt_present("s") && matches.opt_present("S"))
-            || matches.opt_count("s") > 1
-            || matches.opt_count("S") > 1
-        {
-            let msg = "at most one of `-s,--stable-path` and `-S,--stable-pkg` allowed";
-            return Err(anyhow::Error::msg(msg.to_owned()));
+        if candidates.is_empty() {
+            continue;
         }
 
-        if (matches.opt_present("c") && matches.opt_present("C"))
-            || matches.opt_count("c") > 1
-            || matches.opt_count("C") > 1
-        {
-            let msg = "at most one of `-c,--current-path` and `-C,--current-pkg` allowed";
-            return Err(anyhow::Error::msg(msg.to_owned()));
+        eprintln!("`{}` has been removed, and might have been renamed to", old);
+        for (i, new) in candidates.iter().enumerate() {
+            eprintln!("  {}: `{}`", i + 1, new);
         }
 
-        Ok(())
-    }
+        let choice = loop {
+            eprint!("enter the number of the new item, or nothing if it isn't listed: ");
+            io::stderr().flush()?;
 
-    /// Print a help message
-    pub fn print_help(opts: &getopts::Options) {
-        // FIXME: pass remaining options to cargo
-        let brief = "usage: cargo semver [options]";
-        print!("{}", opts.usage(brief));
+            // stop asking once the input ends, without deciding on the remaining items
+            let answer = match answers.next() {
+                Some(answer) => answer?,
+                None => break 'items,
+            };
+
+            match answer.trim() {
+                "" => break None,
+                answer => match answer.parse::<usize>() {
+                    Ok(i) if (1..=candidates.len()).contains(&i) => break Some(candidates[i - 1]),
+                    _ => eprintln!("expected a number between 1 and {}", candidates.len()),
+                },
+            }
+        };
+
+        if let Some(new) = choice {
+            decisions.push_str(&format!("{} = {}\n", old, new));
+            taken.insert(new);
+            confirmed += 1;
+        } else {
+            for new in candidates {
+                decisions.push_str(&format!("# not renamed: {} = {}\n", old, new));
+            }
+        }
     }
 
-    /// Print a version message.
-    pub fn print_version() {
-        println!("{}", env!("CARGO_PKG_VERSION"));
+    if decisions.is_empty() {
+        return Ok(0);
     }
 
-    /// Exit with error `e`.
-    pub fn exit_with_error(config: &cargo::Config, e: anyhow::Error) -> ! {
-        config
-            .shell()
-            .set_verbosity(cargo::core::shell::Verbosity::Normal);
-        cargo::exit_with_error(CliError::new(e, 1), &mut config.shell());
+    let mut contents = recorded;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
     }
+    contents.push_str(&decisions);
+
+    std::fs::write(renames_file, contents)?;
+
+    Ok(confirmed)
 }
 
-/// A package's name and version.
-pub struct PackageNameAndVersion<'a> {
-    /// The crate's name.
-    pub name: &'a str,
-    /// The package's version, as a semver-string.
-    pub version: &'a str,
+/// The verdict on a crate version, as written to the file given by `--verdict-path`.
+#[derive(Debug, Deserialize, Serialize)]
+struct Verdict {
+    max_category: ChangeCategory,
+    bump: Option<String>,
+    old_version: String,
+    suggested_version: Option<String>,
+    #[serde(default)]
+    counts: Counts,
+    /// The changes reported as errors, passed on unchanged.
+    #[serde(default)]
+    blocking: Vec<serde_json::Value>,
 }
 
-impl<'a> PackageNameAndVersion<'a> {
-    /// Parses the string "name:version" into `Self`
-    pub fn parse(s: &'a str) -> Result<Self> {
-        let err = || {
-            anyhow::Error::msg(format!(
-                "spec has to be of form `name:version` but is `{}`",
-                s
-            ))
+/// The number of reported changes per category, as part of a `Verdict`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Counts {
+    breaking: usize,
+    technically_breaking: usize,
+    non_breaking: usize,
+    patch: usize,
+}
+
+impl Counts {
+    /// Get the total number of reported changes.
+    fn total(&self) -> usize {
+        self.breaking + self.technically_breaking + self.non_breaking + self.patch
+    }
+}
+
+/// The outcome of checking a single workspace member.
+struct MemberCheck {
+    /// The name of the member.
+    name: String,
+    /// The version of the member in the workspace.
+    version: String,
+    /// The verdict on the member.
+    verdict: Verdict,
+    /// Whether the check of the member succeeded according to the failure policy.
+    success: bool,
+    /// The names of the other members the member depends on, apart from dev-dependencies.
+    dependencies: Vec<String>,
+}
+
+/// Collect the arguments of the current invocation, apart from the given flags and options
+/// taking a value.
+fn forwarded_args(flags: &[&str], opts: &[&str]) -> Vec<String> {
+    let mut args = env::args().skip(1);
+    let mut forwarded = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let name = match arg.strip_prefix("--") {
+            Some(name) => name.split('=').next().unwrap(),
+            None => {
+                forwarded.push(arg);
+                continue;
+            }
         };
-        let mut split = s.split(':');
-        let name = split.next().ok_or_else(err)?;
-        let version = split.next().ok_or_else(err)?;
-        if split.next().is_some() {
-            Err(err())
-        } else {
-            Ok(Self { name, version })
+
+        if opts.contains(&name) {
+            // the value is given separately unless it's attached using `=`
+            if !arg.contains('=') {
+                args.next();
+            }
+        } else if !flags.contains(&name) {
+            forwarded.push(arg);
         }
     }
-}
 
-/// A specification of a package and it's workspace.
-pub struct WorkInfo<'a> {
-    /// The package to be compiled.
-    pub package: Package,
-    /// The package's workspace.
-    workspace: Workspace<'a>,
+    forwarded
 }
 
-impl<'a> WorkInfo<'a> {
-    /// Construct a package/workspace pair for the `manifest_path`
-    pub fn local(config: &'a cargo::Config, manifest_path: &Path) -> Result<WorkInfo<'a>> {
-        let workspace = Workspace::new(manifest_path, config)?;
-        let package = workspace.load(manifest_path)?;
-        Ok(Self { package, workspace })
+/// Check the current crate against several versions, and report the version bump required to
+/// stay compatible with all of them.
+///
+/// Each of the given baselines is either a version, or a version requirement selecting all
+/// versions published on crates.io that match it and precede the current one, such as `^1`
+/// for the previous releases of the 1.x line. Every version is checked by a separate
+/// invocation of `cargo semver` with the arguments of the current one, apart from
+/// `--baselines`. If `verdict_path` is given, a verdict covering all versions is written to it,
+/// which lets `--next-versions` check workspace members against several versions.
+fn run_baselines(
+    config: &cargo::Config,
+    current: &WorkInfo,
+    name: &str,
+    baselines: &[String],
+    verdict_path: Option<PathBuf>,
+) -> Result<()> {
+    let current_version = current.package.version();
+    let mut published = None;
+    let mut versions = BTreeSet::new();
+
+    for baseline in baselines {
+        if let Ok(version) = semver::Version::parse(baseline) {
+            versions.insert(version);
+            continue;
+        }
+
+        let req = semver::VersionReq::parse(baseline).map_err(|_| {
+            anyhow::anyhow!(
+                "invalid baseline `{}`, expected a version or a version requirement",
+                baseline
+            )
+        })?;
+
+        if published.is_none() {
+            published = Some(find_versions_on_crates_io(name)?);
+        }
+
+        versions.extend(
+            published
+                .iter()
+                .flatten()
+                .filter(|v| req.matches(v) && *v < current_version)
+                .cloned(),
+        );
     }
 
-    /// Construct a package/workspace pair by fetching the package of a
-    /// specified `PackageNameAndVersion` from the `source`.
-    pub fn remote(
-        config: &'a cargo::Config,
-        &PackageNameAndVersion { name, version }: &PackageNameAndVersion,
-    ) -> Result<WorkInfo<'a>> {
-        let source = {
-            let source_id = SourceId::crates_io(config)?;
-            let mut source = RegistrySource::remote(source_id, &HashSet::new(), config)?;
+    if versions.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no published versions of `{}` match the given baselines",
+            name
+        ));
+    }
 
-            debug!("source id loaded: {:?}", source_id);
+    let verdict_dir = current
+        .workspace
+        .target_dir()
+        .into_path_unlocked()
+        .join("semverver")
+        .join("baselines");
+    std::fs::create_dir_all(&verdict_dir)?;
 
-            if !config.offline() {
-                let _lock = config.acquire_package_cache_lock()?;
-                source.invalidate_cache();
-            }
+    // the versions are checked in quiet mode anyway, and write verdicts of their own
+    let forwarded: Vec<_> = forwarded_args(&["quiet"], &["baselines", "verdict-path"])
+        .into_iter()
+        .filter(|arg| arg != "-q")
+        .collect();
+    let exe = env::current_exe()?;
+    let mut checks = Vec::new();
 
-            Box::new(source)
+    for version in &versions {
+        let verdict_path = verdict_dir.join(format!("{}.json", version));
+        let _ = std::fs::remove_file(&verdict_path);
+
+        config
+            .shell()
+            .status("Checking", format!("against {} v{}", name, version))?;
+
+        // the versions are checked in quiet mode, as only their verdicts are reported
+        let status = Command::new(&exe)
+            .args(&forwarded)
+            .arg("--stable-pkg")
+            .arg(version.to_string())
+            .arg("--quiet")
+            .arg("--verdict-path")
+            .arg(&verdict_path)
+            .stdout(Stdio::null())
+            .status()
+            .map_err(|e| anyhow::anyhow!("could not spawn cargo-semver: {}", e))?;
+
+        // the verdict is only written if the analysis ran to completion
+        let verdict: Verdict = match std::fs::read_to_string(&verdict_path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(_) => {
+                return Err(anyhow::anyhow!(
+                    "could not check against version {}",
+                    version
+                ))
+            }
         };
 
-        // TODO: fall back to locally cached package instance, or better yet, search for it
-        // first.
-        let package_id = PackageId::new(name, version, source.source_id())?;
-        debug!("(remote) package id: {:?}", package_id);
+        checks.push((version, verdict, status.success()));
+    }
 
-        let package = source.download_now(package_id, config)?;
-        let workspace = Workspace::ephemeral(package.clone(), config, None, false)?;
+    for (version, verdict, _) in &checks {
+        println!("{}: {} changes", version, verdict.max_category);
+    }
 
-        Ok(Self { package, workspace })
+    let max = checks.iter().map(|(_, v, _)| v.max_category).max().unwrap();
+    let required_by: Vec<_> = checks
+        .iter()
+        .filter(|(_, v, _)| v.max_category == max)
+        .map(|(version, _, _)| version.to_string())
+        .collect();
+
+    // each suggestion follows from its own baseline, so the highest one satisfies all of them
+    let latest = versions.iter().next_back().unwrap();
+    let suggested = checks
+        .iter()
+        .filter_map(|(_, v, _)| v.suggested_version.as_ref())
+        .filter_map(|v| semver::Version::parse(v).ok())
+        .max();
+
+    match suggested {
+        Some(ref suggested) => println!("version bump: {} -> ({}) -> {}", latest, max, suggested),
+        None => println!("max change: {}, could not parse {}", max, latest),
     }
+    println!("required by {}", required_by.join(", "));
 
-    /// Obtain the paths to the produced rlib and the dependency output directory.
-    pub fn rlib_and_dep_output(
-        &self,
-        config: &'a cargo::Config,
-        name: &str,
-        current: bool,
-        matches: &getopts::Matches,
-    ) -> Result<(PathBuf, Vec<PathBuf>)> {
-        // We don't need codegen-ready artifacts (which .rlib files are) so
-        // settle for .rmeta files, which result from `cargo check` mode
-        let mode = cargo::core::compiler::CompileMode::Check { test: false };
-        let mut opts = cargo::ops::CompileOptions::new(config, mode)?;
-        // we need the build plan to find our build artifacts
-        opts.build_config.build_plan = true;
+    if let Some(path) = verdict_path {
+        // the highest suggestion satisfies all versions, so its bump is the one to make
+        let bump = checks
+            .iter()
+            .find(|(_, v, _)| {
+                v.suggested_version
+                    .as_ref()
+                    .and_then(|v| semver::Version::parse(v).ok())
+                    == suggested
+            })
+            .and_then(|(_, v, _)| v.bump.clone());
 
-        let compile_kind = if let Some(target) = matches.opt_str("target") {
-            let target = cargo::core::compiler::CompileTarget::new(&target)?;
+        let mut counts = Counts::default();
+        let mut blocking = Vec::new();
 
-            let kind = cargo::core::compiler::CompileKind::Target(target);
-            opts.build_config.requested_kinds = vec![kind];
-            kind
-        } else {
-            cargo::core::compiler::CompileKind::Host
-        };
+        for (_, verdict, _) in &checks {
+            counts.breaking = counts.breaking.max(verdict.counts.breaking);
+            counts.technically_breaking = counts
+                .technically_breaking
+                .max(verdict.counts.technically_breaking);
+            counts.non_breaking = counts.non_breaking.max(verdict.counts.non_breaking);
+            counts.patch = counts.patch.max(verdict.counts.patch);
 
-        if let Some(s) = matches.opt_str("features") {
-            opts.cli_features.features = Rc::new(
-                s.split(' ')
-                    .map(InternedString::new)
-                    .map(FeatureValue::new)
-                    .collect(),
-            );
+            for change in &verdict.blocking {
+                if !blocking.contains(change) {
+                    blocking.push(change.clone());
+                }
+            }
         }
 
-        opts.cli_features.all_features = matches.opt_present("all-features");
-        opts.cli_features.uses_default_features = !matches.opt_present("no-default-features");
+        let verdict = Verdict {
+            max_category: max,
+            bump,
+            old_version: latest.to_string(),
+            suggested_version: suggested.map(|v| v.to_string()),
+            counts,
+            blocking,
+        };
 
-        env::set_var(
-            "RUSTFLAGS",
-            format!("-C metadata={}", if current { "new" } else { "old" }),
-        );
+        std::fs::write(path, serde_json::to_string(&verdict)?)?;
+    }
 
-        // Capture build plan from a separate Cargo invocation
-        let output = VecWrite(Arc::new(RwLock::new(Vec::new())));
+    let failed: Vec<_> = checks
+        .iter()
+        .filter(|(_, _, success)| !success)
+        .map(|(version, _, _)| version.to_string())
+        .collect();
 
-        let mut file_write = cargo::core::Shell::from_write(Box::new(output.clone()));
-        file_write.set_verbosity(cargo::core::Verbosity::Quiet);
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "the check failed against versions {}",
+            failed.join(", ")
+        ))
+    }
+}
 
-        let old_shell = std::mem::replace(&mut *config.shell(), file_write);
+/// Check all members of the current workspace with a library target.
+///
+/// Every member is checked by a separate invocation of `cargo semver` with the arguments of
+/// the current one, apart from the ones selecting the workspace mode, and with `member_args`
+/// appended. Its verdict is written to a file, while its standard output is written to the
+/// file returned by `output` for the member, or discarded.
+fn check_members(
+    config: &cargo::Config,
+    member_args: &[&str],
+    output: impl Fn(&str) -> Option<PathBuf>,
+) -> Result<Vec<MemberCheck>> {
+    use cargo::util::important_paths::find_root_manifest_for_wd;
 
-        cargo::ops::compile(&self.workspace, &opts)?;
+    let workspace = Workspace::new(&find_root_manifest_for_wd(config.cwd())?, config)?;
+    let verdict_dir = workspace
+        .target_dir()
+        .into_path_unlocked()
+        .join("semverver")
+        .join("workspace");
+    std::fs::create_dir_all(&verdict_dir)?;
 
-        let _ = std::mem::replace(&mut *config.shell(), old_shell);
-        let plan_output = output.read()?;
+    let mut members: Vec<_> = workspace
+        .members()
+        .filter(|member| member.targets().iter().any(|t| t.is_lib()))
+        .collect();
+    members.sort_by_key(|member| member.name());
 
-        // actually compile things now
-        opts.build_config.build_plan = false;
+    if members.is_empty() {
+        return Err(anyhow::anyhow!(
+            "workspace lacks members with a [lib] target"
+        ));
+    }
 
-        let compilation = cargo::ops::compile(&self.workspace, &opts)?;
-        env::remove_var("RUSTFLAGS");
+    let names: HashSet<_> = members.iter().map(|m| m.name().to_string()).collect();
 
-        let build_plan: BuildPlan = serde_json::from_slice(&plan_output)
-            .map_err(|_| anyhow::anyhow!("Can't read build plan"))?;
+    // all arguments not selecting the workspace mode are passed on to the members
+    let forwarded = forwarded_args(&["lockstep", "next-versions"], &["dashboard", "format"]);
+    let exe = env::current_exe()?;
+    let mut checks = Vec::new();
 
-        debug!("{:?}", &build_plan.invocations);
-        let paths = build_plan.invocations.iter().find_map(|i| {
-            if let Some(kind) = i.target_kind.get(0) {
-                if kind.contains("lib") && i.package_name == name {
-                    let rlib_path = i.outputs[0].clone();
-                    let mut link_paths = vec![
-                        compilation.deps_output[&compile_kind].clone(),
-                        compilation.deps_output[&cargo::core::compiler::CompileKind::Host].clone(),
-                    ];
-                    // if host and `compile_kind` are the same, only return once
-                    link_paths.dedup();
-                    return Some((rlib_path, link_paths));
-                }
+    for member in members {
+        let name = member.name().to_string();
+        let verdict_path = verdict_dir.join(format!("{}.json", name));
+        let _ = std::fs::remove_file(&verdict_path);
+
+        config.shell().status("Checking", member.package_id())?;
+
+        let stdout = match output(&name) {
+            Some(path) => Stdio::from(std::fs::File::create(path)?),
+            None => Stdio::null(),
+        };
+
+        let status = Command::new(&exe)
+            .args(&forwarded)
+            .arg("--current-path")
+            .arg(member.root())
+            .args(member_args)
+            .arg("--verdict-path")
+            .arg(&verdict_path)
+            .stdout(stdout)
+            .status()
+            .map_err(|e| anyhow::anyhow!("could not spawn cargo-semver: {}", e))?;
+
+        // the verdict is only written if the analysis ran to completion
+        let verdict = match std::fs::read_to_string(&verdict_path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(_) => {
+                return Err(anyhow::anyhow!(
+                    "could not check workspace member `{}`",
+                    name
+                ))
             }
+        };
 
-            None
+        let dependencies = member
+            .dependencies()
+            .iter()
+            .filter(|dep| dep.kind() != DepKind::Development)
+            .map(|dep| dep.package_name().to_string())
+            .filter(|dep| names.contains(dep))
+            .collect();
+
+        checks.push(MemberCheck {
+            name,
+            version: member.version().to_string(),
+            verdict,
+            success: status.success(),
+            dependencies,
         });
+    }
 
-        if let Some(paths) = paths {
-            Ok(paths)
-        } else {
-            Err(anyhow::Error::msg("lost build artifact".to_owned()))
-        }
+    Ok(checks)
+}
+
+/// Check all members of the current workspace, which share a single version, and report the
+/// version bump required by the most significant change in any of them, along with the members
+/// requiring it.
+fn run_lockstep(config: &cargo::Config) -> Result<()> {
+    // the members are checked in quiet mode, as only their verdicts are reported
+    let checks = check_members(config, &["--quiet"], |_| None)?;
+
+    let max = checks.iter().map(|c| c.verdict.max_category).max().unwrap();
+    let required_by: Vec<_> = checks
+        .iter()
+        .filter(|c| c.verdict.max_category == max)
+        .map(|c| format!("`{}`", c.name))
+        .collect();
+
+    let mut old_versions: Vec<_> = checks
+        .iter()
+        .map(|c| c.verdict.old_version.as_str())
+        .collect();
+    sort_versions(&mut old_versions);
+
+    if old_versions.len() > 1 {
+        config.shell().warn(format!(
+            "workspace members were compared against different versions: {}",
+            old_versions.join(", ")
+        ))?;
+    }
+
+    // lockstep versions only ever move forward, so the highest suggestion wins
+    let old_version = old_versions[old_versions.len() - 1];
+    let suggested = checks
+        .iter()
+        .filter_map(|c| c.verdict.suggested_version.as_ref())
+        .filter_map(|v| semver::Version::parse(v).ok())
+        .max();
+
+    match suggested {
+        Some(suggested) => println!(
+            "version bump: {} -> ({}) -> {}",
+            old_version, max, suggested
+        ),
+        None => println!("max change: {}, could not parse {}", max, old_version),
     }
+    println!("required by {}", required_by.join(", "));
+
+    check_members_passed(&checks)
 }
 
-/// Given a `crate_name`, try to locate the corresponding crate on `crates.io`.
+/// Sort and deduplicate versions, ordering them as semantic versions, not as text.
 ///
-/// If no crate with the exact name is present, error out.
-pub fn find_on_crates_io(crate_name: &str) -> Result<crates_io::Crate> {
-    let mut handle = Easy::new();
-    handle.useragent(&format!("rust-semverver {}", env!("CARGO_PKG_VERSION")))?;
-    let mut registry =
-        crates_io::Registry::new_handle("https://crates.io".to_owned(), None, handle);
+/// Versions that can't be parsed are ordered before all others.
+fn sort_versions(versions: &mut Vec<&str>) {
+    versions.sort_by_cached_key(|v| (semver::Version::parse(v).ok(), *v));
+    versions.dedup();
+}
 
-    registry
-        .search(crate_name, 1)
-        .map_err(|e| {
-            anyhow::Error::msg(format!(
-                "failed to retrieve search results from the registry: {}",
-                e
-            ))
+/// The next version of a workspace member.
+struct NextVersion {
+    /// The component of the version to increment, if a new version is needed and the current
+    /// one could be parsed.
+    bump: Option<String>,
+    /// The next version, under the same conditions.
+    version: Option<String>,
+    /// The members with breaking changes the member needs a new version for, if it has no
+    /// changes of its own.
+    required_by: Vec<String>,
+}
+
+/// Compute the minimal next version of each checked workspace member.
+///
+/// A member without reported changes doesn't need a new version, unless it depends on another
+/// member requiring a breaking one: to use the new version of its dependency, it needs at least
+/// a patch release itself. Breaking changes to a member's API caused by such a dependency are
+/// found by the check of the member itself.
+fn next_versions(checks: &[MemberCheck]) -> Vec<NextVersion> {
+    let breaking: HashSet<_> = checks
+        .iter()
+        .filter(|c| {
+            c.verdict.counts.total() > 0 && c.verdict.max_category == ChangeCategory::Breaking
         })
-        .and_then(|(mut crates, _)| {
-            crates
-                .drain(..)
-                .find(|krate| krate.name == crate_name)
-                .ok_or_else(|| {
-                    anyhow::Error::msg(format!("failed to find a matching crate `{}`", crate_name))
-                })
+        .map(|c| c.name.as_str())
+        .collect();
+
+    checks
+        .iter()
+        .map(|check| {
+            let verdict = &check.verdict;
+
+            if verdict.counts.total() > 0 {
+                return NextVersion {
+                    bump: verdict.bump.clone(),
+                    version: verdict.suggested_version.clone(),
+                    required_by: Vec::new(),
+                };
+            }
+
+            let required_by: Vec<_> = check
+                .dependencies
+                .iter()
+                .filter(|dep| breaking.contains(dep.as_str()))
+                .cloned()
+                .collect();
+
+            if required_by.is_empty() {
+                return NextVersion {
+                    bump: None,
+                    version: None,
+                    required_by,
+                };
+            }
+
+            let version = semver::Version::parse(&verdict.old_version)
+                .ok()
+                .map(|mut next| {
+                    next.patch += 1;
+                    next.pre = semver::Prerelease::EMPTY;
+                    next.build = semver::BuildMetadata::EMPTY;
+                    next.to_string()
+                });
+
+            NextVersion {
+                bump: Some("patch".to_owned()),
+                version,
+                required_by,
+            }
         })
+        .collect()
 }
 
-/// Thread-safe byte buffer that implements `io::Write`.
-#[derive(Clone)]
-struct VecWrite(Arc<RwLock<Vec<u8>>>);
+/// Fail if the check of any workspace member failed.
+fn check_members_passed(checks: &[MemberCheck]) -> Result<()> {
+    let failed: Vec<_> = checks
+        .iter()
+        .filter(|c| !c.success)
+        .map(|c| format!("`{}`", c.name))
+        .collect();
 
-impl VecWrite {
-    pub fn read(&self) -> io::Result<std::sync::RwLockReadGuard<'_, Vec<u8>>> {
-        self.0
-            .read()
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "lock poison"))
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "the check failed for workspace members {}",
+            failed.join(", ")
+        ))
     }
-    pub fn write(&self) -> io::Result<std::sync::RwLockWriteGuard<'_, Vec<u8>>> {
-        self.0
-            .write()
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "lock poison"))
+}
+
+/// Check all members of the current workspace, and report the minimal next version of each.
+fn run_next_versions(config: &cargo::Config) -> Result<()> {
+    // the members are checked in quiet mode, as only their verdicts are reported
+    let checks = check_members(config, &["--quiet"], |_| None)?;
+
+    for (check, next) in checks.iter().zip(next_versions(&checks)) {
+        let verdict = &check.verdict;
+
+        let needed = verdict.counts.total() > 0 || !next.required_by.is_empty();
+
+        match next.version {
+            _ if !needed => println!("{}: {} -> no changes", check.name, verdict.old_version),
+            None => println!(
+                "{}: max change: {}, could not parse {}",
+                check.name, verdict.max_category, verdict.old_version
+            ),
+            Some(version) if !next.required_by.is_empty() => {
+                let required_by: Vec<_> = next
+                    .required_by
+                    .iter()
+                    .map(|d| format!("`{}`", d))
+                    .collect();
+
+                println!(
+                    "{}: {} -> (patch) -> {}, to use new versions of {}",
+                    check.name,
+                    verdict.old_version,
+                    version,
+                    required_by.join(", ")
+                );
+            }
+            Some(version) => println!(
+                "{}: {} -> ({}) -> {}",
+                check.name, verdict.old_version, verdict.max_category, version
+            ),
+        }
     }
+
+    check_members_passed(&checks)
 }
 
-impl io::Write for VecWrite {
-    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-        let mut lock = Self::write(self)?;
-        io::Write::write(&mut *lock, data)
+/// The version of the release plan format, incremented on incompatible changes to it.
+const RELEASE_PLAN_VERSION: u32 = 1;
+
+/// Check all members of the current workspace, and print a release plan for release tools.
+///
+/// The plan is a JSON document following the schema in `doc/release-plan.schema.json`,
+/// listing the minimal next version of every member, as computed by `next_versions`, along
+/// with the changes reported as errors, which block the release.
+fn run_release_plan(config: &cargo::Config) -> Result<()> {
+    #[derive(Serialize)]
+    struct Crate<'a> {
+        name: &'a str,
+        version: &'a str,
+        baseline_version: &'a str,
+        max_category: ChangeCategory,
+        bump: Option<String>,
+        next_version: Option<String>,
+        required_by: Vec<String>,
+        dependencies: &'a [String],
+        passed: bool,
+        blocking: &'a [serde_json::Value],
     }
-    fn flush(&mut self) -> io::Result<()> {
-        Ok(())
+
+    #[derive(Serialize)]
+    struct ReleasePlan<'a> {
+        format_version: u32,
+        tool_version: &'static str,
+        crates: Vec<Crate<'a>>,
+    }
+
+    // the members are checked in quiet mode, as their verdicts hold all the plan needs
+    let checks = check_members(config, &["--quiet"], |_| None)?;
+
+    let crates = checks
+        .iter()
+        .zip(next_versions(&checks))
+        .map(|(check, next)| Crate {
+            name: &check.name,
+            version: &check.version,
+            baseline_version: &check.verdict.old_version,
+            max_category: check.verdict.max_category,
+            bump: next.bump,
+            next_version: next.version,
+            required_by: next.required_by,
+            dependencies: &check.dependencies,
+            passed: check.success,
+            blocking: &check.verdict.blocking,
+        })
+        .collect();
+
+    let plan = ReleasePlan {
+        format_version: RELEASE_PLAN_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION"),
+        crates,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&plan)?);
+
+    // the plan is printed regardless, as it lists the reasons of failed checks
+    check_members_passed(&checks)
+}
+
+/// Check all members of the current workspace, and write an aggregated report to `path`.
+///
+/// The report lists the verdict and suggested version of every member, and links to the JSON
+/// report of its changes, which are written to a directory named after the report next to it.
+/// Reports with an `.html` extension are written as an HTML page, all others as JSON.
+fn run_dashboard(config: &cargo::Config, path: &Path) -> Result<()> {
+    #[derive(Serialize)]
+    struct Entry<'a> {
+        name: &'a str,
+        version: &'a str,
+        #[serde(flatten)]
+        verdict: &'a Verdict,
+        passed: bool,
+        report: String,
+    }
+
+    let path = config.cwd().join(path);
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let stem = path.file_stem().map_or_else(
+        || "dashboard".to_owned(),
+        |s| s.to_string_lossy().into_owned(),
+    );
+    std::fs::create_dir_all(dir.join(&stem))?;
+
+    let report_path = |name: &str| format!("{}/{}.json", stem, name);
+    let checks = check_members(config, &["--json"], |name| {
+        Some(dir.join(report_path(name)))
+    })?;
+
+    let entries: Vec<_> = checks
+        .iter()
+        .map(|c| Entry {
+            name: &c.name,
+            version: &c.version,
+            verdict: &c.verdict,
+            passed: c.success,
+            report: report_path(&c.name),
+        })
+        .collect();
+
+    let contents = if path.extension().map_or(false, |e| e == "html") {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>cargo semver</title>\n</head>\n<body>\n<table>\n<tr><th>crate</th>\
+             <th>version</th><th>verdict</th><th>suggested version</th><th>status</th>\
+             <th>changes</th></tr>\n",
+        );
+
+        for entry in &entries {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td><a href=\"{}\">report</a></td></tr>\n",
+                escape_html(entry.name),
+                escape_html(entry.version),
+                entry.verdict.max_category,
+                escape_html(
+                    entry
+                        .verdict
+                        .suggested_version
+                        .as_deref()
+                        .unwrap_or("unknown")
+                ),
+                if entry.passed { "passed" } else { "failed" },
+                escape_html(&entry.report),
+            ));
+        }
+
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    } else {
+        serde_json::to_string_pretty(&entries)?
+    };
+
+    std::fs::write(&path, contents)?;
+    config.shell().status(
+        "Written",
+        format!("report on {} crates to {}", entries.len(), path.display()),
+    )?;
+
+    Ok(())
+}
+
+/// Escape the characters of a string that are special in HTML.
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Compare the manifests of both versions of a package.
+///
+/// The edition and `rust-version` are taken from the manifests as interpreted by cargo, so an
+/// edition that isn't declared is the 2015 one.
+fn manifest_changes(stable: &Package, current: &Package) -> Vec<ManifestChange> {
+    let mut changes =
+        semverver::manifest::diff_features(&feature_map(stable), &feature_map(current));
+    changes.extend(semverver::manifest::diff_rust_version(
+        stable.rust_version(),
+        current.rust_version(),
+    ));
+    changes.extend(semverver::manifest::diff_edition(
+        &stable.manifest().edition().to_string(),
+        &current.manifest().edition().to_string(),
+    ));
+
+    changes
+}
+
+/// Collect the features declared by a package.
+///
+/// This includes the features cargo implicitly declares for optional dependencies that aren't
+/// referred to using `dep:` elsewhere.
+fn feature_map(package: &Package) -> semverver::manifest::FeatureMap {
+    package
+        .summary()
+        .features()
+        .iter()
+        .map(|(name, values)| {
+            (
+                name.to_string(),
+                values.iter().map(ToString::to_string).collect(),
+            )
+        })
+        .collect()
+}
+
+/// Report the time it took to build one of the crate versions.
+fn report_build_time(config: &cargo::Config, version: &str, elapsed: Duration) -> Result<()> {
+    config.shell().status(
+        "Timing",
+        format!("built {} version in {:.3}s", version, elapsed.as_secs_f64()),
+    )
+}
+
+/// Read a file listing renamed or moved items, with one `old::path = new::path` pair per line.
+///
+/// Empty lines and lines starting with `#` are ignored. The pairs are returned in the `old=new`
+/// form expected by `rust-semverver`.
+fn read_renames(path: &Path) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("could not read {}: {}", path.display(), e))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_no, line)| {
+            let pair = line
+                .split_once('=')
+                .map(|(old, new)| (old.trim(), new.trim()))
+                .filter(|(old, new)| {
+                    !old.is_empty() && !new.is_empty() && !new.contains('=') && !line.contains(',')
+                });
+
+            match pair {
+                Some((old, new)) => Ok(format!("{}={}", old, new)),
+                None => Err(anyhow::anyhow!(
+                    "{}:{}: expected a rename of the form `old::path = new::path`",
+                    path.display(),
+                    line_no
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Write the suggested version to the manifest of the given package.
+///
+/// Only the value of the `version` field is replaced, so formatting and comments in the rest of
+/// the manifest are preserved. Versions at least as high as the suggested one are kept.
+fn write_version(config: &cargo::Config, package: &Package, suggested: &str) -> Result<()> {
+    let manifest_path = package.manifest_path();
+    let declared = package.version();
+
+    let suggested_version = semver::Version::parse(suggested)
+        .map_err(|_| anyhow::anyhow!("could not determine the suggested version"))?;
+
+    if *declared >= suggested_version {
+        config.shell().status(
+            "Skipping",
+            format!(
+                "version bump of `{}`, {} is at least the suggested {}",
+                package.name(),
+                declared,
+                suggested
+            ),
+        )?;
+
+        return Ok(());
+    }
+
+    let contents = std::fs::read_to_string(manifest_path)?;
+    let mut doc: toml_edit::Document = contents.parse()?;
+
+    let item = doc
+        .get_mut("package")
+        .and_then(|package| package.get_mut("version"))
+        .filter(|version| version.is_str())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "could not find a `package.version` field in {}",
+                manifest_path.display()
+            )
+        })?;
+
+    let mut value = toml_edit::Value::from(suggested);
+    if let Some(old_value) = item.as_value() {
+        *value.decor_mut() = old_value.decor().clone();
+    }
+    *item = toml_edit::Item::Value(value);
+
+    std::fs::write(manifest_path, doc.to_string())?;
+    config.shell().status(
+        "Bumped",
+        format!(
+            "version of `{}` from {} to {} in {}",
+            package.name(),
+            declared,
+            suggested,
+            manifest_path.display()
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Run `rust-semver-public` on the given crate.
+///
+/// Prints the public types of the crate, or captures and returns a listing of the whole public
+/// API if `dump_api` is set.
+fn run_public(
+    config: &cargo::Config,
+    matches: &getopts::Matches,
+    info: &WorkInfo,
+    name: &str,
+    current: bool,
+    dump_api: bool,
+) -> Result<Vec<u8>> {
+    let (rlib, deps_output) = info.rlib_and_dep_output(config, name, current, matches)?;
+
+    let mut child = Command::new("rust-semver-public");
+    child
+        .arg("--crate-type=lib")
+        .args(&["--extern", &*format!("new={}", rlib.display())]);
+
+    for link_path in deps_output {
+        child.args(&[format!("-L{}", link_path.display())]);
+    }
+
+    if let Some(target) = matches.opt_str("target") {
+        child.args(&["--target", &target]);
+    }
+
+    if dump_api {
+        child
+            .env("RUST_SEMVER_DUMP_API", "true")
+            .stdout(Stdio::piped());
+    }
+
+    let mut child = child
+        .arg("-")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::Error::msg(format!("could not spawn rustc: {}", e)))?;
+
+    if let Some(ref mut stdin) = child.stdin {
+        stdin.write_fmt(format_args!(
+            "#[allow(unused_extern_crates)] \
+             extern crate new;"
+        ))?;
+    } else {
+        return Err(anyhow::Error::msg(
+            "could not pipe to rustc (wtf?)".to_owned(),
+        ));
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| anyhow::Error::msg(format!("failed to wait for rustc: {}", e)))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(anyhow::Error::msg("rustc-semver-public errored".to_owned()))
+    }
+}
+
+/// Print the differences between two public API listings in the style of a unified diff.
+fn print_api_diff(old_label: &str, old: &str, new_label: &str, new: &str) {
+    println!("--- {}", old_label);
+    println!("+++ {}", new_label);
+
+    for (marker, line) in diff_api(old, new) {
+        println!("{}{}", marker, line);
+    }
+}
+
+/// Compute the lines removed from and added to a public API listing.
+///
+/// The listings are sorted, so a single pass over both suffices. Changed items show up as a
+/// removed and an added line next to each other.
+fn diff_api<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let mut old = old.lines().peekable();
+    let mut new = new.lines().peekable();
+    let mut diff = Vec::new();
+
+    loop {
+        match (old.peek(), new.peek()) {
+            (Some(o), Some(n)) if o == n => {
+                old.next();
+                new.next();
+            }
+            (Some(&o), Some(&n)) if o < n => {
+                diff.push(('-', o));
+                old.next();
+            }
+            (_, Some(&n)) => {
+                diff.push(('+', n));
+                new.next();
+            }
+            (Some(&o), None) => {
+                diff.push(('-', o));
+                old.next();
+            }
+            (None, None) => break,
+        }
+    }
+
+    diff
+}
+
+/// Print the differences between the public items listed in two rustdoc JSON documents, in the
+/// style of `api-diff`.
+///
+/// There's no analysis based on rustdoc's JSON output, so changes aren't classified in detail.
+/// Instead, removed items and items whose signature changed fail the comparison.
+fn run_diff_doc(old: &Path, new: &Path) -> Result<()> {
+    let old = doc_api(old)?;
+    let new = doc_api(new)?;
+
+    println!("--- {}", old.label);
+    println!("+++ {}", new.label);
+
+    let diff = diff_doc_api(&old, &new);
+
+    for (marker, item) in &diff {
+        println!("{}{}", marker, item);
+    }
+
+    let removed = diff.iter().filter(|&&(marker, _)| marker == '-').count();
+    let changed = diff.iter().filter(|&&(marker, _)| marker == '~').count();
+
+    if removed > 0 || changed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} item{} removed and {} item{} changed, which requires a breaking release",
+            removed,
+            if removed == 1 { "" } else { "s" },
+            changed,
+            if changed == 1 { "" } else { "s" },
+        ));
+    }
+
+    Ok(())
+}
+
+/// The public items of a crate, as listed in a rustdoc JSON document.
+struct DocApi {
+    /// A label for the crate and its version.
+    label: String,
+    /// The signatures of the documented items, by their kind and path.
+    items: BTreeMap<String, serde_json::Value>,
+}
+
+/// Compare the items of two rustdoc JSON documents.
+///
+/// Every item removed, added or changed is returned along with a marker, which is `-`, `+` or
+/// `~` respectively, in the order of their kinds and paths.
+fn diff_doc_api<'a>(old: &'a DocApi, new: &'a DocApi) -> Vec<(char, &'a str)> {
+    let mut diff: Vec<_> = old
+        .items
+        .iter()
+        .filter_map(|(item, old_sig)| match new.items.get(item) {
+            None => Some(('-', item.as_str())),
+            Some(new_sig) if new_sig != old_sig => Some(('~', item.as_str())),
+            Some(_) => None,
+        })
+        .chain(
+            new.items
+                .keys()
+                .filter(|item| !old.items.contains_key(*item))
+                .map(|item| ('+', item.as_str())),
+        )
+        .collect();
+    diff.sort_by_key(|&(_, item)| item);
+
+    diff
+}
+
+/// Read a rustdoc JSON document and obtain a label for its crate and version, and the
+/// signatures of the crate's documented items.
+fn doc_api(path: &Path) -> Result<DocApi> {
+    use serde_json::Value;
+
+    let doc: Value = serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("failed to read `{}`: {}", path.display(), err))?,
+    )
+    .map_err(|err| anyhow::anyhow!("failed to parse `{}`: {}", path.display(), err))?;
+
+    let (index, paths) = match (&doc["format_version"], &doc["index"], &doc["paths"]) {
+        (Value::Number(_), Value::Object(index), Value::Object(paths)) => (index, paths),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "`{}` is not a rustdoc JSON document",
+                path.display()
+            ))
+        }
+    };
+
+    let name = doc["root"]
+        .as_str()
+        .and_then(|root| index.get(root))
+        .and_then(|root| root["name"].as_str())
+        .unwrap_or("?");
+    let label = match doc["crate_version"].as_str() {
+        Some(version) => format!("{} {}", name, version),
+        None => name.to_owned(),
+    };
+
+    // items of other crates are listed as well, and only documented items are public
+    let items = paths
+        .iter()
+        .filter(|(_, summary)| summary["crate_id"] == 0)
+        .filter_map(|(id, summary)| {
+            let item = index.get(id)?;
+            let path: Vec<_> = summary["path"]
+                .as_array()?
+                .iter()
+                .map(Value::as_str)
+                .collect::<Option<_>>()?;
+            let key = format!("{} {}", summary["kind"].as_str()?, path.join("::"));
+
+            Some((key, doc_signature(index, item)))
+        })
+        .collect();
+
+    Ok(DocApi { label, items })
+}
+
+/// Get the signature of an item of a rustdoc JSON document, which can be compared with that
+/// of the item in another document.
+///
+/// Ids differ between documents, so they are left out, and the fields, variants and items of
+/// types and traits are replaced by their names and signatures. Modules are only compared by
+/// their path, as their items are listed on their own, and so are impls.
+fn doc_signature(
+    index: &serde_json::Map<String, serde_json::Value>,
+    item: &serde_json::Value,
+) -> serde_json::Value {
+    use serde_json::Value;
+
+    /// The keys holding ids, or other information that doesn't belong to a signature.
+    const SKIPPED: &[&str] = &[
+        "id",
+        "span",
+        "docs",
+        "links",
+        "impls",
+        "implementations",
+        "implementors",
+        "blanket_impls",
+        "synthetic_impls",
+    ];
+
+    /// The keys holding the ids of members of an item.
+    const MEMBERS: &[&str] = &["fields", "variants", "items"];
+
+    fn normalize(index: &serde_json::Map<String, Value>, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| !SKIPPED.contains(&key.as_str()))
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::Array(ids) if MEMBERS.contains(&key.as_str()) => Value::Array(
+                                ids.iter()
+                                    .filter_map(|id| index.get(id.as_str()?))
+                                    .map(|member| doc_signature(index, member))
+                                    .collect(),
+                            ),
+                            value => normalize(index, value),
+                        };
+
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            Value::Array(values) => {
+                Value::Array(values.iter().map(|value| normalize(index, value)).collect())
+            }
+            value => value.clone(),
+        }
+    }
+
+    // documents before format version 26 give the kind separately from the inner item
+    let kind = item["kind"]
+        .as_str()
+        .or_else(|| item["inner"].as_object()?.keys().next().map(String::as_str));
+
+    if kind == Some("module") {
+        return Value::Null;
+    }
+
+    let mut signature = serde_json::Map::new();
+    signature.insert("name".to_owned(), item["name"].clone());
+    signature.insert("inner".to_owned(), normalize(index, &item["inner"]));
+
+    Value::Object(signature)
+}
+
+/// Project configuration read from a `semver.toml` file in the crate root, and from
+/// `SEMVERVER_*` environment variables.
+///
+/// The settings are translated to command line arguments, which are overridden by the ones
+/// actually given on the command line. Environment variables in turn override the file.
+mod project_config {
+    use cargo::util::important_paths::find_root_manifest_for_wd;
+    use rustc_session::getopts;
+    use serde::Deserialize;
+    use std::{
+        collections::BTreeMap,
+        env,
+        path::{Path, PathBuf},
+    };
+
+    /// The name of the configuration file.
+    const FILE_NAME: &str = "semver.toml";
+
+    /// The prefix of the environment variables holding settings.
+    const ENV_PREFIX: &str = "SEMVERVER_";
+
+    /// The contents of a configuration file.
+    #[derive(Debug, Default, Deserialize)]
+    #[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+    pub struct ProjectConfig {
+        /// The published version (or `name:version`) to compare against.
+        baseline: Option<String>,
+        /// The local path of the version to compare against, relative to the configuration file.
+        baseline_path: Option<PathBuf>,
+        /// The features to activate.
+        features: Option<Vec<String>>,
+        /// Whether to activate all features.
+        all_features: bool,
+        /// Whether not to activate the default features.
+        no_default_features: bool,
+        /// The target triple to build for.
+        target: Option<String>,
+        /// Path patterns restricting the reported items.
+        only: Vec<String>,
+        /// Path patterns of items not to report.
+        exclude: Vec<String>,
+        /// The features designating an unstable API.
+        unstable_features: Vec<String>,
+        /// Severity overrides for change categories.
+        severity: BTreeMap<String, String>,
+        /// The output format, one of `human`, `json` or `compact`.
+        output: Option<String>,
+        /// Whether to only report changes that are breaking according to the API guidelines.
+        api_guidelines: bool,
+        /// When to fail the check.
+        fail_on: Option<String>,
+        /// The number of breaking changes tolerated before failing the check.
+        max_breaking: Option<usize>,
+        /// Whether to fail the check if any item couldn't be analyzed completely.
+        strict: bool,
+        /// The number of seconds the comparison of a single item may take.
+        timeout_per_item: Option<u64>,
+        /// The number of seconds the comparison of all items may take.
+        time_budget: Option<u64>,
+        /// The file listing renamed items, relative to the configuration file.
+        renames: Option<PathBuf>,
+        /// Whether to follow cargo's rules for versions below 1.0.0.
+        zero_semantics: Option<bool>,
+    }
+
+    impl ProjectConfig {
+        /// Read the configuration from the `SEMVERVER_*` environment variables.
+        ///
+        /// Each setting of the configuration file has a variable of its own, named after the
+        /// setting in upper case, e.g. `SEMVERVER_FAIL_ON` for `fail-on`. Lists are given as
+        /// comma-separated values, and severity overrides as `category=severity` pairs.
+        pub fn from_env() -> anyhow::Result<Self> {
+            fn var(name: &str) -> Option<String> {
+                env::var(format!("{}{}", ENV_PREFIX, name))
+                    .ok()
+                    .filter(|v| !v.is_empty())
+            }
+
+            fn flag(name: &str) -> anyhow::Result<Option<bool>> {
+                var(name)
+                    .map(|v| {
+                        v.parse().map_err(|_| {
+                            anyhow::anyhow!(
+                                "invalid value `{}` of {}{}, expected `true` or `false`",
+                                v,
+                                ENV_PREFIX,
+                                name
+                            )
+                        })
+                    })
+                    .transpose()
+            }
+
+            fn list(name: &str) -> Vec<String> {
+                var(name)
+                    .map(|v| {
+                        v.split(',')
+                            .map(str::trim)
+                            .filter(|e| !e.is_empty())
+                            .map(str::to_owned)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            }
+
+            let mut severity = BTreeMap::new();
+            for entry in list("SEVERITY") {
+                let (category, value) = entry.split_once('=').ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "invalid severity override `{}` in {}SEVERITY",
+                        entry,
+                        ENV_PREFIX
+                    )
+                })?;
+
+                severity.insert(category.trim().to_owned(), value.trim().to_owned());
+            }
+
+            let max_breaking = var("MAX_BREAKING")
+                .map(|v| {
+                    v.parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "invalid maximum number of breaking changes `{}` in {}MAX_BREAKING",
+                            v,
+                            ENV_PREFIX
+                        )
+                    })
+                })
+                .transpose()?;
+
+            let seconds = |name: &str| {
+                var(name)
+                    .map(|v| {
+                        v.parse().map_err(|_| {
+                            anyhow::anyhow!(
+                                "invalid number of seconds `{}` in {}{}",
+                                v,
+                                ENV_PREFIX,
+                                name
+                            )
+                        })
+                    })
+                    .transpose()
+            };
+
+            let features = var("FEATURES").map(|_| list("FEATURES"));
+
+            Ok(ProjectConfig {
+                baseline: var("BASELINE"),
+                baseline_path: var("BASELINE_PATH").map(PathBuf::from),
+                features,
+                all_features: flag("ALL_FEATURES")?.unwrap_or(false),
+                no_default_features: flag("NO_DEFAULT_FEATURES")?.unwrap_or(false),
+                target: var("TARGET"),
+                only: list("ONLY"),
+                exclude: list("EXCLUDE"),
+                unstable_features: list("UNSTABLE_FEATURES"),
+                severity,
+                output: var("OUTPUT"),
+                api_guidelines: flag("API_GUIDELINES")?.unwrap_or(false),
+                fail_on: var("FAIL_ON"),
+                max_breaking,
+                strict: flag("STRICT")?.unwrap_or(false),
+                timeout_per_item: seconds("TIMEOUT_PER_ITEM")?,
+                time_budget: seconds("TIME_BUDGET")?,
+                renames: var("RENAMES").map(PathBuf::from),
+                zero_semantics: flag("ZERO_SEMANTICS")?,
+            })
+        }
+
+        /// Translate the configuration to command line arguments, skipping settings already
+        /// given on the command line. Paths are resolved relative to the directory `dir`
+        /// containing the configuration file, a bare baseline version refers to the crate
+        /// `name`, while `source` names where the configuration comes from in error messages.
+        pub fn to_args(
+            &self,
+            matches: &getopts::Matches,
+            dir: &Path,
+            name: Option<&str>,
+            source: &str,
+        ) -> anyhow::Result<Vec<String>> {
+            let mut args = Vec::new();
+            let mut push = |name: &str, value: Option<&str>| {
+                args.push(format!("--{}", name));
+                if let Some(value) = value {
+                    args.push(value.to_owned());
+                }
+            };
+
+            if !["s", "S", "base-crate"]
+                .iter()
+                .any(|o| matches.opt_present(o))
+            {
+                match (&self.baseline, &self.baseline_path) {
+                    (Some(_), Some(_)) => {
+                        return Err(anyhow::anyhow!(
+                            "at most one of `baseline` and `baseline-path` allowed in {}",
+                            source
+                        ));
+                    }
+                    (Some(version), None) => match name {
+                        Some(name) if !version.contains(':') => {
+                            push("stable-pkg", Some(format!("{}:{}", name, version).as_str()))
+                        }
+                        _ => push("stable-pkg", Some(version.as_str())),
+                    },
+                    (None, Some(path)) => push(
+                        "stable-path",
+                        Some(dir.join(path).to_string_lossy().as_ref()),
+                    ),
+                    (None, None) => (),
+                }
+            }
+
+            let features_given = matches.opt_present("features")
+                || matches.opt_present("all-features")
+                || matches.opt_present("no-default-features");
+
+            if !features_given {
+                if let Some(ref features) = self.features {
+                    push("features", Some(features.join(" ").as_str()));
+                }
+
+                if self.all_features {
+                    push("all-features", None);
+                }
+
+                if self.no_default_features {
+                    push("no-default-features", None);
+                }
+            }
+
+            if !matches.opt_present("target") {
+                if let Some(ref target) = self.target {
+                    push("target", Some(target.as_str()));
+                }
+            }
+
+            for pattern in &self.only {
+                push("only", Some(pattern.as_str()));
+            }
+
+            for pattern in &self.exclude {
+                push("exclude", Some(pattern.as_str()));
+            }
+
+            for feature in &self.unstable_features {
+                push("unstable-feature", Some(feature.as_str()));
+            }
+
+            // severity flags given on the command line come later, and thus take precedence
+            for (category, severity) in &self.severity {
+                match severity.as_str() {
+                    "allow" | "warn" | "deny" => push(severity, Some(category.as_str())),
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "invalid severity `{}` for `{}` in {}, expected one of `allow`, \
+                             `warn` or `deny`",
+                            severity,
+                            category,
+                            source
+                        ));
+                    }
+                }
+            }
+
+            if !matches.opt_present("j")
+                && !matches.opt_present("compact")
+                && !matches.opt_present("dot")
+            {
+                match self.output.as_deref() {
+                    None | Some("human") => (),
+                    Some("json") => push("json", None),
+                    Some("compact") => push("compact", None),
+                    Some(other) => {
+                        return Err(anyhow::anyhow!(
+                            "invalid output format `{}` in {}, expected one of `human`, `json` \
+                             or `compact`",
+                            other,
+                            source
+                        ));
+                    }
+                }
+            }
+
+            if self.api_guidelines && !matches.opt_present("a") {
+                push("api-guidelines", None);
+            }
+
+            if self.zero_semantics == Some(false) && !matches.opt_present("no-zero-semantics") {
+                push("no-zero-semantics", None);
+            }
+
+            if !matches.opt_present("fail-on") && !matches.opt_present("max-breaking") {
+                if let Some(ref policy) = self.fail_on {
+                    push("fail-on", Some(policy.as_str()));
+                }
+
+                if let Some(max) = self.max_breaking {
+                    push("max-breaking", Some(max.to_string().as_str()));
+                }
+            }
+
+            if self.strict && !matches.opt_present("strict") {
+                push("strict", None);
+            }
+
+            for (name, secs) in [
+                ("timeout-per-item", self.timeout_per_item),
+                ("time-budget", self.time_budget),
+            ] {
+                if let Some(secs) = secs {
+                    if !matches.opt_present(name) {
+                        push(name, Some(secs.to_string().as_str()));
+                    }
+                }
+            }
+
+            if let Some(ref renames) = self.renames {
+                if !matches.opt_present("renames") {
+                    push(
+                        "renames",
+                        Some(dir.join(renames).to_string_lossy().as_ref()),
+                    );
+                }
+            }
+
+            Ok(args)
+        }
+    }
+
+    /// The parts of a crate manifest holding the `[package.metadata.semverver]` table.
+    #[derive(Deserialize)]
+    struct Manifest {
+        package: Option<ManifestPackage>,
+    }
+
+    /// The `[package]` table of a crate manifest.
+    #[derive(Deserialize)]
+    struct ManifestPackage {
+        name: Option<String>,
+        metadata: Option<ManifestMetadata>,
+    }
+
+    /// The `[package.metadata]` table of a crate manifest.
+    #[derive(Deserialize)]
+    struct ManifestMetadata {
+        semverver: Option<ProjectConfig>,
+    }
+
+    /// Find the manifest of the current crate, unless it's a remote package.
+    fn find_manifest(config: &cargo::Config, matches: &getopts::Matches) -> Option<PathBuf> {
+        // remote packages can't be configured
+        if matches.opt_present("C") {
+            return None;
+        }
+
+        let dir = matches
+            .opt_str("c")
+            .map_or_else(|| config.cwd().to_owned(), PathBuf::from);
+        find_root_manifest_for_wd(&dir).ok()
+    }
+
+    /// Find the configuration file of the current crate, if any.
+    fn find(config: &cargo::Config, matches: &getopts::Matches) -> Option<PathBuf> {
+        let path = find_manifest(config, matches)?.parent()?.join(FILE_NAME);
+
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Read the `[package]` table of the current crate's manifest, if any, which holds its name
+    /// and the `[package.metadata.semverver]` table.
+    fn read_package(
+        config: &cargo::Config,
+        matches: &getopts::Matches,
+    ) -> anyhow::Result<Option<(PathBuf, ManifestPackage)>> {
+        let path = match find_manifest(config, matches) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let contents = std::fs::read_to_string(&path)?;
+        let manifest: Manifest = toml_edit::easy::from_str(&contents).map_err(|e| {
+            anyhow::anyhow!(
+                "could not parse [package.metadata.semverver] in {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        Ok(manifest.package.map(|package| (path, package)))
+    }
+
+    /// Apply the settings of the `SEMVERVER_*` environment variables, of the current crate's
+    /// configuration file and of the `[package.metadata.semverver]` table in its manifest, by
+    /// parsing them as command line arguments preceding the actual ones. Earlier sources take
+    /// precedence over later ones.
+    pub fn apply(
+        config: &cargo::Config,
+        opts: &getopts::Options,
+        matches: getopts::Matches,
+    ) -> anyhow::Result<getopts::Matches> {
+        let mut args: Vec<String> = std::env::args().skip(1).collect();
+        let mut matches = matches;
+
+        let package = read_package(config, &matches)?;
+        let name = package.as_ref().and_then(|(_, p)| p.name.as_deref());
+
+        // the environment is applied first, so that the file only fills in what's left
+        let env_config = ProjectConfig::from_env()?;
+        let env_source = format!("the {}* environment variables", ENV_PREFIX);
+        let env_args = env_config.to_args(&matches, config.cwd(), name, &env_source)?;
+        if !env_args.is_empty() {
+            args = env_args.into_iter().chain(args).collect();
+            matches = opts.parse(&args)?;
+        }
+
+        if let Some(path) = find(config, &matches) {
+            let contents = std::fs::read_to_string(&path)?;
+            let project_config: ProjectConfig = toml_edit::easy::from_str(&contents)
+                .map_err(|e| anyhow::anyhow!("could not parse {}: {}", path.display(), e))?;
+
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let file_args = project_config.to_args(&matches, dir, name, FILE_NAME)?;
+            if !file_args.is_empty() {
+                args = file_args.into_iter().chain(args).collect();
+                matches = opts.parse(&args)?;
+            }
+        }
+
+        if let Some((path, project_config)) = package.as_ref().and_then(|(path, p)| {
+            let project_config = p.metadata.as_ref()?.semverver.as_ref()?;
+            Some((path, project_config))
+        }) {
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let source = format!("[package.metadata.semverver] in {}", path.display());
+            let metadata_args = project_config.to_args(&matches, dir, name, &source)?;
+            if !metadata_args.is_empty() {
+                args = metadata_args.into_iter().chain(args).collect();
+                matches = opts.parse(&args)?;
+            }
+        }
+
+        Ok(matches)
+    }
+}
+
+/// CLI utils
+mod cli {
+    use cargo::util::CliError;
+    use rustc_session::getopts;
+    use std::path::PathBuf;
+
+    /// The severities changes can be reported with.
+    const SEVERITIES: [&str; 3] = ["allow", "warn", "deny"];
+
+    /// The categories of changes.
+    const CATEGORIES: [&str; 4] = ["patch", "non-breaking", "technically-breaking", "breaking"];
+
+    /// CLI options
+    pub fn options() -> getopts::Options {
+        let mut opts = getopts::Options::new();
+
+        opts.optflag("h", "help", "print this message and exit");
+        opts.optflag("V", "version", "print version information and exit");
+        opts.optflag("e", "explain", "print detailed error explanations");
+        opts.optflag(
+            "q",
+            "quiet",
+            "suppress regular cargo output and individual changes, print only the required \
+             version bump",
+        );
+        opts.optflag(
+            "",
+            "show-public",
+            "print the public types in the current crate given by -c or -C and exit",
+        );
+        opts.optflag("d", "debug", "print command to debug and exit");
+        opts.optopt(
+            "",
+            "check-rdeps",
+            "check whether the N most downloaded reverse dependencies on crates.io still \
+             compile against the current crate and exit",
+            "N",
+        );
+        opts.optflag(
+            "a",
+            "api-guidelines",
+            "report only changes that are breaking according to the API-guidelines",
+        );
+        opts.optflag(
+            "",
+            "mir-diff",
+            "report functions whose body changed while their signature didn't (experimental)",
+        );
+        opts.optflag(
+            "",
+            "panic-scan",
+            "report functions that can newly panic while their signature didn't change \
+             (experimental)",
+        );
+        opts.optflag(
+            "",
+            "no-zero-semantics",
+            "suggest versions below 1.0.0 using the rules for 1.0.0 and above",
+        );
+        opts.optflag(
+            "",
+            "serde",
+            "report changes to the serialized form of types implementing serde's traits",
+        );
+        opts.optflag(
+            "",
+            "wasm-bindgen",
+            "report changes to the functions exported to JavaScript using #[wasm_bindgen]",
+        );
+        opts.optmulti(
+            "",
+            "only",
+            "only report changes to items whose path matches the given pattern",
+            "PATTERN",
+        );
+        opts.optmulti(
+            "",
+            "exclude",
+            "don't report changes to items whose path matches the given pattern",
+            "PATTERN",
+        );
+        opts.optmulti(
+            "",
+            "unstable-feature",
+            "don't count changes to items gated behind the given feature towards the verdict",
+            "NAME",
+        );
+        opts.optopt(
+            "",
+            "technically-breaking",
+            "how to report technically breaking changes (default: warn), same as passing \
+             `--allow`, `--warn` or `--deny technically-breaking`",
+            "allow|warn|deny",
+        );
+        opts.optmulti(
+            "",
+            "allow",
+            "don't report changes of the given category",
+            "CATEGORY",
+        );
+        opts.optmulti(
+            "",
+            "warn",
+            "report changes of the given category as warnings",
+            "CATEGORY",
+        );
+        opts.optmulti(
+            "",
+            "deny",
+            "report changes of the given category as errors, failing the check",
+            "CATEGORY",
+        );
+        opts.optopt(
+            "",
+            "fail-on",
+            "when to fail the check: if any change is reported as an error (default), if any \
+             breaking change is found, if the current version is lower than the suggested one, \
+             or never",
+            "denied|breaking|bump-mismatch|never",
+        );
+        opts.optopt(
+            "",
+            "max-breaking",
+            "only fail the check if more than N breaking changes are found",
+            "N",
+        );
+        opts.optflag(
+            "",
+            "strict",
+            "fail the check if any item could not be fully analyzed",
+        );
+        opts.optopt(
+            "",
+            "renames",
+            "compare items renamed or moved between versions, as listed in FILE",
+            "FILE",
+        );
+        opts.optflag(
+            "",
+            "interactive",
+            "ask whether ambiguously renamed items have been renamed, and record the decisions \
+             in the file given by `--renames`",
+        );
+        opts.optflag(
+            "",
+            "check-doctests",
+            "compile the doctests of the stable version against the current version",
+        );
+        opts.optflag(
+            "",
+            "check-examples",
+            "compile the examples of the stable version against the current version",
+        );
+        opts.optflag(
+            "",
+            "timings",
+            "report the time spent in each phase of the analysis and the slowest items",
+        );
+        opts.optflag(
+            "",
+            "stream",
+            "print the changes to each item on stderr as soon as it has been compared",
+        );
+        opts.optopt(
+            "",
+            "trace-item",
+            "log every step of the analysis of the item with the given path",
+            "PATH",
+        );
+        opts.optopt(
+            "",
+            "timeout-per-item",
+            "give up on comparing an item after SECS seconds, and report it as not analyzed",
+            "SECS",
+        );
+        opts.optopt(
+            "",
+            "time-budget",
+            "stop comparing items after SECS seconds, and report the remaining ones as not \
+             analyzed",
+            "SECS",
+        );
+        opts.optopt(
+            "",
+            "features",
+            "Space-separated list of features to activate",
+            "FEATURES",
+        );
+        opts.optflag("", "all-features", "Activate all available features");
+        opts.optflag(
+            "",
+            "no-default-features",
+            "Do not activate the `default` feature",
+        );
+        opts.optflag(
+            "",
+            "compact",
+            "Only output the suggested version on stdout for further processing",
+        );
+        opts.optflag(
+            "j",
+            "json",
+            "Output a JSON-formatted description of all collected data on stdout.",
+        );
+        opts.optflag(
+            "",
+            "dot",
+            "print a Graphviz DOT graph of the items affected by breaking changes on stdout",
+        );
+        opts.optflag(
+            "",
+            "impact",
+            "list the public items mentioning each item with breaking changes",
+        );
+        opts.optflag(
+            "",
+            "bump-write",
+            "write the suggested version to the current crate's Cargo.toml",
+        );
+        opts.optflag(
+            "",
+            "lockstep",
+            "check all members of the current workspace, which share a version, and report \
+             the version bump required across all of them",
+        );
+        opts.optflag(
+            "",
+            "next-versions",
+            "check all members of the current workspace, and report the minimal next version \
+             of each, taking dependencies between them into account",
+        );
+        opts.optopt(
+            "",
+            "external-rules",
+            "pass the changes found to the given command as JSON lines, and apply the \
+             adjustments to their categories it prints",
+            "COMMAND",
+        );
+        opts.optopt(
+            "",
+            "dashboard",
+            "check all members of the current workspace and write a report listing their \
+             verdicts to the given file, as HTML if it ends in `.html` and as JSON otherwise",
+            "PATH",
+        );
+        opts.optopt(
+            "",
+            "format",
+            "check all members of the current workspace, and print a machine readable summary \
+             in the given format, for now only `release-plan`",
+            "FORMAT",
+        );
+        opts.optmulti(
+            "",
+            "baselines",
+            "check against each of the given versions, or all published versions preceding \
+             the current one matching the given requirement, and report the version bump \
+             required across all of them",
+            "VERSION|REQ",
+        );
+        opts.optopt(
+            "",
+            "verdict-path",
+            "write the required version bump and the number of changes per category as JSON \
+             to the given file, regardless of the output format",
+            "PATH",
+        );
+        opts.optopt(
+            "s",
+            "stable-path",
+            "use local path as stable/old crate",
+            "PATH",
+        );
+        opts.optopt(
+            "",
+            "base-crate",
+            "use a packaged `.crate` file, as uploaded to registries, as stable/old crate",
+            "PATH",
+        );
+        opts.optopt(
+            "c",
+            "current-path",
+            "use local path as current/new crate",
+            "PATH",
+        );
+        opts.optopt(
+            "S",
+            "stable-pkg",
+            "use a `name:version` string as stable/old crate",
+            "NAME:VERSION",
+        );
+        opts.optopt(
+            "C",
+            "current-pkg",
+            "use a `name:version` string as current/new crate",
+            "NAME:VERSION",
+        );
+        opts.optflag("", "offline", "Run without accessing the network.");
+        opts.optopt("", "target", "Build for the target triple", "<TRIPLE>");
+        opts
+    }
+
+    /// The subcommands of `cargo semver`, replacing the default analysis.
+    pub enum Subcommand {
+        /// Write a listing of the current crate's public API to the given file.
+        DumpApi(Option<PathBuf>),
+        /// Print the differences between the stable and current crate's public API.
+        ApiDiff,
+        /// Print the detailed description of the change with the given code.
+        Explain(String),
+        /// Print the differences between the items listed in two rustdoc JSON documents.
+        DiffDoc(PathBuf, PathBuf),
+    }
+
+    /// Get the subcommand given on the command line, if any.
+    ///
+    /// When invoked as `cargo semver`, cargo passes `semver` as the first argument, which is
+    /// skipped here.
+    pub fn subcommand(matches: &getopts::Matches) -> Option<Subcommand> {
+        let mut free = matches.free.iter().skip_while(|arg| *arg == "semver");
+
+        match free.next().map(String::as_str) {
+            Some("dump-api") => Some(Subcommand::DumpApi(free.next().map(PathBuf::from))),
+            Some("api-diff") => Some(Subcommand::ApiDiff),
+            Some("explain") => free.next().cloned().map(Subcommand::Explain),
+            Some("diff-doc") => match (free.next(), free.next()) {
+                (Some(old), Some(new)) => {
+                    Some(Subcommand::DiffDoc(PathBuf::from(old), PathBuf::from(new)))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Parse CLI arguments
+    pub fn parse_args(opts: &getopts::Options) -> Result<getopts::Matches, getopts::Fail> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        opts.parse(&args)
+    }
+
+    /// Validate CLI arguments
+    pub fn validate_args(matches: &getopts::Matches) -> Result<(), anyhow::Error> {
+        if ["s", "S", "base-crate"]
+            .iter()
+            .map(|o| matches.opt_count(o))
+            .sum::<usize>()
+            > 1
+        {
+            let msg = "at most one of `-s,--stable-path`, `-S,--stable-pkg` and `--base-crate` \
+                       allowed";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        if (matches.opt_present("c") && matches.opt_present("C"))
+            || matches.opt_count("c") > 1
+            || matches.opt_count("C") > 1
+        {
+            let msg = "at most one of `-c,--current-path` and `-C,--current-pkg` allowed";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        if matches.opt_present("q") && (matches.opt_present("j") || matches.opt_present("compact"))
+        {
+            let msg = "`-q,--quiet` can't be combined with `-j,--json` or `--compact`";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        if matches.opt_present("dot")
+            && ["q", "j", "compact"].iter().any(|o| matches.opt_present(o))
+        {
+            let msg = "`--dot` can't be combined with `-q,--quiet`, `-j,--json` or `--compact`";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        if matches.opt_present("bump-write") && matches.opt_present("C") {
+            let msg = "`--bump-write` can't be combined with `-C,--current-pkg`";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        if matches.opt_present("interactive") && !matches.opt_present("renames") {
+            let msg = "`--interactive` needs `--renames FILE` to record the decisions in";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        // the members are checked with their own paths, output formats and verdict files
+        let workspace_conflicts = [
+            "c",
+            "C",
+            "q",
+            "j",
+            "compact",
+            "dot",
+            "bump-write",
+            "verdict-path",
+        ];
+        for mode in ["lockstep", "dashboard", "next-versions", "format"] {
+            if matches.opt_present(mode)
+                && workspace_conflicts.iter().any(|o| matches.opt_present(o))
+            {
+                let msg = format!(
+                    "`--{}` can't be combined with `-c,--current-path`, `-C,--current-pkg`, \
+                     `-q,--quiet`, `-j,--json`, `--compact`, `--dot`, `--bump-write` or \
+                     `--verdict-path`",
+                    mode
+                );
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        let modes = ["lockstep", "dashboard", "next-versions", "format"];
+        if modes.iter().filter(|m| matches.opt_present(m)).count() > 1 {
+            let msg = "at most one of `--lockstep`, `--dashboard`, `--next-versions` and \
+                       `--format` allowed";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        if let Some(format) = matches.opt_str("format") {
+            if format != "release-plan" {
+                let msg = format!("unknown format `{}`, expected `release-plan`", format);
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        // the baselines are checked with their own versions and output formats
+        let baselines_conflicts = [
+            "s",
+            "S",
+            "base-crate",
+            "j",
+            "compact",
+            "dot",
+            "bump-write",
+            "lockstep",
+            "dashboard",
+            "format",
+        ];
+        if matches.opt_present("baselines")
+            && baselines_conflicts.iter().any(|o| matches.opt_present(o))
+        {
+            let msg = "`--baselines` can't be combined with `-s,--stable-path`, \
+                       `-S,--stable-pkg`, `--base-crate`, `-j,--json`, `--compact`, `--dot`, \
+                       `--bump-write`, `--lockstep`, `--dashboard` or `--format`";
+            return Err(anyhow::Error::msg(msg.to_owned()));
+        }
+
+        let mut free = matches.free.iter().skip_while(|arg| *arg == "semver");
+        match (free.next().map(String::as_str), free.count()) {
+            (None, _)
+            | (Some("dump-api"), 0..=1)
+            | (Some("api-diff"), 0)
+            | (Some("explain"), 1)
+            | (Some("diff-doc"), 2) => (),
+            (Some("dump-api"), _) => {
+                let msg = "at most one output file allowed for `dump-api`";
+                return Err(anyhow::Error::msg(msg.to_owned()));
+            }
+            (Some("api-diff"), _) => {
+                let msg = "`api-diff` takes no arguments";
+                return Err(anyhow::Error::msg(msg.to_owned()));
+            }
+            (Some("explain"), _) => {
+                let msg = "`explain` takes exactly one code, e.g. `cargo semver explain SV0002`";
+                return Err(anyhow::Error::msg(msg.to_owned()));
+            }
+            (Some("diff-doc"), _) => {
+                let msg = "`diff-doc` takes exactly two rustdoc JSON files, the old and new one";
+                return Err(anyhow::Error::msg(msg.to_owned()));
+            }
+            (Some(other), _) => {
+                let msg = format!("unknown subcommand `{}`", other);
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        if let Some(count) = matches.opt_str("check-rdeps") {
+            if !matches!(count.parse::<usize>(), Ok(1..=100)) {
+                let msg = format!(
+                    "invalid value `{}` for `--check-rdeps`, expected a number between 1 and 100",
+                    count
+                );
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        for pattern in matches
+            .opt_strs("only")
+            .iter()
+            .chain(&matches.opt_strs("exclude"))
+        {
+            if pattern.is_empty() || pattern.contains(',') {
+                let msg = format!("invalid path pattern `{}`", pattern);
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        for feature in matches.opt_strs("unstable-feature") {
+            if feature.is_empty() || feature.contains(',') {
+                let msg = format!("invalid feature name `{}`", feature);
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        if let Some(severity) = matches.opt_str("technically-breaking") {
+            if !SEVERITIES.contains(&severity.as_str()) {
+                let msg = format!(
+                    "invalid value `{}` for `--technically-breaking`, expected one of \
+                     `allow`, `warn` or `deny`",
+                    severity
+                );
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        if let Some(policy) = matches.opt_str("fail-on") {
+            if !["denied", "breaking", "bump-mismatch", "never"].contains(&policy.as_str()) {
+                let msg = format!(
+                    "invalid value `{}` for `--fail-on`, expected one of `denied`, `breaking`, \
+                     `bump-mismatch` or `never`",
+                    policy
+                );
+                return Err(anyhow::Error::msg(msg));
+            }
+
+            if policy != "breaking" && matches.opt_present("max-breaking") {
+                let msg = "`--max-breaking` can only be combined with `--fail-on breaking`";
+                return Err(anyhow::Error::msg(msg.to_owned()));
+            }
+        }
+
+        for name in ["timeout-per-item", "time-budget"] {
+            if let Some(secs) = matches.opt_str(name) {
+                if secs.parse::<u64>().is_err() {
+                    let msg = format!(
+                        "invalid value `{}` for `--{}`, expected a number of seconds",
+                        secs, name
+                    );
+                    return Err(anyhow::Error::msg(msg));
+                }
+            }
+        }
+
+        if let Some(max) = matches.opt_str("max-breaking") {
+            if max.parse::<usize>().is_err() {
+                let msg = format!(
+                    "invalid value `{}` for `--max-breaking`, expected a number",
+                    max
+                );
+                return Err(anyhow::Error::msg(msg));
+            }
+        }
+
+        for severity in SEVERITIES {
+            for category in matches.opt_strs(severity) {
+                if !CATEGORIES.contains(&category.as_str()) {
+                    let msg = format!(
+                        "invalid value `{}` for `--{}`, expected one of `patch`, \
+                         `non-breaking`, `technically-breaking` or `breaking`",
+                        category, severity
+                    );
+                    return Err(anyhow::Error::msg(msg));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collect the severity overrides given on the command line as a comma-separated list of
+    /// `category=severity` pairs, in the order they were given.
+    pub fn severity_overrides(matches: &getopts::Matches) -> String {
+        let mut overrides: Vec<_> = matches
+            .opt_strs_pos("technically-breaking")
+            .into_iter()
+            .map(|(pos, severity)| (pos, format!("technically-breaking={}", severity)))
+            .collect();
+
+        for severity in SEVERITIES {
+            for (pos, category) in matches.opt_strs_pos(severity) {
+                overrides.push((pos, format!("{}={}", category, severity)));
+            }
+        }
+
+        overrides.sort_by_key(|&(pos, _)| pos);
+        overrides
+            .into_iter()
+            .map(|(_, o)| o)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Print a help message
+    pub fn print_help(opts: &getopts::Options) {
+        // FIXME: pass remaining options to cargo
+        let brief = "usage: cargo semver [options]\n       \
+                     cargo semver dump-api [FILE] [options]\n       \
+                     cargo semver api-diff [options]\n       \
+                     cargo semver diff-doc OLD.json NEW.json\n       \
+                     cargo semver explain CODE";
+        print!("{}", opts.usage(brief));
+    }
+
+    /// Print a version message.
+    pub fn print_version() {
+        println!("{}", env!("CARGO_PKG_VERSION"));
+    }
+
+    /// Exit with error `e`.
+    pub fn exit_with_error(config: &cargo::Config, e: anyhow::Error) -> ! {
+        config
+            .shell()
+            .set_verbosity(cargo::core::shell::Verbosity::Normal);
+        cargo::exit_with_error(CliError::new(e, 1), &mut config.shell());
+    }
+}
+
+/// A package's name and version.
+pub struct PackageNameAndVersion<'a> {
+    /// The crate's name.
+    pub name: &'a str,
+    /// The package's version, as a semver-string.
+    pub version: &'a str,
+}
+
+impl<'a> PackageNameAndVersion<'a> {
+    /// Parses the string "name:version" into `Self`
+    pub fn parse(s: &'a str) -> Result<Self> {
+        let err = || {
+            anyhow::Error::msg(format!(
+                "spec has to be of form `name:version` but is `{}`",
+                s
+            ))
+        };
+        let mut split = s.split(':');
+        let name = split.next().ok_or_else(err)?;
+        let version = split.next().ok_or_else(err)?;
+        if split.next().is_some() {
+            Err(err())
+        } else {
+            Ok(Self { name, version })
+        }
+    }
+}
+
+/// A specification of a package and it's workspace.
+pub struct WorkInfo<'a> {
+    /// The package to be compiled.
+    pub package: Package,
+    /// The package's workspace.
+    workspace: Workspace<'a>,
+}
+
+impl<'a> WorkInfo<'a> {
+    /// Construct a package/workspace pair for the `manifest_path`
+    pub fn local(config: &'a cargo::Config, manifest_path: &Path) -> Result<WorkInfo<'a>> {
+        let workspace = Workspace::new(manifest_path, config)?;
+        let package = workspace.load(manifest_path)?;
+        Ok(Self { package, workspace })
+    }
+
+    /// Construct a package/workspace pair by fetching the package of a
+    /// specified `PackageNameAndVersion` from the `source`.
+    pub fn remote(
+        config: &'a cargo::Config,
+        &PackageNameAndVersion { name, version }: &PackageNameAndVersion,
+    ) -> Result<WorkInfo<'a>> {
+        let source = {
+            let source_id = SourceId::crates_io(config)?;
+            let mut source = RegistrySource::remote(source_id, &HashSet::new(), config)?;
+
+            debug!("source id loaded: {:?}", source_id);
+
+            if !config.offline() {
+                let _lock = config.acquire_package_cache_lock()?;
+                source.invalidate_cache();
+            }
+
+            Box::new(source)
+        };
+
+        // TODO: fall back to locally cached package instance, or better yet, search for it
+        // first.
+        let package_id = PackageId::new(name, version, source.source_id())?;
+        debug!("(remote) package id: {:?}", package_id);
+
+        let package = source.download_now(package_id, config)?;
+        let workspace = Workspace::ephemeral(package.clone(), config, None, false)?;
+
+        Ok(Self { package, workspace })
+    }
+
+    /// Construct a package/workspace pair by unpacking a packaged `.crate` file, as produced by
+    /// `cargo package` and uploaded to registries, into `unpack_dir`.
+    ///
+    /// Packaged crates contain a directory named after the file, holding the package.
+    pub fn packaged(
+        config: &'a cargo::Config,
+        crate_file: &Path,
+        unpack_dir: &Path,
+    ) -> Result<WorkInfo<'a>> {
+        let stem = crate_file
+            .file_stem()
+            .filter(|_| crate_file.extension().map_or(false, |ext| ext == "crate"))
+            .ok_or_else(|| anyhow::anyhow!("`{}` is not a `.crate` file", crate_file.display()))?;
+        let package_dir = unpack_dir.join(stem);
+
+        // don't let files of an earlier unpacked version of the file linger
+        if package_dir.exists() {
+            std::fs::remove_dir_all(&package_dir)?;
+        }
+
+        let file = std::fs::File::open(crate_file)
+            .map_err(|err| anyhow::anyhow!("failed to open `{}`: {}", crate_file.display(), err))?;
+        tar::Archive::new(flate2::read::GzDecoder::new(file))
+            .unpack(unpack_dir)
+            .map_err(|err| {
+                anyhow::anyhow!("failed to unpack `{}`: {}", crate_file.display(), err)
+            })?;
+
+        let manifest_path = package_dir.join("Cargo.toml");
+        if !manifest_path.exists() {
+            return Err(anyhow::anyhow!(
+                "`{}` doesn't contain `{}/Cargo.toml`",
+                crate_file.display(),
+                stem.to_string_lossy()
+            ));
+        }
+        debug!("(packaged) manifest path: {:?}", manifest_path);
+
+        // the package isn't part of any workspace, so it's not looked up from its manifest, which
+        // would find the workspace it's unpacked in instead
+        let source_id = SourceId::for_path(&package_dir)?;
+        let (package, _) = cargo::ops::read_package(&manifest_path, source_id, config)?;
+        let workspace = Workspace::ephemeral(package.clone(), config, None, false)?;
+
+        Ok(Self { package, workspace })
+    }
+
+    /// Build the package in the given target directory instead of the workspace's default one.
+    pub fn set_target_dir(&mut self, target_dir: PathBuf) {
+        self.workspace.set_target_dir(Filesystem::new(target_dir));
+    }
+
+    /// Obtain the paths to the produced rlib and the dependency output directory.
+    pub fn rlib_and_dep_output(
+        &self,
+        config: &'a cargo::Config,
+        name: &str,
+        current: bool,
+        matches: &getopts::Matches,
+    ) -> Result<(PathBuf, Vec<PathBuf>)> {
+        // We don't need codegen-ready artifacts (which .rlib files are) so
+        // settle for .rmeta files, which result from `cargo check` mode
+        let mode = cargo::core::compiler::CompileMode::Check { test: false };
+        let mut opts = cargo::ops::CompileOptions::new(config, mode)?;
+        // we need the build plan to find our build artifacts
+        opts.build_config.build_plan = true;
+
+        let compile_kind = if let Some(target) = matches.opt_str("target") {
+            let target = cargo::core::compiler::CompileTarget::new(&target)?;
+
+            let kind = cargo::core::compiler::CompileKind::Target(target);
+            opts.build_config.requested_kinds = vec![kind];
+            kind
+        } else {
+            cargo::core::compiler::CompileKind::Host
+        };
+
+        if let Some(s) = matches.opt_str("features") {
+            opts.cli_features.features = Rc::new(
+                s.split(' ')
+                    .map(InternedString::new)
+                    .map(FeatureValue::new)
+                    .collect(),
+            );
+        }
+
+        opts.cli_features.all_features = matches.opt_present("all-features");
+        opts.cli_features.uses_default_features = !matches.opt_present("no-default-features");
+
+        let old_rustflags = env::var_os("RUSTFLAGS");
+        if let Some(flags) = rustflags(current, matches) {
+            // the flags set by the user apply to both versions alike
+            let flags = match old_rustflags {
+                Some(ref user) => format!("{} {}", user.to_string_lossy(), flags),
+                None => flags,
+            };
+            env::set_var("RUSTFLAGS", flags);
+        }
+
+        // Capture build plan from a separate Cargo invocation
+        let output = VecWrite(Arc::new(RwLock::new(Vec::new())));
+
+        let mut file_write = cargo::core::Shell::from_write(Box::new(output.clone()));
+        file_write.set_verbosity(cargo::core::Verbosity::Quiet);
+
+        let old_shell = std::mem::replace(&mut *config.shell(), file_write);
+
+        cargo::ops::compile(&self.workspace, &opts)?;
+
+        let _ = std::mem::replace(&mut *config.shell(), old_shell);
+        let plan_output = output.read()?;
+
+        // actually compile things now
+        opts.build_config.build_plan = false;
+
+        let compilation = cargo::ops::compile(&self.workspace, &opts)?;
+        match old_rustflags {
+            Some(flags) => env::set_var("RUSTFLAGS", flags),
+            None => env::remove_var("RUSTFLAGS"),
+        }
+
+        let build_plan: BuildPlan = serde_json::from_slice(&plan_output)
+            .map_err(|_| anyhow::anyhow!("Can't read build plan"))?;
+
+        debug!("{:?}", &build_plan.invocations);
+        let paths = build_plan.invocations.iter().find_map(|i| {
+            if let Some(kind) = i.target_kind.get(0) {
+                if kind.contains("lib") && i.package_name == name {
+                    let rlib_path = i.outputs[0].clone();
+                    let mut link_paths = vec![
+                        compilation.deps_output[&compile_kind].clone(),
+                        compilation.deps_output[&cargo::core::compiler::CompileKind::Host].clone(),
+                    ];
+                    // if host and `compile_kind` are the same, only return once
+                    link_paths.dedup();
+                    return Some((rlib_path, link_paths));
+                }
+            }
+
+            None
+        });
+
+        if let Some(paths) = paths {
+            Ok(paths)
+        } else {
+            Err(anyhow::Error::msg("lost build artifact".to_owned()))
+        }
+    }
+}
+
+/// Get the `RUSTFLAGS` to build a crate version with, or `None` to build it as usual.
+///
+/// The old version is built with crate metadata of its own, so that it can be told apart from
+/// the new one even if both are the same package. The new version only needs flags of its own if
+/// MIR has to be encoded for all functions, which is only done for generic and inlinable ones by
+/// default.
+fn rustflags(current: bool, matches: &getopts::Matches) -> Option<String> {
+    let encode_mir = matches.opt_present("mir-diff") || matches.opt_present("panic-scan");
+
+    match (current, encode_mir) {
+        (true, false) => None,
+        (true, true) => Some("-C metadata=new -Z always-encode-mir".to_owned()),
+        (false, false) => Some("-C metadata=old".to_owned()),
+        (false, true) => Some("-C metadata=old -Z always-encode-mir".to_owned()),
+    }
+}
+
+/// Given a `crate_name`, try to locate the corresponding crate on `crates.io`.
+///
+/// If no crate with the exact name is present, error out.
+pub fn find_on_crates_io(crate_name: &str) -> Result<crates_io::Crate> {
+    let mut handle = Easy::new();
+    handle.useragent(&format!("rust-semverver {}", env!("CARGO_PKG_VERSION")))?;
+    let mut registry =
+        crates_io::Registry::new_handle("https://crates.io".to_owned(), None, handle);
+
+    registry
+        .search(crate_name, 1)
+        .map_err(|e| {
+            anyhow::Error::msg(format!(
+                "failed to retrieve search results from the registry: {}",
+                e
+            ))
+        })
+        .and_then(|(mut crates, _)| {
+            crates
+                .drain(..)
+                .find(|krate| krate.name == crate_name)
+                .ok_or_else(|| {
+                    anyhow::Error::msg(format!("failed to find a matching crate `{}`", crate_name))
+                })
+        })
+}
+
+/// Given a `crate_name`, retrieve all of its versions published on `crates.io` that haven't been
+/// yanked.
+pub fn find_versions_on_crates_io(crate_name: &str) -> Result<Vec<semver::Version>> {
+    #[derive(Deserialize)]
+    struct Version {
+        num: String,
+        yanked: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct Response {
+        versions: Vec<Version>,
+    }
+
+    let mut handle = Easy::new();
+    handle.useragent(&format!("rust-semverver {}", env!("CARGO_PKG_VERSION")))?;
+    handle.url(&format!("https://crates.io/api/v1/crates/{}", crate_name))?;
+
+    let mut body = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform().map_err(|e| {
+            anyhow::Error::msg(format!(
+                "failed to retrieve versions from the registry: {}",
+                e
+            ))
+        })?;
+    }
+
+    let response: Response = serde_json::from_slice(&body)
+        .map_err(|_| anyhow::anyhow!("Can't read versions of `{}`", crate_name))?;
+
+    Ok(response
+        .versions
+        .iter()
+        .filter(|v| !v.yanked)
+        .filter_map(|v| semver::Version::parse(&v.num).ok())
+        .collect())
+}
+
+/// A reverse dependency of a crate, as listed on `crates.io`.
+pub struct ReverseDependency {
+    /// The name of the dependent crate.
+    pub name: String,
+    /// The most recent version of the dependent crate depending on our crate.
+    pub version: String,
+}
+
+/// Given a `crate_name`, retrieve it's `count` most downloaded reverse dependencies from
+/// `crates.io`.
+pub fn find_reverse_dependencies(crate_name: &str, count: usize) -> Result<Vec<ReverseDependency>> {
+    let mut handle = Easy::new();
+    handle.useragent(&format!("rust-semverver {}", env!("CARGO_PKG_VERSION")))?;
+    handle.url(&format!(
+        "https://crates.io/api/v1/crates/{}/reverse_dependencies?per_page={}",
+        crate_name, count
+    ))?;
+
+    let mut body = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform().map_err(|e| {
+            anyhow::Error::msg(format!(
+                "failed to retrieve reverse dependencies from the registry: {}",
+                e
+            ))
+        })?;
+    }
+
+    parse_reverse_dependencies(&body, crate_name, count)
+}
+
+/// Read the `count` most downloaded reverse dependencies of a crate from the response of the
+/// `crates.io` API, which lists the dependent versions separately from the dependencies.
+fn parse_reverse_dependencies(
+    body: &[u8],
+    crate_name: &str,
+    count: usize,
+) -> Result<Vec<ReverseDependency>> {
+    #[derive(Deserialize)]
+    struct Dependency {
+        version_id: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct Version {
+        id: u64,
+        #[serde(rename = "crate")]
+        krate: String,
+        num: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Response {
+        dependencies: Vec<Dependency>,
+        versions: Vec<Version>,
+    }
+
+    let response: Response = serde_json::from_slice(body)
+        .map_err(|_| anyhow::anyhow!("Can't read reverse dependencies of `{}`", crate_name))?;
+
+    // the dependencies are sorted by downloads, so we keep their order
+    Ok(response
+        .dependencies
+        .iter()
+        .filter_map(|dep| response.versions.iter().find(|v| v.id == dep.version_id))
+        .map(|v| ReverseDependency {
+            name: v.krate.clone(),
+            version: v.num.clone(),
+        })
+        .take(count)
+        .collect())
+}
+
+/// The outcome of checking a reverse dependency against the current version of the crate.
+#[derive(Debug, PartialEq, Eq)]
+enum RdepStatus {
+    /// The reverse dependency compiles against the current version.
+    Compiles,
+    /// The reverse dependency only compiles against the published version.
+    Broken,
+    /// The current version doesn't match the reverse dependency's requirement.
+    Unmatched,
+    /// The reverse dependency fails to compile against the published version as well.
+    Unbuildable,
+}
+
+impl RdepStatus {
+    /// Get the description of the outcome reported to the user.
+    fn desc(&self) -> &'static str {
+        match *self {
+            RdepStatus::Compiles => "ok",
+            RdepStatus::Broken => "broken",
+            RdepStatus::Unmatched => {
+                "skipped (current version doesn't match the dependency requirement)"
+            }
+            RdepStatus::Unbuildable => {
+                "skipped (fails to compile against the published version as well)"
+            }
+        }
+    }
+}
+
+/// Generate the manifest of the scratch package building a reverse dependency, optionally
+/// patching the crates.io version of the crate with the given name with the one in the given
+/// directory.
+fn rdep_manifest(rdep: &ReverseDependency, patch: Option<(&str, &Path)>) -> String {
+    let mut manifest = format!(
+        "[package]\n\
+         name = \"semverver-rdep-check\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"2018\"\n\
+         \n\
+         [lib]\n\
+         path = \"lib.rs\"\n\
+         \n\
+         [dependencies]\n\
+         {} = \"={}\"\n\
+         \n\
+         [workspace]\n",
+        rdep.name, rdep.version
+    );
+
+    if let Some((name, dir)) = patch {
+        manifest.push_str(&format!(
+            "\n[patch.crates-io]\n{} = {{ path = {:?} }}\n",
+            name,
+            dir.display().to_string()
+        ));
+    }
+
+    manifest
+}
+
+/// Check a reverse dependency, given a function building it with or without the current
+/// version patched in, which returns whether the build succeeded, along with cargo's errors.
+///
+/// Reverse dependencies failing to compile against the current version are built once more
+/// without the patch, to tell apart the ones that were broken to begin with.
+fn rdep_status(mut check: impl FnMut(bool) -> Result<(bool, String)>) -> Result<RdepStatus> {
+    let (success, stderr) = check(true)?;
+
+    Ok(if stderr.contains("was not used in the crate graph") {
+        RdepStatus::Unmatched
+    } else if success {
+        RdepStatus::Compiles
+    } else if check(false)?.0 {
+        RdepStatus::Broken
+    } else {
+        RdepStatus::Unbuildable
+    })
+}
+
+/// Build the `count` most downloaded reverse dependencies of the current crate against it, and
+/// report the ones that fail to compile.
+///
+/// Each reverse dependency is checked in a scratch package in the current crate's target
+/// directory, which depends on the reverse dependency and patches the crates.io version of the
+/// current crate with the local one.
+fn check_reverse_dependencies(
+    config: &cargo::Config,
+    current: &WorkInfo,
+    name: &str,
+    count: usize,
+) -> Result<()> {
+    let rdeps = find_reverse_dependencies(name, count)?;
+    let base_dir = current
+        .workspace
+        .target_dir()
+        .into_path_unlocked()
+        .join("semverver-rdeps");
+
+    let mut broken = 0;
+
+    for rdep in &rdeps {
+        let dir = base_dir.join(format!("{}-{}", rdep.name, rdep.version));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("lib.rs"), "")?;
+
+        let status = rdep_status(|patch| {
+            let patch = if patch {
+                Some((name, current.package.root()))
+            } else {
+                None
+            };
+            std::fs::write(dir.join("Cargo.toml"), rdep_manifest(rdep, patch))?;
+
+            let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+                .args(&["check", "--quiet", "--manifest-path"])
+                .arg(dir.join("Cargo.toml"))
+                .env_remove("RUSTFLAGS")
+                .output()
+                .map_err(|e| anyhow::Error::msg(format!("could not spawn cargo: {}", e)))?;
+
+            Ok((
+                output.status.success(),
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ))
+        })?;
+
+        if status == RdepStatus::Broken {
+            broken += 1;
+        }
+
+        config.shell().status(
+            "Checked",
+            format!("{} {}: {}", rdep.name, rdep.version, status.desc()),
+        )?;
+    }
+
+    if broken == 0 {
+        Ok(())
+    } else {
+        Err(anyhow::Error::msg(format!(
+            "{} of {} reverse dependencies fail to compile against the current version",
+            broken,
+            rdeps.len()
+        )))
+    }
+}
+
+/// A snippet of code from the stable version showing how to use the crate.
+struct UsageExample {
+    /// Where the snippet comes from, such as `doctest at src/lib.rs:12`.
+    origin: String,
+    /// The code of the snippet.
+    source: UsageSource,
+    /// The features of the crate the snippet needs.
+    required_features: Vec<String>,
+}
+
+/// The code of a usage example.
+enum UsageSource {
+    /// The source of a complete program, assembled from a doctest.
+    Program(String),
+    /// The main file of an example, which can include other files next to it.
+    File(PathBuf),
+}
+
+/// Extract the doctests from the sources of a package, as complete programs.
+///
+/// Only code blocks that rustdoc compiles are extracted, i.e. the ones without a language or
+/// marked as `rust`, `no_run` or `should_panic`. The programs are assembled the way rustdoc
+/// does, by revealing hidden lines, declaring the crate as an external one and wrapping the
+/// snippet in a `main` function if it lacks one.
+fn extract_doctests(package: &Package) -> Result<Vec<UsageExample>> {
+    fn source_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                source_files(&path, files)?;
+            } else if path.extension().map_or(false, |ext| ext == "rs") {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    let crate_name = package
+        .targets()
+        .iter()
+        .find(|t| t.is_lib())
+        .map_or_else(|| package.name().replace('-', "_"), |t| t.crate_name());
+    let root = package.root();
+    let mut files = Vec::new();
+    source_files(&root.join("src"), &mut files)?;
+    files.sort();
+
+    let mut doctests = Vec::new();
+
+    for file in files {
+        let contents = std::fs::read_to_string(&file)?;
+        let file = file
+            .strip_prefix(root)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        // the line the current code block starts on, whether it's compiled, and its lines
+        let mut block: Option<(usize, bool, Vec<&str>)> = None;
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim_start();
+            let doc = if let Some(doc) = line
+                .strip_prefix("///")
+                .or_else(|| line.strip_prefix("//!"))
+            {
+                doc.strip_prefix(' ').unwrap_or(doc)
+            } else {
+                block = None;
+                continue;
+            };
+
+            if let Some(info) = doc.trim().strip_prefix("```") {
+                match block.take() {
+                    Some((start, true, lines)) => doctests.push(UsageExample {
+                        origin: format!("doctest at {}:{}", file, start),
+                        source: UsageSource::Program(doctest_program(&lines, &crate_name)),
+                        required_features: Vec::new(),
+                    }),
+                    Some((_, false, _)) => (),
+                    None => block = Some((i + 1, is_compiled_doctest(info), Vec::new())),
+                }
+            } else if let Some((_, _, ref mut lines)) = block {
+                lines.push(doc);
+            }
+        }
+    }
+
+    Ok(doctests)
+}
+
+/// Check whether rustdoc compiles a code block with the given info string.
+fn is_compiled_doctest(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attr| !attr.is_empty())
+        .all(|attr| {
+            matches!(attr, "rust" | "no_run" | "should_panic") || attr.starts_with("edition")
+        })
+}
+
+/// Assemble a complete program from the lines of a doctest of the given crate, the way rustdoc
+/// does.
+///
+/// Like rustdoc, an `extern crate` item is added for snippets mentioning the crate without
+/// declaring it, which is needed to use the crate in the 2015 edition.
+fn doctest_program(lines: &[&str], crate_name: &str) -> String {
+    let lines: Vec<_> = lines
+        .iter()
+        .map(|&line| match line.trim_start().strip_prefix('#') {
+            // hidden lines are compiled, but `#[attr]` and `#!` lines aren't hidden lines
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => rest.trim_start(),
+            _ => line,
+        })
+        .collect();
+
+    let (crate_attrs, body): (Vec<_>, Vec<_>) =
+        lines.iter().partition(|line| line.starts_with("#!["));
+    let body = body.into_iter().copied().collect::<Vec<_>>().join("\n");
+    let mut crate_attrs = crate_attrs
+        .into_iter()
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let extern_crate = format!("extern crate {}", crate_name);
+    let declared = lines.iter().any(|line| {
+        line.trim_start()
+            .strip_prefix(&extern_crate)
+            .map_or(false, |rest| {
+                rest.starts_with(|c: char| c == ';' || c.is_whitespace())
+            })
+    });
+    if body.contains(crate_name) && !declared {
+        crate_attrs.push_str(&format!("\n{};", extern_crate));
+    }
+
+    if body.contains("fn main") {
+        format!("{}\n{}\n", crate_attrs, body)
+    } else if body.trim_end().ends_with("(())") {
+        // snippets ending in `Ok::<(), E>(())` use `?`, and are run in a fallible function
+        format!(
+            "{}\nfn main() {{\n    let _ = (|| {{\n{}\n    }})();\n}}\n",
+            crate_attrs, body
+        )
+    } else {
+        format!("{}\nfn main() {{\n{}\n}}\n", crate_attrs, body)
+    }
+}
+
+/// The parts of the output of `cargo metadata` describing a package.
+#[derive(Debug, Deserialize)]
+struct MetadataPackage {
+    name: String,
+    edition: String,
+    dependencies: Vec<MetadataDependency>,
+    targets: Vec<MetadataTarget>,
+}
+
+/// A dependency of a package, as described by `cargo metadata`.
+#[derive(Debug, Deserialize)]
+struct MetadataDependency {
+    name: String,
+    source: Option<String>,
+    req: String,
+    kind: Option<String>,
+    rename: Option<String>,
+    optional: bool,
+    uses_default_features: bool,
+    features: Vec<String>,
+    target: Option<String>,
+    path: Option<PathBuf>,
+}
+
+/// A target of a package, as described by `cargo metadata`.
+#[derive(Debug, Deserialize)]
+struct MetadataTarget {
+    kind: Vec<String>,
+    src_path: PathBuf,
+    #[serde(rename = "required-features", default)]
+    required_features: Vec<String>,
+}
+
+/// Describe a package using `cargo metadata`.
+///
+/// Unlike the package as loaded by cargo, the description contains the dependencies and targets
+/// as declared in the manifest, which is what the scratch package checking the usage examples
+/// has to replicate.
+fn package_metadata(package: &Package) -> Result<MetadataPackage> {
+    #[derive(Deserialize)]
+    struct Metadata {
+        packages: Vec<MetadataPackage>,
+    }
+
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+        .args(&[
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--manifest-path",
+        ])
+        .arg(package.manifest_path())
+        .output()
+        .map_err(|e| anyhow::Error::msg(format!("could not spawn cargo: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "`cargo metadata` failed for {}:\n{}",
+            package.manifest_path().display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let metadata: Metadata = serde_json::from_slice(&output.stdout)?;
+    metadata
+        .packages
+        .into_iter()
+        .find(|p| p.name == package.name().as_str())
+        .ok_or_else(|| anyhow::anyhow!("`cargo metadata` didn't describe `{}`", package.name()))
+}
+
+/// Collect the examples of a package, as declared in its manifest or found in `examples/`.
+fn extract_examples(package: &Package, metadata: &MetadataPackage) -> Vec<UsageExample> {
+    let root = package.root();
+
+    metadata
+        .targets
+        .iter()
+        .filter(|target| target.kind == ["example"])
+        .map(|target| UsageExample {
+            origin: format!(
+                "example {}",
+                target
+                    .src_path
+                    .strip_prefix(root)
+                    .unwrap_or(&target.src_path)
+                    .display()
+            ),
+            source: UsageSource::File(target.src_path.clone()),
+            required_features: target.required_features.clone(),
+        })
+        .collect()
+}
+
+/// Render a dependency of the stable version as an entry of the scratch package's manifest.
+///
+/// Dependencies from registries, git repositories and paths are declared the same way as in the
+/// stable version, along with the features they enable.
+fn dependency_entry(dep: &MetadataDependency) -> Option<String> {
+    let source = dep.source.as_deref();
+    let mut fields = vec![format!("package = {:?}", dep.name)];
+
+    if let Some(index) = source.and_then(|s| s.strip_prefix("registry+")) {
+        fields.push(format!("version = {:?}", dep.req));
+
+        if index != cargo::sources::CRATES_IO_INDEX {
+            fields.push(format!("registry-index = {:?}", index));
+        }
+    } else if let Some(git) = source.and_then(|s| s.strip_prefix("git+")) {
+        // the revision a lockfile pins the repository to comes after the `#`
+        let git = git.split('#').next().unwrap_or(git);
+        let (url, reference) = git.split_once('?').unwrap_or((git, ""));
+        fields.push(format!("git = {:?}", url));
+
+        if let Some((kind @ ("branch" | "tag" | "rev"), name)) = reference.split_once('=') {
+            fields.push(format!("{} = {:?}", kind, name));
+        }
+    } else if let Some(ref path) = dep.path {
+        fields.push(format!("path = {:?}", path.display().to_string()));
+    } else {
+        return None;
+    }
+
+    if !dep.features.is_empty() {
+        fields.push(format!("features = {:?}", dep.features));
+    }
+
+    if !dep.uses_default_features {
+        fields.push("default-features = false".to_owned());
+    }
+
+    Some(format!(
+        "{} = {{ {} }}\n",
+        dep.rename.as_ref().unwrap_or(&dep.name),
+        fields.join(", ")
+    ))
+}
+
+/// Generate the manifest of the scratch package compiling the usage examples at the given paths
+/// against the crate in the given directory.
+///
+/// The examples of a package can use its normal and dev-dependencies, so the ones of the stable
+/// version are declared in the scratch package as well, with the same features. The features a
+/// dev-dependency on the crate itself enables are enabled on the crate.
+fn usage_manifest(crate_dir: &Path, stable: &MetadataPackage, examples: &[PathBuf]) -> String {
+    let mut tables: BTreeMap<String, String> = BTreeMap::new();
+    let mut crate_features = Vec::new();
+
+    for dep in &stable.dependencies {
+        let table = match dep.kind.as_deref() {
+            None if !dep.optional => "dependencies",
+            Some("dev") => "dev-dependencies",
+            _ => continue,
+        };
+
+        if dep.name == stable.name {
+            crate_features.extend(dep.features.iter().cloned());
+            continue;
+        }
+
+        if let Some(entry) = dependency_entry(dep) {
+            let header = match dep.target {
+                Some(ref target) => format!("target.{:?}.{}", target, table),
+                None => table.to_owned(),
+            };
+
+            tables.entry(header).or_default().push_str(&entry);
+        }
+    }
+
+    crate_features.sort();
+    crate_features.dedup();
+
+    let features = if crate_features.is_empty() {
+        String::new()
+    } else {
+        format!(", features = {:?}", crate_features)
+    };
+    let crate_entry = format!(
+        "{} = {{ path = {:?}{} }}\n",
+        stable.name,
+        crate_dir.display().to_string(),
+        features
+    );
+    tables
+        .entry("dependencies".to_owned())
+        .or_default()
+        .insert_str(0, &crate_entry);
+
+    let mut manifest = format!(
+        "[package]\n\
+         name = \"semverver-usage-check\"\n\
+         version = \"0.0.0\"\n\
+         edition = {:?}\n\
+         autoexamples = false\n\
+         \n\
+         [lib]\n\
+         path = \"lib.rs\"\n",
+        stable.edition
+    );
+
+    for (i, path) in examples.iter().enumerate() {
+        manifest.push_str(&format!(
+            "\n[[example]]\nname = \"usage{}\"\npath = {:?}\n",
+            i,
+            path.display().to_string()
+        ));
+    }
+
+    for (header, entries) in tables {
+        manifest.push_str(&format!("\n[{}]\n{}", header, entries));
+    }
+
+    manifest.push_str("\n[workspace]\n");
+    manifest
+}
+
+/// Compile usage examples from the stable version against the current version, and report the
+/// ones that fail to compile.
+///
+/// The examples are compiled in a scratch package in the current crate's target directory,
+/// which depends on the current crate, as well as on the dependencies of the stable version,
+/// as described by `cargo metadata`. Examples failing to compile are checked once more against
+/// the stable version, to tell apart the ones that were broken to begin with, or can't be
+/// built by the scratch package, which are reported as errors. Returns the number of examples
+/// that compile against the stable version only.
+fn check_usage_examples(
+    config: &cargo::Config,
+    current: &WorkInfo,
+    stable: &WorkInfo,
+    metadata: &MetadataPackage,
+    kind: &str,
+    examples: &[UsageExample],
+) -> Result<usize> {
+    let dir = current
+        .workspace
+        .target_dir()
+        .into_path_unlocked()
+        .join("semverver-usage")
+        .join(kind);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("lib.rs"), "")?;
+
+    if examples
+        .iter()
+        .any(|example| matches!(example.source, UsageSource::Program(_)))
+    {
+        std::fs::create_dir_all(dir.join("doctests"))?;
+    }
+
+    let mut paths = Vec::new();
+    for (i, example) in examples.iter().enumerate() {
+        match example.source {
+            UsageSource::Program(ref source) => {
+                let path = dir.join(format!("doctests/usage{}.rs", i));
+                std::fs::write(&path, source)?;
+                paths.push(path);
+            }
+            UsageSource::File(ref path) => paths.push(path.clone()),
+        }
+    }
+
+    let write_manifest = |package: &Package| -> Result<()> {
+        let manifest = usage_manifest(package.root(), metadata, &paths);
+        std::fs::write(dir.join("Cargo.toml"), manifest)?;
+        Ok(())
+    };
+
+    // returns the errors of the compiler if the example fails to compile
+    let check = |i: usize| -> Result<Option<String>> {
+        let mut cmd = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()));
+        cmd.args(&["check", "--quiet", "--manifest-path"])
+            .arg(dir.join("Cargo.toml"))
+            .args(&["--example", &format!("usage{}", i)])
+            .env_remove("RUSTFLAGS");
+
+        // features of the crate's dependencies can't be enabled from outside of it
+        let features: Vec<_> = examples[i]
+            .required_features
+            .iter()
+            .filter(|feature| !feature.contains('/') && !feature.starts_with("dep:"))
+            .map(|feature| format!("{}/{}", metadata.name, feature))
+            .collect();
+        if !features.is_empty() {
+            cmd.args(&["--features", &features.join(",")]);
+        }
+
+        let output = cmd
+            .output()
+            .map_err(|e| anyhow::Error::msg(format!("could not spawn cargo: {}", e)))?;
+
+        if output.status.success() {
+            Ok(None)
+        } else {
+            Ok(Some(String::from_utf8_lossy(&output.stderr).into_owned()))
+        }
+    };
+
+    write_manifest(&current.package)?;
+    let mut failed = Vec::new();
+    for i in 0..examples.len() {
+        if check(i)?.is_some() {
+            failed.push(i);
+        }
+    }
+
+    write_manifest(&stable.package)?;
+    let mut broken = 0;
+    let mut unbuildable = 0;
+    for i in failed {
+        if let Some(errors) = check(i)? {
+            unbuildable += 1;
+            config.shell().error(format!(
+                "{} fails to compile against the stable version as well:\n{}",
+                examples[i].origin,
+                errors.trim_end()
+            ))?;
+        } else {
+            broken += 1;
+            config.shell().warn(format!(
+                "{} of the stable version no longer compiles",
+                examples[i].origin
+            ))?;
+        }
+    }
+
+    config.shell().status(
+        "Checked",
+        format!(
+            "{} {} of the stable version, {} broken",
+            examples.len(),
+            kind,
+            broken
+        ),
+    )?;
+
+    if unbuildable > 0 {
+        return Err(anyhow::anyhow!(
+            "{} {} of the stable version could not be built, so they were not checked",
+            unbuildable,
+            kind
+        ));
+    }
+
+    Ok(broken)
+}
+
+/// Thread-safe byte buffer that implements `io::Write`.
+#[derive(Clone)]
+struct VecWrite(Arc<RwLock<Vec<u8>>>);
+
+impl VecWrite {
+    pub fn read(&self) -> io::Result<std::sync::RwLockReadGuard<'_, Vec<u8>>> {
+        self.0
+            .read()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "lock poison"))
+    }
+    pub fn write(&self) -> io::Result<std::sync::RwLockWriteGuard<'_, Vec<u8>>> {
+        self.0
+            .write()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "lock poison"))
+    }
+}
+
+impl io::Write for VecWrite {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut lock = Self::write(self)?;
+        io::Write::write(&mut *lock, data)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare the manifests of the old and new version of a package in `tests/manifests`.
+    fn diff_manifests(name: &str) -> Vec<ManifestChange> {
+        let config = cargo::Config::default().unwrap();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/manifests")
+            .join(name);
+        let read = |version| {
+            let package_dir = dir.join(version);
+            let source_id = SourceId::for_path(&package_dir).unwrap();
+            let manifest_path = package_dir.join("Cargo.toml");
+
+            cargo::ops::read_package(&manifest_path, source_id, &config)
+                .unwrap()
+                .0
+        };
+
+        manifest_changes(&read("old"), &read("new"))
+    }
+
+    /// Read a rustdoc JSON document in `tests/rustdoc`.
+    fn read_doc(name: &str) -> DocApi {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/rustdoc")
+            .join(name);
+
+        doc_api(&path).unwrap()
+    }
+
+    #[test]
+    fn api_listings_diff() {
+        let read = |name| {
+            std::fs::read_to_string(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/api_diff")
+                    .join(name),
+            )
+            .unwrap()
+        };
+        let (old, new) = (read("old.txt"), read("new.txt"));
+
+        assert!(diff_api(&old, &old).is_empty());
+        assert_eq!(
+            diff_api(&old, &new),
+            vec![
+                ('+', "fn Abc::new: fn() -> Abc"),
+                ('+', "fn abc: fn(u16) -> u16"),
+                ('-', "fn abc: fn(u8) -> u16"),
+                ('-', "fn removed: fn()"),
+                ('+', "struct Bcd"),
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_api_diff() {
+        let old = read_doc("old.json");
+        let new = read_doc("new.json");
+
+        assert_eq!(old.label, "doc 1.0.0");
+        assert_eq!(new.label, "doc 1.1.0");
+        assert_eq!(
+            diff_doc_api(&old, &new),
+            vec![
+                ('~', "function doc::abc"),
+                ('+', "function doc::added"),
+                ('-', "function doc::removed"),
+            ]
+        );
+        assert!(run_diff_doc(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/rustdoc/old.json"),
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/rustdoc/new.json"),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn doc_api_members() {
+        let old = read_doc("old.json");
+        let mut new = read_doc("old.json");
+        assert!(diff_doc_api(&old, &new).is_empty());
+
+        // the type of a field changed
+        let abc = new.items.get_mut("struct doc::Abc").unwrap();
+        abc["inner"]["fields"][0]["inner"]["inner"] = "u16".into();
+
+        assert_eq!(diff_doc_api(&old, &new), vec![('~', "struct doc::Abc")]);
+    }
+
+    #[test]
+    fn doc_api_additions() {
+        let old = read_doc("old.json");
+        let mut new = read_doc("old.json");
+        new.items
+            .insert("function doc::added".to_owned(), Default::default());
+
+        assert_eq!(diff_doc_api(&old, &new), vec![('+', "function doc::added")]);
+
+        let doc = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/rustdoc/old.json");
+        assert!(run_diff_doc(&doc, &doc).is_ok());
+    }
+
+    /// Translate a configuration file to command line arguments, given some actual ones.
+    fn config_args(contents: &str, args: &[&str]) -> anyhow::Result<Vec<String>> {
+        let project_config: project_config::ProjectConfig = toml_edit::easy::from_str(contents)?;
+        let matches = cli::options().parse(args)?;
+
+        project_config.to_args(&matches, Path::new("crate"), Some("abc"), "semver.toml")
+    }
+
+    #[test]
+    fn versions_sorted() {
+        let mut versions = vec!["0.10.0", "0.9.0", "1.10.0", "0.10.0", "1.9.0", "unknown"];
+        sort_versions(&mut versions);
+
+        assert_eq!(versions, ["unknown", "0.9.0", "0.10.0", "1.9.0", "1.10.0"]);
+    }
+
+    #[test]
+    fn project_config_args() {
+        let contents = r#"
+            baseline = "1.2.0"
+            features = ["serde", "std"]
+            exclude = ["abc::internal::**"]
+            output = "json"
+            strict = true
+            renames = "renames.txt"
+
+            [severity]
+            technically-breaking = "deny"
+        "#;
+
+        assert_eq!(
+            config_args(contents, &[]).unwrap(),
+            vec![
+                "--stable-pkg",
+                "abc:1.2.0",
+                "--features",
+                "serde std",
+                "--exclude",
+                "abc::internal::**",
+                "--deny",
+                "technically-breaking",
+                "--json",
+                "--strict",
+                "--renames",
+                Path::new("crate").join("renames.txt").to_str().unwrap(),
+            ]
+        );
+
+        // settings given on the command line take precedence, except for combined ones
+        assert_eq!(
+            config_args(
+                contents,
+                &[
+                    "-S",
+                    "abc:1.0.0",
+                    "--features",
+                    "std",
+                    "--compact",
+                    "--strict"
+                ]
+            )
+            .unwrap(),
+            vec![
+                "--exclude",
+                "abc::internal::**",
+                "--deny",
+                "technically-breaking",
+                "--renames",
+                Path::new("crate").join("renames.txt").to_str().unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn project_config_baseline() {
+        assert_eq!(
+            config_args(r#"baseline = "xyz:0.3.0""#, &[]).unwrap(),
+            vec!["--stable-pkg", "xyz:0.3.0"]
+        );
+        assert_eq!(
+            config_args(r#"baseline-path = "../old""#, &[]).unwrap(),
+            vec![
+                "--stable-path",
+                Path::new("crate").join("../old").to_str().unwrap()
+            ]
+        );
+        assert!(config_args("baseline = \"1.0.0\"\nbaseline-path = \"../old\"", &[]).is_err());
+    }
+
+    #[test]
+    fn project_config_invalid() {
+        assert!(config_args("unknown = true", &[]).is_err());
+        assert!(config_args(r#"output = "xml""#, &[]).is_err());
+        assert!(config_args("[severity]\nbreaking = \"ignore\"", &[]).is_err());
+    }
+
+    #[test]
+    fn renames_read() {
+        let path = env::temp_dir().join("semverver-read-renames-test");
+
+        std::fs::write(
+            &path,
+            "# moved to the prelude\nold::a = new::prelude::a\n\n  old::b=new::c  \n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_renames(&path).unwrap(),
+            ["old::a=new::prelude::a", "old::b=new::c"]
+        );
+
+        for malformed in [
+            "old::a",
+            "old::a = ",
+            "old::a = new::b = new::c",
+            "old::a = new::b,c",
+        ] {
+            std::fs::write(&path, format!("old::x = new::y\n{}\n", malformed)).unwrap();
+            let err = read_renames(&path).unwrap_err().to_string();
+            assert!(err.ends_with(":2: expected a rename of the form `old::path = new::path`"));
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(read_renames(&path).is_err());
+    }
+
+    #[test]
+    fn rename_candidates_grouped() {
+        let candidates = "old::a = new::b\nold::a = new::c\nold::d = new::e\nmalformed\n";
+        let recorded = "old::x = new::y\n# not renamed: old::a = new::c\n";
+
+        let grouped: Vec<_> = rename_candidates(candidates, recorded)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            grouped,
+            [("old::a", vec!["new::b"]), ("old::d", vec!["new::e"])]
+        );
+    }
+
+    #[test]
+    fn renames_asked_and_recorded() {
+        let path = env::temp_dir().join("semverver-renames-test");
+        std::fs::write(&path, "old::x = new::y").unwrap();
+
+        // `new::c` can't be the new version of `old::d` once `old::a` has been renamed to it
+        let candidates = "old::a = new::b\nold::a = new::c\nold::d = new::c\nold::f = new::g\n";
+        let confirmed = ask_renames(candidates, &path, "3\n2\n\n".as_bytes()).unwrap();
+        assert_eq!(confirmed, 1);
+
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            recorded,
+            "old::x = new::y\nold::a = new::c\n# not renamed: old::f = new::g\n"
+        );
+        assert_eq!(
+            read_renames(&path).unwrap(),
+            ["old::x=new::y", "old::a=new::c"]
+        );
+
+        // rejected candidates aren't asked about again, and nothing is recorded without answers
+        let candidates = "old::f = new::g\nold::h = new::i\n";
+        assert_eq!(
+            rename_candidates(candidates, &recorded)
+                .into_keys()
+                .collect::<Vec<_>>(),
+            ["old::h"]
+        );
+        assert_eq!(ask_renames(candidates, &path, "".as_bytes()).unwrap(), 0);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), recorded);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn edition_undeclared() {
+        assert_eq!(
+            diff_manifests("edition_undeclared"),
+            vec![ManifestChange::EditionChanged {
+                old: "2015".to_owned(),
+                new: "2018".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn manifest_unchanged() {
+        assert_eq!(diff_manifests("unchanged"), vec![]);
+    }
+
+    #[test]
+    fn manifest_changed() {
+        assert_eq!(
+            diff_manifests("changed"),
+            vec![
+                ManifestChange::FeatureRemoved {
+                    name: "serde".to_owned(),
+                    renamed_to: Some("serde1".to_owned()),
+                },
+                ManifestChange::OptionalDepFeatureRemoved {
+                    name: "log".to_owned(),
+                    namespaced: false,
+                },
+                ManifestChange::OptionalDepFeatureRemoved {
+                    name: "rand".to_owned(),
+                    namespaced: true,
+                },
+                ManifestChange::DefaultFeatureRemoved {
+                    name: "std".to_owned(),
+                },
+                ManifestChange::DefaultFeatureAdded {
+                    name: "alloc".to_owned(),
+                },
+                ManifestChange::RustVersionChanged {
+                    old: Some("1.56".to_owned()),
+                    new: Some("1.60".to_owned()),
+                },
+                ManifestChange::EditionChanged {
+                    old: "2018".to_owned(),
+                    new: "2021".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn compiled_doctests() {
+        assert!(is_compiled_doctest(""));
+        assert!(is_compiled_doctest("rust"));
+        assert!(is_compiled_doctest("no_run"));
+        assert!(is_compiled_doctest("rust, should_panic"));
+        assert!(is_compiled_doctest("should_panic,edition2018"));
+        assert!(!is_compiled_doctest("ignore"));
+        assert!(!is_compiled_doctest("text"));
+        assert!(!is_compiled_doctest("rust,compile_fail"));
+    }
+
+    #[test]
+    fn doctest_wrapped_in_main() {
+        assert_eq!(
+            doctest_program(
+                &["let x = 1;", "# let y = 2;", "#", "assert_eq!(x, 1);"],
+                "abc"
+            ),
+            "\nfn main() {\nlet x = 1;\nlet y = 2;\n\nassert_eq!(x, 1);\n}\n"
+        );
+    }
+
+    #[test]
+    fn doctest_attributes() {
+        // outer attributes aren't hidden lines, and crate attributes are moved out of `main`
+        assert_eq!(
+            doctest_program(
+                &["#![allow(unused)]", "#[derive(Debug)]", "struct Abc;"],
+                "abc"
+            ),
+            "#![allow(unused)]\nfn main() {\n#[derive(Debug)]\nstruct Abc;\n}\n"
+        );
+    }
+
+    #[test]
+    fn doctest_with_main() {
+        assert_eq!(
+            doctest_program(&["# #![allow(unused)]", "fn main() {", "}"], "abc"),
+            "#![allow(unused)]\nfn main() {\n}\n"
+        );
+    }
+
+    #[test]
+    fn doctest_extern_crate() {
+        assert_eq!(
+            doctest_program(&["use abc::Abc;", "Abc::new();"], "abc"),
+            "\nextern crate abc;\nfn main() {\nuse abc::Abc;\nAbc::new();\n}\n"
+        );
+        assert_eq!(
+            doctest_program(&["#![no_std]", "use abc::Abc;"], "abc"),
+            "#![no_std]\nextern crate abc;\nfn main() {\nuse abc::Abc;\n}\n"
+        );
+
+        // crates declared by the snippet itself are left alone
+        assert_eq!(
+            doctest_program(&["# extern crate abc;", "fn main() {", "}"], "abc"),
+            "\nextern crate abc;\nfn main() {\n}\n"
+        );
+    }
+
+    #[test]
+    fn doctest_fallible() {
+        assert_eq!(
+            doctest_program(
+                &[
+                    "let x: u8 = \"1\".parse()?;",
+                    "Ok::<(), std::num::ParseIntError>(())"
+                ],
+                "abc"
+            ),
+            "\nfn main() {\n    let _ = (|| {\nlet x: u8 = \"1\".parse()?;\n\
+             Ok::<(), std::num::ParseIntError>(())\n    })();\n}\n"
+        );
+    }
+
+    #[test]
+    fn usage_manifest_dependencies() {
+        let metadata: MetadataPackage = serde_json::from_str(
+            r#"{
+                "name": "abc",
+                "edition": "2018",
+                "dependencies": [
+                    {
+                        "name": "serde",
+                        "source": "registry+https://github.com/rust-lang/crates.io-index",
+                        "req": "^1.0",
+                        "kind": null,
+                        "rename": null,
+                        "optional": false,
+                        "uses_default_features": true,
+                        "features": ["derive"],
+                        "target": null
+                    },
+                    {
+                        "name": "rand",
+                        "source": "registry+https://github.com/rust-lang/crates.io-index",
+                        "req": "^0.8",
+                        "kind": null,
+                        "rename": null,
+                        "optional": true,
+                        "uses_default_features": true,
+                        "features": [],
+                        "target": null
+                    },
+                    {
+                        "name": "cc",
+                        "source": "registry+https://github.com/rust-lang/crates.io-index",
+                        "req": "^1",
+                        "kind": "build",
+                        "rename": null,
+                        "optional": false,
+                        "uses_default_features": true,
+                        "features": [],
+                        "target": null
+                    },
+                    {
+                        "name": "abc",
+                        "source": null,
+                        "req": "*",
+                        "kind": "dev",
+                        "rename": null,
+                        "optional": false,
+                        "uses_default_features": true,
+                        "features": ["full"],
+                        "target": null,
+                        "path": "/abc"
+                    },
+                    {
+                        "name": "tokio",
+                        "source": "registry+https://example.com/index",
+                        "req": "^1",
+                        "kind": "dev",
+                        "rename": "rt",
+                        "optional": false,
+                        "uses_default_features": false,
+                        "features": ["macros", "rt"],
+                        "target": "cfg(unix)"
+                    }
+                ],
+                "targets": []
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            usage_manifest(
+                Path::new("/abc"),
+                &metadata,
+                &[PathBuf::from("/abc/examples/def.rs")]
+            ),
+            r#"[package]
+name = "semverver-usage-check"
+version = "0.0.0"
+edition = "2018"
+autoexamples = false
+
+[lib]
+path = "lib.rs"
+
+[[example]]
+name = "usage0"
+path = "/abc/examples/def.rs"
+
+[dependencies]
+abc = { path = "/abc", features = ["full"] }
+serde = { package = "serde", version = "^1.0", features = ["derive"] }
+
+[target."cfg(unix)".dev-dependencies]
+rt = { package = "tokio", version = "^1", registry-index = "https://example.com/index", features = ["macros", "rt"], default-features = false }
+
+[workspace]
+"#
+        );
+    }
+
+    #[test]
+    fn dependency_entries() {
+        let deps: Vec<MetadataDependency> = serde_json::from_str(
+            r#"[
+                {
+                    "name": "abc",
+                    "source": "git+https://example.com/abc.git?branch=dev",
+                    "req": "*",
+                    "kind": "dev",
+                    "rename": null,
+                    "optional": false,
+                    "uses_default_features": true,
+                    "features": ["std"],
+                    "target": null
+                },
+                {
+                    "name": "def",
+                    "source": "git+https://example.com/def.git",
+                    "req": "^1",
+                    "kind": "dev",
+                    "rename": "def1",
+                    "optional": false,
+                    "uses_default_features": false,
+                    "features": [],
+                    "target": null
+                },
+                {
+                    "name": "ghi",
+                    "source": null,
+                    "req": "*",
+                    "kind": "dev",
+                    "rename": null,
+                    "optional": false,
+                    "uses_default_features": true,
+                    "features": ["extra"],
+                    "target": null,
+                    "path": "/ghi"
+                }
+            ]"#,
+        )
+        .unwrap();
+        let entries: Vec<_> = deps.iter().filter_map(dependency_entry).collect();
+
+        assert_eq!(
+            entries,
+            [
+                "abc = { package = \"abc\", git = \"https://example.com/abc.git\", \
+                 branch = \"dev\", features = [\"std\"] }\n",
+                "def1 = { package = \"def\", git = \"https://example.com/def.git\", \
+                 default-features = false }\n",
+                "ghi = { package = \"ghi\", path = \"/ghi\", features = [\"extra\"] }\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn usage_example_with_dev_dependency() {
+        let config = cargo::Config::default().unwrap();
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/usage/crate");
+        let source_id = SourceId::for_path(&crate_dir).unwrap();
+        let (package, _) =
+            cargo::ops::read_package(&crate_dir.join("Cargo.toml"), source_id, &config).unwrap();
+
+        let metadata = package_metadata(&package).unwrap();
+        let paths: Vec<_> = extract_examples(&package, &metadata)
+            .into_iter()
+            .map(|example| match example.source {
+                UsageSource::File(path) => path,
+                UsageSource::Program(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(paths, [crate_dir.join("examples/helper.rs")]);
+
+        // the example only compiles with the dev-dependency and its feature enabled
+        let dir = env::temp_dir().join("semverver-usage-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("lib.rs"), "").unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            usage_manifest(package.root(), &metadata, &paths),
+        )
+        .unwrap();
+
+        let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned()))
+            .args(&["check", "--quiet", "--offline", "--example", "usage0"])
+            .arg("--manifest-path")
+            .arg(dir.join("Cargo.toml"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn reverse_dependencies_parsed() {
+        let body = br#"{
+            "dependencies": [
+                {"version_id": 3, "downloads": 300},
+                {"version_id": 1, "downloads": 200},
+                {"version_id": 2, "downloads": 100}
+            ],
+            "versions": [
+                {"id": 1, "crate": "def", "num": "0.3.1"},
+                {"id": 2, "crate": "ghi", "num": "1.0.0"},
+                {"id": 3, "crate": "jkl", "num": "2.1.0"}
+            ]
+        }"#;
+
+        let rdeps: Vec<_> = parse_reverse_dependencies(body, "abc", 2)
+            .unwrap()
+            .into_iter()
+            .map(|rdep| (rdep.name, rdep.version))
+            .collect();
+        assert_eq!(
+            rdeps,
+            [
+                ("jkl".to_owned(), "2.1.0".to_owned()),
+                ("def".to_owned(), "0.3.1".to_owned())
+            ]
+        );
+
+        assert!(parse_reverse_dependencies(b"{}", "abc", 2).is_err());
+    }
+
+    #[test]
+    fn rdep_manifest_patched() {
+        let rdep = ReverseDependency {
+            name: "def".to_owned(),
+            version: "0.3.1".to_owned(),
+        };
+        let unpatched = r#"[package]
+name = "semverver-rdep-check"
+version = "0.0.0"
+edition = "2018"
+
+[lib]
+path = "lib.rs"
+
+[dependencies]
+def = "=0.3.1"
+
+[workspace]
+"#;
+
+        assert_eq!(rdep_manifest(&rdep, None), unpatched);
+        assert_eq!(
+            rdep_manifest(&rdep, Some(("abc", Path::new("/abc")))),
+            format!(
+                "{}\n[patch.crates-io]\nabc = {{ path = \"/abc\" }}\n",
+                unpatched
+            )
+        );
+    }
+
+    #[test]
+    fn rdep_rechecked_unpatched() {
+        // the outcome of building the reverse dependency with and without the patch
+        let status = |patched: (bool, &str), unpatched: bool| {
+            let mut builds = Vec::new();
+            let status = rdep_status(|patch| {
+                builds.push(patch);
+                Ok(if patch {
+                    (patched.0, patched.1.to_owned())
+                } else {
+                    (unpatched, String::new())
+                })
+            })
+            .unwrap();
+
+            (status, builds)
+        };
+
+        assert_eq!(
+            status((true, ""), false),
+            (RdepStatus::Compiles, vec![true])
+        );
+        assert_eq!(
+            status((false, "error[E0308]: mismatched types"), true),
+            (RdepStatus::Broken, vec![true, false])
+        );
+        assert_eq!(
+            status((false, "error[E0308]: mismatched types"), false),
+            (RdepStatus::Unbuildable, vec![true, false])
+        );
+        assert_eq!(
+            status(
+                (
+                    true,
+                    "warning: Patch `abc v2.0.0` was not used in the crate graph."
+                ),
+                true
+            ),
+            (RdepStatus::Unmatched, vec![true])
+        );
     }
 }
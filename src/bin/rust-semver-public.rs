@@ -8,7 +8,7 @@ use log::debug;
 use rustc_driver::{Callbacks, Compilation, RunCompiler};
 use rustc_interface::{interface, Queries};
 use rustc_span::source_map::Pos;
-use semverver::run_traversal;
+use semverver::{dump_api, run_traversal};
 use std::{
     path::Path,
     process::{exit, Command},
@@ -52,8 +52,15 @@ fn main() {
                             .next();
 
                         if let Some(krate_def_id) = krate {
-                            debug!("running semver analysis");
-                            run_traversal(tcx, krate_def_id);
+                            if env::var("RUST_SEMVER_DUMP_API") == Ok("true".to_string()) {
+                                debug!("dumping public api");
+                                for line in dump_api(tcx, krate_def_id) {
+                                    println!("{}", line);
+                                }
+                            } else {
+                                debug!("running semver analysis");
+                                run_traversal(tcx, krate_def_id);
+                            }
                         } else {
                             tcx.sess.err("could not find `new` crate");
                         }
@@ -4,11 +4,11 @@ extern crate rustc_driver;
 extern crate rustc_interface;
 extern crate rustc_span;
 
-use log::debug;
+use log::{debug, LevelFilter, Log, Metadata, Record};
 use rustc_driver::{Callbacks, Compilation, RunCompiler};
 use rustc_interface::{interface, Queries};
 use rustc_span::source_map::Pos;
-use semverver::run_analysis;
+use semverver::{budget, run_analysis, timings, trace, Config};
 use std::{
     path::Path,
     process::{exit, Command},
@@ -19,129 +19,226 @@ fn show_version() {
     println!(env!("CARGO_PKG_VERSION"));
 }
 
+/// A logger emitting all log records of the analysis while an item is traced, and deferring to
+/// the logger configured using `RUSTC_LOG` otherwise.
+struct TraceLogger(env_logger::Logger);
+
+impl TraceLogger {
+    /// Check whether a log record is emitted because of an item being traced.
+    fn traced(metadata: &Metadata) -> bool {
+        trace::is_active() && metadata.target().starts_with("semverver")
+    }
+}
+
+impl Log for TraceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        Self::traced(metadata) || self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if Self::traced(record.metadata()) {
+            eprintln!("trace: {}: {}", record.target(), record.args());
+        } else {
+            self.0.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
 /// Main routine.
 ///
 /// Find the sysroot before passing our args to the custom compiler driver we register.
 fn main() {
-    env_logger::init_from_env("RUSTC_LOG");
+    let logger = env_logger::Builder::from_env("RUSTC_LOG").build();
+    if std::env::var_os("RUST_SEMVER_TRACE_ITEM").is_some() {
+        log::set_max_level(LevelFilter::Trace);
+    } else {
+        log::set_max_level(logger.filter());
+    }
+    log::set_boxed_logger(Box::new(TraceLogger(logger))).expect("logger already set");
 
     debug!("running rust-semverver compiler driver");
-    exit(
-        {
-            use std::env;
-
-            struct SemverCallbacks;
-
-            impl Callbacks for SemverCallbacks {
-                fn after_analysis<'tcx>(&mut self, _compiler: &interface::Compiler, queries: &'tcx Queries<'tcx>) -> Compilation {
-                    debug!("running rust-semverver after_analysis callback");
-
-                    let verbose =
-                        env::var("RUST_SEMVER_VERBOSE") == Ok("true".to_string());
-                    let compact =
-                        env::var("RUST_SEMVER_COMPACT") == Ok("true".to_string());
-                    let json =
-                        env::var("RUST_SEMVER_JSON") == Ok("true".to_string());
-                    let api_guidelines =
-                        env::var("RUST_SEMVER_API_GUIDELINES") == Ok("true".to_string());
-                    let version = if let Ok(ver) = env::var("RUST_SEMVER_CRATE_VERSION") {
-                        ver
-                    } else {
-                        "no_version".to_owned()
-                    };
+    exit({
+        use std::env;
 
-                    queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
-                        // To select the old and new crates we look at the position of the
-                        // declaration in the source file. The first one will be the `old`
-                        // and the other will be `new`. This is unfortunately a bit hacky...
-                        // See issue #64 for details.
-
-                        let mut crates: Vec<_> = tcx
-                            .crates(())
-                            .iter()
-                            .flat_map(|crate_num| {
-                                let def_id = crate_num.as_def_id();
-
-                                match tcx.extern_crate(def_id) {
-                                    Some(extern_crate) if extern_crate.is_direct() && extern_crate.span.data().lo.to_usize() > 0 =>
-                                        Some((extern_crate.span.data().lo.to_usize(), def_id)),
-                                    _ => None,
+        struct SemverCallbacks {
+            /// The outcome of the analysis according to the failure policy, if it overrides
+            /// the errors emitted.
+            failed: Option<bool>,
+        }
+
+        impl Callbacks for SemverCallbacks {
+            fn after_analysis<'tcx>(
+                &mut self,
+                _compiler: &interface::Compiler,
+                queries: &'tcx Queries<'tcx>,
+            ) -> Compilation {
+                debug!("running rust-semverver after_analysis callback");
+
+                queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
+                    // To select the old and new crates we look at the position of the
+                    // declaration in the source file. The first one will be the `old`
+                    // and the other will be `new`. This is unfortunately a bit hacky...
+                    // See issue #64 for details.
+
+                    let mut crates: Vec<_> = tcx
+                        .crates(())
+                        .iter()
+                        .flat_map(|crate_num| {
+                            let def_id = crate_num.as_def_id();
+
+                            match tcx.extern_crate(def_id) {
+                                Some(extern_crate)
+                                    if extern_crate.is_direct()
+                                        && extern_crate.span.data().lo.to_usize() > 0 =>
+                                {
+                                    Some((extern_crate.span.data().lo.to_usize(), def_id))
                                 }
-                            })
-                            .collect();
+                                _ => None,
+                            }
+                        })
+                        .collect();
+
+                    crates.sort_by_key(|&(span_lo, _)| span_lo);
+
+                    let config = match Config::from_env() {
+                        Ok(config) => config,
+                        Err(err) => {
+                            tcx.sess.err(&err);
+                            return;
+                        }
+                    };
+
+                    if config.timings {
+                        timings::enable();
+                    }
+
+                    if let Some(ref path) = config.trace_item {
+                        trace::enable(path.clone());
+                    }
+
+                    budget::enable(config.time_budget, config.timeout_per_item);
 
-                        crates.sort_by_key(|&(span_lo, _)| span_lo);
+                    if let [(_, old_def_id), (_, new_def_id)] = *crates.as_slice() {
+                        debug!("running semver analysis");
+                        let changes = run_analysis(tcx, old_def_id, new_def_id, &config);
 
-                        if let [(_, old_def_id), (_, new_def_id)] = *crates.as_slice() {
-                            debug!("running semver analysis");
-                            let changes = run_analysis(tcx, old_def_id, new_def_id);
-                            if json {
-                                changes.output_json(tcx.sess, &version);
+                        timings::time("rendering", || {
+                            if config.json {
+                                changes.output_json(tcx.sess, &config);
+                            } else if config.dot {
+                                changes.output_dot();
                             } else {
-                                changes.output(tcx.sess, &version, verbose, compact, api_guidelines);
+                                changes.output(tcx.sess, &config);
                             }
-                        } else {
-                            tcx.sess.err("could not find `old` and `new` crates");
+                        });
+
+                        if let Some(report) = timings::report() {
+                            eprint!("{}", report);
                         }
-                    });
 
-                    debug!("rust-semverver after_analysis callback finished!");
+                        self.failed = changes.failed(&config);
 
-                    Compilation::Stop
-                }
-            }
+                        if let Some(ref path) = config.version_file {
+                            let new_version = changes.get_new_version(&config).unwrap_or_default();
 
-            if env::args().any(|a| a == "--version" || a == "-V") {
-                show_version();
-                exit(0);
-            }
+                            if let Err(err) = std::fs::write(path, new_version) {
+                                tcx.sess.err(&format!(
+                                    "could not write suggested version to {}: {}",
+                                    path.display(),
+                                    err
+                                ));
+                            }
+                        }
 
-            let sys_root = option_env!("SYSROOT")
-                .map(String::from)
-                .or_else(|| env::var("SYSROOT").ok())
-                .or_else(|| {
-                    let home = option_env!("RUSTUP_HOME").or(option_env!("MULTIRUST_HOME"));
-                    let toolchain = option_env!("RUSTUP_TOOLCHAIN").or(option_env!("MULTIRUST_TOOLCHAIN"));
-                    home.and_then(|home| toolchain.map(|toolchain| format!("{}/toolchains/{}", home, toolchain)))
-                })
-                .or_else(|| {
-                    Command::new("rustc")
-                        .arg("--print")
-                        .arg("sysroot")
-                        .output()
-                        .ok()
-                        .and_then(|out| String::from_utf8(out.stdout).ok())
-                        .map(|s| s.trim().to_owned())
-                })
-                .expect("need to specify SYSROOT env var during clippy compilation, or use rustup or multirust");
-
-            // Setting RUSTC_WRAPPER causes Cargo to pass 'rustc' as the first argument.
-            // We're invoking the compiler programmatically, so we ignore this/
-            let mut orig_args: Vec<String> = env::args().collect();
-            if orig_args.len() <= 1 {
-                std::process::exit(1);
-            }
+                        if let Some(ref path) = config.candidates_file {
+                            if let Err(err) = changes.write_rename_candidates(path) {
+                                tcx.sess.err(&format!(
+                                    "could not write rename candidates to {}: {}",
+                                    path.display(),
+                                    err
+                                ));
+                            }
+                        }
+
+                        if let Some(ref path) = config.verdict_file {
+                            if let Err(err) = changes.write_verdict(tcx.sess, &config, path) {
+                                tcx.sess.err(&format!(
+                                    "could not write verdict to {}: {}",
+                                    path.display(),
+                                    err
+                                ));
+                            }
+                        }
+                    } else {
+                        tcx.sess.err("could not find `old` and `new` crates");
+                    }
+                });
+
+                debug!("rust-semverver after_analysis callback finished!");
 
-            if Path::new(&orig_args[1]).file_stem() == Some("rustc".as_ref()) {
-                // we still want to be able to invoke it normally though
-                orig_args.remove(1);
+                Compilation::Stop
             }
+        }
+
+        if env::args().any(|a| a == "--version" || a == "-V") {
+            show_version();
+            exit(0);
+        }
+
+        let sys_root = option_env!("SYSROOT")
+            .map(String::from)
+            .or_else(|| env::var("SYSROOT").ok())
+            .or_else(|| {
+                let home = option_env!("RUSTUP_HOME").or(option_env!("MULTIRUST_HOME"));
+                let toolchain = option_env!("RUSTUP_TOOLCHAIN").or(option_env!("MULTIRUST_TOOLCHAIN"));
+                home.and_then(|home| toolchain.map(|toolchain| format!("{}/toolchains/{}", home, toolchain)))
+            })
+            .or_else(|| {
+                Command::new("rustc")
+                    .arg("--print")
+                    .arg("sysroot")
+                    .output()
+                    .ok()
+                    .and_then(|out| String::from_utf8(out.stdout).ok())
+                    .map(|s| s.trim().to_owned())
+            })
+            .expect("need to specify SYSROOT env var during clippy compilation, or use rustup or multirust");
+
+        // Setting RUSTC_WRAPPER causes Cargo to pass 'rustc' as the first argument.
+        // We're invoking the compiler programmatically, so we ignore this/
+        let mut orig_args: Vec<String> = env::args().collect();
+        if orig_args.len() <= 1 {
+            std::process::exit(1);
+        }
+
+        if Path::new(&orig_args[1]).file_stem() == Some("rustc".as_ref()) {
+            // we still want to be able to invoke it normally though
+            orig_args.remove(1);
+        }
 
-            // this conditional check for the --sysroot flag is there so users can call
-            // `clippy_driver` directly
-            // without having to pass --sysroot or anything
-            let args: Vec<String> = if orig_args.iter().any(|s| s == "--sysroot") {
-                orig_args
-            } else {
-                orig_args
-                    .into_iter()
-                    .chain(Some("--sysroot".to_owned()))
-                    .chain(Some(sys_root))
-                    .collect()
-            };
-
-            RunCompiler::new(&args, &mut SemverCallbacks).run()
+        // this conditional check for the --sysroot flag is there so users can call
+        // `clippy_driver` directly
+        // without having to pass --sysroot or anything
+        let args: Vec<String> = if orig_args.iter().any(|s| s == "--sysroot") {
+            orig_args
+        } else {
+            orig_args
+                .into_iter()
+                .chain(Some("--sysroot".to_owned()))
+                .chain(Some(sys_root))
+                .collect()
+        };
+
+        let mut callbacks = SemverCallbacks { failed: None };
+        let result = RunCompiler::new(&args, &mut callbacks).run();
+
+        match callbacks.failed {
+            Some(failed) => i32::from(failed),
+            None => result.map_or_else(|_| 1, |_| 0),
         }
-        .map_or_else(|_| 1, |_| 0),
-    )
+    })
 }
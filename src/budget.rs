@@ -0,0 +1,216 @@
+//! Limits on the time spent comparing items, set using `--timeout-per-item` and `--time-budget`.
+//!
+//! Trait solving can take very long on pathological bounds, such as deeply recursive ones. The
+//! analysis can't be interrupted at arbitrary points, so the limits are checked whenever a type
+//! is translated or bounds are solved. An item exceeding its limit is given up on by unwinding
+//! out of its comparison, and reported as not analyzed, like items the compiler panics on. Once
+//! the overall budget is exhausted, the remaining items aren't compared at all.
+//!
+//! The checks can't reach into the compiler's trait solver, which can hang on a single goal. A
+//! watchdog thread catches that case: once an item exceeds its limit by `GRACE` without being
+//! given up on, the whole analysis is aborted with an error.
+
+use std::{
+    cell::Cell,
+    process,
+    sync::{Mutex, Once},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The payload of the panic giving up on an item that exceeded its time limit.
+pub struct Timeout(pub &'static str);
+
+/// The configured limits.
+#[derive(Clone, Copy, Default)]
+struct Limits {
+    /// The time after which no more items are compared, if any.
+    budget_deadline: Option<Instant>,
+    /// The time a single item may take to compare, if limited.
+    per_item: Option<Duration>,
+}
+
+/// The time an item may exceed its limit by before the watchdog aborts the analysis.
+const GRACE: Duration = Duration::from_secs(10);
+
+/// The interval in which the watchdog checks the deadline of the item being compared.
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+thread_local! {
+    static LIMITS: Cell<Limits> = Cell::new(Limits::default());
+    static ITEM_DEADLINE: Cell<Option<(Instant, &'static str)>> = Cell::new(None);
+}
+
+/// The deadline of the item being compared, as seen by the watchdog.
+static WATCHED_DEADLINE: Mutex<Option<(Instant, &'static str)>> = Mutex::new(None);
+
+/// Ensures only one watchdog is started.
+static WATCHDOG: Once = Once::new();
+
+/// Start enforcing the given limits on the current thread, with the budget starting now.
+pub fn enable(budget: Option<Duration>, per_item: Option<Duration>) {
+    LIMITS.with(|l| {
+        l.set(Limits {
+            budget_deadline: budget.map(|budget| Instant::now() + budget),
+            per_item,
+        })
+    });
+
+    if budget.is_some() || per_item.is_some() {
+        WATCHDOG.call_once(|| {
+            thread::spawn(watchdog);
+        });
+    }
+}
+
+/// Abort the analysis once the item being compared exceeds its deadline by `GRACE`.
+///
+/// This only happens if the comparison is stuck in the compiler, where `check` is never called.
+fn watchdog() {
+    loop {
+        thread::sleep(WATCHDOG_INTERVAL);
+
+        if let Some((deadline, reason)) = watched_deadline() {
+            if overdue(deadline, Instant::now()) {
+                eprintln!(
+                    "error: {}, and the compiler could not be interrupted while solving trait \
+                     bounds, giving up on the analysis",
+                    reason
+                );
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Get the deadline of the item being compared, if any.
+fn watched_deadline() -> Option<(Instant, &'static str)> {
+    *WATCHED_DEADLINE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Set the deadline of the item being compared, as seen by the watchdog.
+fn watch(deadline: Option<(Instant, &'static str)>) {
+    *WATCHED_DEADLINE.lock().unwrap_or_else(|e| e.into_inner()) = deadline;
+}
+
+/// Check whether an item with the given deadline has exceeded it by more than `GRACE`.
+fn overdue(deadline: Instant, now: Instant) -> bool {
+    now >= deadline + GRACE
+}
+
+/// Check whether the overall time budget has been exhausted.
+pub fn exhausted() -> bool {
+    LIMITS.with(|l| {
+        l.get()
+            .budget_deadline
+            .map_or(false, |deadline| Instant::now() >= deadline)
+    })
+}
+
+/// Run a closure comparing an item, giving up on it once it exceeds its time limit.
+///
+/// The closure has to be run such that a `Timeout` panic is recovered from.
+pub fn item<T>(f: impl FnOnce() -> T) -> T {
+    let limits = LIMITS.with(Cell::get);
+    let item_deadline = limits
+        .per_item
+        .map(|per_item| (Instant::now() + per_item, "item exceeded its time limit"));
+    let budget_deadline = limits
+        .budget_deadline
+        .map(|deadline| (deadline, "time budget exhausted"));
+
+    let deadline = match (item_deadline, budget_deadline) {
+        (Some(item), Some(budget)) => Some(if item.0 <= budget.0 { item } else { budget }),
+        (item, budget) => item.or(budget),
+    };
+
+    ITEM_DEADLINE.with(|d| d.set(deadline));
+    watch(deadline);
+    let _item = ActiveItem;
+
+    f()
+}
+
+/// Give up on the item being compared if it exceeded its time limit.
+///
+/// Outside of the comparison of an item, this does nothing.
+pub fn check() {
+    if let Some((deadline, reason)) = ITEM_DEADLINE.with(Cell::get) {
+        if Instant::now() >= deadline {
+            std::panic::panic_any(Timeout(reason));
+        }
+    }
+}
+
+/// Ends the time limit of an item when dropped, even if it was exceeded.
+struct ActiveItem;
+
+impl Drop for ActiveItem {
+    fn drop(&mut self) {
+        ITEM_DEADLINE.with(|d| d.set(None));
+        watch(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn check_outside_of_items() {
+        enable(Some(Duration::ZERO), Some(Duration::ZERO));
+
+        // only the comparison of an item is given up on
+        check();
+        assert!(exhausted());
+    }
+
+    #[test]
+    fn item_exceeding_limit() {
+        enable(None, Some(Duration::ZERO));
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| item(check)));
+        let payload = res.unwrap_err();
+        let Timeout(reason) = payload.downcast_ref::<Timeout>().unwrap();
+
+        assert_eq!(*reason, "item exceeded its time limit");
+        assert!(!exhausted());
+        assert!(ITEM_DEADLINE.with(Cell::get).is_none());
+    }
+
+    #[test]
+    fn item_exceeding_budget() {
+        enable(Some(Duration::ZERO), Some(Duration::from_secs(60)));
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| item(check)));
+        let payload = res.unwrap_err();
+        let Timeout(reason) = payload.downcast_ref::<Timeout>().unwrap();
+
+        assert_eq!(*reason, "time budget exhausted");
+        assert!(exhausted());
+    }
+
+    #[test]
+    fn item_within_limit() {
+        enable(Some(Duration::from_secs(60)), Some(Duration::from_secs(60)));
+
+        assert_eq!(
+            item(|| {
+                check();
+                42
+            }),
+            42
+        );
+        assert!(!exhausted());
+    }
+
+    #[test]
+    fn overdue_after_grace() {
+        let deadline = Instant::now();
+
+        assert!(!overdue(deadline, deadline));
+        assert!(!overdue(deadline, deadline + GRACE / 2));
+        assert!(overdue(deadline, deadline + GRACE));
+    }
+}
@@ -8,21 +8,40 @@
 //! is available, which leads to (complete) removals being displayed first. Matters are further
 //! complicated by the fact that we still group changes by the item they refer to, even if it's
 //! path changes.
-
+//!
+//! All changes are stored in ordered collections, so that the output of two runs on the same
+//! crates is the same, regardless of the order in which the analysis passes found the changes.
+//! Human-readable output is rendered in the order of spans, while JSON output is sorted by the
+//! paths of the changed items and the kinds of their changes, as spans of distinct items can
+//! coincide, for example for items generated by the same macro invocation.
+
+use crate::{
+    config::{Config, FailOn, Severity},
+    manifest::ManifestChange,
+    report::{
+        ChangeReport, ChangeTypeReport, NotAnalyzedReport, PathChangeReport, Report, SpanReport,
+    },
+};
 use rustc_hir::def_id::DefId;
-use rustc_middle::ty::{error::TypeError, Predicate};
+use rustc_middle::ty::{error::TypeError, Predicate, Ty};
 use rustc_session::Session;
 use rustc_span::symbol::Symbol;
 use rustc_span::{FileName, Span};
 use semver::{BuildMetadata, Prerelease, Version};
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet, HashMap},
-    fmt,
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    io::{self, Write},
+    mem,
+    path::Path,
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
 };
 
 use serde::ser::{SerializeSeq, SerializeStruct, Serializer};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// The categories we use when analyzing changes between crate versions.
 ///
@@ -32,7 +51,8 @@ use serde::Serialize;
 /// exotic and/or unlikely scenarios, while we have a separate category for them.
 ///
 /// [1]: https://github.com/rust-lang/rfcs/blob/master/text/1105-api-evolution.md
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum ChangeCategory {
     /// A patch-level change - no change to the public API of a crate.
     Patch,
@@ -46,6 +66,17 @@ pub enum ChangeCategory {
 
 pub use self::ChangeCategory::*;
 
+impl ChangeCategory {
+    /// Get the severity with which changes of this category are reported by default.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Patch => Severity::Allow,
+            NonBreaking | TechnicallyBreaking => Severity::Warn,
+            Breaking => Severity::Deny,
+        }
+    }
+}
+
 impl Default for ChangeCategory {
     fn default() -> Self {
         Patch
@@ -65,6 +96,24 @@ impl fmt::Display for ChangeCategory {
     }
 }
 
+impl FromStr for ChangeCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "patch" => Ok(Patch),
+            "non-breaking" => Ok(NonBreaking),
+            "technically-breaking" => Ok(TechnicallyBreaking),
+            "breaking" => Ok(Breaking),
+            _ => Err(format!(
+                "invalid change category `{}`, expected one of `patch`, `non-breaking`, \
+                 `technically-breaking` or `breaking`",
+                s
+            )),
+        }
+    }
+}
+
 pub struct RSymbol(pub Symbol);
 
 impl Serialize for RSymbol {
@@ -76,6 +125,28 @@ impl Serialize for RSymbol {
     }
 }
 
+/// Look up the file and position of a span.
+fn span_report(session: &Session, span: Span) -> SpanReport {
+    let lo = session.source_map().lookup_char_pos(span.lo());
+    let hi = session.source_map().lookup_char_pos(span.hi());
+
+    assert!(lo.file.name == hi.file.name);
+    let file = if let FileName::Real(ref name) = lo.file.name {
+        name.local_path().map(|p| format!("{}", p.display()))
+    } else {
+        None
+    }
+    .unwrap_or_else(|| "no file name".to_owned());
+
+    SpanReport {
+        file,
+        line_lo: lo.line,
+        line_hi: hi.line,
+        col_lo: lo.col.0,
+        col_hi: hi.col.0,
+    }
+}
+
 struct RSpan<'a>(&'a Session, &'a Span);
 
 impl<'a> Serialize for RSpan<'a> {
@@ -83,27 +154,39 @@ impl<'a> Serialize for RSpan<'a> {
     where
         S: Serializer,
     {
-        let lo = self.0.source_map().lookup_char_pos(self.1.lo());
-        let hi = self.0.source_map().lookup_char_pos(self.1.hi());
-
-        assert!(lo.file.name == hi.file.name);
-        let file_name = if let FileName::Real(ref name) = lo.file.name {
-            name.local_path().map(|p| format!("{}", p.display()))
-        } else {
-            None
-        }
-        .unwrap_or_else(|| "no file name".to_owned());
+        let span = span_report(self.0, *self.1);
 
         let mut state = serializer.serialize_struct("Span", 5)?;
-        state.serialize_field("file", &file_name)?;
-        state.serialize_field("line_lo", &lo.line)?;
-        state.serialize_field("line_hi", &hi.line)?;
-        state.serialize_field("col_lo", &lo.col.0)?;
-        state.serialize_field("col_hi", &hi.col.0)?;
+        state.serialize_field("file", &span.file)?;
+        state.serialize_field("line_lo", &span.line_lo)?;
+        state.serialize_field("line_hi", &span.line_hi)?;
+        state.serialize_field("col_lo", &span.col_lo)?;
+        state.serialize_field("col_hi", &span.col_hi)?;
         state.end()
     }
 }
 
+/// A change reported as an error, as listed in the verdict of the analysis.
+#[derive(Serialize)]
+struct BlockingChange<'a> {
+    /// The name of the changed item, or `None` for changes to the manifest.
+    item: Option<String>,
+    /// The code of the change, if any.
+    code: Option<&'static str>,
+    /// The category of the change.
+    category: ChangeCategory,
+    /// The description of the change.
+    description: String,
+    /// The location of the change, or `None` for changes to the manifest.
+    span: Option<RSpan<'a>>,
+}
+
+/// Check whether changes of the given category are reported as errors.
+fn is_denied(config: &Config, cat: ChangeCategory) -> bool {
+    let reported = !config.api_guidelines || cat == Patch || cat == Breaking;
+    reported && config.severity(cat) == Severity::Deny
+}
+
 /// Different ways to refer to a changed item.
 ///
 /// Used in the header of a change description to identify an item that was subject to change.
@@ -155,6 +238,13 @@ pub struct PathChange {
     additions: BTreeSet<Span>,
     /// The set of spans of removed exports of the item.
     removals: BTreeSet<Span>,
+    /// The unstable feature the item is gated behind, if any.
+    unstable_feature: Option<String>,
+    /// The categories of the exports recategorized by external rules.
+    categories: BTreeMap<Span, ChangeCategory>,
+    /// The path of an added item with a similar name, suggested as a replacement of the removed
+    /// exports.
+    suggestion: Option<String>,
 }
 
 impl PathChange {
@@ -165,6 +255,9 @@ impl PathChange {
             def_span,
             additions: BTreeSet::new(),
             removals: BTreeSet::new(),
+            unstable_feature: None,
+            categories: BTreeMap::new(),
+            suggestion: None,
         }
     }
 
@@ -177,31 +270,74 @@ impl PathChange {
         }
     }
 
-    /// Get the change's category.
-    pub fn to_category(&self) -> ChangeCategory {
-        if !self.removals.is_empty() {
-            Breaking
-        } else if self.additions.is_empty() {
+    /// Get the spans of the removed and added exports, along with their categories, and whether
+    /// they have been added.
+    ///
+    /// Removals are breaking, and additions technically breaking, unless recategorized by
+    /// external rules.
+    fn exports(&self) -> impl Iterator<Item = (&Span, ChangeCategory, bool)> + '_ {
+        let category =
+            move |span: &Span, default| self.categories.get(span).copied().unwrap_or(default);
+
+        self.removals
+            .iter()
+            .map(move |span| (span, category(span, Breaking), false))
+            .chain(
+                self.additions
+                    .iter()
+                    .map(move |span| (span, category(span, TechnicallyBreaking), true)),
+            )
+    }
+
+    /// Get the category an export of the given category counts as towards the verdict.
+    ///
+    /// Changes to items gated behind unstable features don't count towards the verdict.
+    fn counted(&self, category: ChangeCategory) -> ChangeCategory {
+        if self.unstable_feature.is_some() {
             Patch
         } else {
-            TechnicallyBreaking
+            category
+        }
+    }
+
+    /// Get the severity with which an export of the given category is reported.
+    ///
+    /// Changes to items gated behind unstable features are reported as informational changes.
+    fn severity(&self, config: &Config, category: ChangeCategory) -> Severity {
+        if self.unstable_feature.is_some() {
+            config.informational_severity()
+        } else {
+            config.severity(category)
         }
     }
 
+    /// Get the change's category.
+    pub fn to_category(&self) -> ChangeCategory {
+        self.exports()
+            .map(|(_, category, _)| self.counted(category))
+            .max()
+            .unwrap_or(Patch)
+    }
+
     /// Get the change item's definition span.
     pub fn span(&self) -> &Span {
         &self.def_span
     }
 
     /// Report the change in a structured manner, using rustc's error reporting capabilities.
-    fn report(&self, session: &Session) {
-        let cat = self.to_category();
-        if cat == Patch {
+    fn report(&self, session: &Session, config: &Config) {
+        let severity = |cat| self.severity(config, cat);
+        let max_severity = self
+            .exports()
+            .map(|(_, cat, _)| severity(cat))
+            .max()
+            .unwrap_or(Severity::Allow);
+        if max_severity == Severity::Allow {
             return;
         }
 
         let msg = format!("path changes to `{}`", self.name.0);
-        let mut builder = if cat == Breaking {
+        let mut builder = if max_severity == Severity::Deny {
             session
                 .struct_span_err(self.def_span, &msg)
                 .forget_guarantee()
@@ -209,22 +345,45 @@ impl PathChange {
             session.struct_span_warn(self.def_span, &msg)
         };
 
-        for removed_span in &self.removals {
-            if *removed_span == self.def_span {
-                builder.warn("removed definition (breaking)");
-            } else {
-                builder.span_warn(*removed_span, "removed path (breaking)");
+        for (span, cat, added) in self.exports() {
+            let def = *span == self.def_span;
+            let sub_msg = format!(
+                "{} {} ({})",
+                if added { "added" } else { "removed" },
+                if def { "definition" } else { "path" },
+                cat
+            );
+
+            match severity(cat) {
+                Severity::Allow => (),
+                Severity::Warn if def => {
+                    builder.note(&sub_msg);
+                }
+                Severity::Warn => {
+                    builder.span_note(*span, &sub_msg);
+                }
+                Severity::Deny if def => {
+                    builder.warn(&sub_msg);
+                }
+                Severity::Deny => {
+                    builder.span_warn(*span, &sub_msg);
+                }
             }
         }
 
-        for added_span in &self.additions {
-            if *added_span == self.def_span {
-                builder.note("added definition (technically breaking)");
-            } else {
-                builder.span_note(*added_span, "added path (technically breaking)");
+        if let Some(ref path) = self.suggestion {
+            if !self.removals.is_empty() {
+                builder.help(&format!("did you mean `{}`?", path));
             }
         }
 
+        if let Some(ref feature) = self.unstable_feature {
+            builder.note(&format!(
+                "gated behind unstable feature `{}`, not counted towards the verdict",
+                feature
+            ));
+        }
+
         builder.emit();
     }
 }
@@ -268,15 +427,52 @@ impl<'a> Serialize for RPathChange<'a> {
 
         state.serialize_field("removals", &removals)?;
 
+        if let Some(ref feature) = self.1.unstable_feature {
+            state.serialize_field("unstable_feature", feature)?;
+        }
+
+        if let Some(ref path) = self.1.suggestion {
+            state.serialize_field("suggestion", path)?;
+        }
+
         state.end()
     }
 }
 
+/// The ways a rule of a declarative macro can change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MacroRuleChange {
+    /// A rule has been removed.
+    Removed,
+    /// A rule has been added.
+    Added,
+    /// The matcher of a rule has changed.
+    MatcherChanged,
+    /// The transcriber of a rule has changed, while its matcher hasn't.
+    TranscriberChanged,
+}
+
+/// The ways a function exported to JavaScript using `#[wasm_bindgen]` can change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WasmExportChange {
+    /// An export has been removed.
+    Removed,
+    /// An export has been added.
+    Added,
+    /// The signature of an export has changed.
+    SignatureChanged,
+}
+
 /// The types of changes we identify between items present in both crate versions.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum ChangeType<'tcx> {
     /// An item has been made public.
     ItemMadePublic,
+    /// A line of the public API, as listed by `dump-api`, was added by making an item public.
+    PublicApiAdded { line: String },
     /// An item has been made private.
     ItemMadePrivate,
     /// An item has changed it's kind.
@@ -300,18 +496,34 @@ pub enum ChangeType<'tcx> {
     TypeParameterAdded { defaulted: bool },
     /// A possibly defaulted type parameter has been removed from an item.
     TypeParameterRemoved { defaulted: bool },
+    /// The default value of a const parameter has been added, removed or changed.
+    ConstParamDefaultChanged {
+        name: Symbol,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// The computed alignment of a type with a guaranteed layout has been reduced.
+    AlignmentReduced { old: u64, new: u64 },
+    /// The packing of a type has been added, removed or changed.
+    PackingChanged { old: Option<u64>, new: Option<u64> },
     /// A variant has been added to an enum.
     VariantAdded,
     /// A variant has been removed from an enum.
     VariantRemoved,
     /// A possibly public field has been added to a variant or struct.
     ///
-    /// This also records whether all fields are public were public before the change.
+    /// This also records whether all fields are public were public before the change, and
+    /// whether user code could construct the variant or struct with a literal expression (and
+    /// match on it exhaustively), i.e. whether all fields were public and the field list wasn't
+    /// marked `#[non_exhaustive]`.
     VariantFieldAdded {
         public: bool,
         total_public: bool,
         is_enum: bool,
+        constructible: bool,
     },
+    /// A public field of a variant or struct has been made private or `#[doc(hidden)]`.
+    FieldMadePrivate { hidden: bool },
     /// A possibly public field has been removed from a variant or struct.
     ///
     /// This also records whether all fields were public before the change.
@@ -342,8 +554,30 @@ pub enum ChangeType<'tcx> {
     TraitUnsafetyChanged { now_unsafe: bool },
     /// An item's type has changed.
     TypeChanged { error: TypeError<'tcx> },
+    /// An item's type has changed only because it refers to an item of a dependency, which is
+    /// used in different versions by the old and new crate.
+    DependencyVersionSkew { krate: Symbol, path: String },
+    /// The length of an array in an item's type has changed.
+    ArrayLengthChanged { old: u64, new: u64 },
+    /// The type a type alias expands to has changed.
+    TypeAliasChanged { old: String, new: String },
+    /// A function's return type changed from a named type to `impl Trait`, or vice versa.
+    ReturnTypeOpacityChanged { now_opaque: bool },
+    /// A bound of an opaque type defined by a type alias has been added or removed.
+    OpaqueBoundChanged { bound: String, removed: bool },
+    /// An opaque type defined by a type alias started or stopped implementing an auto trait.
+    AutoTraitChanged { name: Symbol, now_impl: bool },
+    /// Trait objects of a trait can no longer be upcast to a trait object of a supertrait.
+    UpcastingRemoved { supertrait: String },
     /// An item's (trait) bounds have been tightened.
-    BoundsTightened { pred: Predicate<'tcx> },
+    ///
+    /// This includes information on whether the affected item is a trait definition, since
+    /// adding bounds on those breaks implementors of the trait in user code, in addition to
+    /// code merely using it.
+    BoundsTightened {
+        pred: Predicate<'tcx>,
+        trait_def: bool,
+    },
     /// An item's (trait) bounds have been loosened.
     ///
     /// This includes information on whether the affected item is a trait definition, since
@@ -353,14 +587,111 @@ pub enum ChangeType<'tcx> {
         pred: Predicate<'tcx>,
         trait_def: bool,
     },
+    /// A `?Sized` relaxation has been added to or removed from a type parameter.
+    ///
+    /// Like other bounds, this includes whether the affected item is a trait definition.
+    SizedRelaxationChanged {
+        param: String,
+        relaxed: bool,
+        trait_def: bool,
+    },
+    /// A lifetime bound, such as `T: 'static` or `'a: 'b`, has been added to an item.
+    ///
+    /// The trait solver defers these to region checking, so they are compared as written.
+    LifetimeBoundAdded { pred: Predicate<'tcx> },
     /// A trait impl has been specialized or removed for some type(s).
     TraitImplTightened,
+    /// The bounds of an inherent impl have been tightened, restricting all its items to a
+    /// smaller set of types.
+    InherentImplTightened { pred: Predicate<'tcx> },
     /// A trait impl has been generalized or newly added for some type(s).
     TraitImplLoosened,
+    /// An associated type of a trait impl has changed.
+    TraitImplAssocTypeChanged {
+        name: Symbol,
+        old: Ty<'tcx>,
+        new: Ty<'tcx>,
+    },
+    /// A trait impl has changed its constness.
+    TraitImplConstChanged { now_const: bool },
+    /// An item of a trait impl has gained or lost its `default` marker, which allows
+    /// specializing impls to override it.
+    DefaultnessChanged { name: Symbol, now_default: bool },
+    /// An impl of `Fn`, `FnMut` or `FnOnce` has been removed from a type.
+    CallImplRemoved { trait_name: Symbol },
+    /// The call signature of a type's impl of `Fn`, `FnMut` or `FnOnce` has changed.
+    CallSignatureChanged {
+        trait_name: Symbol,
+        old: Ty<'tcx>,
+        new: Ty<'tcx>,
+    },
+    /// A newly added trait impl could overlap with impls downstream crates are allowed to write
+    /// under the orphan rules.
+    TraitImplPossiblyConflicting,
+    /// A `Deref` or `DerefMut` impl has been added to an existing type.
+    DerefImplAdded { mutable: bool },
+    /// A trait impl has been added that can make types in the given calls ambiguous.
+    TraitImplBreaksInference { calls: &'static str },
+    /// A `CoerceUnsized` or `DispatchFromDyn` impl of a smart pointer has been removed.
+    UnsizingImplRemoved { dispatch: bool },
+    /// The value of a constant has changed, while it's type has not.
+    ConstValueChanged { old: String, new: String },
+    /// The body of a function has changed, while it's signature has not.
+    FnBodyChanged,
+    /// The serialized form of a type implementing serde's traits has changed.
+    SerdeFormatChanged { desc: String, breaking: bool },
     /// An associated item has been newly added to some inherent impls.
     AssociatedItemAdded,
+    /// A newly added inherent method shares its name with a method of a trait implemented for the
+    /// type, and takes precedence over it in method calls.
+    TraitMethodShadowed { trait_path: String },
     /// An associated item has been removed from some inherent impls.
     AssociatedItemRemoved,
+    /// A helper attribute of a derive macro has been removed, possibly in favour of a new one.
+    DeriveHelperRemoved {
+        name: Symbol,
+        renamed_to: Option<Symbol>,
+    },
+    /// An item has been moved to a different module, without being re-exported at its old path.
+    ItemMoved { old_path: String, new_path: String },
+    /// An item has been paired up with an added item of a similar name and the same shape, or,
+    /// for types and traits, of the same structure.
+    ItemProbablyRenamed { old_path: String, new_path: String },
+    /// A function or type has been marked `#[must_use]`.
+    MustUseAdded,
+    /// A function has gained or lost the `#[track_caller]` attribute.
+    TrackCallerChanged { now_tracked: bool },
+    /// A function that didn't panic can now panic, while it's signature hasn't changed.
+    PanicIntroduced,
+    /// An item uses a standard library item stabilized after the declared `rust-version`.
+    RustVersionExceeded { path: String, since: Symbol },
+    /// A rule of an exported declarative macro has changed.
+    MacroRuleChanged { desc: String, kind: MacroRuleChange },
+    /// A function exported to JavaScript using `#[wasm_bindgen]` has changed.
+    WasmExportChanged {
+        desc: String,
+        kind: WasmExportChange,
+    },
+    /// A finding of a custom check, with the category the check assigned to it.
+    Custom {
+        check: String,
+        desc: String,
+        category: ChangeCategory,
+    },
+    /// A change whose category has been adjusted by external rules.
+    Recategorized {
+        change: Box<ChangeType<'tcx>>,
+        category: ChangeCategory,
+    },
+    /// A change to an item gated behind a cargo feature designated as unstable, which doesn't
+    /// count towards the verdict.
+    UnstableFeatureGated {
+        change: Box<ChangeType<'tcx>>,
+        feature: String,
+    },
+    /// The analysis of an item has been aborted by a panic of the compiler, or for exceeding
+    /// its time limit.
+    AnalysisFailed { reason: String },
     /// An unknown change we don't yet explicitly handle.
     Unknown,
 }
@@ -384,13 +715,22 @@ impl<'tcx> ChangeType<'tcx> {
             RegionParameterRemoved |
             TypeParameterAdded { defaulted: false } |
             TypeParameterRemoved { .. } |
+            ConstParamDefaultChanged { old: Some(_), .. } |
             VariantAdded |
             VariantRemoved |
-            VariantFieldAdded { total_public: true, .. } |
+            VariantFieldAdded { constructible: true, .. } |
+            FieldMadePrivate { .. } |
             VariantFieldRemoved { public: true, .. } |
             VariantFieldRemoved { public: false, is_enum: true, .. } |
             VariantStyleChanged { .. } |
             TypeChanged { .. } |
+            DependencyVersionSkew { .. } |
+            ArrayLengthChanged { .. } |
+            TypeAliasChanged { .. } |
+            ReturnTypeOpacityChanged { now_opaque: true } |
+            OpaqueBoundChanged { removed: true, .. } |
+            AutoTraitChanged { now_impl: false, .. } |
+            UpcastingRemoved { .. } |
             FnConstChanged { now_const: false } |
             MethodSelfChanged { now_self: false } |
             TraitItemAdded { defaulted: false, sealed_trait: false } |
@@ -398,28 +738,181 @@ impl<'tcx> ChangeType<'tcx> {
             TraitUnsafetyChanged { .. } |
             BoundsTightened { .. } |
             BoundsLoosened { trait_def: true, .. } |
+            LifetimeBoundAdded { .. } |
+            SizedRelaxationChanged { relaxed: false, .. } |
+            SizedRelaxationChanged { trait_def: true, .. } |
             TraitImplTightened |
+            InherentImplTightened { .. } |
+            TraitImplAssocTypeChanged { .. } |
+            TraitImplConstChanged { now_const: false } |
+            CallImplRemoved { .. } |
+            CallSignatureChanged { .. } |
+            DefaultnessChanged { now_default: false, .. } |
+            UnsizingImplRemoved { .. } |
+            AlignmentReduced { .. } |
+            PackingChanged { .. } |
+            SerdeFormatChanged { breaking: true, .. } |
             AssociatedItemRemoved |
+            DeriveHelperRemoved { .. } |
+            ItemMoved { .. } |
+            ItemProbablyRenamed { .. } |
+            WasmExportChanged { kind: WasmExportChange::Removed, .. } |
+            WasmExportChanged { kind: WasmExportChange::SignatureChanged, .. } |
+            MacroRuleChanged { kind: MacroRuleChange::Removed, .. } |
+            MacroRuleChanged { kind: MacroRuleChange::MatcherChanged, .. } |
             Unknown => Breaking,
             //
             // Technically breaking
             //
             MethodSelfChanged { now_self: true } |
             TraitItemAdded { .. } | // either defaulted or sealed
+            TypeParameterAdded { defaulted: true } | // breaks explicit generic arguments
             BoundsLoosened { trait_def: false, .. } |
             TraitImplLoosened |
+            TraitImplPossiblyConflicting |
+            DerefImplAdded { .. } |
+            TraitImplBreaksInference { .. } |
+            ConstValueChanged { .. } |
+            SerdeFormatChanged { breaking: false, .. } |
             AssociatedItemAdded |
+            TraitMethodShadowed { .. } |
             VariantFieldAdded { public: true, .. } |
+            RustVersionExceeded { .. } |
+            PublicApiAdded { .. } |
+            AnalysisFailed { .. } | // unknown, but we don't want to fail on our own bugs
             ItemMadePublic => TechnicallyBreaking,
             //
             // Non breaking
             //
             StaticMutabilityChanged { now_mut: true } |
             VarianceLoosened |
-            TypeParameterAdded { defaulted: true } |
+            SizedRelaxationChanged { .. } | // added to a non-trait definition
             VariantFieldAdded { public: false, .. } |
             VariantFieldRemoved { public: false, .. } |
-            FnConstChanged { now_const: true } => NonBreaking,
+            OpaqueBoundChanged { removed: false, .. } |
+            AutoTraitChanged { now_impl: true, .. } |
+            MacroRuleChanged { kind: MacroRuleChange::Added, .. } |
+            WasmExportChanged { kind: WasmExportChange::Added, .. } |
+            ReturnTypeOpacityChanged { now_opaque: false } |
+            ConstParamDefaultChanged { old: None, .. } |
+            FnConstChanged { now_const: true } |
+            TraitImplConstChanged { now_const: true } |
+            DefaultnessChanged { now_default: true, .. } => NonBreaking,
+            //
+            // Patch
+            //
+            FnBodyChanged |
+            MustUseAdded |
+            TrackCallerChanged { .. } |
+            PanicIntroduced |
+            MacroRuleChanged { kind: MacroRuleChange::TranscriberChanged, .. } |
+            UnstableFeatureGated { .. } => Patch,
+            //
+            // Custom
+            //
+            Custom { category, .. } | Recategorized { category, .. } => category,
+        }
+    }
+
+    /// Check whether the change type is informational.
+    ///
+    /// Informational changes don't count towards the verdict, but are reported nonetheless.
+    pub fn is_informational(&self) -> bool {
+        matches!(
+            *self,
+            MustUseAdded
+                | TrackCallerChanged { .. }
+                | MacroRuleChanged {
+                    kind: MacroRuleChange::TranscriberChanged,
+                    ..
+                }
+                | UnstableFeatureGated { .. }
+        )
+    }
+
+    /// Get the severity with which the change type is reported.
+    pub fn severity(&self, config: &Config) -> Severity {
+        if self.is_informational() {
+            config.informational_severity()
+        } else {
+            config.severity(self.to_category())
+        }
+    }
+
+    /// Get the stable code of the change type, used to look up its description.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ItemMadePublic => "SV0001",
+            ItemMadePrivate => "SV0002",
+            KindDifference => "SV0003",
+            StaticMutabilityChanged { .. } => "SV0004",
+            VarianceLoosened => "SV0005",
+            VarianceTightened => "SV0006",
+            VarianceChanged { .. } => "SV0007",
+            RegionParameterAdded => "SV0008",
+            RegionParameterRemoved => "SV0009",
+            TypeParameterAdded { .. } => "SV0010",
+            TypeParameterRemoved { .. } => "SV0011",
+            VariantAdded => "SV0012",
+            VariantRemoved => "SV0013",
+            VariantFieldAdded { .. } => "SV0014",
+            FieldMadePrivate { .. } => "SV0015",
+            VariantFieldRemoved { .. } => "SV0016",
+            VariantStyleChanged { .. } => "SV0017",
+            FnConstChanged { .. } => "SV0018",
+            MethodSelfChanged { .. } => "SV0019",
+            TraitItemAdded { .. } => "SV0020",
+            TraitItemRemoved { .. } => "SV0021",
+            TraitUnsafetyChanged { .. } => "SV0022",
+            TypeChanged { .. } => "SV0023",
+            BoundsTightened { .. } => "SV0024",
+            BoundsLoosened { .. } => "SV0025",
+            TraitImplTightened => "SV0026",
+            TraitImplLoosened => "SV0027",
+            TraitImplAssocTypeChanged { .. } => "SV0028",
+            TraitImplPossiblyConflicting => "SV0029",
+            ConstValueChanged { .. } => "SV0030",
+            FnBodyChanged => "SV0031",
+            SerdeFormatChanged { .. } => "SV0032",
+            AssociatedItemAdded => "SV0033",
+            AssociatedItemRemoved => "SV0034",
+            Unknown => "SV0035",
+            DeriveHelperRemoved { .. } => "SV0036",
+            ItemMoved { .. } => "SV0037",
+            ArrayLengthChanged { .. } => "SV0038",
+            TypeAliasChanged { .. } => "SV0039",
+            OpaqueBoundChanged { .. } => "SV0040",
+            AutoTraitChanged { .. } => "SV0041",
+            UpcastingRemoved { .. } => "SV0042",
+            MustUseAdded => "SV0043",
+            TrackCallerChanged { .. } => "SV0044",
+            PanicIntroduced => "SV0045",
+            RustVersionExceeded { .. } => "SV0046",
+            MacroRuleChanged { .. } => "SV0047",
+            WasmExportChanged { .. } => "SV0048",
+            InherentImplTightened { .. } => "SV0049",
+            ReturnTypeOpacityChanged { .. } => "SV0050",
+            ConstParamDefaultChanged { .. } => "SV0051",
+            UnsizingImplRemoved { .. } => "SV0052",
+            AlignmentReduced { .. } => "SV0053",
+            PackingChanged { .. } => "SV0054",
+            PublicApiAdded { .. } => "SV0055",
+            TraitMethodShadowed { .. } => "SV0056",
+            DerefImplAdded { .. } => "SV0057",
+            TraitImplBreaksInference { .. } => "SV0058",
+            Custom { .. } => "SV0059",
+            DependencyVersionSkew { .. } => "SV0060",
+            AnalysisFailed { .. } => "SV0061",
+            LifetimeBoundAdded { .. } => "SV0062",
+            SizedRelaxationChanged { .. } => "SV0063",
+            TraitImplConstChanged { .. } => "SV0064",
+            CallImplRemoved { .. } => "SV0065",
+            CallSignatureChanged { .. } => "SV0066",
+            DefaultnessChanged { .. } => "SV0067",
+            ItemProbablyRenamed { .. } => "SV0068",
+            Recategorized { ref change, .. } | UnstableFeatureGated { ref change, .. } => {
+                change.code()
+            }
         }
     }
 
@@ -431,6 +924,11 @@ impl<'tcx> ChangeType<'tcx> {
 change, except in the special case of wildcard imports in user code, where
 they can cause nameclashes. Thus, the change is classified as \"technically
 breaking\"."
+            }
+            PublicApiAdded { .. } => {
+                "Making an item public adds it to the public interface of the crate, just like
+defining a new item does. The added parts of the interface are listed so they
+can be documented, and are classified like the item being made public."
             }
             ItemMadePrivate => {
                 "Removing an item from a module's public interface is a breaking change."
@@ -477,9 +975,13 @@ explicit type annotations, as well as prevent region inference working as
 before."
             }
             TypeParameterAdded { defaulted: true } => {
-                "Adding a new defaulted type parameter is a non-breaking change, because
-all old references to the item are still valid, provided that no type
-errors appear."
+                "Adding a new defaulted type parameter, or a type parameter to a function,
+keeps most old references to the item valid, as the parameter is filled in
+by its default or inferred. However, references giving all generic
+arguments explicitly, such as calls using the turbofish syntax like
+`foo::<u8>()`, become invalid due to the wrong number of arguments, and
+expressions relying on the default can fail to infer the parameter. Thus,
+the change is classified as \"technically breaking\"."
             }
             TypeParameterAdded { defaulted: false } => {
                 "Adding a new non-defaulted type parameter is a breaking change, because
@@ -490,6 +992,27 @@ can't be inferred."
                 "Removing any type parameter, defaulted or not, is a breaking change,
 because old references to the item are become invalid if the type parameter
 is instantiated in a manner not compatible with the new type of the item."
+            }
+            ConstParamDefaultChanged { old: Some(_), .. } => {
+                "Changing or removing the default value of a const parameter is a breaking
+change, because old references to the item relying on the default, such as
+type annotations omitting the parameter, either denote a different type or
+become invalid."
+            }
+            ConstParamDefaultChanged { old: None, .. } => {
+                "Adding a default value to a const parameter is a non-breaking change,
+because all old references to the item still give the parameter explicitly."
+            }
+            AlignmentReduced { .. } => {
+                "Reducing the alignment of a type with a `repr(C)`, `repr(packed)` or
+`repr(align)` attribute is a breaking change, because user code passing it
+across an FFI boundary, or relying on the alignment of pointers to it, is
+no longer correct."
+            }
+            PackingChanged { .. } => {
+                "Changing the packing of a type is a breaking change, because it moves its
+fields to different offsets, which user code relying on the layout of the
+type can observe, and changes which of its fields can be borrowed."
             }
             VariantAdded => {
                 "Adding a new enum variant is a breaking change, because a match expression
@@ -498,11 +1021,28 @@ on said enum can become non-exhaustive."
             VariantRemoved => {
                 "Removing an enum variant is a braking change, because every old reference
 to the removed variant is rendered invalid."
+            }
+            VariantFieldAdded {
+                constructible: true,
+                ..
+            } => {
+                "Adding a field to an enum variant or struct that can be constructed by user
+code is breaking, as struct literals and exhaustive matches on the variant or
+struct are invalidated. In case of structs, this holds for public fields, as
+well as for the first private field being added."
             }
             VariantFieldAdded { .. } => {
-                "Adding a field to an enum variant or struct is breaking, as matches on the
-variant or struct are invalidated. In case of structs, this only holds for
-public fields, or the first private field being added."
+                "Adding a field to an enum variant or struct that can't be constructed by user
+code, because it has private fields or is marked `#[non_exhaustive]`, doesn't
+invalidate struct literals or matches on it. Adding a public field can cause
+name clashes in rare cases, so it is classified as \"technically breaking\"."
+            }
+            FieldMadePrivate { .. } => {
+                "Making a public field of an enum variant or struct private (or hiding it from
+the documentation) is breaking, as user code can't access the field anymore.
+This also invalidates patterns destructuring the variant or struct by naming
+the field, as well as struct literals using functional record update syntax
+(`Foo { field, ..other }`)."
             }
             VariantFieldRemoved { .. } => {
                 "Removing a field from an enum variant or struct is breaking, as matches on the
@@ -521,6 +1061,17 @@ can appear anywhere a regular function is expected."
                 "Making a const function non-const is a breaking change, because values
 assigned to constants can't be determined by expressions containing
 non-const functions."
+            }
+            ReturnTypeOpacityChanged { now_opaque: true } => {
+                "Changing the return type of a function from a named type to `impl Trait`
+is a breaking change, because user code can no longer name the returned
+type, nor use any of its methods and trait impls not implied by the
+bounds of the `impl Trait`."
+            }
+            ReturnTypeOpacityChanged { now_opaque: false } => {
+                "Changing the return type of a function from `impl Trait` to a named type
+is a non-breaking change, as long as the named type fulfills the bounds
+of the `impl Trait`, because user code can only have relied on these."
             }
             MethodSelfChanged { now_self: true } => {
                 "Adding a self parameter to a method is a breaking change in some specific
@@ -566,7 +1117,24 @@ implementations become invalid."
                 "Changing the type of an item is a breaking change, because user code
 using the item becomes type-incorrect."
             }
-            BoundsTightened { .. } => {
+            DependencyVersionSkew { .. } => {
+                "Using a different version of a dependency in the type of an item is a
+breaking change, because the dependency's items are different types in both
+versions, and user code passing values of the old version becomes
+type-incorrect."
+            }
+            BoundsTightened {
+                trait_def: true, ..
+            } => {
+                "Tightening the bounds of a trait definition (its supertraits or where
+clauses) is a breaking change, because all implementations of the trait in
+user code for types not fulfilling the new bounds are rendered invalid, as
+well as references to the trait instantiating a parameter with a type or
+lifetime not fulfilling the bound."
+            }
+            BoundsTightened {
+                trait_def: false, ..
+            } => {
                 "Tightening the bounds of a lifetime or type parameter is a breaking
 change, because all old references instantiating the parameter with a
 type or lifetime not fulfilling the bound are rendered invalid."
@@ -585,28 +1153,314 @@ trait implementation or usage."
                 "Loosening the bounds of a lifetime or type parameter in a non-trait
 definition is a non-breaking change, because all old references to the
 item would remain valid."
+            }
+            SizedRelaxationChanged { relaxed: false, .. } => {
+                "Removing a `?Sized` relaxation from a type parameter is a breaking change,
+because the parameter becomes implicitly bounded by `Sized`, and all old
+references instantiating it with an unsized type, such as `str`, a slice
+or a trait object, are rendered invalid."
+            }
+            SizedRelaxationChanged {
+                relaxed: true,
+                trait_def: true,
+                ..
+            } => {
+                "Adding a `?Sized` relaxation to a type parameter in a trait definition is
+a breaking change, because implementations of the trait in user code
+assume the parameter to be `Sized`, and their methods are rendered
+invalid by requiring more than the trait does."
+            }
+            SizedRelaxationChanged { relaxed: true, .. } => {
+                "Adding a `?Sized` relaxation to a type parameter in a non-trait definition
+is a non-breaking change, because all old references to the item remain
+valid."
+            }
+            LifetimeBoundAdded { .. } => {
+                "Adding a lifetime bound to a parameter is a breaking change, because all
+old references instantiating the parameter with a type containing shorter
+lived references, or a shorter lifetime, are rendered invalid. For
+example, a parameter bounded by `'static` no longer accepts types
+borrowing from local variables."
             }
             TraitImplTightened => {
                 "Effectively removing a trait implementation for a (possibly
 parametrized) type is a breaking change, as all old references to trait
 methods on the type become invalid."
+            }
+            InherentImplTightened { .. } => {
+                "Tightening the bounds of an inherent impl is a breaking change, because
+all old references to its items on types not fulfilling the new bounds
+become invalid. This applies to all items in the impl at once, so the
+change is reported for the impl instead of each of its items."
             }
             TraitImplLoosened => {
                 "Effectively adding a trait implementation for a (possibly
 parametrized) type is a breaking change in some specific situations,
 as name clashes with other trait implementations in user code can be
 caused."
+            }
+            TraitImplAssocTypeChanged { .. } => {
+                "Changing an associated type of a trait implementation (like the `Item` of an
+`Iterator` or the `Target` of a `Deref` implementation) is a breaking change,
+because all user code relying on the old type becomes type-incorrect."
+            }
+            TraitImplConstChanged { now_const: true } => {
+                "Making a trait implementation const is a non-breaking change, because a
+const implementation can be used anywhere a regular one is expected."
+            }
+            TraitImplConstChanged { now_const: false } => {
+                "Making a const trait implementation non-const is a breaking change, because
+user code calling the trait's methods in const contexts, or relying on the
+implementation to satisfy `~const` bounds, is rendered invalid."
+            }
+            DefaultnessChanged {
+                now_default: false, ..
+            } => {
+                "Removing the `default` marker from an item of a trait implementation is a
+breaking change, because specializing implementations in user code that
+override the item are rendered invalid."
+            }
+            DefaultnessChanged {
+                now_default: true, ..
+            } => {
+                "Marking an item of a trait implementation as `default` is a non-breaking
+change, because it only allows specializing implementations in user code
+to override the item."
+            }
+            CallImplRemoved { .. } => {
+                "Removing an implementation of `Fn`, `FnMut` or `FnOnce` from a type is a
+breaking change, because user code calling values of the type like
+functions, or passing them where a closure is expected, is rendered
+invalid, just like when removing a method."
+            }
+            CallSignatureChanged { .. } => {
+                "Changing the arguments of a type's implementation of `Fn`, `FnMut` or
+`FnOnce` is a breaking change, because user code calling values of the
+type with the old arguments, or passing them where a closure with the old
+signature is expected, is rendered invalid, just like when changing the
+signature of a method."
+            }
+            TraitImplPossiblyConflicting => {
+                "Adding a trait implementation that is generic over some of its input types
+(for example `impl<T> Trait for T` or `impl<T> Trait for Box<T>`) can conflict
+with implementations in user code: the orphan rules allow user code to
+implement the trait for its own types, which can overlap with the new impl
+and cause a coherence error. Whether this happens depends on the bounds of the
+impl and on user code, so the change is classified as \"technically
+breaking\"."
+            }
+            DerefImplAdded { .. } => {
+                "Adding a `Deref` or `DerefMut` implementation to an existing type is a
+breaking change in some situations, because method calls and field accesses
+on the type now also autoderef to the target type. Calls of methods that
+the type doesn't have resolve to methods of the target type instead of
+failing, and methods of the target type can become ambiguous with trait
+methods in scope. This is rare enough to only be considered \"technically
+breaking\"."
+            }
+            TraitImplBreaksInference { .. } => {
+                "Adding a trait impl is a breaking change in some situations, because
+type inference in user code can rely on a trait having a single impl that
+fits. For instance, adding an impl `From<T> for String` makes the target
+type of `.into()` calls ambiguous where only the impl was used to infer it,
+and blanket impls do the same for calls of generic functions bounded by the
+trait. Such calls need type annotations once the impl is added. This is
+rare enough to only be considered \"technically breaking\"."
+            }
+            UnsizingImplRemoved { dispatch: false } => {
+                "Removing a `CoerceUnsized` implementation of a smart pointer type is a
+breaking change, as user code can no longer coerce it to a pointer to an
+unsized type, like `Ptr<[T]>` from `Ptr<[T; N]>` or `Ptr<dyn Trait>` from
+`Ptr<T>`."
+            }
+            UnsizingImplRemoved { dispatch: true } => {
+                "Removing a `DispatchFromDyn` implementation of a smart pointer type is a
+breaking change, as user code can no longer use it as the receiver of
+methods called on trait objects."
+            }
+            ConstValueChanged { .. } => {
+                "Changing the value of a constant is a breaking change in some specific
+situations, for example if user code uses it as an array length, or relies on
+it in other constant expressions or patterns. Because the constant remains
+usable in all other cases, this change is classified as \"technically
+breaking\"."
+            }
+            FnBodyChanged => {
+                "Changing the body of a function without changing it's signature doesn't
+affect the public interface of a crate. However, user code can depend on the
+behaviour of the function, so the change is reported as a hint to document it
+in the changelog or to consider a minor version bump."
+            }
+            SerdeFormatChanged { breaking: true, .. } => {
+                "Changing the serialized form of a type implementing serde's `Serialize` or
+`Deserialize` traits, for example by renaming or removing fields or variants,
+means data written by one version of the crate can't be read by the other.
+Crates persisting or exchanging such data break, so the change is
+considered breaking."
+            }
+            SerdeFormatChanged {
+                breaking: false, ..
+            } => {
+                "Extending the serialized form of a type implementing serde's `Serialize` or
+`Deserialize` traits, for example by adding variants or defaulted fields,
+keeps data written by the old version readable, but users of the old
+version can't read data written by the new one. Thus, the change is
+classified as \"technically breaking\"."
             }
             AssociatedItemAdded => {
                 "Adding a new item to an inherent impl is a breaking change in some
 specific situations, for example if this causes name clashes with a trait
 method. This is rare enough to only be considered \"technically
 breaking\"."
+            }
+            TraitMethodShadowed { .. } => {
+                "Adding an inherent method with the same name as a method of a trait
+implemented for the type is a breaking change in some situations, because
+inherent methods take precedence in method calls. Calls in user code that
+used to resolve to the trait method silently call the new method instead,
+or fail to compile if its signature differs. Calls using the trait's path
+are unaffected, so the change is classified as \"technically breaking\"."
             }
             AssociatedItemRemoved => {
                 "Removing an item from an inherent impl is a breaking change, as all old
 references to it become invalid."
             }
+            DeriveHelperRemoved { .. } => {
+                "Removing or renaming a helper attribute of a derive macro is a breaking
+change, because user code annotating fields or variants with the old
+attribute while deriving the trait stops compiling."
+            }
+            ArrayLengthChanged { .. } => {
+                "Changing the length of an array in an item's type is a breaking change,
+because user code passing or receiving arrays of the old length stops
+compiling."
+            }
+            TypeAliasChanged { .. } => {
+                "Changing the type a type alias expands to is a breaking change, because
+user code relying on the old type, as well as all items using the alias,
+are affected."
+            }
+            OpaqueBoundChanged { removed: true, .. } => {
+                "Removing a bound from an opaque type defined by a type alias is a breaking
+change, because user code relying on the values of the type implementing
+the bound stops compiling."
+            }
+            OpaqueBoundChanged { removed: false, .. } => {
+                "Adding a bound to an opaque type defined by a type alias is a non-breaking
+change, because values of the type can only be used in more ways than
+before."
+            }
+            AutoTraitChanged {
+                now_impl: false, ..
+            } => {
+                "An opaque type implements an auto trait like `Send` whenever its hidden
+type does. If the hidden type stops implementing it, user code relying on
+the auto trait, for instance by sending values across threads, stops
+compiling. Thus, the change is breaking."
+            }
+            AutoTraitChanged { now_impl: true, .. } => {
+                "An opaque type implements an auto trait like `Send` whenever its hidden
+type does. If the hidden type starts implementing it, values of the type
+can be used in more ways than before, which is a non-breaking change."
+            }
+            UpcastingRemoved { .. } => {
+                "Removing a supertrait of an object safe trait, directly or further up the
+trait hierarchy, is a breaking change, because user code upcasting trait
+objects of the trait to trait objects of the supertrait stops compiling."
+            }
+            ItemMoved { .. } => {
+                "Moving an item to a different module without re-exporting it at its old
+path is a breaking change, because all old references to the item through
+its old path become invalid."
+            }
+            ItemProbablyRenamed { .. } => {
+                "Renaming an item is a breaking change, because all old references to the
+item through its old name become invalid. A removed item is assumed to have
+been renamed if a single added item has a similar name and the same shape,
+or, for types and traits, the same fields and associated items. As this is a
+guess, the rename can be stated explicitly using `--renames`."
+            }
+            MustUseAdded => {
+                "Marking a function or type `#[must_use]` doesn't affect the public interface
+of a crate. However, user code ignoring the results of the function, or values
+of the type, starts to trigger the `unused_must_use` lint, which fails builds
+denying warnings. Thus, the change is reported as a hint."
+            }
+            TrackCallerChanged { .. } => {
+                "Adding or removing `#[track_caller]` on a function doesn't affect the public
+interface of a crate. However, it changes the location reported when the
+function panics, and makes function pointers to it go through a shim with a
+different ABI. Thus, the change is reported as a hint."
+            }
+            PanicIntroduced => {
+                "A function calling panicking functions like `unwrap` where it didn't before
+doesn't change the public interface of a crate. However, user code calling
+the function can now panic in cases it previously handled. The detection is
+heuristic, so the change is reported as a hint to review it and mention it
+in the changelog."
+            }
+            RustVersionExceeded { .. } => {
+                "Using a standard library item stabilized after the minimum supported Rust
+version declared in the `rust-version` field of the manifest breaks users on
+older toolchains, even though cargo accepts them. Raising the minimum
+supported Rust version is commonly considered a minor change, so this is
+classified as \"technically breaking\", and the declared version should be
+raised as well."
+            }
+            MacroRuleChanged {
+                kind: MacroRuleChange::Removed | MacroRuleChange::MatcherChanged,
+                ..
+            } => {
+                "Removing or changing a rule of an exported macro can cause invocations in
+user code to no longer match any rule, unless they are accepted by other
+rules. As this can't be ruled out reliably, the change is breaking, and is
+reported along with a diff of the rule's tokens to judge the actual impact."
+            }
+            MacroRuleChanged {
+                kind: MacroRuleChange::Added,
+                ..
+            } => {
+                "Adding a rule to an exported macro lets it accept more invocations, which is
+a non-breaking change, unless the new rule shadows a later one."
+            }
+            MacroRuleChanged {
+                kind: MacroRuleChange::TranscriberChanged,
+                ..
+            } => {
+                "Changing the expansion of a rule of an exported macro doesn't change the
+invocations it accepts, but the expanded code can behave differently, so
+the change is reported as a hint."
+            }
+            WasmExportChanged {
+                kind: WasmExportChange::Removed | WasmExportChange::SignatureChanged,
+                ..
+            } => {
+                "The JavaScript bindings generated by `wasm-bindgen` call the functions
+exported from the WebAssembly module by name. Removing an export, or
+changing the types it's called with, breaks JavaScript code using the
+bindings, even if the Rust API is unchanged, so the change is breaking."
+            }
+            WasmExportChanged {
+                kind: WasmExportChange::Added,
+                ..
+            } => {
+                "Adding a function to the exports of the WebAssembly module extends the
+JavaScript bindings generated by `wasm-bindgen`, which is a non-breaking
+change."
+            }
+            Custom { .. } => {
+                "This change has been found by a custom check, which enforces a policy of the
+crate's maintainers rather than a rule of the language. Refer to the
+documentation of the check for its rationale."
+            }
+            AnalysisFailed { .. } => {
+                "The item couldn't be compared to its counterpart, because the compiler
+panicked while doing so, or the comparison exceeded its time limit. It may
+have changed in any way, so it should be checked by hand."
+            }
+            Recategorized { ref change, .. } | UnstableFeatureGated { ref change, .. } => {
+                change.explanation()
+            }
             Unknown => "No explanation for unknown changes.",
         }
     }
@@ -616,6 +1470,9 @@ impl<'a> fmt::Display for ChangeType<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let desc = match *self {
             ItemMadePublic => "item made public",
+            PublicApiAdded { ref line } => {
+                return write!(f, "added to the public API: `{}`", line);
+            }
             ItemMadePrivate => "item made private",
             KindDifference => "item kind changed",
             StaticMutabilityChanged { now_mut: true } => "static item made mutable",
@@ -634,48 +1491,115 @@ impl<'a> fmt::Display for ChangeType<'a> {
             TypeParameterAdded { defaulted: false } => "type parameter added",
             TypeParameterRemoved { defaulted: true } => "defaulted type parameter removed",
             TypeParameterRemoved { defaulted: false } => "type parameter removed",
+            ConstParamDefaultChanged {
+                name,
+                old: Some(ref old),
+                new: Some(ref new),
+            } => {
+                return write!(
+                    f,
+                    "default of const parameter `{}` changed from `{}` to `{}`",
+                    name, old, new
+                );
+            }
+            ConstParamDefaultChanged {
+                name,
+                new: Some(ref new),
+                ..
+            } => {
+                return write!(f, "default `{}` added to const parameter `{}`", new, name);
+            }
+            ConstParamDefaultChanged { name, .. } => {
+                return write!(f, "default removed from const parameter `{}`", name);
+            }
+            AlignmentReduced { old, new } => {
+                return write!(f, "alignment reduced from {} to {} bytes", old, new);
+            }
+            PackingChanged { old, new } => {
+                let render = |pack: Option<u64>| match pack {
+                    Some(1) => "`packed`".to_owned(),
+                    Some(pack) => format!("`packed({})`", pack),
+                    None => "no packing".to_owned(),
+                };
+
+                return write!(f, "packing changed from {} to {}", render(old), render(new));
+            }
             VariantAdded => "enum variant added",
             VariantRemoved => "enum variant removed",
             VariantFieldAdded {
                 public: true,
                 total_public: true,
                 is_enum: true,
+                constructible: false,
+            } => "public field added to non-exhaustive variant",
+            VariantFieldAdded {
+                public: true,
+                total_public: true,
+                is_enum: false,
+                constructible: false,
+            } => "public field added to non-exhaustive struct",
+            VariantFieldAdded {
+                public: false,
+                total_public: true,
+                is_enum: true,
+                constructible: false,
+            } => "private field added to non-exhaustive variant",
+            VariantFieldAdded {
+                public: false,
+                total_public: true,
+                is_enum: false,
+                constructible: false,
+            } => "private field added to non-exhaustive struct",
+            VariantFieldAdded {
+                public: true,
+                total_public: true,
+                is_enum: true,
+                ..
             } => "public field added to variant with no private fields",
             VariantFieldAdded {
                 public: true,
                 total_public: true,
                 is_enum: false,
+                ..
             } => "public field added to struct with no private fields",
             VariantFieldAdded {
                 public: true,
                 total_public: false,
                 is_enum: true,
+                ..
             } => "public field added to variant with private fields",
             VariantFieldAdded {
                 public: true,
                 total_public: false,
                 is_enum: false,
+                ..
             } => "public field added to struct with private fields",
             VariantFieldAdded {
                 public: false,
                 total_public: true,
                 is_enum: true,
+                ..
             } => "private field added to variant with no private fields",
             VariantFieldAdded {
                 public: false,
                 total_public: true,
                 is_enum: false,
+                ..
             } => "private field added to struct with no private fields",
             VariantFieldAdded {
                 public: false,
                 total_public: false,
                 is_enum: true,
+                ..
             } => "private field added to variant with private fields",
             VariantFieldAdded {
                 public: false,
                 total_public: false,
                 is_enum: false,
+                ..
             } => "private field added to struct with private fields",
+            FieldMadePrivate { hidden: false } => "public field made private",
+            FieldMadePrivate { hidden: true } => "public field hidden from documentation",
             VariantFieldRemoved {
                 public: true,
                 total_public: true,
@@ -758,6 +1682,12 @@ impl<'a> fmt::Display for ChangeType<'a> {
             } => "struct with public fields changed to a tuple struct",
             FnConstChanged { now_const: true } => "fn item made const",
             FnConstChanged { now_const: false } => "fn item made non-const",
+            ReturnTypeOpacityChanged { now_opaque: true } => {
+                "return type changed from a named type to `impl Trait`"
+            }
+            ReturnTypeOpacityChanged { now_opaque: false } => {
+                "return type changed from `impl Trait` to a named type"
+            }
             MethodSelfChanged { now_self: true } => "added self-argument to method",
             MethodSelfChanged { now_self: false } => "removed self-argument from method",
             TraitItemAdded {
@@ -773,7 +1703,23 @@ impl<'a> fmt::Display for ChangeType<'a> {
             TraitUnsafetyChanged { now_unsafe: true } => "trait made unsafe",
             TraitUnsafetyChanged { now_unsafe: false } => "trait no longer unsafe",
             TypeChanged { ref error } => return write!(f, "type error: {}", error),
-            BoundsTightened { ref pred } => return write!(f, "added bound: `{}`", pred),
+            DependencyVersionSkew { krate, ref path } => {
+                return write!(
+                    f,
+                    "`{}` refers to a different version of `{}`, and is a different type",
+                    path, krate
+                );
+            }
+            BoundsTightened {
+                ref pred,
+                trait_def,
+            } => {
+                if trait_def {
+                    return write!(f, "added bound on trait definition: `{}`", pred);
+                } else {
+                    return write!(f, "added bound: `{}`", pred);
+                }
+            }
             BoundsLoosened {
                 ref pred,
                 trait_def,
@@ -784,24 +1730,243 @@ impl<'a> fmt::Display for ChangeType<'a> {
                     return write!(f, "removed bound: `{}`", pred);
                 }
             }
+            SizedRelaxationChanged {
+                ref param,
+                relaxed: false,
+                ..
+            } => {
+                return write!(
+                    f,
+                    "removed `?Sized` from `{}`, unsized types such as `str` or slices are no \
+                     longer accepted",
+                    param
+                );
+            }
+            SizedRelaxationChanged {
+                ref param,
+                trait_def,
+                ..
+            } => {
+                if trait_def {
+                    return write!(f, "added `?Sized` to `{}` on trait definition", param);
+                } else {
+                    return write!(f, "added `?Sized` to `{}`", param);
+                }
+            }
+            LifetimeBoundAdded { ref pred } => {
+                use rustc_middle::ty::{OutlivesPredicate, PredicateKind};
+
+                return match pred.kind().skip_binder() {
+                    PredicateKind::TypeOutlives(OutlivesPredicate(ty, region))
+                        if region.is_static() =>
+                    {
+                        write!(
+                            f,
+                            "added lifetime bound: `{}`, `{}` can no longer be a type \
+                             borrowing non-`'static` data",
+                            pred, ty
+                        )
+                    }
+                    PredicateKind::TypeOutlives(OutlivesPredicate(ty, region)) => write!(
+                        f,
+                        "added lifetime bound: `{}`, `{}` can no longer be a type borrowing \
+                         data for less than `{}`",
+                        pred, ty, region
+                    ),
+                    PredicateKind::RegionOutlives(OutlivesPredicate(a, b)) => write!(
+                        f,
+                        "added lifetime bound: `{}`, `{}` can no longer be a lifetime shorter \
+                         than `{}`",
+                        pred, a, b
+                    ),
+                    _ => write!(f, "added lifetime bound: `{}`", pred),
+                };
+            }
             TraitImplTightened => "trait impl specialized or removed",
+            InherentImplTightened { ref pred } => {
+                return write!(f, "added bound on inherent impl: `{}`", pred);
+            }
             TraitImplLoosened => "trait impl generalized or newly added",
+            TraitImplAssocTypeChanged {
+                name,
+                ref old,
+                ref new,
+            } => {
+                return write!(
+                    f,
+                    "associated type `{}` changed from `{}` to `{}`",
+                    name, old, new
+                );
+            }
+            DefaultnessChanged {
+                name,
+                now_default: false,
+            } => {
+                return write!(
+                    f,
+                    "`default` removed from `{}`, specializing impls can no longer override it",
+                    name
+                );
+            }
+            DefaultnessChanged {
+                name,
+                now_default: true,
+            } => {
+                return write!(
+                    f,
+                    "`{}` made `default`, specializing impls can now override it",
+                    name
+                );
+            }
+            CallImplRemoved { trait_name } => {
+                return write!(
+                    f,
+                    "`{}` impl removed, values can no longer be called",
+                    trait_name
+                );
+            }
+            CallSignatureChanged {
+                trait_name,
+                ref old,
+                ref new,
+            } => {
+                return write!(
+                    f,
+                    "arguments of `{}` impl changed from `{}` to `{}`",
+                    trait_name, old, new
+                );
+            }
+            TraitImplConstChanged { now_const: true } => "trait impl made const",
+            TraitImplConstChanged { now_const: false } => "trait impl made non-const",
+            TraitImplPossiblyConflicting => "trait impl could overlap with impls in user code",
+            DerefImplAdded { mutable: false } => {
+                "`Deref` impl added, method calls can now resolve through autoderef"
+            }
+            DerefImplAdded { mutable: true } => {
+                "`DerefMut` impl added, method calls can now resolve through autoderef"
+            }
+            TraitImplBreaksInference { calls } => {
+                return write!(f, "trait impl can break type inference in {}", calls);
+            }
+            UnsizingImplRemoved { dispatch: false } => {
+                "unsizing coercion impl removed (`CoerceUnsized`)"
+            }
+            UnsizingImplRemoved { dispatch: true } => {
+                "dynamic dispatch impl removed (`DispatchFromDyn`)"
+            }
+            ConstValueChanged { ref old, ref new } => {
+                return write!(f, "const value changed from `{}` to `{}`", old, new);
+            }
+            FnBodyChanged => "function body changed",
+            SerdeFormatChanged { ref desc, .. } => {
+                return write!(f, "serialized form changed: {}", desc);
+            }
             AssociatedItemAdded => "added item in inherent impl",
+            TraitMethodShadowed { ref trait_path } => {
+                return write!(f, "method shadows the method of trait `{}`", trait_path);
+            }
             AssociatedItemRemoved => "removed item in inherent impl",
-            Unknown => "unknown change",
-        };
-        write!(f, "{}", desc)
-    }
-}
-
-impl<'tcx> Serialize for ChangeType<'tcx> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&format!("{}", self))
-    }
-}
+            DeriveHelperRemoved {
+                name,
+                renamed_to: Some(new_name),
+            } => {
+                return write!(
+                    f,
+                    "derive helper attribute `#[{}]` renamed to `#[{}]`",
+                    name, new_name
+                );
+            }
+            DeriveHelperRemoved { name, .. } => {
+                return write!(f, "derive helper attribute `#[{}]` removed", name);
+            }
+            ArrayLengthChanged { old, new } => {
+                return write!(f, "array length changed from {} to {}", old, new);
+            }
+            TypeAliasChanged { ref old, ref new } => {
+                return write!(f, "aliased type changed from `{}` to `{}`", old, new);
+            }
+            OpaqueBoundChanged { ref bound, removed } => {
+                let action = if removed { "removed from" } else { "added to" };
+                return write!(f, "bound `{}` {} opaque type", bound, action);
+            }
+            AutoTraitChanged { name, now_impl } => {
+                let state = if now_impl { "now" } else { "no longer" };
+                return write!(f, "auto trait `{}` {} implemented", name, state);
+            }
+            UpcastingRemoved { ref supertrait } => {
+                return write!(
+                    f,
+                    "trait objects can no longer be upcast to `dyn {}`",
+                    supertrait
+                );
+            }
+            ItemMoved {
+                ref old_path,
+                ref new_path,
+            } => {
+                return write!(f, "item moved from `{}` to `{}`", old_path, new_path);
+            }
+            ItemProbablyRenamed {
+                ref old_path,
+                ref new_path,
+            } => {
+                return write!(
+                    f,
+                    "item probably renamed from `{}` to `{}`",
+                    old_path, new_path
+                );
+            }
+            MustUseAdded => "`#[must_use]` added",
+            TrackCallerChanged { now_tracked: true } => "`#[track_caller]` added",
+            TrackCallerChanged { now_tracked: false } => "`#[track_caller]` removed",
+            PanicIntroduced => "function can now panic (behavioral risk)",
+            RustVersionExceeded { ref path, since } => {
+                return write!(
+                    f,
+                    "uses `{}`, stable since Rust {}, above the declared `rust-version`",
+                    path, since
+                );
+            }
+            MacroRuleChanged { ref desc, .. } | WasmExportChanged { ref desc, .. } => {
+                return write!(f, "{}", desc);
+            }
+            Custom {
+                ref check,
+                ref desc,
+                ..
+            } => {
+                return write!(f, "{} (found by `{}`)", desc, check);
+            }
+            Recategorized { ref change, .. } => {
+                return write!(f, "{} (adjusted by external rules)", change);
+            }
+            UnstableFeatureGated {
+                ref change,
+                ref feature,
+            } => {
+                return write!(
+                    f,
+                    "{} (gated behind unstable feature `{}`)",
+                    change, feature
+                );
+            }
+            AnalysisFailed { ref reason } => {
+                return write!(f, "could not analyze item: {}", reason);
+            }
+            Unknown => "unknown change",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+impl<'tcx> Serialize for ChangeType<'tcx> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{}", self))
+    }
+}
 
 /// A change record of an item present in both crate versions.
 ///
@@ -818,6 +1983,16 @@ pub struct Change<'tcx> {
     new_span: Span,
     /// Whether to output changes. Used to distinguish all-private items.
     output: bool,
+    /// The paths of other items whose changes are caused by this one.
+    affected: BTreeSet<String>,
+    /// The paths of other items whose changes caused type changes of this one.
+    causes: BTreeSet<String>,
+    /// The paths of the public items mentioning this one, directly or through other items, if
+    /// the impact of its breaking changes is reported.
+    mentioned_by: BTreeSet<String>,
+    /// The paths of items referred to by this one that couldn't be related to their
+    /// counterparts, making the verdict on this item unreliable.
+    unrelated: BTreeSet<String>,
 }
 
 impl<'tcx> Change<'tcx> {
@@ -829,9 +2004,20 @@ impl<'tcx> Change<'tcx> {
             name,
             new_span: span,
             output,
+            affected: BTreeSet::new(),
+            causes: BTreeSet::new(),
+            mentioned_by: BTreeSet::new(),
+            unrelated: BTreeSet::new(),
         }
     }
 
+    /// Get the change types affecting the item, sorted by their kind.
+    fn sorted_changes(&self) -> Vec<&(ChangeType<'tcx>, Option<Span>)> {
+        let mut changes: Vec<_> = self.changes.iter().collect();
+        changes.sort_by_cached_key(|(type_, _)| (type_.code(), type_.to_string()));
+        changes
+    }
+
     /// Insert another change type into an existing path change record.
     fn insert(&mut self, type_: ChangeType<'tcx>, span: Option<Span>) {
         let cat = type_.to_category();
@@ -854,14 +2040,25 @@ impl<'tcx> Change<'tcx> {
                 | KindDifference
                 | RegionParameterRemoved
                 | TypeParameterRemoved { .. }
+                | ConstParamDefaultChanged { old: Some(_), .. }
                 | VariantAdded
                 | VariantRemoved
                 | VariantFieldAdded { .. }
+                | FieldMadePrivate { .. }
                 | VariantFieldRemoved { .. }
                 | VariantStyleChanged { .. }
                 | TypeChanged { .. }
+                | DependencyVersionSkew { .. }
+                | ArrayLengthChanged { .. }
+                | TypeAliasChanged { .. }
+                | OpaqueBoundChanged { removed: true, .. }
+                | AutoTraitChanged {
+                    now_impl: false, ..
+                }
                 | FnConstChanged { now_const: false }
                 | MethodSelfChanged { now_self: false }
+                | ReturnTypeOpacityChanged { now_opaque: true }
+                | AnalysisFailed { .. }
                 | Unknown => return true,
                 StaticMutabilityChanged { .. }
                 | RegionParameterAdded
@@ -869,18 +2066,54 @@ impl<'tcx> Change<'tcx> {
                 | TraitItemAdded { .. }
                 | TraitItemRemoved { .. }
                 | ItemMadePublic
+                | PublicApiAdded { .. }
                 | VarianceLoosened
                 | VarianceTightened
                 | VarianceChanged { .. }
                 | TypeParameterAdded { .. }
+                | ConstParamDefaultChanged { old: None, .. }
                 | TraitUnsafetyChanged { .. }
                 | FnConstChanged { now_const: true }
                 | BoundsTightened { .. }
                 | BoundsLoosened { .. }
+                | LifetimeBoundAdded { .. }
+                | SizedRelaxationChanged { .. }
                 | TraitImplTightened
+                | InherentImplTightened { .. }
+                | ReturnTypeOpacityChanged { now_opaque: false }
                 | TraitImplLoosened
+                | TraitImplAssocTypeChanged { .. }
+                | TraitImplConstChanged { .. }
+                | CallImplRemoved { .. }
+                | CallSignatureChanged { .. }
+                | DefaultnessChanged { .. }
+                | TraitImplPossiblyConflicting
+                | DerefImplAdded { .. }
+                | TraitImplBreaksInference { .. }
+                | UnsizingImplRemoved { .. }
+                | AlignmentReduced { .. }
+                | PackingChanged { .. }
+                | ConstValueChanged { .. }
+                | FnBodyChanged
+                | SerdeFormatChanged { .. }
                 | AssociatedItemAdded
-                | AssociatedItemRemoved => (),
+                | TraitMethodShadowed { .. }
+                | AssociatedItemRemoved
+                | DeriveHelperRemoved { .. }
+                | ItemMoved { .. }
+                | ItemProbablyRenamed { .. }
+                | OpaqueBoundChanged { removed: false, .. }
+                | AutoTraitChanged { now_impl: true, .. }
+                | UpcastingRemoved { .. }
+                | MustUseAdded
+                | TrackCallerChanged { .. }
+                | PanicIntroduced
+                | RustVersionExceeded { .. }
+                | MacroRuleChanged { .. }
+                | WasmExportChanged { .. }
+                | Custom { .. }
+                | Recategorized { .. }
+                | UnstableFeatureGated { .. } => (),
             }
         }
 
@@ -898,13 +2131,36 @@ impl<'tcx> Change<'tcx> {
     }
 
     /// Report the change in a structured manner, using rustc's error reporting capabilities.
-    fn report(&self, session: &Session, verbose: bool) {
-        if self.max == Patch || !self.output {
+    fn report(&self, session: &Session, config: &Config) {
+        if !self.output {
+            return;
+        }
+
+        let severity = self
+            .changes
+            .iter()
+            .map(|change| change.0.severity(config))
+            .max()
+            .unwrap_or(Severity::Allow);
+
+        // an item without reported changes could still differ in ways that went unnoticed
+        if severity == Severity::Allow {
+            if !self.unrelated.is_empty() {
+                let msg = format!("unreliable verdict for {}", self.name);
+                let mut builder = session.struct_span_warn(self.new_span, &msg);
+
+                for note in self.unrelated_notes() {
+                    builder.note(&note);
+                }
+
+                builder.emit();
+            }
+
             return;
         }
 
         let msg = format!("{} changes in {}", self.max, self.name);
-        let mut builder = if self.max == Breaking {
+        let mut builder = if severity == Severity::Deny {
             session
                 .struct_span_err(self.new_span, &msg)
                 .forget_guarantee()
@@ -914,19 +2170,31 @@ impl<'tcx> Change<'tcx> {
 
         for change in &self.changes {
             let cat = change.0.to_category();
-            let sub_msg = if verbose {
-                format!("{} ({}):\n{}", change.0, cat, change.0.explanation())
+            let sub_severity = change.0.severity(config);
+
+            if sub_severity == Severity::Allow {
+                continue;
+            }
+
+            let sub_msg = if config.verbose {
+                format!(
+                    "{} ({}):\n{}\nfor more information, run `cargo semver explain {}`",
+                    change.0,
+                    cat,
+                    change.0.explanation(),
+                    change.0.code()
+                )
             } else {
                 format!("{} ({})", change.0, cat)
             };
 
             if let Some(span) = change.1 {
-                if cat == Breaking {
+                if sub_severity == Severity::Deny {
                     builder.span_warn(span, &sub_msg);
                 } else {
                     builder.span_note(span, &sub_msg);
                 }
-            } else if cat == Breaking {
+            } else if sub_severity == Severity::Deny {
                 // change.1 == None from here on.
                 builder.warn(&sub_msg);
             } else {
@@ -934,8 +2202,76 @@ impl<'tcx> Change<'tcx> {
             }
         }
 
+        for cause in &self.causes {
+            builder.note(&format!("caused by change to `{}`", cause));
+        }
+
+        if !self.affected.is_empty() {
+            let affected: Vec<_> = self.affected.iter().map(|p| format!("`{}`", p)).collect();
+
+            builder.note(&format!(
+                "{} other item{} changed as a result: {}",
+                affected.len(),
+                if affected.len() == 1 { "" } else { "s" },
+                affected.join(", ")
+            ));
+        }
+
+        if !self.mentioned_by.is_empty() {
+            let mentioned_by: Vec<_> = self
+                .mentioned_by
+                .iter()
+                .map(|p| format!("`{}`", p))
+                .collect();
+
+            builder.note(&format!(
+                "mentioned by {} public item{}, directly or through other items: {}",
+                mentioned_by.len(),
+                if mentioned_by.len() == 1 { "" } else { "s" },
+                mentioned_by.join(", ")
+            ));
+        }
+
+        for note in self.unrelated_notes() {
+            builder.note(&note);
+        }
+
         builder.emit();
     }
+
+    /// Get a note for each item that couldn't be related to its counterpart.
+    fn unrelated_notes(&self) -> impl Iterator<Item = String> + '_ {
+        self.unrelated.iter().map(|path| {
+            format!(
+                "could not relate `{}` to the other version, the verdict is unreliable",
+                path
+            )
+        })
+    }
+
+    /// Get the reasons the item couldn't be analyzed completely, if any.
+    ///
+    /// Changes to such an item can go unnoticed, so its verdict is unknown.
+    fn blind_spots(&self) -> Vec<String> {
+        let mut reasons: Vec<_> = self
+            .changes
+            .iter()
+            .filter_map(|(type_, _)| match *type_ {
+                AnalysisFailed { ref reason } => {
+                    Some(format!("could not analyze item: {}", reason))
+                }
+                _ => None,
+            })
+            .collect();
+
+        reasons.extend(
+            self.unrelated
+                .iter()
+                .map(|path| format!("could not relate `{}` to the other version", path)),
+        );
+
+        reasons
+    }
 }
 
 impl<'tcx> PartialEq for Change<'tcx> {
@@ -965,19 +2301,23 @@ impl<'a, 'tcx> Serialize for RChange<'a, 'tcx> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Change", 4)?;
+        let mut state = serializer.serialize_struct("Change", 8)?;
         state.serialize_field("name", &self.1.name)?;
         state.serialize_field("max_category", &self.1.max)?;
         state.serialize_field("new_span", &RSpan(self.0, &self.1.new_span))?;
 
         let changes: Vec<_> = self
             .1
-            .changes
-            .iter()
+            .sorted_changes()
+            .into_iter()
             .map(|(t, s)| (t, s.as_ref().map(|s| RSpan(self.0, s))))
             .collect();
 
         state.serialize_field("changes", &changes)?;
+        state.serialize_field("affected", &self.1.affected)?;
+        state.serialize_field("caused_by", &self.1.causes)?;
+        state.serialize_field("mentioned_by", &self.1.mentioned_by)?;
+        state.serialize_field("unrelated", &self.1.unrelated)?;
         state.end()
     }
 }
@@ -986,11 +2326,21 @@ impl<'a, 'tcx> Serialize for RChange<'a, 'tcx> {
 #[derive(Default)]
 pub struct ChangeSet<'tcx> {
     /// The set of currently recorded path changes.
-    path_changes: HashMap<DefId, PathChange>,
+    path_changes: BTreeMap<DefId, PathChange>,
     /// The set of currently recorded regular changes.
-    changes: HashMap<DefId, Change<'tcx>>,
+    changes: BTreeMap<DefId, Change<'tcx>>,
     /// The mapping of spans to changes, for ordering purposes.
     spans: BTreeMap<Span, DefId>,
+    /// The changes to the crate manifest.
+    manifest_changes: Vec<ManifestChange>,
+    /// The pairs of old and new paths of removed and added items that could have been renamed,
+    /// but weren't matched up, as other items could have been renamed as well.
+    rename_candidates: BTreeSet<(String, String)>,
+    /// The public items mentioning each item in their signatures, types, fields or bounds.
+    mentions: BTreeMap<DefId, BTreeSet<DefId>>,
+    /// The paths of the public items and of the changed items, used to render the items
+    /// affected by breaking changes and to sort JSON output.
+    item_paths: BTreeMap<DefId, String>,
     /// The most severe change category already recorded.
     max: ChangeCategory,
 }
@@ -1025,6 +2375,11 @@ impl<'tcx> ChangeSet<'tcx> {
         self.path_changes.get_mut(&old).unwrap().insert(span, add);
     }
 
+    /// Suggest an added item with a similar name as a replacement of a removed one.
+    pub fn add_path_suggestion(&mut self, old: DefId, path: String) {
+        self.path_changes.get_mut(&old).unwrap().suggestion = Some(path);
+    }
+
     /// Add a new change entry for the given item pair.
     pub fn new_change(
         &mut self,
@@ -1061,6 +2416,31 @@ impl<'tcx> ChangeSet<'tcx> {
         self.changes.get_mut(&old).unwrap().insert(type_, span);
     }
 
+    /// Add a change to the crate manifest.
+    pub fn add_manifest_change(&mut self, change: ManifestChange) {
+        let cat = change.to_category();
+
+        if cat > self.max {
+            self.max = cat;
+        }
+
+        self.manifest_changes.push(change);
+    }
+
+    /// Record the path of an item whose changes caused type changes of an existing entry.
+    pub fn add_cause(&mut self, old: DefId, cause: String) {
+        if let Some(change) = self.changes.get_mut(&old) {
+            change.causes.insert(cause);
+        }
+    }
+
+    /// Record an item that couldn't be related to its counterpart while comparing another one.
+    pub fn add_unrelated(&mut self, old: DefId, path: String) {
+        if let Some(change) = self.changes.get_mut(&old) {
+            change.unrelated.insert(path);
+        }
+    }
+
     /// Check whether the changes associated with a `DefId` will be reported.
     pub fn get_output(&self, old: DefId) -> bool {
         self.changes.get(&old).map_or(true, |change| change.output)
@@ -1080,6 +2460,70 @@ impl<'tcx> ChangeSet<'tcx> {
         }
     }
 
+    /// Print the path changes recorded so far on stderr, as soon as all items are matched up.
+    ///
+    /// Only the changes the final report would include are printed, taking severities and the
+    /// `only` and `exclude` patterns into account, given the path of each item.
+    pub fn stream_paths(&self, session: &Session, config: &Config, path: impl Fn(DefId) -> String) {
+        for (&def_id, change) in &self.path_changes {
+            let reported = change
+                .exports()
+                .any(|(_, cat, _)| change.severity(config, cat) != Severity::Allow);
+
+            if reported && config.path_included(&path(def_id)) {
+                session.note_without_error(&format!(
+                    "found: path changes to `{}` ({})",
+                    change.name.0,
+                    change.to_category()
+                ));
+            }
+        }
+    }
+
+    /// Print the changes recorded for an item on stderr, as soon as it has been compared.
+    ///
+    /// Later passes can still add to, filter or collapse these changes, so the report printed
+    /// at the end remains the authoritative one.
+    pub fn stream_item(&self, session: &Session, config: &Config, old: DefId, path: &str) {
+        if let Some(change) = self.changes.get(&old) {
+            if config.path_included(path) {
+                Self::stream_change(session, config, change, path);
+            }
+        }
+    }
+
+    /// Print the changes recorded for trait and inherent impls on stderr, once they have been
+    /// compared, given the path of each impl to match against the `only` and `exclude` patterns.
+    pub fn stream_impls(&self, session: &Session, config: &Config, path: impl Fn(DefId) -> String) {
+        for (&def_id, change) in &self.changes {
+            if let Name::ImplDesc(ref desc) = change.name {
+                if config.path_included(&path(def_id)) {
+                    Self::stream_change(session, config, change, desc);
+                }
+            }
+        }
+    }
+
+    /// Print the changes to an item or impl that the final report would include.
+    fn stream_change(session: &Session, config: &Config, change: &Change, name: &str) {
+        if !change.output {
+            return;
+        }
+
+        for (type_, _) in change.sorted_changes() {
+            let cat = type_.to_category();
+
+            if type_.severity(config) != Severity::Allow {
+                session.note_without_error(&format!("found: {}: {} ({})", name, type_, cat));
+            }
+        }
+    }
+
+    /// Check whether an entry for the item or impl with the given id exists.
+    pub fn has_change(&self, old: DefId) -> bool {
+        self.changes.contains_key(&old)
+    }
+
     /// Check whether an item with the given id has undergone breaking changes.
     ///
     /// The expected `DefId` is obviously an *old* one.
@@ -1090,6 +2534,86 @@ impl<'tcx> ChangeSet<'tcx> {
             .map_or(false, |change| change.to_category() == Breaking)
     }
 
+    /// Check whether an item with the given id has undergone breaking changes other than type
+    /// changes (including changed array lengths), which can cause type changes in items
+    /// mentioning it.
+    pub fn item_structurally_breaking(&self, old: DefId) -> bool {
+        self.changes.get(&old).map_or(false, |change| {
+            change.output
+                && change.changes.iter().any(|(type_, _)| {
+                    type_.to_category() == Breaking
+                        && !matches!(
+                            type_,
+                            TypeChanged { .. }
+                                | DependencyVersionSkew { .. }
+                                | ArrayLengthChanged { .. }
+                        )
+                })
+        })
+    }
+
+    /// Get the reported items with breaking changes other than type changes.
+    pub fn structurally_breaking_items(&self) -> Vec<DefId> {
+        self.changes
+            .keys()
+            .copied()
+            .filter(|&def_id| self.item_structurally_breaking(def_id))
+            .collect()
+    }
+
+    /// Replace the type changes (including changed array lengths) recorded for an item with a
+    /// single, more descriptive change.
+    ///
+    /// Nothing is recorded if the item has no type changes.
+    pub fn replace_type_changes(&mut self, old: DefId, type_: ChangeType<'tcx>) {
+        if let Some(change) = self.changes.get_mut(&old) {
+            let len = change.changes.len();
+            change.changes.retain(|(type_, _)| {
+                !matches!(
+                    type_,
+                    TypeChanged { .. } | DependencyVersionSkew { .. } | ArrayLengthChanged { .. }
+                )
+            });
+
+            if change.changes.len() < len {
+                change.insert(type_, None);
+            }
+        }
+    }
+
+    /// Get the reported items whose changes consist of type changes only.
+    pub fn type_changed_items(&self) -> Vec<DefId> {
+        self.changes
+            .iter()
+            .filter(|(_, change)| {
+                change.output
+                    && !change.changes.is_empty()
+                    && change.changes.iter().all(|(type_, _)| {
+                        matches!(
+                            type_,
+                            TypeChanged { .. }
+                                | DependencyVersionSkew { .. }
+                                | ArrayLengthChanged { .. }
+                        )
+                    })
+            })
+            .map(|(def_id, _)| *def_id)
+            .collect()
+    }
+
+    /// Report the changes to an item as part of the changes to the item causing them.
+    ///
+    /// The changes are kept, but no longer reported on their own.
+    pub fn collapse(&mut self, old: DefId, root: DefId, path: String) {
+        if let Some(change) = self.changes.get_mut(&old) {
+            change.output = false;
+        }
+
+        if let Some(change) = self.changes.get_mut(&root) {
+            change.affected.insert(path);
+        }
+    }
+
     /// Check whether a trait item contains breaking changes preventing further analysis of it's
     /// child items.
     pub fn trait_item_breaking(&self, old: DefId) -> bool {
@@ -1098,10 +2622,358 @@ impl<'tcx> ChangeSet<'tcx> {
             .map_or(false, Change::trait_item_breaking)
     }
 
-    fn get_new_version(&self, version: &str) -> Option<String> {
-        if let Ok(mut new_version) = Version::parse(version) {
-            if new_version.major == 0 {
+    /// Only keep the changes to items for which the given predicate holds.
+    ///
+    /// The most severe change category is recomputed from the remaining changes.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(DefId) -> bool,
+    {
+        self.path_changes.retain(|def_id, _| f(*def_id));
+        self.changes.retain(|def_id, _| f(*def_id));
+
+        self.update_max();
+    }
+
+    /// Recompute the most severe change category after changes have been dropped or adjusted.
+    fn update_max(&mut self) {
+        self.max = self
+            .path_changes
+            .values()
+            .map(PathChange::to_category)
+            .chain(
+                self.changes
+                    .values()
+                    .filter(|change| change.output)
+                    .map(Change::to_category),
+            )
+            .chain(
+                self.manifest_changes
+                    .iter()
+                    .map(ManifestChange::to_category),
+            )
+            .max()
+            .unwrap_or(Patch);
+    }
+
+    /// Let an external process adjust the reported changes to items and their exports.
+    ///
+    /// The command is given as the program to run, followed by its arguments. Every change is
+    /// written to the standard input of the process as a JSON object on a line of its own,
+    /// holding an `id`, the `path` of the item, and the `code`, `category`, `description` and
+    /// `span` of the change. Removed and added exports have no code. The process answers with a
+    /// JSON object per line for each change to adjust, holding its `id` and either a new
+    /// `category`, or `remove` set to `true` to drop it. Changes it doesn't answer for are kept
+    /// as they are.
+    pub fn apply_external_rules(
+        &mut self,
+        session: &Session,
+        command: &[String],
+        path: impl Fn(DefId) -> String,
+    ) -> Result<(), String> {
+        #[derive(Serialize)]
+        struct Input<'a> {
+            id: usize,
+            path: String,
+            code: Option<&'static str>,
+            category: ChangeCategory,
+            description: String,
+            span: RSpan<'a>,
+        }
+
+        #[derive(Deserialize)]
+        struct Adjustment {
+            id: usize,
+            category: Option<ChangeCategory>,
+            #[serde(default)]
+            remove: bool,
+        }
+
+        /// What an id passed to the process refers to.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+        enum Target {
+            /// The change with the given index in the entry of an item.
+            Change(DefId, usize),
+            /// The removed or added export of an item with the given span.
+            Export(DefId, Span),
+        }
+
+        let mut ids = Vec::new();
+        let mut input = String::new();
+
+        for (def_id, change) in &self.changes {
+            if !change.output {
+                continue;
+            }
+
+            for (index, (type_, span)) in change.changes.iter().enumerate() {
+                let line = Input {
+                    id: ids.len(),
+                    path: path(*def_id),
+                    code: Some(type_.code()),
+                    category: type_.to_category(),
+                    description: type_.to_string(),
+                    span: RSpan(session, span.as_ref().unwrap_or(&change.new_span)),
+                };
+
+                input.push_str(&serde_json::to_string(&line).unwrap());
+                input.push('\n');
+                ids.push(Target::Change(*def_id, index));
+            }
+        }
+
+        for (def_id, change) in &self.path_changes {
+            for (span, category, added) in change.exports() {
+                let line = Input {
+                    id: ids.len(),
+                    path: path(*def_id),
+                    code: None,
+                    category,
+                    description: format!(
+                        "{} {}",
+                        if added { "added" } else { "removed" },
+                        if *span == change.def_span {
+                            "definition"
+                        } else {
+                            "path"
+                        }
+                    ),
+                    span: RSpan(session, span),
+                };
+
+                input.push_str(&serde_json::to_string(&line).unwrap());
+                input.push('\n');
+                ids.push(Target::Export(*def_id, *span));
+            }
+        }
+
+        let display = command.join(" ");
+        let (program, args) = command
+            .split_first()
+            .ok_or("the external rules command is empty")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not run external rules `{}`: {}", display, e))?;
+
+        // the input is written concurrently, so that a process answering early can't block
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("could not run external rules `{}`: {}", display, e))?;
+        // a process may stop reading once it has seen enough
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(format!(
+                "external rules `{}` failed: {}",
+                display, output.status
+            ));
+        }
+
+        let mut removed = BTreeSet::new();
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let adjustment: Adjustment = serde_json::from_str(line)
+                .map_err(|e| format!("invalid adjustment `{}` by external rules: {}", line, e))?;
+            let target = *ids.get(adjustment.id).ok_or_else(|| {
+                format!("external rules adjusted unknown change {}", adjustment.id)
+            })?;
+
+            if adjustment.remove {
+                removed.insert(target);
+            } else if let Some(category) = adjustment.category {
+                match target {
+                    Target::Change(def_id, index) => {
+                        let type_ = &mut self.changes.get_mut(&def_id).unwrap().changes[index].0;
+
+                        let change = match mem::replace(type_, Unknown) {
+                            Recategorized { change, .. } => change,
+                            change => Box::new(change),
+                        };
+                        *type_ = Recategorized { change, category };
+                    }
+                    Target::Export(def_id, span) => {
+                        let change = self.path_changes.get_mut(&def_id).unwrap();
+                        change.categories.insert(span, category);
+                    }
+                }
+            }
+        }
+
+        // removing changes back to front keeps the indices of the remaining ones intact
+        for target in removed.into_iter().rev() {
+            match target {
+                Target::Change(def_id, index) => {
+                    self.changes.get_mut(&def_id).unwrap().changes.remove(index);
+                }
+                Target::Export(def_id, span) => {
+                    let change = self.path_changes.get_mut(&def_id).unwrap();
+                    change.removals.remove(&span);
+                    change.additions.remove(&span);
+                }
+            }
+        }
+
+        self.path_changes
+            .retain(|_, change| !change.removals.is_empty() || !change.additions.is_empty());
+
+        for change in self.changes.values_mut() {
+            change.max = change
+                .changes
+                .iter()
+                .map(|(type_, _)| type_.to_category())
+                .max()
+                .unwrap_or(Patch);
+        }
+
+        self.update_max();
+
+        Ok(())
+    }
+
+    /// Exclude the changes to items gated behind unstable features from the verdict.
+    ///
+    /// The function `gate` returns the unstable feature an item is gated behind, if any. The
+    /// changes to such items are still reported, along with the feature, but count as patch
+    /// changes only.
+    pub fn exclude_unstable(&mut self, gate: impl Fn(DefId) -> Option<String>) {
+        for (def_id, change) in &mut self.path_changes {
+            change.unstable_feature = gate(*def_id);
+        }
+
+        for (def_id, change) in &mut self.changes {
+            let feature = match gate(*def_id) {
+                Some(feature) => feature,
+                None => continue,
+            };
+
+            for (type_, _) in &mut change.changes {
+                let inner = Box::new(mem::replace(type_, Unknown));
+                *type_ = UnstableFeatureGated {
+                    change: inner,
+                    feature: feature.clone(),
+                };
+            }
+
+            change.max = Patch;
+        }
+
+        self.update_max();
+    }
+
+    /// Count the breaking changes that are reported.
+    fn breaking_count(&self) -> usize {
+        let path_changes: usize = self
+            .path_changes
+            .values()
+            .map(|change| {
+                change
+                    .exports()
+                    .filter(|&(_, cat, _)| change.counted(cat) == Breaking)
+                    .count()
+            })
+            .sum();
+        let changes: usize = self
+            .changes
+            .values()
+            .filter(|change| change.output)
+            .map(|change| {
+                change
+                    .changes
+                    .iter()
+                    .filter(|(type_, _)| type_.to_category() == Breaking)
+                    .count()
+            })
+            .sum();
+
+        let manifest_changes = self
+            .manifest_changes
+            .iter()
+            .filter(|change| change.to_category() == Breaking)
+            .count();
+
+        path_changes + changes + manifest_changes
+    }
+
+    /// Check whether any change would be reported as an error.
+    fn denied(&self, config: &Config) -> bool {
+        let denied = |cat| is_denied(config, cat);
+
+        let path_changes = self.path_changes.values().any(|change| {
+            change
+                .exports()
+                .any(|(_, cat, _)| denied(change.counted(cat)))
+        });
+        let changes = self.changes.values().any(|change| {
+            change.output
+                && change
+                    .changes
+                    .iter()
+                    .any(|(type_, _)| denied(type_.to_category()))
+        });
+
+        let manifest_changes = self
+            .manifest_changes
+            .iter()
+            .any(|change| denied(change.to_category()));
+
+        path_changes || changes || manifest_changes
+    }
+
+    /// Check whether the analysis fails according to the configured failure policy.
+    ///
+    /// Returns `None` if the outcome is determined by the changes reported as errors.
+    pub fn failed(&self, config: &Config) -> Option<bool> {
+        if config.strict && !self.not_analyzed().is_empty() {
+            return Some(true);
+        }
+
+        match config.fail_on {
+            // in quiet mode and when printing a graph, no changes are reported
+            FailOn::Denied if config.quiet || config.dot => Some(self.denied(config)),
+            FailOn::Denied => None,
+            FailOn::Breaking(max) => Some(self.breaking_count() > max),
+            FailOn::BumpMismatch => {
+                let suggested = self
+                    .get_new_version(config)
+                    .and_then(|v| Version::parse(&v).ok());
+                let declared = config
+                    .new_version
+                    .as_ref()
+                    .and_then(|v| Version::parse(v).ok());
+
+                match (suggested, declared) {
+                    (Some(suggested), Some(declared)) => Some(declared < suggested),
+                    _ => Some(true),
+                }
+            }
+            FailOn::Never => Some(false),
+        }
+    }
+
+    /// Get the version suggested for the new crate, given the version of the old one.
+    ///
+    /// Unless `zero_semantics` is disabled, versions below 1.0.0 are treated the way cargo's
+    /// resolver does: breaking changes to a `0.y.z` version increment `y`, while all other
+    /// changes increment `z`. Any change to a `0.0.z` version is breaking, and increments `z`.
+    pub fn get_new_version(&self, config: &Config) -> Option<String> {
+        if let Ok(mut new_version) = Version::parse(&config.version) {
+            if new_version.major == 0 && new_version.minor == 0 && config.zero_semantics {
                 increment_patch(&mut new_version);
+            } else if new_version.major == 0 && config.zero_semantics {
+                match self.max {
+                    Patch | NonBreaking | TechnicallyBreaking => increment_patch(&mut new_version),
+                    Breaking => increment_minor(&mut new_version),
+                }
             } else {
                 match self.max {
                     Patch => increment_patch(&mut new_version),
@@ -1116,7 +2988,35 @@ impl<'tcx> ChangeSet<'tcx> {
         }
     }
 
-    pub fn output_json(&self, session: &Session, version: &str) {
+    /// Get the path changes and the regular changes to output, sorted by the paths and names of
+    /// the changed items.
+    ///
+    /// Spans aren't used for ordering here, as the spans of distinct items can coincide.
+    fn sorted(&self) -> (Vec<&PathChange>, Vec<&Change<'tcx>>) {
+        let path = |def_id: &DefId| {
+            self.item_paths
+                .get(def_id)
+                .map(String::as_str)
+                .unwrap_or_default()
+        };
+
+        let mut path_changes: Vec<_> = self.path_changes.iter().collect();
+        path_changes.sort_by_cached_key(|(def_id, c)| (path(def_id), c.name.0.to_string()));
+
+        let mut changes: Vec<_> = self
+            .changes
+            .iter()
+            .filter(|(_, c)| c.output && !c.changes.is_empty())
+            .collect();
+        changes.sort_by_cached_key(|(def_id, c)| (path(def_id), c.name.to_string()));
+
+        (
+            path_changes.into_iter().map(|(_, c)| c).collect(),
+            changes.into_iter().map(|(_, c)| c).collect(),
+        )
+    }
+
+    pub fn output_json(&self, session: &Session, config: &Config) {
         #[derive(Serialize)]
         struct Output<'a, 'tcx> {
             old_version: String,
@@ -1125,11 +3025,11 @@ impl<'tcx> ChangeSet<'tcx> {
         }
 
         let new_version = self
-            .get_new_version(version)
+            .get_new_version(config)
             .unwrap_or_else(|| "parse error".to_owned());
 
         let output = Output {
-            old_version: version.to_owned(),
+            old_version: config.version.clone(),
             new_version,
             changes: RChangeSet(session, self),
         };
@@ -1137,17 +3037,418 @@ impl<'tcx> ChangeSet<'tcx> {
         println!("{}", serde_json::to_string(&output).unwrap());
     }
 
+    /// Get the changes that are reported as errors, in the order they are reported in.
+    fn blocking_changes<'a>(
+        &'a self,
+        session: &'a Session,
+        config: &Config,
+    ) -> Vec<BlockingChange<'a>> {
+        let mut blocking = Vec::new();
+
+        for change in self.manifest_changes.iter() {
+            let category = change.to_category();
+
+            if is_denied(config, category) {
+                blocking.push(BlockingChange {
+                    item: None,
+                    code: None,
+                    category,
+                    description: change.to_string(),
+                    span: None,
+                });
+            }
+        }
+
+        for key in self.spans.values() {
+            if let Some(change) = self.path_changes.get(key) {
+                for (span, category, added) in change.exports() {
+                    let category = change.counted(category);
+
+                    if is_denied(config, category) {
+                        let kind = if added { "added" } else { "removed" };
+                        let what = if *span == change.def_span {
+                            "definition"
+                        } else {
+                            "path"
+                        };
+
+                        blocking.push(BlockingChange {
+                            item: Some(change.name.0.to_string()),
+                            code: None,
+                            category,
+                            description: format!("{} {}", kind, what),
+                            span: Some(RSpan(session, span)),
+                        });
+                    }
+                }
+            }
+
+            if let Some(change) = self.changes.get(key) {
+                if !change.output {
+                    continue;
+                }
+
+                for (type_, span) in &change.changes {
+                    let category = type_.to_category();
+
+                    if is_denied(config, category) {
+                        blocking.push(BlockingChange {
+                            item: Some(match change.name {
+                                Name::Symbol(ref name) => name.0.to_string(),
+                                Name::ImplDesc(ref desc) => desc.clone(),
+                            }),
+                            code: Some(type_.code()),
+                            category,
+                            description: type_.to_string(),
+                            span: Some(RSpan(session, span.as_ref().unwrap_or(&change.new_span))),
+                        });
+                    }
+                }
+            }
+        }
+
+        blocking
+    }
+
+    /// Write a machine-readable verdict of the analysis to the given file.
+    ///
+    /// The verdict is written regardless of the output format, and consists of the required
+    /// version bump, the number of reported changes per category and the changes reported as
+    /// errors, along with the version of the tool and descriptions of the compared crate
+    /// versions.
+    pub fn write_verdict(&self, session: &Session, config: &Config, path: &Path) -> io::Result<()> {
+        #[derive(Default, Serialize)]
+        struct Counts {
+            breaking: usize,
+            technically_breaking: usize,
+            non_breaking: usize,
+            patch: usize,
+        }
+
+        #[derive(Serialize)]
+        struct Baselines<'a> {
+            old: Option<&'a str>,
+            new: Option<&'a str>,
+        }
+
+        #[derive(Serialize)]
+        struct Verdict<'a> {
+            tool_version: &'static str,
+            max_category: ChangeCategory,
+            bump: Option<&'static str>,
+            old_version: &'a str,
+            suggested_version: Option<String>,
+            counts: Counts,
+            blocking: Vec<BlockingChange<'a>>,
+            baselines: Baselines<'a>,
+        }
+
+        let path_changes = self.path_changes.values().flat_map(|change| {
+            change
+                .exports()
+                .map(move |(_, category, _)| change.counted(category))
+        });
+        let changes = self
+            .changes
+            .values()
+            .filter(|change| change.output)
+            .flat_map(|change| change.changes.iter().map(|(type_, _)| type_.to_category()));
+        let manifest_changes = self
+            .manifest_changes
+            .iter()
+            .map(ManifestChange::to_category);
+
+        let mut counts = Counts::default();
+        for category in path_changes.chain(changes).chain(manifest_changes) {
+            match category {
+                Breaking => counts.breaking += 1,
+                TechnicallyBreaking => counts.technically_breaking += 1,
+                NonBreaking => counts.non_breaking += 1,
+                Patch => counts.patch += 1,
+            }
+        }
+
+        let verdict = Verdict {
+            tool_version: env!("CARGO_PKG_VERSION"),
+            max_category: self.max,
+            bump: self.required_bump(config),
+            old_version: &config.version,
+            suggested_version: self.get_new_version(config),
+            counts,
+            blocking: self.blocking_changes(session, config),
+            baselines: Baselines {
+                old: config.old_baseline.as_deref(),
+                new: config.new_baseline.as_deref(),
+            },
+        };
+
+        fs::write(path, serde_json::to_string_pretty(&verdict)?)
+    }
+
+    /// Record a removed and an added item that could have been renamed to each other.
+    pub fn add_rename_candidate(&mut self, old_path: String, new_path: String) {
+        self.rename_candidates.insert((old_path, new_path));
+    }
+
+    /// Write the recorded rename candidates to a file, one `old::path = new::path` pair per line.
+    pub fn write_rename_candidates(&self, path: &Path) -> io::Result<()> {
+        let mut contents = String::new();
+
+        for (old_path, new_path) in &self.rename_candidates {
+            contents.push_str(&format!("{} = {}\n", old_path, new_path));
+        }
+
+        fs::write(path, contents)
+    }
+
+    /// Record a public item, along with its path.
+    pub fn add_item_path(&mut self, old: DefId, path: String) {
+        self.item_paths.insert(old, path);
+    }
+
+    /// Record the paths of all items with changes that don't have one yet.
+    pub fn add_changed_item_paths(&mut self, path: impl Fn(DefId) -> String) {
+        let def_ids: Vec<_> = self
+            .path_changes
+            .keys()
+            .chain(self.changes.keys())
+            .copied()
+            .collect();
+
+        for def_id in def_ids {
+            self.item_paths
+                .entry(def_id)
+                .or_insert_with(|| path(def_id));
+        }
+    }
+
+    /// Record that an item is mentioned in the signature, type, fields or bounds of another.
+    pub fn add_mention(&mut self, mentioned: DefId, by: DefId) {
+        self.mentions.entry(mentioned).or_default().insert(by);
+    }
+
+    /// Get the items with reported breaking changes.
+    fn breaking_items(&self) -> Vec<DefId> {
+        self.changes
+            .iter()
+            .filter(|(_, change)| change.output && change.to_category() == Breaking)
+            .map(|(&def_id, _)| def_id)
+            .collect()
+    }
+
+    /// Get the edges leading from the given items to the public items mentioning them, directly
+    /// or through other such items.
+    fn impact_edges(&self, roots: &[DefId]) -> BTreeSet<(DefId, DefId)> {
+        let mut visited: BTreeSet<_> = roots.iter().copied().collect();
+        let mut stack = roots.to_vec();
+        let mut edges = BTreeSet::new();
+
+        while let Some(def_id) = stack.pop() {
+            for &by in self.mentions.get(&def_id).into_iter().flatten() {
+                edges.insert((def_id, by));
+
+                if visited.insert(by) {
+                    stack.push(by);
+                }
+            }
+        }
+
+        edges
+    }
+
+    /// Record the public items mentioning each item with breaking changes, directly or through
+    /// other such items, to report them along with the changes.
+    pub fn record_impact(&mut self) {
+        for root in self.breaking_items() {
+            let mentioned_by = self
+                .impact_edges(&[root])
+                .into_iter()
+                .filter(|&(_, by)| by != root)
+                .filter_map(|(_, by)| self.item_paths.get(&by).cloned())
+                .collect();
+
+            self.changes.get_mut(&root).unwrap().mentioned_by = mentioned_by;
+        }
+    }
+
+    /// Get the items with reported breaking changes and the edges leading from them to the
+    /// public items mentioning them, directly or through other such items.
+    ///
+    /// Both are rendered using the recorded item paths, and sorted by them.
+    fn impact(&self) -> (BTreeSet<&str>, BTreeSet<(&str, &str)>) {
+        let path = |def_id: &DefId| self.item_paths.get(def_id).map(String::as_str);
+        let roots = self.breaking_items();
+
+        let edges = self
+            .impact_edges(&roots)
+            .iter()
+            .filter_map(|(from, to)| Some((path(from)?, path(to)?)))
+            .collect();
+
+        (roots.iter().filter_map(path).collect(), edges)
+    }
+
+    /// Print a Graphviz DOT graph of the items affected by breaking changes.
+    ///
+    /// The items with breaking changes are highlighted, and have edges to all public items
+    /// mentioning them in their signatures, types, fields or bounds, which in turn have edges to
+    /// the items mentioning them.
+    pub fn output_dot(&self) {
+        let quote = |path: &str| format!("\"{}\"", path.replace('"', "\\\""));
+        let (roots, edges) = self.impact();
+
+        println!("digraph impact {{");
+
+        for root in roots {
+            println!("    {} [color=red];", quote(root));
+        }
+
+        for (from, to) in edges {
+            println!("    {} -> {};", quote(from), quote(to));
+        }
+
+        println!("}}");
+    }
+
+    /// Convert the reported changes to an owned report, which outlives the compiler session.
+    ///
+    /// The report lists the same changes in the same order as the JSON output.
+    pub fn report(&self, session: &Session, config: &Config) -> Report {
+        let spans = |spans: &BTreeSet<Span>| -> Vec<_> {
+            spans.iter().map(|s| span_report(session, *s)).collect()
+        };
+
+        let path_changes = self
+            .spans
+            .values()
+            .filter_map(|key| self.path_changes.get(key))
+            .map(|change| PathChangeReport {
+                name: change.name.0.to_string(),
+                def_span: span_report(session, change.def_span),
+                additions: spans(&change.additions),
+                removals: spans(&change.removals),
+                unstable_feature: change.unstable_feature.clone(),
+            })
+            .collect();
+
+        let changes = self
+            .spans
+            .values()
+            .filter_map(|key| self.changes.get(key))
+            .filter(|change| change.output && !change.changes.is_empty())
+            .map(|change| ChangeReport {
+                name: match change.name {
+                    Name::Symbol(ref name) => name.0.to_string(),
+                    Name::ImplDesc(ref desc) => desc.clone(),
+                },
+                max_category: change.max,
+                new_span: span_report(session, change.new_span),
+                changes: change
+                    .changes
+                    .iter()
+                    .map(|(type_, span)| ChangeTypeReport {
+                        code: type_.code().to_owned(),
+                        category: type_.to_category(),
+                        description: type_.to_string(),
+                        span: span.map(|s| span_report(session, s)),
+                    })
+                    .collect(),
+                affected: change.affected.iter().cloned().collect(),
+                caused_by: change.causes.iter().cloned().collect(),
+                mentioned_by: change.mentioned_by.iter().cloned().collect(),
+                unrelated: change.unrelated.iter().cloned().collect(),
+            })
+            .collect();
+
+        let not_analyzed = self
+            .not_analyzed()
+            .into_iter()
+            .map(|(change, reasons)| NotAnalyzedReport {
+                name: match change.name {
+                    Name::Symbol(ref name) => name.0.to_string(),
+                    Name::ImplDesc(ref desc) => desc.clone(),
+                },
+                new_span: span_report(session, change.new_span),
+                reasons,
+            })
+            .collect();
+
+        Report {
+            old_version: config.version.clone(),
+            new_version: self.get_new_version(config),
+            max_category: self.max,
+            path_changes,
+            changes,
+            not_analyzed,
+            manifest_changes: self.manifest_changes.clone(),
+        }
+    }
+
+    /// Get the required version bump, i.e. the component of the version to increment.
+    fn required_bump(&self, config: &Config) -> Option<&'static str> {
+        let old = Version::parse(&config.version).ok()?;
+        let new = Version::parse(&self.get_new_version(config)?).ok()?;
+
+        Some(if new.major > old.major {
+            "major"
+        } else if new.minor > old.minor {
+            "minor"
+        } else {
+            "patch"
+        })
+    }
+
+    /// Report the changes to the crate manifest, using rustc's error reporting capabilities.
+    fn report_manifest_changes(&self, session: &Session, config: &Config) {
+        let reported: Vec<_> = self
+            .manifest_changes
+            .iter()
+            .map(|change| (change, change.to_category(), change.severity(config)))
+            .filter(|&(_, cat, _)| !config.api_guidelines || matches!(cat, Patch | Breaking))
+            .filter(|&(_, _, severity)| severity != Severity::Allow)
+            .collect();
+
+        let max = match reported.iter().map(|&(_, cat, _)| cat).max() {
+            Some(max) => max,
+            None => return,
+        };
+        let max_severity = reported.iter().map(|&(_, _, severity)| severity).max();
+
+        let msg = format!("{} changes in the manifest", max);
+        let mut builder = if max_severity == Some(Severity::Deny) {
+            session.struct_err(&msg).forget_guarantee()
+        } else {
+            session.struct_warn(&msg)
+        };
+
+        for (change, cat, severity) in reported {
+            let sub_msg = format!("{} ({})", change, cat);
+
+            if severity == Severity::Deny {
+                builder.warn(&sub_msg);
+            } else {
+                builder.note(&sub_msg);
+            }
+        }
+
+        builder.emit();
+    }
+
     /// Format the contents of a change set for user output.
-    pub fn output(
-        &self,
-        session: &Session,
-        version: &str,
-        verbose: bool,
-        compact: bool,
-        api_guidelines: bool,
-    ) {
-        if let Some(new_version) = self.get_new_version(version) {
-            if compact {
+    pub fn output(&self, session: &Session, config: &Config) {
+        let version = &config.version;
+
+        if config.quiet {
+            match self.required_bump(config) {
+                Some(bump) => println!("{}", bump),
+                None => println!("{}", self.max),
+            }
+
+            return;
+        }
+
+        if let Some(new_version) = self.get_new_version(config) {
+            if config.compact {
                 println!("{}", new_version);
             } else {
                 println!(
@@ -1159,29 +3460,80 @@ impl<'tcx> ChangeSet<'tcx> {
             println!("max change: {}, could not parse {}", self.max, version);
         }
 
+        self.report_manifest_changes(session, config);
+
         for key in self.spans.values() {
             if let Some(change) = self.path_changes.get(key) {
-                if api_guidelines {
+                if config.api_guidelines {
                     match change.to_category() {
-                        Patch | Breaking => change.report(session),
+                        Patch | Breaking => change.report(session, config),
                         _ => (),
                     }
                 } else {
-                    change.report(session);
+                    change.report(session, config);
                 }
             }
 
             if let Some(change) = self.changes.get(key) {
-                if api_guidelines {
+                if config.api_guidelines {
                     match change.to_category() {
-                        Patch | Breaking => change.report(session, verbose),
+                        Patch | Breaking => change.report(session, config),
                         _ => (),
                     }
                 } else {
-                    change.report(session, verbose);
+                    change.report(session, config);
                 }
             }
         }
+
+        self.report_not_analyzed(session, config);
+    }
+
+    /// Get the reported items that couldn't be analyzed completely, in the order they are
+    /// reported in, along with the reasons why.
+    fn not_analyzed(&self) -> Vec<(&Change<'tcx>, Vec<String>)> {
+        self.spans
+            .values()
+            .filter_map(|key| self.changes.get(key))
+            .filter(|change| change.output)
+            .map(|change| (change, change.blind_spots()))
+            .filter(|(_, reasons)| !reasons.is_empty())
+            .collect()
+    }
+
+    /// Report the items whose verdict is unknown in a section of their own, after all changes.
+    ///
+    /// In strict mode, the section is reported as an error.
+    fn report_not_analyzed(&self, session: &Session, config: &Config) {
+        let not_analyzed = self.not_analyzed();
+
+        if not_analyzed.is_empty() {
+            return;
+        }
+
+        let msg = format!(
+            "{} item{} not analyzed, changes to {} can go unnoticed",
+            not_analyzed.len(),
+            if not_analyzed.len() == 1 { "" } else { "s" },
+            if not_analyzed.len() == 1 {
+                "it"
+            } else {
+                "them"
+            },
+        );
+        let mut builder = if config.strict {
+            session.struct_err(&msg).forget_guarantee()
+        } else {
+            session.struct_warn(&msg)
+        };
+
+        for (change, reasons) in not_analyzed {
+            for reason in reasons {
+                builder.span_note(change.new_span, &format!("{}: {}", change.name, reason));
+            }
+        }
+
+        builder.emit();
     }
 }
 
@@ -1202,6 +3554,21 @@ impl<'a> Serialize for RPathChanges<'a> {
     }
 }
 
+struct RNotAnalyzed<'a, 'tcx>(&'a Session, &'a Change<'tcx>, Vec<String>);
+
+impl<'a, 'tcx> Serialize for RNotAnalyzed<'a, 'tcx> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("NotAnalyzed", 3)?;
+        state.serialize_field("name", &self.1.name)?;
+        state.serialize_field("new_span", &RSpan(self.0, &self.1.new_span))?;
+        state.serialize_field("reasons", &self.2)?;
+        state.end()
+    }
+}
+
 struct RChangeSet<'a, 'tcx>(&'a Session, &'a ChangeSet<'tcx>);
 
 impl<'a, 'tcx> Serialize for RChangeSet<'a, 'tcx> {
@@ -1209,24 +3576,29 @@ impl<'a, 'tcx> Serialize for RChangeSet<'a, 'tcx> {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("ChangeSet", 3)?;
+        let mut state = serializer.serialize_struct("ChangeSet", 5)?;
 
-        let path_changes: Vec<_> = self.1.path_changes.values().collect();
+        let (path_changes, changes) = self.1.sorted();
         state.serialize_field("path_changes", &RPathChanges(self.0, path_changes))?;
 
-        let changes: Vec<_> = self
+        let changes: Vec<_> = changes.into_iter().map(|c| RChange(self.0, c)).collect();
+        state.serialize_field("changes", &changes)?;
+
+        let not_analyzed: Vec<_> = self
             .1
-            .changes
-            .values()
-            .filter_map(|c| {
-                if c.output && !c.changes.is_empty() {
-                    Some(RChange(self.0, c))
-                } else {
-                    None
-                }
-            })
+            .not_analyzed()
+            .into_iter()
+            .map(|(change, reasons)| RNotAnalyzed(self.0, change, reasons))
             .collect();
-        state.serialize_field("changes", &changes)?;
+        state.serialize_field("not_analyzed", &not_analyzed)?;
+
+        let manifest_changes: Vec<_> = self
+            .1
+            .manifest_changes
+            .iter()
+            .map(|change| (change.to_string(), change.to_category()))
+            .collect();
+        state.serialize_field("manifest_changes", &manifest_changes)?;
 
         state.serialize_field("max_category", &self.1.max)?;
         state.end()
@@ -1326,6 +3698,10 @@ pub mod tests {
             public: bool,
             total_public: bool,
             is_enum: bool,
+            constructible: bool,
+        },
+        FieldMadePrivate {
+            hidden: bool,
         },
         VariantFieldRemoved {
             public: bool,
@@ -1353,6 +3729,7 @@ pub mod tests {
         TraitUnsafetyChanged {
             now_unsafe: bool,
         },
+        FnBodyChanged,
         Unknown,
     }
 
@@ -1374,11 +3751,14 @@ pub mod tests {
                     public,
                     total_public,
                     is_enum,
+                    constructible,
                 } => VariantFieldAdded {
                     public,
                     total_public,
                     is_enum,
+                    constructible,
                 },
+                ChangeType_::FieldMadePrivate { hidden } => FieldMadePrivate { hidden },
                 ChangeType_::VariantFieldRemoved {
                     public,
                     total_public,
@@ -1410,6 +3790,7 @@ pub mod tests {
                 ChangeType_::TraitUnsafetyChanged { now_unsafe } => {
                     TraitUnsafetyChanged { now_unsafe }
                 }
+                ChangeType_::FnBodyChanged => FnBodyChanged,
                 ChangeType_::Unknown => Unknown,
             }
         }
@@ -1436,7 +3817,9 @@ pub mod tests {
                     public: b1,
                     total_public: b2,
                     is_enum: b2,
+                    constructible: b2,
                 },
+                FieldMadePrivate { hidden: b1 },
                 VariantFieldRemoved {
                     public: b1,
                     total_public: b2,
@@ -1455,6 +3838,7 @@ pub mod tests {
                 },
                 TraitItemRemoved { defaulted: b1 },
                 TraitUnsafetyChanged { now_unsafe: b1 },
+                FnBodyChanged,
                 Unknown,
             ])
             .unwrap()
@@ -1501,6 +3885,87 @@ pub mod tests {
         change
     }
 
+    fn local_def_id(index: u32) -> DefId {
+        use rustc_hir::def_id::{DefIndex, LOCAL_CRATE};
+
+        DefId {
+            krate: LOCAL_CRATE,
+            index: DefIndex::from_u32(index),
+        }
+    }
+
+    #[test]
+    fn sorted_by_path_with_coinciding_spans() {
+        rustc_span::create_default_session_globals_then(|| {
+            let span = Span_(0, 10).inner();
+            let mut set = ChangeSet::default();
+
+            for (index, name) in [(1, "b"), (2, "a"), (3, "c")] {
+                let def_id = local_def_id(index);
+                set.new_change(def_id, def_id, Symbol::intern(name), span, span, true);
+                set.add_change(ChangeType::ItemMadePrivate, def_id, None);
+                set.add_change(ChangeType::KindDifference, def_id, None);
+                set.add_change(ChangeType::ItemMadePublic, def_id, None);
+            }
+
+            set.new_path_change(local_def_id(4), Symbol::intern("d"), span);
+            set.add_path_removal(local_def_id(4), span);
+            set.new_path_change(local_def_id(5), Symbol::intern("e"), span);
+            set.add_path_addition(local_def_id(5), span);
+
+            set.add_changed_item_paths(|def_id| match def_id.index.as_u32() {
+                1 => "krate::b".to_owned(),
+                2 => "krate::a".to_owned(),
+                3 => "krate::c".to_owned(),
+                4 => "krate::z::d".to_owned(),
+                _ => "krate::y::e".to_owned(),
+            });
+
+            let (path_changes, changes) = set.sorted();
+
+            let names: Vec<_> = path_changes.iter().map(|c| c.name.0.to_string()).collect();
+            assert_eq!(names, ["e", "d"]);
+
+            let names: Vec<_> = changes.iter().map(|c| c.name.to_string()).collect();
+            assert_eq!(names, ["`a`", "`b`", "`c`"]);
+
+            for change in changes {
+                let codes: Vec<_> = change
+                    .sorted_changes()
+                    .iter()
+                    .map(|(type_, _)| type_.code())
+                    .collect();
+                assert_eq!(codes, ["SV0001", "SV0002", "SV0003"]);
+            }
+        });
+    }
+
+    #[test]
+    fn informational_changes_reported() {
+        let gated = UnstableFeatureGated {
+            change: Box::new(ItemMadePrivate),
+            feature: "unstable".to_owned(),
+        };
+
+        let mut config = Config::default();
+        assert_eq!(gated.to_category(), Patch);
+        assert_eq!(gated.severity(&config), Severity::Warn);
+        assert_eq!(MustUseAdded.severity(&config), Severity::Warn);
+        assert_eq!(
+            TrackCallerChanged { now_tracked: true }.severity(&config),
+            Severity::Warn
+        );
+        let transcriber_changed = MacroRuleChanged {
+            desc: "expansion of macro rule `( )` changed".to_owned(),
+            kind: MacroRuleChange::TranscriberChanged,
+        };
+        assert_eq!(transcriber_changed.severity(&config), Severity::Warn);
+        assert_eq!(FnBodyChanged.severity(&config), Severity::Allow);
+
+        config.severities.insert(Patch, Severity::Allow);
+        assert_eq!(gated.severity(&config), Severity::Allow);
+    }
+
     quickcheck! {
         /// The `Ord` instance of `PathChange` is transitive.
         fn ord_pchange_transitive(c1: PathChange_, c2: PathChange_, c3: PathChange_) -> bool {
@@ -1711,6 +4176,31 @@ pub mod tests {
             }
         })
         }
+
+        /// Versions below 1.0.0 are bumped according to cargo's rules.
+        fn zero_version_bump(minor: u8, patch: u8, breaking: bool) -> bool {
+            let mut set = ChangeSet::default();
+            set.max = if breaking { Breaking } else { TechnicallyBreaking };
+
+            let config = Config {
+                version: format!("0.{}.{}", minor, patch),
+                ..Config::default()
+            };
+            let new_version = Version::parse(&set.get_new_version(&config).unwrap()).unwrap();
+
+            let expected = if breaking && minor > 0 {
+                Version::new(0, u64::from(minor) + 1, 0)
+            } else {
+                Version::new(0, u64::from(minor), u64::from(patch) + 1)
+            };
+
+            new_version == expected
+        }
+
+        /// Every change type has a code with an entry in the explanation registry.
+        fn code_explained(change: ChangeType_) -> bool {
+            crate::explain::explain(change.inner().code()).is_some()
+        }
     }
 }
 
@@ -0,0 +1,121 @@
+//! Custom checks run as part of the analysis.
+//!
+//! Besides the built-in passes, embedders of the library can enforce their own policies, such
+//! as naming rules or internal stability attributes, by implementing `SemverCheck` and passing
+//! their checks to `run_analysis_with_checks`. Checks record their findings through `Findings`,
+//! which are reported and accounted for in the suggested version like all other changes. See
+//! `examples/custom_check.rs` for a complete driver registering a check.
+
+use crate::{
+    changes::{ChangeCategory, ChangeSet, ChangeType},
+    mapping::IdMapping,
+};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+/// A custom check comparing two crate versions.
+pub trait SemverCheck {
+    /// The name of the check, shown along with its findings.
+    fn name(&self) -> &str;
+
+    /// Compare the crate versions, recording findings.
+    ///
+    /// Both crates are accessed through the same type context, while the id mapping relates
+    /// the items of the old crate to their counterparts in the new one. Findings can be
+    /// recorded for the items listed by `IdMapping::items`.
+    fn check<'tcx>(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        id_mapping: &IdMapping,
+        findings: &mut Findings<'_, 'tcx>,
+    );
+}
+
+/// The findings of a custom check, recorded as changes to the items they concern.
+pub struct Findings<'a, 'tcx> {
+    /// The name of the check, shown along with its findings.
+    check: &'a str,
+    /// The changes found so far.
+    changes: &'a mut ChangeSet<'tcx>,
+}
+
+impl<'a, 'tcx> Findings<'a, 'tcx> {
+    /// Construct a new set of findings of the given check, recorded in a change set.
+    pub fn new(check: &'a str, changes: &'a mut ChangeSet<'tcx>) -> Self {
+        Findings { check, changes }
+    }
+
+    /// Check whether findings about an item of the old crate are reported.
+    ///
+    /// Findings about items that are private in both versions are recorded, but not reported.
+    pub fn is_reported(&self, old: DefId) -> bool {
+        self.changes.get_output(old)
+    }
+
+    /// Record a finding about an item of the old crate, optionally pointing at a span.
+    ///
+    /// Findings about items that aren't present in both versions are ignored.
+    pub fn add(&mut self, old: DefId, desc: String, category: ChangeCategory, span: Option<Span>) {
+        if !self.changes.has_change(old) {
+            return;
+        }
+
+        let type_ = ChangeType::Custom {
+            check: self.check.to_owned(),
+            desc,
+            category,
+        };
+
+        self.changes.add_change(type_, old, span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use rustc_hir::def_id::{DefIndex, LOCAL_CRATE};
+    use rustc_span::{symbol::sym, DUMMY_SP};
+
+    #[test]
+    fn findings_recorded() {
+        rustc_span::create_default_session_globals_then(|| {
+            let item = DefId {
+                krate: LOCAL_CRATE,
+                index: DefIndex::from_u32(1),
+            };
+            let other = DefId {
+                krate: LOCAL_CRATE,
+                index: DefIndex::from_u32(2),
+            };
+
+            let mut changes = ChangeSet::default();
+            changes.new_change(item, item, sym::test, DUMMY_SP, DUMMY_SP, true);
+
+            let mut findings = Findings::new("naming", &mut changes);
+            assert!(findings.is_reported(item));
+
+            findings.add(
+                item,
+                "name too long".to_owned(),
+                ChangeCategory::NonBreaking,
+                None,
+            );
+            findings.add(
+                other,
+                "name too short".to_owned(),
+                ChangeCategory::Breaking,
+                None,
+            );
+
+            assert!(changes.has_change(item));
+            assert!(!changes.has_change(other));
+            let config = Config {
+                version: "1.0.0".to_owned(),
+                ..Config::default()
+            };
+            assert_eq!(changes.get_new_version(&config), Some("1.1.0".to_owned()));
+        });
+    }
+}
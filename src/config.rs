@@ -0,0 +1,399 @@
+//! Configuration of the analysis output.
+//!
+//! The `cargo semver` frontend passes its settings to the compiler driver using environment
+//! variables, which are collected here in a single structure that gets handed to the output
+//! routines of the `ChangeSet`.
+
+use crate::{changes::ChangeCategory, manifest::ManifestChange};
+use std::{collections::BTreeMap, env, fmt, path::PathBuf, str::FromStr, time::Duration};
+
+/// The severity with which changes of a given category are reported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Severity {
+    /// Changes aren't reported at all.
+    Allow,
+    /// Changes are reported as warnings.
+    Warn,
+    /// Changes are reported as errors, failing the analysis.
+    Deny,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Severity::Allow),
+            "warn" => Ok(Severity::Warn),
+            "deny" => Ok(Severity::Deny),
+            _ => Err(format!(
+                "invalid severity `{}`, expected one of `allow`, `warn` or `deny`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let desc = match *self {
+            Severity::Allow => "allow",
+            Severity::Warn => "warn",
+            Severity::Deny => "deny",
+        };
+
+        write!(f, "{}", desc)
+    }
+}
+
+/// The condition under which an analysis fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FailOn {
+    /// Fail if any change is reported as an error.
+    Denied,
+    /// Fail if the number of breaking changes exceeds the given threshold.
+    Breaking(usize),
+    /// Fail if the version of the new crate is lower than the suggested one.
+    BumpMismatch,
+    /// Never fail because of the changes found.
+    Never,
+}
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "denied" => Ok(FailOn::Denied),
+            "breaking" => Ok(FailOn::Breaking(0)),
+            "bump-mismatch" => Ok(FailOn::BumpMismatch),
+            "never" => Ok(FailOn::Never),
+            _ => Err(format!(
+                "invalid failure policy `{}`, expected one of `denied`, `breaking`, \
+                 `bump-mismatch` or `never`",
+                s
+            )),
+        }
+    }
+}
+
+/// The settings influencing how the results of an analysis are presented.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The version of the old crate, used to compute the suggested new version.
+    pub version: String,
+    /// The version of the new crate, if known.
+    pub new_version: Option<String>,
+    /// Whether to print detailed explanations of changes.
+    pub verbose: bool,
+    /// Whether to only print the suggested version.
+    pub compact: bool,
+    /// Whether to only print the required version bump, without reporting individual changes.
+    pub quiet: bool,
+    /// Whether to print a JSON description of all changes.
+    pub json: bool,
+    /// Whether to print a Graphviz DOT graph of the items affected by breaking changes.
+    pub dot: bool,
+    /// Whether to list the public items affected by each item with breaking changes.
+    pub impact: bool,
+    /// Whether to only report changes that are breaking according to the API guidelines.
+    pub api_guidelines: bool,
+    /// The severities overriding the default ones of some change categories.
+    pub severities: BTreeMap<ChangeCategory, Severity>,
+    /// Whether to compare the MIR of functions to find changed bodies (experimental).
+    pub mir_diff: bool,
+    /// Whether to scan the MIR of functions for newly introduced panics (experimental).
+    pub panic_scan: bool,
+    /// Whether to compare the serialized form of types implementing serde's traits.
+    pub serde: bool,
+    /// Whether to compare the functions exported to JavaScript using `#[wasm_bindgen]`.
+    pub wasm_bindgen: bool,
+    /// Path patterns restricting the items whose changes are reported, if non-empty.
+    pub only: Vec<String>,
+    /// Path patterns of items whose changes are never reported.
+    pub exclude: Vec<String>,
+    /// The cargo features designating an unstable API, whose changes don't count towards the
+    /// verdict.
+    pub unstable_features: Vec<String>,
+    /// The condition under which the analysis fails.
+    pub fail_on: FailOn,
+    /// Whether to fail the analysis if any item couldn't be analyzed completely.
+    pub strict: bool,
+    /// The file to write the suggested version to, if any.
+    pub version_file: Option<PathBuf>,
+    /// The file to write a machine-readable verdict of the analysis to, if any.
+    pub verdict_file: Option<PathBuf>,
+    /// A description of the old crate version compared against, if known.
+    pub old_baseline: Option<String>,
+    /// A description of the new crate version compared, if known.
+    pub new_baseline: Option<String>,
+    /// Whether to follow cargo's rules for versions below 1.0.0 when suggesting a version.
+    pub zero_semantics: bool,
+    /// Pairs of old and new paths of items that have been renamed or moved.
+    pub renames: Vec<(String, String)>,
+    /// The file to write ambiguous rename candidates to after the analysis, if any.
+    pub candidates_file: Option<PathBuf>,
+    /// Whether to report the time spent in the phases of the analysis.
+    pub timings: bool,
+    /// Whether to print the changes to each item on stderr as soon as it has been compared.
+    pub stream: bool,
+    /// The path of an item whose analysis is logged in detail, if any.
+    pub trace_item: Option<String>,
+    /// The time the comparison of a single item may take, if limited.
+    pub timeout_per_item: Option<Duration>,
+    /// The time the comparison of all items may take, if limited.
+    pub time_budget: Option<Duration>,
+    /// The minimum supported Rust version declared by the new crate, if any.
+    pub rust_version: Option<String>,
+    /// The changes to the crate manifest, found by `cargo semver`.
+    pub manifest_changes: Vec<ManifestChange>,
+    /// The program and arguments of an external process adjusting the reported changes, if any.
+    pub external_rules: Option<Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version: "no_version".to_owned(),
+            new_version: None,
+            verbose: false,
+            compact: false,
+            quiet: false,
+            json: false,
+            dot: false,
+            impact: false,
+            api_guidelines: false,
+            severities: BTreeMap::new(),
+            mir_diff: false,
+            panic_scan: false,
+            serde: false,
+            wasm_bindgen: false,
+            only: Vec::new(),
+            exclude: Vec::new(),
+            unstable_features: Vec::new(),
+            fail_on: FailOn::Denied,
+            strict: false,
+            version_file: None,
+            verdict_file: None,
+            old_baseline: None,
+            new_baseline: None,
+            zero_semantics: true,
+            renames: Vec::new(),
+            candidates_file: None,
+            timings: false,
+            stream: false,
+            trace_item: None,
+            timeout_per_item: None,
+            time_budget: None,
+            rust_version: None,
+            manifest_changes: Vec::new(),
+            external_rules: None,
+        }
+    }
+}
+
+impl Config {
+    /// Construct a configuration from the `RUST_SEMVER_*` environment variables.
+    ///
+    /// Unset variables keep their default values, while invalid values are reported as an error.
+    pub fn from_env() -> Result<Self, String> {
+        fn flag(name: &str) -> bool {
+            env::var(name) == Ok("true".to_string())
+        }
+
+        fn patterns(name: &str) -> Vec<String> {
+            env::var(name)
+                .map(|v| {
+                    v.split(',')
+                        .filter(|p| !p.is_empty())
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        let mut config = Config {
+            verbose: flag("RUST_SEMVER_VERBOSE"),
+            compact: flag("RUST_SEMVER_COMPACT"),
+            quiet: flag("RUST_SEMVER_QUIET"),
+            json: flag("RUST_SEMVER_JSON"),
+            dot: flag("RUST_SEMVER_DOT"),
+            impact: flag("RUST_SEMVER_IMPACT"),
+            api_guidelines: flag("RUST_SEMVER_API_GUIDELINES"),
+            mir_diff: flag("RUST_SEMVER_MIR_DIFF"),
+            panic_scan: flag("RUST_SEMVER_PANIC_SCAN"),
+            serde: flag("RUST_SEMVER_SERDE"),
+            wasm_bindgen: flag("RUST_SEMVER_WASM_BINDGEN"),
+            strict: flag("RUST_SEMVER_STRICT"),
+            timings: flag("RUST_SEMVER_TIMINGS"),
+            stream: flag("RUST_SEMVER_STREAM"),
+            only: patterns("RUST_SEMVER_ONLY"),
+            exclude: patterns("RUST_SEMVER_EXCLUDE"),
+            unstable_features: patterns("RUST_SEMVER_UNSTABLE_FEATURES"),
+            zero_semantics: env::var("RUST_SEMVER_ZERO_SEMANTICS") != Ok("false".to_string()),
+            ..Config::default()
+        };
+
+        if let Ok(ver) = env::var("RUST_SEMVER_CRATE_VERSION") {
+            config.version = ver;
+        }
+
+        config.new_version = env::var("RUST_SEMVER_NEW_VERSION").ok();
+        config.trace_item = env::var("RUST_SEMVER_TRACE_ITEM").ok();
+        config.rust_version = env::var("RUST_SEMVER_RUST_VERSION").ok();
+        config.version_file = env::var_os("RUST_SEMVER_VERSION_FILE").map(PathBuf::from);
+        config.verdict_file = env::var_os("RUST_SEMVER_VERDICT_FILE").map(PathBuf::from);
+        config.candidates_file = env::var_os("RUST_SEMVER_CANDIDATES_FILE").map(PathBuf::from);
+        config.old_baseline = env::var("RUST_SEMVER_OLD_BASELINE").ok();
+        config.new_baseline = env::var("RUST_SEMVER_NEW_BASELINE").ok();
+
+        if let Ok(fail_on) = env::var("RUST_SEMVER_FAIL_ON") {
+            config.fail_on = fail_on.parse()?;
+        }
+
+        if let Ok(max) = env::var("RUST_SEMVER_MAX_BREAKING") {
+            let max = max
+                .parse()
+                .map_err(|_| format!("invalid maximum number of breaking changes `{}`", max))?;
+            config.fail_on = FailOn::Breaking(max);
+        }
+
+        // time limits are given in whole seconds
+        let seconds = |name: &str| {
+            env::var(name)
+                .ok()
+                .map(|secs| {
+                    secs.parse()
+                        .map(Duration::from_secs)
+                        .map_err(|_| format!("invalid number of seconds `{}`", secs))
+                })
+                .transpose()
+        };
+
+        config.timeout_per_item = seconds("RUST_SEMVER_TIMEOUT_PER_ITEM")?;
+        config.time_budget = seconds("RUST_SEMVER_TIME_BUDGET")?;
+
+        if config.fail_on == FailOn::BumpMismatch && config.new_version.is_none() {
+            return Err("the version of the new crate is needed to check the version bump".into());
+        }
+
+        // the function bodies are compared to find patch-level changes, which are reported
+        if config.mir_diff || config.panic_scan {
+            config
+                .severities
+                .insert(ChangeCategory::Patch, Severity::Warn);
+        }
+
+        // overrides are given as a comma-separated list of `category=severity` pairs, with
+        // later entries taking precedence.
+        if let Ok(severities) = env::var("RUST_SEMVER_SEVERITIES") {
+            for entry in severities.split(',').filter(|e| !e.is_empty()) {
+                let (category, severity) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid severity override `{}`", entry))?;
+
+                config
+                    .severities
+                    .insert(category.parse()?, severity.parse()?);
+            }
+        }
+
+        // renames are given as a comma-separated list of `old=new` path pairs.
+        if let Ok(renames) = env::var("RUST_SEMVER_RENAMES") {
+            for entry in renames.split(',').filter(|e| !e.is_empty()) {
+                let (old, new) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid rename `{}`", entry))?;
+
+                config.renames.push((old.to_owned(), new.to_owned()));
+            }
+        }
+
+        // the external rules command is passed as a JSON list of the program and its arguments
+        if let Ok(command) = env::var("RUST_SEMVER_EXTERNAL_RULES") {
+            let command: Vec<String> = serde_json::from_str(&command)
+                .map_err(|e| format!("invalid external rules command `{}`: {}", command, e))?;
+
+            if command.is_empty() {
+                return Err("the external rules command is empty".to_owned());
+            }
+
+            config.external_rules = Some(command);
+        }
+
+        // manifest changes are passed as a JSON list
+        if let Ok(changes) = env::var("RUST_SEMVER_MANIFEST_CHANGES") {
+            config.manifest_changes = serde_json::from_str(&changes)
+                .map_err(|e| format!("invalid manifest changes `{}`: {}", changes, e))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Get the severity with which changes of the given category are reported.
+    pub fn severity(&self, category: ChangeCategory) -> Severity {
+        self.severities
+            .get(&category)
+            .copied()
+            .unwrap_or_else(|| category.default_severity())
+    }
+
+    /// Get the severity with which informational changes are reported.
+    ///
+    /// These are patch-level changes worth mentioning nonetheless, such as changes to items
+    /// gated behind unstable features. They are reported as warnings, unless a severity for
+    /// patch-level changes is configured explicitly.
+    pub fn informational_severity(&self) -> Severity {
+        self.severities
+            .get(&ChangeCategory::Patch)
+            .copied()
+            .unwrap_or(Severity::Warn)
+    }
+
+    /// Check whether changes to the item with the given path are to be reported.
+    ///
+    /// The path is matched against the `only` and `exclude` patterns, which are paths whose
+    /// segments can be `**` to match any number of segments, or contain `*` to match any
+    /// sequence of characters within a segment.
+    pub fn path_included(&self, path: &str) -> bool {
+        let path: Vec<_> = path.split("::").collect();
+        let matches = |pattern: &String| {
+            let pattern: Vec<_> = pattern.split("::").collect();
+            glob_matches(&pattern, &path)
+        };
+
+        (self.only.is_empty() || self.only.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Check whether a path matches a pattern, both given as a list of segments.
+fn glob_matches(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            glob_matches(&pattern[1..], path)
+                || (!path.is_empty() && glob_matches(pattern, &path[1..]))
+        }
+        (Some(p), Some(s)) => segment_matches(p, s) && glob_matches(&pattern[1..], &path[1..]),
+        _ => false,
+    }
+}
+
+/// Check whether a single path segment matches a pattern segment possibly containing `*`.
+fn segment_matches(pattern: &str, segment: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == segment,
+        Some(i) => {
+            let (prefix, rest) = (&pattern[..i], &pattern[i + 1..]);
+
+            segment.strip_prefix(prefix).map_or(false, |tail| {
+                tail.char_indices()
+                    .map(|(j, _)| j)
+                    .chain(Some(tail.len()))
+                    .any(|j| segment_matches(rest, &tail[j..]))
+            })
+        }
+    }
+}
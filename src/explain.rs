@@ -0,0 +1,971 @@
+//! A registry of detailed descriptions of the changes we detect.
+//!
+//! Every `ChangeType` has a stable code, which can be passed to `cargo semver explain` to get a
+//! description of the change, an example of how it affects user code, and ways to avoid the
+//! breakage.
+
+/// The registry of descriptions, indexed by code.
+const REGISTRY: &[(&str, &str)] = &[
+    (
+        "SV0001",
+        "An item has been made public, or a new public item has been added.
+
+This is usually a non-breaking change, but user code importing all items of a module using a
+glob import can observe name clashes:
+
+```
+use dependency::*; // now also imports `Config`
+use my_crate::Config; // error: `Config` is ambiguous
+```
+
+Such code can be fixed by importing the conflicting names explicitly, which is why this change
+is only considered technically breaking.",
+    ),
+    (
+        "SV0002",
+        "An item has been removed from the public interface of the crate.
+
+All user code referring to the item stops compiling:
+
+```
+use dependency::Helper; // error: unresolved import
+```
+
+To avoid the breakage, keep a (possibly deprecated) public alias or re-export of the item
+around until the next major release.",
+    ),
+    (
+        "SV0003",
+        "The kind of an item has changed, for example a struct has been turned into an enum, or a
+function into a constant.
+
+Items of different kinds are used in different ways, so user code relying on the old kind of
+item generally stops compiling:
+
+```
+let value = dependency::Value { inner: 1 }; // error: `Value` is now an enum
+```
+
+Introduce an item of the new kind under a new name instead.",
+    ),
+    (
+        "SV0004",
+        "The mutability of a static has changed.
+
+Making a static immutable breaks all user code writing to it. Making a static mutable requires
+user code reading it to use `unsafe` blocks:
+
+```
+let x = dependency::COUNTER; // error: use of mutable static is unsafe
+```
+
+Consider introducing a new static instead, or using interior mutability.",
+    ),
+    (
+        "SV0005",
+        "The variance of a type or lifetime parameter has been loosened, for example from invariant
+to covariant.
+
+This only allows user code to coerce values in more situations, which is non-breaking.",
+    ),
+    (
+        "SV0006",
+        "The variance of a type or lifetime parameter has been tightened, for example from
+covariant to invariant.
+
+User code relying on subtyping of the affected type stops compiling:
+
+```
+fn shorten<'a>(x: dependency::Wrapper<&'static str>) -> dependency::Wrapper<&'a str> {
+    x // error: lifetime may not live long enough
+}
+```
+
+Variance is usually changed by adding fields containing `Cell`, `&mut` or function pointers.
+Use `PhantomData` of the old field types to retain the previous variance if possible.",
+    ),
+    (
+        "SV0007",
+        "The variance of a type or lifetime parameter has changed between covariant and
+contravariant.
+
+User code relying on subtyping of the affected type in either direction stops compiling. Such
+changes are usually caused by changing a field from holding a value to holding a function
+taking such a value.",
+    ),
+    (
+        "SV0008",
+        "A lifetime parameter has been added to an item.
+
+All user code naming the item with explicit lifetime arguments stops compiling:
+
+```
+struct Holder<'a>(dependency::Parser<'a>); // error: wrong number of lifetime arguments
+```
+
+Add a new item with the additional parameter instead.",
+    ),
+    (
+        "SV0009",
+        "A lifetime parameter has been removed from an item.
+
+All user code naming the item with explicit lifetime arguments stops compiling:
+
+```
+struct Holder<'a>(dependency::Parser<'a>); // error: wrong number of lifetime arguments
+```
+
+Add a new item without the parameter instead.",
+    ),
+    (
+        "SV0010",
+        "A type parameter has been added to an item.
+
+If the parameter has no default, all user code naming the item stops compiling:
+
+```
+fn run(x: dependency::Runner) {} // error: missing generics for struct `Runner`
+```
+
+Giving the new parameter a default value that preserves the old behaviour makes this change
+non-breaking in most situations. It's still reported as technically breaking, as code giving
+all generic arguments explicitly stops compiling, which also applies to type parameters added
+to functions:
+
+```
+dependency::parse::<u32>(input); // error: this function takes 2 generic arguments but 1 generic argument was supplied
+```
+
+Type inference can also fail for expressions that relied on the default, since defaults aren't
+used to infer the parameters of paths in expressions.",
+    ),
+    (
+        "SV0011",
+        "A type parameter has been removed from an item.
+
+All user code naming the item with explicit type arguments stops compiling:
+
+```
+fn run(x: dependency::Runner<u8>) {} // error: wrong number of generic arguments
+```
+
+Add a new item without the parameter instead.",
+    ),
+    (
+        "SV0012",
+        "A variant has been added to a public enum.
+
+User code matching on the enum exhaustively stops compiling:
+
+```
+match kind {
+    dependency::Kind::A => {}
+    dependency::Kind::B => {}
+} // error: non-exhaustive patterns: `Kind::C` not covered
+```
+
+Mark enums that are expected to grow as `#[non_exhaustive]` in the next major release, which
+forces user code to include a wildcard arm.",
+    ),
+    (
+        "SV0013",
+        "A variant has been removed from a public enum.
+
+All user code constructing or matching on the variant stops compiling:
+
+```
+let kind = dependency::Kind::C; // error: no variant named `C`
+```
+
+Keep the variant around, possibly deprecated, until the next major release.",
+    ),
+    (
+        "SV0014",
+        "A field has been added to a struct or enum variant.
+
+If user code can construct the struct or variant using a literal (all fields are public and
+the type isn't `#[non_exhaustive]`), or match on it without a `..` pattern, that code stops
+compiling:
+
+```
+let p = dependency::Point { x: 1, y: 2 }; // error: missing field `z`
+```
+
+Mark types that are expected to grow as `#[non_exhaustive]`, or keep at least one private field
+around and provide a constructor function.",
+    ),
+    (
+        "SV0015",
+        "A public field has been made private or hidden from the documentation.
+
+All user code accessing the field stops compiling, or relies on an undocumented detail:
+
+```
+let x = point.x; // error: field `x` of struct `Point` is private
+```
+
+Provide an accessor method before making the field private, and keep the field public until
+the next major release.",
+    ),
+    (
+        "SV0016",
+        "A field has been removed from a struct or enum variant.
+
+All user code accessing the field stops compiling. If all fields were private, the removal is
+invisible to user code and non-breaking:
+
+```
+let x = point.x; // error: no field `x` on type `Point`
+```
+
+Keep the field around, or replace it by an accessor method computing the old value.",
+    ),
+    (
+        "SV0017",
+        "A struct or enum variant has been changed between tuple- and struct-style.
+
+User code constructing or matching on the struct or variant stops compiling:
+
+```
+let p = dependency::Point(1, 2); // error: expected function, found struct `Point`
+```
+
+Add a new struct or variant with the new style instead.",
+    ),
+    (
+        "SV0018",
+        "A function has been made `const` or non-`const`.
+
+Removing `const` breaks all user code calling the function in constant contexts:
+
+```
+const SIZE: usize = dependency::size(); // error: cannot call non-const fn
+```
+
+Adding `const` is non-breaking.",
+    ),
+    (
+        "SV0019",
+        "A method gained or lost its `self` parameter, turning it into an associated function or
+vice versa.
+
+User code calling the function with the old syntax stops compiling:
+
+```
+value.reset(); // error: `reset` is an associated function, not a method
+```
+
+Add a new method or function under a different name instead.",
+    ),
+    (
+        "SV0020",
+        "An item has been added to a trait.
+
+If the item has no default, all user code implementing the trait stops compiling:
+
+```
+impl dependency::Visitor for MyVisitor {} // error: missing `visit_new` in implementation
+```
+
+Provide a default implementation for the new item, or seal the trait so user code can't
+implement it. Even defaulted items can cause method resolution ambiguities in user code,
+which is why such additions are considered technically breaking.",
+    ),
+    (
+        "SV0021",
+        "An item has been removed from a trait.
+
+All user code implementing or using the item stops compiling:
+
+```
+impl dependency::Visitor for MyVisitor {
+    fn visit_old(&mut self) {} // error: method `visit_old` is not a member of trait
+}
+```
+
+Keep the item around, possibly deprecated and with a default, until the next major release.",
+    ),
+    (
+        "SV0022",
+        "A trait has been made `unsafe` or safe.
+
+User code implementing the trait stops compiling, because the `unsafe` keyword on its impls
+has to be added or removed:
+
+```
+impl dependency::Plugin for MyPlugin {} // error: the trait requires an `unsafe impl`
+```
+
+Introduce a new trait instead.",
+    ),
+    (
+        "SV0023",
+        "The type of an item has changed, for example the signature of a function, the type of a
+field or constant, or the target of a type alias.
+
+User code relying on the old type generally stops compiling:
+
+```
+let n: u32 = dependency::count(); // error: expected `u32`, found `u64`
+```
+
+Add a new item with the new type instead, and deprecate the old one.",
+    ),
+    (
+        "SV0024",
+        "A bound has been added to an item, for example a trait bound on a type parameter of a
+function, or a supertrait of a trait.
+
+User code that doesn't satisfy the new bound stops compiling:
+
+```
+dependency::spawn(rc_value); // error: `Rc<u8>` cannot be sent between threads safely
+```
+
+Bounds on trait definitions additionally affect all implementors of the trait. Avoid adding
+bounds, or introduce a new item with the tightened bounds.",
+    ),
+    (
+        "SV0025",
+        "A bound has been removed from an item.
+
+This allows user code to use the item in more situations, and is usually non-breaking. Removing
+bounds from a trait definition breaks user code relying on them, for example by calling
+supertrait methods on values of a generic type bounded by the trait:
+
+```
+fn print<T: dependency::Shape>(x: T) {
+    println!(\"{:?}\", x) // error: `T` doesn't implement `Debug`
+}
+```
+
+Keep the bounds on trait definitions until the next major release.",
+    ),
+    (
+        "SV0026",
+        "A trait impl has been removed or specialized to a smaller set of types.
+
+User code relying on the trait being implemented for some type stops compiling:
+
+```
+let copy = value.clone(); // error: no method named `clone` found
+```
+
+Keep the impl around until the next major release.",
+    ),
+    (
+        "SV0027",
+        "A trait impl has been added or generalized to a larger set of types.
+
+This is usually non-breaking, but can cause type inference failures or method resolution
+ambiguities in user code that relied on only one impl being present:
+
+```
+let x = \"1\".parse().unwrap(); // error: type annotations needed
+```
+
+Such code can be fixed by adding type annotations, which is why this change is only
+considered technically breaking.",
+    ),
+    (
+        "SV0028",
+        "An associated type of a trait impl has changed, for example the `Item` type of an
+`Iterator` impl.
+
+User code relying on the old associated type stops compiling:
+
+```
+let bytes: Vec<u8> = reader.items().collect(); // error: expected `u8`, found `u16`
+```
+
+Provide a new type implementing the trait with the new associated type instead.",
+    ),
+    (
+        "SV0029",
+        "A trait impl has been added that is generic over some of its input types, for example
+`impl<T> Trait for Box<T>`.
+
+The orphan rules allow user code to implement the trait for its own types, which can overlap
+with the new impl:
+
+```
+impl dependency::Trait for Box<MyType> {} // error: conflicting implementations
+```
+
+Whether this happens depends on the bounds of the new impl and on user code. Consider adding
+blanket impls only in major releases.",
+    ),
+    (
+        "SV0030",
+        "The value of a public constant has changed, while its type has not.
+
+User code using the constant in array lengths, patterns or other constant expressions can
+behave differently or stop compiling:
+
+```
+let buf = [0u8; dependency::BUF_SIZE];
+let arr: [u8; 64] = buf; // error: expected an array with a fixed size of 64 elements
+```
+
+Document the change, and avoid changing constants users are likely to depend on.",
+    ),
+    (
+        "SV0031",
+        "The body of a function has changed, while its signature has not.
+
+This doesn't affect the public interface of the crate, but user code can depend on the
+behaviour of the function. Document behavioural changes in the changelog, and consider a minor
+release for significant changes.",
+    ),
+    (
+        "SV0032",
+        "The serialized form of a type implementing serde's `Serialize` or `Deserialize` traits
+has changed, for example by renaming a field or variant, or changing a `#[serde(...)]`
+attribute.
+
+Data written by one version of the crate can't be read by the other:
+
+```
+let config: dependency::Config = serde_json::from_str(stored)?; // error: missing field `name`
+```
+
+Use `#[serde(rename = \"...\")]` to keep the old names, and `#[serde(default)]` on new fields.",
+    ),
+    (
+        "SV0033",
+        "An item has been added to an inherent impl.
+
+This is usually non-breaking, but the new method can shadow a trait method with the same name
+in user code:
+
+```
+use my_crate::Ext; // provides `value.len()` for `dependency::Buffer`
+let n = buffer.len(); // now calls the new inherent method
+```
+
+Such code can be fixed by using fully qualified syntax, which is why this change is only
+considered technically breaking.",
+    ),
+    (
+        "SV0034",
+        "An item has been removed from an inherent impl.
+
+All user code using the item stops compiling:
+
+```
+buffer.clear(); // error: no method named `clear` found
+```
+
+Keep the item around, possibly deprecated, until the next major release.",
+    ),
+    (
+        "SV0035",
+        "A change has been detected that isn't handled explicitly yet.
+
+Such changes are conservatively considered breaking. Please report the case on the issue
+tracker, so it can be handled properly.",
+    ),
+    (
+        "SV0036",
+        "A helper attribute declared by a derive macro has been removed or renamed.
+
+User code annotating fields or variants with the old attribute stops compiling:
+
+```
+#[derive(dependency::Builder)]
+struct Config {
+    #[builder(default)] // error: cannot find attribute `builder` in this scope
+    verbose: bool,
+}
+```
+
+Keep accepting the old attribute, possibly ignoring it, until the next major release.",
+    ),
+    (
+        "SV0037",
+        "An item has been moved to a different module, and isn't available at its old path anymore.
+
+All user code referring to the item by its old path stops compiling:
+
+```
+use dependency::Error; // error: unresolved import `dependency::Error`
+```
+
+Re-export the item at its old path, possibly deprecated, until the next major release:
+
+```
+#[doc(no_inline)]
+pub use crate::error::Error;
+```",
+    ),
+    (
+        "SV0038",
+        "The length of an array in the type of an item has changed.
+
+User code passing arrays of the old length to the item, or relying on the length of arrays
+returned by it, stops compiling:
+
+```
+dependency::set_key([0; 16]); // error: mismatched types, expected an array with a fixed
+                              // size of 32 elements, found one with 16 elements
+```
+
+Keep the old length, or add a new item using the new length.",
+    ),
+    (
+        "SV0039",
+        "The type a public type alias expands to has changed.
+
+User code relying on the alias being the old type stops compiling, as do uses of all items
+mentioning the alias:
+
+```
+let id: dependency::Id = 42u32; // error: mismatched types, expected `u64`, found `u32`
+```
+
+Keep the alias as-is, and add a new alias for the new type.",
+    ),
+    (
+        "SV0040",
+        "A bound of an opaque type defined by a type alias has been added or removed.
+
+Removing a bound breaks user code relying on values of the type implementing it:
+
+```
+let it: dependency::Iter = dependency::iter();
+let copy = it.clone(); // error: the trait `Clone` is not implemented for `Iter`
+```
+
+Adding a bound is non-breaking. Keep all bounds of the old version.",
+    ),
+    (
+        "SV0041",
+        "An opaque type defined by a type alias started or stopped implementing an auto trait.
+
+Opaque types leak the auto traits `Send`, `Sync` and `Unpin` of their hidden type, so
+changing the hidden type can silently change them, breaking user code relying on them:
+
+```
+let fut = dependency::fetch();
+std::thread::spawn(move || fut); // error: `Fetch` cannot be sent between threads safely
+```
+
+Make sure the hidden type keeps implementing the auto traits, or state them as explicit
+bounds of the opaque type to catch such changes.",
+    ),
+    (
+        "SV0042",
+        "Trait objects of a public trait can no longer be upcast to trait objects of a supertrait.
+
+This happens when a supertrait is removed, either from the trait itself or from one of its
+supertraits, and breaks user code relying on the upcast:
+
+```
+let shape: &dyn dependency::Shape = &circle;
+let named: &dyn dependency::Named = shape; // error: mismatched types
+```
+
+Keep the supertrait, or provide a method converting to the supertrait object instead.",
+    ),
+    (
+        "SV0043",
+        "A public function or type has been marked `#[must_use]`.
+
+This doesn't affect the public interface of the crate, but user code ignoring the result now
+triggers the `unused_must_use` lint, which fails builds using `-D warnings`:
+
+```
+dependency::Buffer::new(); // warning: unused `Buffer` that must be used
+```
+
+Mention the attribute in the changelog, so users denying warnings aren't caught off guard.",
+    ),
+    (
+        "SV0044",
+        "A public function has gained or lost the `#[track_caller]` attribute.
+
+This doesn't affect the public interface of the crate, but panics inside the function are now
+reported at a different location, either the function itself or its caller:
+
+```
+dependency::parse(\"\"); // panicked at 'empty input', src/main.rs:1:1
+```
+
+Function pointers to the function point to a shim passing the caller location, instead of the
+function itself. Mention the change in the changelog if users could rely on panic locations.",
+    ),
+    (
+        "SV0045",
+        "A public function now calls panicking functions, while its signature hasn't changed.
+
+This doesn't affect the public interface of the crate, but user code calling the function can
+now panic in cases that were previously handled:
+
+```
+let port = dependency::parse_port(\"http\"); // now panics instead of returning a default
+```
+
+The check is a heuristic, looking for explicit panics and calls to `unwrap` and `expect` in
+the function and the functions of the crate it calls. Review the reported functions, and
+consider returning an error instead, or documenting the panic in the changelog.",
+    ),
+    (
+        "SV0046",
+        "A public item uses a standard library item stabilized after the declared `rust-version`.
+
+Cargo lets users build the crate with any toolchain at least as new as the declared minimum
+supported Rust version, so users of older toolchains get errors instead of a clear message:
+
+```
+// with `rust-version = \"1.56\"` declared in the manifest
+pub fn buffer() -> std::sync::OnceLock<Vec<u8>> { ... } // stable since 1.70
+```
+
+Raise the `rust-version` field along with the code, so that cargo reports the requirement
+clearly. Only items mentioned in signatures, types and bounds are checked, not the ones used
+in function bodies.",
+    ),
+    (
+        "SV0047",
+        "A rule of an exported declarative macro has been removed, added or changed.
+
+Macros can't be compared semantically, so their rules are compared token by token. Removing a
+rule, or changing its matcher, can make invocations in user code stop matching:
+
+```
+// `($x:expr)` changed to `($x:ident)`
+dependency::log!(1 + 2); // error: no rules expected the token `1`
+```
+
+Check the reported token diff to judge whether the invocations the macro accepts shrank. If
+they didn't, the change is safe. Otherwise, keep the old rule around as an additional one.",
+    ),
+    (
+        "SV0048",
+        "A function exported to JavaScript using `#[wasm_bindgen]` has been removed, added or changed.
+
+The JavaScript bindings generated by `wasm-bindgen` call the exports of the WebAssembly module
+by name, passing arguments as the types the exports are declared with. Removing an export, or
+changing its signature, breaks JavaScript code calling it, even if the Rust API is unchanged:
+
+```
+// `greet(name: &str)` changed to `greet(name: &str, loud: bool)`
+greet(\"world\"); // `loud` is `undefined`
+```
+
+Keep the old export around, for example under a different `js_name`, or bump the major version.",
+    ),
+    (
+        "SV0049",
+        "The bounds of an inherent impl have been tightened.
+
+All items of the impl become unavailable for types not fulfilling the new bounds:
+
+```
+// `impl<T: Clone> Wrapper<T>` changed to `impl<T: Clone + Send> Wrapper<T>`
+Wrapper::new(Rc::new(1)).get(); // error: the method `get` exists but its bounds weren't satisfied
+```
+
+Move the items needing the new bound to a separate impl, keeping the old bounds on the others.",
+    ),
+    (
+        "SV0050",
+        "The return type of a function changed from a named type to `impl Trait`, or vice versa.
+
+Returning `impl Trait` hides the type, so user code naming it, or relying on methods and trait
+impls of the type not implied by the bounds, stops compiling:
+
+```
+let iter: std::vec::IntoIter<u8> = dependency::items(); // error: mismatched types
+```
+
+Changing an `impl Trait` return type to a named type is non-breaking, as long as the type
+fulfills the bounds, since user code can't have relied on anything else.",
+    ),
+    (
+        "SV0051",
+        "The default value of a const parameter has been added, removed or changed.
+
+User code omitting the parameter relies on its default. Changing it makes such code refer to
+a different type, which breaks as soon as it meets values of the old type:
+
+```
+// `struct Buffer<const N: usize = 4>` changed to `struct Buffer<const N: usize = 8>`
+let buf: dependency::Buffer = dependency::Buffer::<4>::new(); // error: mismatched types
+```
+
+Adding a default is non-breaking, while changing or removing one requires a major version
+bump.",
+    ),
+    (
+        "SV0052",
+        "A `CoerceUnsized` or `DispatchFromDyn` impl of a smart pointer type has been removed.
+
+These impls are what make a custom smart pointer behave like `Box` or `Rc` when unsizing, so
+removing them breaks coercions and trait object method calls in user code, even though no
+method or type in the public API changed:
+
+```
+let ptr: dependency::Ptr<dyn Display> = dependency::Ptr::new(5); // error: mismatched types
+```
+
+This is a breaking change.",
+    ),
+    (
+        "SV0053",
+        "The alignment of a type whose layout is guaranteed by its `repr` has been reduced.
+
+Types with a `repr(C)`, `repr(packed)` or `repr(align)` attribute are typically shared with C
+code or used with raw pointers, which is only correct for values aligned as the type promised:
+
+```
+// `#[repr(C, align(8))]` changed to `#[repr(C, align(4))]`
+assert_eq!(std::mem::align_of::<dependency::Header>(), 8); // fails
+```
+
+This is a breaking change. Increasing the alignment isn't reported.",
+    ),
+    (
+        "SV0054",
+        "The packing of a type has been added, removed or changed.
+
+Packing changes the offsets of the fields of a type, and references to fields of packed types
+can't be taken, as they may be unaligned:
+
+```
+// `#[repr(C)]` changed to `#[repr(C, packed)]`
+let value = &header.length; // error: reference to packed field is unaligned
+```
+
+This is a breaking change.",
+    ),
+    (
+        "SV0055",
+        "An item has been made public, which adds a part of the public API.
+
+The added part is listed as `cargo semver dump-api` would render it, including the signature of
+a function, or the public fields and methods of a type, so it can be mentioned in the
+changelog. Like making an item public (SV0001), this is technically breaking.",
+    ),
+    (
+        "SV0056",
+        "A newly added inherent method has the same name as a method of a trait implemented for
+the type.
+
+Inherent methods take precedence over trait methods in method calls, so calls in user code
+silently resolve to the new method, or stop compiling if its signature differs:
+
+```
+#[derive(Clone)]
+pub struct Config { .. }
+
+impl Config {
+    pub fn clone(&self) -> Box<Config> { .. } // new
+}
+
+let copy: Config = config.clone(); // error: mismatched types
+```
+
+Calling the trait method by its path (`Clone::clone(&config)`) is unaffected. Choose a
+different name for the new method to avoid the ambiguity.",
+    ),
+    (
+        "SV0057",
+        "A `Deref` or `DerefMut` impl has been added to a type that existed before.
+
+Method calls autoderef through the new impl, so methods of the target type become callable on
+the type. Calls in user code that resolved to a trait method in scope can now resolve to a
+method of the target type of the same name instead, and code relying on the type not having
+such a method changes meaning:
+
+```
+pub struct Name(String);
+
+impl Deref for Name { // new
+    type Target = String;
+    ..
+}
+
+name.len() // resolved to an extension trait method before, now calls `String::len`
+```
+
+Adding the impl is usually harmless, but calls in user code should be checked for methods
+that now resolve differently.",
+    ),
+    (
+        "SV0058",
+        "A trait impl has been added that can make types in user code ambiguous.
+
+Type inference picks the only impl of a trait that fits when a type is otherwise unknown. A
+second impl makes it ambiguous, so user code relying on this stops compiling:
+
+```
+impl From<Name> for String { .. } // new
+
+fn greet(name: impl AsRef<str>) { .. }
+
+greet(value.into()); // error: type annotations needed
+```
+
+This is reported for blanket impls like `impl<T: Display> Trait for T`, and for impls of
+`From`, `TryFrom`, `FromIterator`, `AsRef`, `AsMut` and `PartialEq` for types defined in other
+crates, as these are what user code typically infers through `.into()`, `?`, `.collect()`,
+`.as_ref()` and `==`. Adding type annotations at the affected call sites fixes them.",
+    ),
+    (
+        "SV0059",
+        "A custom check has reported a change.
+
+Custom checks are registered by tools embedding the analysis, to enforce policies of a crate's
+maintainers, such as naming rules or the use of internal stability attributes. The check names
+itself in the message, and assigns the change its category. Refer to the documentation of the
+check to find out why the change was reported and how to avoid it.",
+    ),
+    (
+        "SV0060",
+        "The type of an item has changed only because it refers to an item of a dependency, and the
+old and new version of the crate use different versions of that dependency.
+
+Items of incompatible versions of a crate are different types, even if they are declared the
+same way. User code passing values it obtained from the version used before stops compiling:
+
+```
+let url = url::Url::parse(\"https://example.com\")?; // url 1.x, like the old version
+dependency::fetch(url); // error: expected `url::Url`, found `url::Url`
+```
+
+Keep depending on the old version of the dependency, or release a new major version.",
+    ),
+    (
+        "SV0061",
+        "The analysis of an item has been aborted, because the compiler panicked while comparing
+it to its counterpart, or the comparison exceeded the time limit set using `--timeout-per-item`
+or `--time-budget`.
+
+A panic is a bug in rust-semverver or the compiler, usually triggered by rarely used language
+features, such as complex const generics. Timeouts are mostly caused by deeply recursive trait
+bounds. Either way, the remaining items are still analyzed, but the item itself may have
+changed in any way. Check its changes by hand, and consider reporting panics along with the
+item's definition.",
+    ),
+    (
+        "SV0062",
+        "A lifetime bound has been added to a type or lifetime parameter of an item.
+
+User code instantiating the parameter with a type borrowing data for a shorter time, or with a
+shorter lifetime, stops compiling. The most common case is a `'static` bound, which rules out
+any type borrowing from local variables:
+
+```
+// old: pub fn spawn<T: Send>(value: T)
+// new: pub fn spawn<T: Send + 'static>(value: T)
+let data = vec![1, 2, 3];
+spawn(&data); // error: `data` does not live long enough
+```
+
+Such bounds are compared as written, so bounds implied by other ones, such as `T: 'static`
+implied by `T: Any`, are reported when spelled out as well.",
+    ),
+    (
+        "SV0063",
+        "A `?Sized` relaxation has been added to or removed from a type parameter of an item.
+
+Type parameters are implicitly bounded by `Sized`, unless relaxed using `?Sized`. Removing the
+relaxation is breaking, as user code instantiating the parameter with an unsized type stops
+compiling:
+
+```
+// old: pub fn print<T: ?Sized + Display>(value: &T)
+// new: pub fn print<T: Display>(value: &T)
+print(\"text\"); // error: the size for values of type `str` cannot be known
+```
+
+Adding the relaxation is non-breaking, except on trait definitions, where implementations in
+user code relying on the parameter being `Sized` stop matching the trait.",
+    ),
+    (
+        "SV0064",
+        "A trait impl has changed its constness, as declared using `impl const Trait for T`.
+
+Making an impl non-const is breaking, as user code calling the trait's methods on the type in
+const contexts stops compiling:
+
+```
+// old: impl const Default for Config
+// new: impl Default for Config
+const DEFAULT: Config = Config::default(); // error: cannot call non-const fn
+```
+
+Making an impl const is non-breaking.",
+    ),
+    (
+        "SV0065",
+        "An impl of `Fn`, `FnMut` or `FnOnce` has been removed from a type.
+
+Values of the type could be called like functions, and passed where closures are expected.
+User code doing so stops compiling, like when a method is removed.",
+    ),
+    (
+        "SV0066",
+        "The arguments of a type's impl of `Fn`, `FnMut` or `FnOnce` have changed.
+
+User code calling values of the type with the old arguments, or passing them where a closure
+taking the old arguments is expected, stops compiling, like when the signature of a method
+changes:
+
+```
+// old: impl FnOnce<(u8,)> for Handler
+// new: impl FnOnce<(u16,)> for Handler
+handler(1u8); // error: mismatched types
+```",
+    ),
+    (
+        "SV0067",
+        "An item of a trait impl has gained or lost its `default` marker, either on the item itself
+or on the whole impl (`default impl`).
+
+With the `specialization` feature, user code can write impls of the trait for more specific
+types, which override the items marked `default`. Removing the marker is breaking, as such
+impls stop compiling:
+
+```
+// old: impl<T> Display for Wrapper<T> { default fn fmt(...) { ... } }
+// new: impl<T> Display for Wrapper<T> { fn fmt(...) { ... } }
+impl Display for Wrapper<u8> {
+    fn fmt(...) { ... } // error: `fmt` specializes an item that is not marked `default`
+}
+```
+
+Adding the marker is non-breaking.",
+    ),
+    (
+        "SV0068",
+        "An item has been removed, and an added item with a similar name and the same shape has
+been found, so the item has probably been renamed.
+
+All user code referring to the item by its old name stops compiling:
+
+```
+use dependency::Config; // error: unresolved import `dependency::Config`
+```
+
+Keep the old name available, possibly deprecated, until the next major release:
+
+```
+#[deprecated = \"renamed to `Configuration`\"]
+pub type Config = Configuration;
+```
+
+If the guess is wrong, list the actual rename in the file passed with `--renames`.",
+    ),
+];
+
+/// Get the detailed description of the change with the given code, if any.
+///
+/// Codes are matched case-insensitively.
+pub fn explain(code: &str) -> Option<&'static str> {
+    REGISTRY
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, desc)| *desc)
+}
@@ -5,7 +5,11 @@
 #![allow(clippy::too_many_lines)]
 #![deny(warnings)]
 
-extern crate rustc_const_eval; // Requires `rustup component add rustc-dev`
+extern crate rustc_ast; // Requires `rustup component add rustc-dev`
+extern crate rustc_ast_pretty;
+extern crate rustc_attr;
+extern crate rustc_const_eval;
+extern crate rustc_errors;
 extern crate rustc_hir;
 extern crate rustc_infer;
 extern crate rustc_metadata;
@@ -15,11 +19,30 @@ extern crate rustc_span;
 extern crate rustc_trait_selection;
 extern crate rustc_type_ir;
 
+mod api;
+pub mod budget;
 mod changes;
+mod check;
+mod config;
+mod explain;
+mod macro_rules;
+pub mod manifest;
 mod mapping;
 mod mismatch;
+mod recover;
+pub mod report;
+mod serde_shape;
+pub mod timings;
+pub mod trace;
 mod translate;
 mod traverse;
 mod typeck;
+mod wasm_bindgen;
 
-pub use self::traverse::{run_analysis, run_traversal};
+pub use self::api::dump_api;
+pub use self::changes::{ChangeCategory, ChangeSet};
+pub use self::check::{Findings, SemverCheck};
+pub use self::config::{Config, FailOn, Severity};
+pub use self::explain::explain;
+pub use self::mapping::IdMapping;
+pub use self::traverse::{run_analysis, run_analysis_with_checks, run_traversal};
@@ -0,0 +1,371 @@
+//! Comparison of the rules of exported declarative macros.
+//!
+//! The inputs a declarative macro accepts can't be compared semantically, as this would amount
+//! to comparing the languages its matchers describe. Instead, the rules of both versions are
+//! compared token by token, and the changes are reported along with a token-level diff, so that
+//! crate authors can judge whether the accepted invocations shrank.
+//!
+//! Both `macro_rules!` macros and `pub macro` definitions (macros 2.0) are supported, in any
+//! combination. The latter are stored with a single rule if they are defined without braces
+//! around their rules, like `pub macro foo($x:expr) { ... }`.
+//!
+//! Fragment specifiers whose meaning depends on the edition of the defining crate are compared
+//! by their meaning, so that the rewrites done when moving a crate to a newer edition aren't
+//! reported as changes.
+
+use crate::changes::{ChangeSet, ChangeType, MacroRuleChange};
+use rustc_ast::{
+    token::{Delimiter, TokenKind},
+    tokenstream::{TokenStream, TokenTree},
+    ItemKind,
+};
+use rustc_ast_pretty::pprust;
+use rustc_hir::def_id::DefId;
+use rustc_metadata::creader::{CStore, LoadedMacro};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::edition::Edition;
+
+/// A rule of a declarative macro, given as the tokens of its matcher and transcriber.
+#[derive(PartialEq, Eq)]
+struct MacroRule {
+    /// The tokens of the matcher, including its delimiters.
+    matcher: Vec<String>,
+    /// The tokens of the transcriber, including its delimiters.
+    transcriber: Vec<String>,
+}
+
+/// Given two macros, compare their rules if both are declarative macros.
+pub fn diff_macro_rules(changes: &mut ChangeSet, tcx: TyCtxt, old: DefId, new: DefId) {
+    let (old_rules, new_rules) = match (macro_rules(tcx, old), macro_rules(tcx, new)) {
+        (Some(old_rules), Some(new_rules)) => (old_rules, new_rules),
+        _ => return,
+    };
+
+    for (kind, desc) in diff_rules(&old_rules, &new_rules) {
+        changes.add_change(ChangeType::MacroRuleChanged { desc, kind }, old, None);
+    }
+}
+
+/// Compare the rules of two versions of a macro, describing each change found.
+///
+/// Rules with identical matchers are compared by their transcribers. The remaining rules are
+/// paired up in the order they are declared in, and compared by their matchers, while rules
+/// left over are reported as removed or added.
+fn diff_rules(old_rules: &[MacroRule], new_rules: &[MacroRule]) -> Vec<(MacroRuleChange, String)> {
+    let mut res = Vec::new();
+
+    if old_rules == new_rules {
+        return res;
+    }
+
+    let mut add = |kind, desc| res.push((kind, desc));
+
+    let mut old_unmatched = Vec::new();
+    let mut new_unmatched: Vec<_> = new_rules
+        .iter()
+        .filter(|new_rule| !old_rules.iter().any(|r| r.matcher == new_rule.matcher))
+        .collect();
+
+    for old_rule in old_rules {
+        match new_rules.iter().find(|r| r.matcher == old_rule.matcher) {
+            Some(new_rule) if new_rule.transcriber != old_rule.transcriber => add(
+                MacroRuleChange::TranscriberChanged,
+                format!(
+                    "expansion of macro rule `{}` changed: {}",
+                    render_tokens(&old_rule.matcher),
+                    diff_tokens(&old_rule.transcriber, &new_rule.transcriber)
+                ),
+            ),
+            Some(_) => (),
+            None => old_unmatched.push(old_rule),
+        }
+    }
+
+    let paired = old_unmatched.len().min(new_unmatched.len());
+    let removed = old_unmatched.split_off(paired);
+    let added = new_unmatched.split_off(paired);
+
+    for (old_rule, new_rule) in old_unmatched.into_iter().zip(new_unmatched) {
+        add(
+            MacroRuleChange::MatcherChanged,
+            format!(
+                "macro rule `{}` changed to `{}`: {}",
+                render_tokens(&old_rule.matcher),
+                render_tokens(&new_rule.matcher),
+                diff_tokens(&old_rule.matcher, &new_rule.matcher)
+            ),
+        );
+    }
+
+    for old_rule in removed {
+        add(
+            MacroRuleChange::Removed,
+            format!("macro rule `{}` removed", render_tokens(&old_rule.matcher)),
+        );
+    }
+
+    for new_rule in added {
+        add(
+            MacroRuleChange::Added,
+            format!("macro rule `{}` added", render_tokens(&new_rule.matcher)),
+        );
+    }
+
+    res
+}
+
+/// Get the rules of a macro, if it is a declarative macro.
+fn macro_rules(tcx: TyCtxt, def_id: DefId) -> Option<Vec<MacroRule>> {
+    let (item, edition) = match CStore::from_tcx(tcx).load_macro_untracked(def_id, tcx.sess) {
+        LoadedMacro::MacroDef(item, edition) => (item, edition),
+        LoadedMacro::ProcMacro(_) => return None,
+    };
+
+    let (body, separator) = match item.kind {
+        ItemKind::MacroDef(ref def) if def.macro_rules => {
+            (def.body.inner_tokens(), TokenKind::Semi)
+        }
+        ItemKind::MacroDef(ref def) => (def.body.inner_tokens(), TokenKind::Comma),
+        _ => return None,
+    };
+
+    let mut rules = parse_rules(&body, &separator)?;
+
+    for rule in &mut rules {
+        normalize_fragments(&mut rule.matcher, edition);
+    }
+
+    Some(rules)
+}
+
+/// Spell the fragment specifiers of a matcher the way they are spelled in the latest edition.
+///
+/// Before the 2021 edition, `pat` fragments don't accept top-level or-patterns, which is what
+/// `pat_param` fragments do in later editions, and what `cargo fix --edition` rewrites them to.
+fn normalize_fragments(matcher: &mut [String], edition: Edition) {
+    if edition >= Edition::Edition2021 {
+        return;
+    }
+
+    for i in 3..matcher.len() {
+        if matcher[i - 3] == "$" && matcher[i - 1] == ":" && matcher[i] == "pat" {
+            matcher[i] = "pat_param".to_owned();
+        }
+    }
+}
+
+/// Split the body of a macro into its rules, which are separated by semicolons in
+/// `macro_rules!` macros, and by commas in macros 2.0.
+fn parse_rules(body: &TokenStream, separator: &TokenKind) -> Option<Vec<MacroRule>> {
+    let trees: Vec<_> = body.trees().collect();
+
+    trees
+        .split(|tree| matches!(tree, TokenTree::Token(token, ..) if token.kind == *separator))
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| match rule {
+            [matcher, TokenTree::Token(arrow, ..), transcriber]
+                if arrow.kind == TokenKind::FatArrow =>
+            {
+                let mut rule = MacroRule {
+                    matcher: Vec::new(),
+                    transcriber: Vec::new(),
+                };
+                flatten_tokens(matcher, &mut rule.matcher);
+                flatten_tokens(transcriber, &mut rule.transcriber);
+
+                Some(rule)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Render the tokens of a token tree, including the delimiters of delimited groups.
+fn flatten_tokens(tree: &TokenTree, tokens: &mut Vec<String>) {
+    match tree {
+        TokenTree::Token(token, ..) => tokens.push(pprust::token_to_string(token).to_string()),
+        TokenTree::Delimited(_, delim, inner) => {
+            let (open, close) = match delim {
+                Delimiter::Parenthesis => ("(", ")"),
+                Delimiter::Brace => ("{", "}"),
+                Delimiter::Bracket => ("[", "]"),
+                Delimiter::Invisible => ("", ""),
+            };
+
+            if !open.is_empty() {
+                tokens.push(open.to_owned());
+            }
+
+            for tree in inner.trees() {
+                flatten_tokens(&tree, tokens);
+            }
+
+            if !close.is_empty() {
+                tokens.push(close.to_owned());
+            }
+        }
+    }
+}
+
+/// Render a sequence of tokens for display, keeping metavariables together.
+fn render_tokens(tokens: &[String]) -> String {
+    let mut res = String::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && tokens[i - 1] != "$" {
+            res.push(' ');
+        }
+
+        res.push_str(token);
+    }
+
+    res
+}
+
+/// Describe the differences between two sequences of tokens.
+///
+/// The tokens are diffed using their longest common subsequence, and each contiguous run of
+/// removed and added tokens is described on its own.
+fn diff_tokens(old: &[String], new: &[String]) -> String {
+    // lengths of the longest common subsequences of all suffixes
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            hunks.extend(describe_hunk(&removed, &added));
+            removed.clear();
+            added.clear();
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            removed.push(old[i].clone());
+            i += 1;
+        } else {
+            added.push(new[j].clone());
+            j += 1;
+        }
+    }
+
+    hunks.extend(describe_hunk(&removed, &added));
+    hunks.join(", ")
+}
+
+/// Describe a contiguous run of removed and added tokens, if any.
+fn describe_hunk(removed: &[String], added: &[String]) -> Option<String> {
+    match (removed.is_empty(), added.is_empty()) {
+        (true, true) => None,
+        (false, true) => Some(format!("removed `{}`", render_tokens(removed))),
+        (true, false) => Some(format!("added `{}`", render_tokens(added))),
+        (false, false) => Some(format!(
+            "replaced `{}` with `{}`",
+            render_tokens(removed),
+            render_tokens(added)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_owned).collect()
+    }
+
+    fn rule(matcher: &str, transcriber: &str) -> MacroRule {
+        MacroRule {
+            matcher: tokens(matcher),
+            transcriber: tokens(transcriber),
+        }
+    }
+
+    #[test]
+    fn render_metavariables() {
+        assert_eq!(
+            render_tokens(&tokens("( $ x : expr , $ ( $ y : ident ) , * )")),
+            "( $x : expr , $( $y : ident ) , * )"
+        );
+        assert_eq!(render_tokens(&[]), "");
+    }
+
+    #[test]
+    fn diff_narrowed_matcher() {
+        assert_eq!(
+            diff_tokens(&tokens("( $ x : expr )"), &tokens("( $ x : ident )")),
+            "replaced `expr` with `ident`"
+        );
+        assert_eq!(
+            diff_tokens(
+                &tokens("( $ ( $ x : expr ) , * )"),
+                &tokens("( $ x : expr )")
+            ),
+            "removed `( $`, removed `, * )`"
+        );
+        assert_eq!(
+            diff_tokens(
+                &tokens("( $ x : expr )"),
+                &tokens("( $ x : expr , $ y : expr )")
+            ),
+            "added `, $y : expr`"
+        );
+        assert_eq!(diff_tokens(&tokens("( )"), &tokens("( )")), "");
+    }
+
+    #[test]
+    fn narrowed_rule_breaking() {
+        let old = [rule("( $ x : expr )", "{ $ x }")];
+        let new = [rule("( $ x : ident )", "{ $ x }")];
+
+        assert_eq!(
+            diff_rules(&old, &new),
+            [(
+                MacroRuleChange::MatcherChanged,
+                "macro rule `( $x : expr )` changed to `( $x : ident )`: replaced `expr` with \
+                 `ident`"
+                    .to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn removed_rule() {
+        let old = [rule("( )", "{ 0 }"), rule("( $ x : expr )", "{ $ x }")];
+        let new = [rule("( )", "{ 1 }")];
+
+        assert_eq!(
+            diff_rules(&old, &new),
+            [
+                (
+                    MacroRuleChange::TranscriberChanged,
+                    "expansion of macro rule `( )` changed: replaced `0` with `1`".to_owned()
+                ),
+                (
+                    MacroRuleChange::Removed,
+                    "macro rule `( $x : expr )` removed".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_rules() {
+        let old = [rule("( $ x : expr )", "{ $ x }")];
+        let new = [rule("( $ x : expr )", "{ $ x }")];
+
+        assert!(diff_rules(&old, &new).is_empty());
+    }
+}
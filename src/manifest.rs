@@ -0,0 +1,581 @@
+//! Comparison of the manifests of both crate versions.
+//!
+//! Besides the public API of the library, downstream crates depend on the features declared in
+//! its manifest. These are compared by `cargo semver`, which has both manifests at hand, and
+//! passes the changes found to the analysis, so that they are reported and accounted for in the
+//! suggested version along with the changes to the code.
+
+use crate::{
+    changes::ChangeCategory::{self, *},
+    config::{Config, Severity},
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap, fmt};
+
+/// The features declared by a manifest, mapped to the features and dependencies they enable.
+pub type FeatureMap = BTreeMap<String, Vec<String>>;
+
+/// A change to the manifest of a crate.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ManifestChange {
+    /// A feature has been removed, possibly in favour of a new one.
+    FeatureRemoved {
+        name: String,
+        renamed_to: Option<String>,
+    },
+    /// The feature implicitly declared for an optional dependency has been removed, either
+    /// along with the dependency, or because it's now only referred to using `dep:`.
+    OptionalDepFeatureRemoved { name: String, namespaced: bool },
+    /// The declared minimum supported Rust version has changed.
+    RustVersionChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A feature or dependency is no longer enabled by default.
+    DefaultFeatureRemoved { name: String },
+    /// A feature or dependency is newly enabled by default.
+    DefaultFeatureAdded { name: String },
+    /// The edition of the crate has changed.
+    EditionChanged { old: String, new: String },
+}
+
+impl ManifestChange {
+    /// Get the change's category.
+    pub fn to_category(&self) -> ChangeCategory {
+        match *self {
+            ManifestChange::FeatureRemoved { .. }
+            | ManifestChange::OptionalDepFeatureRemoved { .. }
+            | ManifestChange::DefaultFeatureRemoved { .. } => Breaking,
+            ManifestChange::DefaultFeatureAdded { .. } => TechnicallyBreaking,
+            ManifestChange::EditionChanged { .. } => Patch,
+            ManifestChange::RustVersionChanged { ref old, ref new } => {
+                match compare_rust_versions(old.as_deref(), new.as_deref()) {
+                    Some(Ordering::Greater) => TechnicallyBreaking,
+                    _ => Patch,
+                }
+            }
+        }
+    }
+
+    /// Check whether the change is informational, so that it's reported without counting
+    /// towards the verdict.
+    pub fn is_informational(&self) -> bool {
+        matches!(*self, ManifestChange::EditionChanged { .. })
+    }
+
+    /// Get the severity with which the change is reported.
+    pub fn severity(&self, config: &Config) -> Severity {
+        if self.is_informational() {
+            config.informational_severity()
+        } else {
+            config.severity(self.to_category())
+        }
+    }
+}
+
+impl fmt::Display for ManifestChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestChange::FeatureRemoved {
+                ref name,
+                renamed_to: Some(ref new_name),
+            } => write!(f, "feature `{}` renamed to `{}`", name, new_name),
+            ManifestChange::FeatureRemoved { ref name, .. } => {
+                write!(f, "feature `{}` removed", name)
+            }
+            ManifestChange::OptionalDepFeatureRemoved {
+                ref name,
+                namespaced: true,
+            } => write!(
+                f,
+                "feature of optional dependency `{}` removed in favour of `dep:{}`",
+                name, name
+            ),
+            ManifestChange::OptionalDepFeatureRemoved { ref name, .. } => {
+                write!(f, "optional dependency `{}` and its feature removed", name)
+            }
+            ManifestChange::RustVersionChanged {
+                old: Some(ref old),
+                new: Some(ref new),
+            } => match compare_rust_versions(Some(old), Some(new)) {
+                Some(Ordering::Greater) => {
+                    write!(f, "`rust-version` raised from {} to {}", old, new)
+                }
+                _ => write!(
+                    f,
+                    "`rust-version` lowered from {} to {}, make sure the crate still builds \
+                     with it",
+                    old, new
+                ),
+            },
+            ManifestChange::RustVersionChanged {
+                new: Some(ref new), ..
+            } => write!(f, "`rust-version` {} declared", new),
+            ManifestChange::RustVersionChanged { .. } => {
+                write!(f, "`rust-version` no longer declared")
+            }
+            ManifestChange::DefaultFeatureRemoved { ref name } => {
+                write!(f, "`{}` no longer enabled by default", name)
+            }
+            ManifestChange::DefaultFeatureAdded { ref name } => {
+                write!(f, "`{}` now enabled by default", name)
+            }
+            ManifestChange::EditionChanged { ref old, ref new } => {
+                write!(f, "edition changed from {} to {}", old, new)?;
+
+                match edition_rust_version(new) {
+                    Some(version) if new > old => write!(f, ", which requires Rust {}", version),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// Compare the features declared by both versions of a crate.
+///
+/// User code enables features by name, so removing one breaks it. A single removed feature
+/// replaced by a single added one is reported as a rename. The `default` feature is enabled
+/// implicitly, so it's compared by the features it enables instead.
+///
+/// Features only enabling the optional dependency of the same name, such as the ones cargo
+/// declares implicitly, are reported separately, as they disappear when the dependency is
+/// removed, or referred to using `dep:` in another feature.
+pub fn diff_features(old: &FeatureMap, new: &FeatureMap) -> Vec<ManifestChange> {
+    let (dep_features, removed): (Vec<_>, Vec<_>) = old
+        .iter()
+        .filter(|(name, _)| *name != "default" && !new.contains_key(*name))
+        .partition(|(name, values)| **values == [format!("dep:{}", name)]);
+    let removed: Vec<_> = removed.into_iter().map(|(name, _)| name).collect();
+    let added: Vec<_> = new
+        .keys()
+        .filter(|name| *name != "default" && !old.contains_key(*name))
+        .collect();
+
+    let renamed_to = match (removed.len(), added.as_slice()) {
+        (1, [added]) => Some((*added).clone()),
+        _ => None,
+    };
+
+    let mut changes: Vec<_> = removed
+        .into_iter()
+        .map(|name| ManifestChange::FeatureRemoved {
+            name: name.clone(),
+            renamed_to: renamed_to.clone(),
+        })
+        .collect();
+
+    for (name, _) in dep_features {
+        let dep = format!("dep:{}", name);
+        let namespaced = new.values().any(|values| values.contains(&dep));
+
+        changes.push(ManifestChange::OptionalDepFeatureRemoved {
+            name: name.clone(),
+            namespaced,
+        });
+    }
+
+    diff_default_features(&mut changes, old, new);
+
+    changes
+}
+
+/// Parse a Rust version as given in the `rust-version` field of a manifest, or in the
+/// stability attributes of the standard library.
+///
+/// The minor and patch components are optional, and default to zero.
+pub fn parse_rust_version(version: &str) -> Option<Version> {
+    let mut components = version.trim().split('.').map(str::parse);
+
+    let major = components.next()?.ok()?;
+    let minor = components.next().unwrap_or(Ok(0)).ok()?;
+    let patch = components.next().unwrap_or(Ok(0)).ok()?;
+
+    if components.next().is_some() {
+        return None;
+    }
+
+    Some(Version::new(major, minor, patch))
+}
+
+/// Compare the minimum supported Rust versions declared by both versions of a crate.
+///
+/// Returns `Greater` if the new version requires a newer toolchain, treating an undeclared
+/// version as the oldest one, and `None` if any of the versions can't be parsed.
+fn compare_rust_versions(old: Option<&str>, new: Option<&str>) -> Option<Ordering> {
+    let old = old
+        .map(parse_rust_version)
+        .unwrap_or_else(|| Some(Version::new(0, 0, 0)))?;
+    let new = new
+        .map(parse_rust_version)
+        .unwrap_or_else(|| Some(Version::new(0, 0, 0)))?;
+
+    Some(new.cmp(&old))
+}
+
+/// Compare the `rust-version` fields of both versions of a crate.
+///
+/// Raising the minimum supported Rust version breaks users on older toolchains, which is
+/// commonly considered a minor change. Lowering it is suspicious, as it's rarely done on
+/// purpose, so it's reported as well.
+pub fn diff_rust_version(old: Option<&str>, new: Option<&str>) -> Option<ManifestChange> {
+    if old == new || compare_rust_versions(old, new) == Some(Ordering::Equal) {
+        return None;
+    }
+
+    Some(ManifestChange::RustVersionChanged {
+        old: old.map(str::to_owned),
+        new: new.map(str::to_owned),
+    })
+}
+
+/// Get the first Rust version supporting an edition, if it is known to us.
+fn edition_rust_version(edition: &str) -> Option<&'static str> {
+    match edition {
+        "2018" => Some("1.31"),
+        "2021" => Some("1.56"),
+        _ => None,
+    }
+}
+
+/// Compare the editions of both versions of a crate.
+///
+/// The edition is local to a crate, and doesn't affect its users, apart from the toolchain
+/// needed to build it. A newer edition can thus raise the minimum supported Rust version, so
+/// the change is reported for information.
+pub fn diff_edition(old: &str, new: &str) -> Option<ManifestChange> {
+    if old == new {
+        return None;
+    }
+
+    Some(ManifestChange::EditionChanged {
+        old: old.to_owned(),
+        new: new.to_owned(),
+    })
+}
+
+/// Compare the features and dependencies enabled by default in both versions of a crate.
+///
+/// User code relying on default features doesn't mention them, so removing one silently
+/// changes what it's compiled with, and is breaking. Adding one only pulls in more code, which
+/// can still break builds for targets or environments the new code doesn't support.
+fn diff_default_features(changes: &mut Vec<ManifestChange>, old: &FeatureMap, new: &FeatureMap) {
+    let no_defaults = Vec::new();
+    let old_defaults = old.get("default").unwrap_or(&no_defaults);
+    let new_defaults = new.get("default").unwrap_or(&no_defaults);
+
+    for name in old_defaults {
+        if !new_defaults.contains(name) {
+            changes.push(ManifestChange::DefaultFeatureRemoved { name: name.clone() });
+        }
+    }
+
+    for name in new_defaults {
+        if !old_defaults.contains(name) {
+            changes.push(ManifestChange::DefaultFeatureAdded { name: name.clone() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The parts of a manifest compared.
+    #[derive(Deserialize)]
+    struct Manifest {
+        #[serde(default)]
+        features: FeatureMap,
+    }
+
+    /// Get the features declared in a manifest.
+    fn features(manifest: &str) -> FeatureMap {
+        toml_edit::easy::from_str::<Manifest>(manifest)
+            .unwrap()
+            .features
+    }
+
+    /// Compare the features declared in two manifests.
+    fn diff(old: &str, new: &str) -> Vec<ManifestChange> {
+        diff_features(&features(old), &features(new))
+    }
+
+    const BASE: &str = r#"
+        [package]
+        name = "abc"
+        version = "1.0.0"
+
+        [features]
+        default = ["std"]
+        std = []
+        alloc = []
+        rand = ["dep:rand"]
+    "#;
+
+    #[test]
+    fn features_unchanged() {
+        assert_eq!(diff(BASE, BASE), vec![]);
+    }
+
+    #[test]
+    fn features_removed() {
+        let new = r#"
+            [features]
+            default = ["std"]
+            std = []
+            rand = ["dep:rand"]
+        "#;
+
+        assert_eq!(
+            diff(BASE, new),
+            vec![ManifestChange::FeatureRemoved {
+                name: "alloc".to_owned(),
+                renamed_to: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn feature_renamed() {
+        let new = r#"
+            [features]
+            default = ["std"]
+            std = []
+            alloc1 = []
+            rand = ["dep:rand"]
+        "#;
+
+        assert_eq!(
+            diff(BASE, new),
+            vec![ManifestChange::FeatureRemoved {
+                name: "alloc".to_owned(),
+                renamed_to: Some("alloc1".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn features_added() {
+        let new = r#"
+            [features]
+            default = ["std"]
+            std = []
+            alloc = []
+            rand = ["dep:rand"]
+            serde = []
+        "#;
+
+        assert_eq!(diff(BASE, new), vec![]);
+    }
+
+    #[test]
+    fn optional_dep_features_removed() {
+        let namespaced = r#"
+            [features]
+            default = ["std"]
+            std = ["dep:rand"]
+            alloc = []
+        "#;
+        let removed = r#"
+            [features]
+            default = ["std"]
+            std = []
+            alloc = []
+        "#;
+
+        assert_eq!(
+            diff(BASE, namespaced),
+            vec![ManifestChange::OptionalDepFeatureRemoved {
+                name: "rand".to_owned(),
+                namespaced: true,
+            }]
+        );
+        assert_eq!(
+            diff(BASE, removed),
+            vec![ManifestChange::OptionalDepFeatureRemoved {
+                name: "rand".to_owned(),
+                namespaced: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn default_features_changed() {
+        let new = r#"
+            [features]
+            default = ["alloc", "rand"]
+            std = []
+            alloc = []
+            rand = ["dep:rand"]
+        "#;
+
+        assert_eq!(
+            diff(BASE, new),
+            vec![
+                ManifestChange::DefaultFeatureRemoved {
+                    name: "std".to_owned()
+                },
+                ManifestChange::DefaultFeatureAdded {
+                    name: "alloc".to_owned()
+                },
+                ManifestChange::DefaultFeatureAdded {
+                    name: "rand".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn default_feature_declared() {
+        let old = r#"
+            [features]
+            std = []
+        "#;
+        let new = r#"
+            [features]
+            default = ["std"]
+            std = []
+        "#;
+
+        let mut changes = Vec::new();
+        diff_default_features(&mut changes, &features(old), &features(new));
+
+        assert_eq!(
+            changes,
+            vec![ManifestChange::DefaultFeatureAdded {
+                name: "std".to_owned()
+            }]
+        );
+
+        changes.clear();
+        diff_default_features(&mut changes, &features(new), &features(old));
+
+        assert_eq!(
+            changes,
+            vec![ManifestChange::DefaultFeatureRemoved {
+                name: "std".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    fn rust_version_parsed() {
+        assert_eq!(parse_rust_version("1.56"), Some(Version::new(1, 56, 0)));
+        assert_eq!(parse_rust_version("1.56.1"), Some(Version::new(1, 56, 1)));
+        assert_eq!(parse_rust_version(" 1 "), Some(Version::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn rust_version_malformed() {
+        assert_eq!(parse_rust_version(""), None);
+        assert_eq!(parse_rust_version("1.56.0.1"), None);
+        assert_eq!(parse_rust_version("1.x"), None);
+        assert_eq!(parse_rust_version("1.56-nightly"), None);
+        assert_eq!(parse_rust_version("1..0"), None);
+    }
+
+    #[test]
+    fn rust_versions_compared() {
+        assert_eq!(
+            compare_rust_versions(Some("1.56"), Some("1.56.0")),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            compare_rust_versions(Some("1.56"), Some("1.56.1")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_rust_versions(Some("1.60.0"), Some("1.56")),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_rust_versions(None, Some("1.56")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_rust_versions(Some("1.56"), None),
+            Some(Ordering::Less)
+        );
+        assert_eq!(compare_rust_versions(Some("1.x"), Some("1.56")), None);
+    }
+
+    #[test]
+    fn rust_version_unchanged() {
+        assert_eq!(diff_rust_version(None, None), None);
+        assert_eq!(diff_rust_version(Some("1.56"), Some("1.56")), None);
+        assert_eq!(diff_rust_version(Some("1.56"), Some("1.56.0")), None);
+    }
+
+    #[test]
+    fn rust_version_raised() {
+        let change = diff_rust_version(Some("1.56"), Some("1.60.0")).unwrap();
+
+        assert_eq!(change.to_category(), TechnicallyBreaking);
+        assert_eq!(
+            change.to_string(),
+            "`rust-version` raised from 1.56 to 1.60.0"
+        );
+
+        let change = diff_rust_version(None, Some("1.56")).unwrap();
+
+        assert_eq!(change.to_category(), TechnicallyBreaking);
+        assert_eq!(change.to_string(), "`rust-version` 1.56 declared");
+    }
+
+    #[test]
+    fn rust_version_lowered() {
+        let change = diff_rust_version(Some("1.60"), Some("1.56")).unwrap();
+
+        assert_eq!(change.to_category(), Patch);
+        assert_eq!(
+            change.to_string(),
+            "`rust-version` lowered from 1.60 to 1.56, make sure the crate still builds with it"
+        );
+
+        let change = diff_rust_version(Some("1.60"), None).unwrap();
+
+        assert_eq!(change.to_category(), Patch);
+        assert_eq!(change.to_string(), "`rust-version` no longer declared");
+    }
+
+    #[test]
+    fn rust_version_malformed_changed() {
+        // versions that can't be compared are reported, but don't count towards the verdict
+        let change = diff_rust_version(Some("1.56"), Some("1.x")).unwrap();
+
+        assert_eq!(change.to_category(), Patch);
+    }
+
+    #[test]
+    fn edition_unchanged() {
+        assert_eq!(diff_edition("2018", "2018"), None);
+    }
+
+    #[test]
+    fn edition_bumped() {
+        let change = diff_edition("2018", "2021").unwrap();
+
+        assert_eq!(change.to_category(), Patch);
+        assert_eq!(change.severity(&Config::default()), Severity::Warn);
+        assert_eq!(
+            change.to_string(),
+            "edition changed from 2018 to 2021, which requires Rust 1.56"
+        );
+
+        let change = diff_edition("2015", "2018").unwrap();
+
+        assert_eq!(
+            change.to_string(),
+            "edition changed from 2015 to 2018, which requires Rust 1.31"
+        );
+    }
+
+    #[test]
+    fn edition_lowered() {
+        let change = diff_edition("2021", "2018").unwrap();
+
+        assert_eq!(change.to_category(), Patch);
+        assert_eq!(change.to_string(), "edition changed from 2021 to 2018");
+    }
+}
@@ -70,6 +70,8 @@ pub struct IdMapping {
     type_params: HashMap<DefId, GenericParamDef>,
     /// Map from inherent impls' descriptors to the impls they are declared in.
     inherent_items: HashMap<InherentEntry, InherentImplSet>,
+    /// The old items the new crate re-exports from another version of the old crate.
+    semver_trick_items: HashSet<DefId>,
 }
 
 impl IdMapping {
@@ -87,6 +89,7 @@ impl IdMapping {
             reverse_mapping: HashMap::new(),
             type_params: HashMap::new(),
             inherent_items: HashMap::new(),
+            semver_trick_items: HashSet::new(),
         }
     }
 
@@ -114,6 +117,22 @@ impl IdMapping {
         self.non_mapped_items.insert(def_id);
     }
 
+    /// Check whether an item is known to have no corresponding item in the other crate.
+    pub fn is_non_mapped(&self, def_id: DefId) -> bool {
+        self.non_mapped_items.contains(&def_id)
+    }
+
+    /// Register that the new crate re-exports an old item from another version of the old crate.
+    pub fn add_semver_trick_item(&mut self, old: DefId) {
+        self.semver_trick_items.insert(old);
+    }
+
+    /// Check whether the new crate re-exports an old item from another version of the old
+    /// crate, making both the same item for users.
+    pub fn is_semver_trick_item(&self, old: DefId) -> bool {
+        self.semver_trick_items.contains(&old)
+    }
+
     /// Add any trait item's old and new `DefId`s.
     pub fn add_trait_item(&mut self, old: Res, new: Res, old_trait: DefId) {
         let old_def_id = old.def_id();
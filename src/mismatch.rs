@@ -3,18 +3,30 @@
 //! Any two items' types found in the same place which are not matched with other items yet are
 //! are treated as renamed instances of the same item (as long as they are both unknown to us at
 //! the time of analysis). Thus, we may match them up to avoid some false positives.
+//!
+//! Similarly, public items that have been moved to a different module without keeping a
+//! re-export at their old path are matched up by their name and shape, so that they can be
+//! reported as moved instead of being removed and added. Items whose names have merely changed
+//! slightly, like `Config` becoming `Configuration`, are matched up by their shape and the edit
+//! distance of their names, and reported as probably renamed. Types and traits that have been
+//! renamed and moved at once are matched up by their structure, i.e. their generics, fields and
+//! associated items, and reported as probably renamed as well.
 
 use crate::mapping::IdMapping;
 use log::debug;
 use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::DefId;
+use rustc_hir::hir_id::HirId;
+use rustc_middle::metadata::ModChild;
 use rustc_middle::ty::{
     self,
     relate::{Relate, RelateResult, TypeRelation},
     subst::SubstsRef,
-    ParamEnv, Ty, TyCtxt,
+    AssocKind, ParamEnv, Ty, TyCtxt,
     Visibility::Public,
 };
-use std::collections::{HashMap, HashSet, VecDeque};
+use rustc_span::{lev_distance::lev_distance, symbol::Symbol};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
 /// A relation searching for items appearing at the same spot in a type.
 ///
@@ -59,15 +71,22 @@ impl<'a, 'tcx> MismatchRelation<'a, 'tcx> {
             debug!("old: {:?}, new: {:?}", old_res, new_res);
 
             // FIXME: this is odd, see if we can lift the restriction on traits
+            // closures and generators have been related along with their substs already
             let (old_def_id, new_def_id) = match (old_res, new_res) {
                 (Res::Def(k1, o), Res::Def(k2, n)) => {
                     match k1 {
-                        DefKind::Trait | DefKind::Macro(_) => continue,
+                        DefKind::Trait
+                        | DefKind::Macro(_)
+                        | DefKind::Closure
+                        | DefKind::Generator => continue,
                         _ => (),
                     };
 
                     match k2 {
-                        DefKind::Trait | DefKind::Macro(_) => continue,
+                        DefKind::Trait
+                        | DefKind::Macro(_)
+                        | DefKind::Closure
+                        | DefKind::Generator => continue,
                         _ => (),
                     };
 
@@ -253,15 +272,40 @@ impl<'a, 'tcx> TypeRelation<'tcx> for MismatchRelation<'a, 'tcx> {
 
                 Some((a, b))
             }
-            (&TyKind::Opaque(_a_def_id, a_substs), &TyKind::Opaque(_b_def_id, b_substs)) => {
+            (&TyKind::Opaque(a_def_id, a_substs), &TyKind::Opaque(b_def_id, b_substs)) => {
                 if self.check_substs(a_substs, b_substs) {
                     let _ = ty::relate::relate_substs(self, a_substs, b_substs)?;
                 }
 
-                // TODO: we are talking impl trait here, so we can build a Res for that or the
-                // associated type
-                // Some((a_def_id, b_def_id))
-                None
+                // the hidden types are related in turn, which matches up the closures and
+                // generators returned as `impl Trait`
+                let a = Res::Def(DefKind::OpaqueTy, a_def_id);
+                let b = Res::Def(DefKind::OpaqueTy, b_def_id);
+
+                Some((a, b))
+            }
+            (&TyKind::Closure(a_def_id, a_substs), &TyKind::Closure(b_def_id, b_substs)) => {
+                if self.check_substs(a_substs, b_substs) {
+                    let _ = ty::relate::relate_substs(self, a_substs, b_substs)?;
+                }
+
+                let a = Res::Def(DefKind::Closure, a_def_id);
+                let b = Res::Def(DefKind::Closure, b_def_id);
+
+                Some((a, b))
+            }
+            (
+                &TyKind::Generator(a_def_id, a_substs, _),
+                &TyKind::Generator(b_def_id, b_substs, _),
+            ) => {
+                if self.check_substs(a_substs, b_substs) {
+                    let _ = ty::relate::relate_substs(self, a_substs, b_substs)?;
+                }
+
+                let a = Res::Def(DefKind::Generator, a_def_id);
+                let b = Res::Def(DefKind::Generator, b_def_id);
+
+                Some((a, b))
             }
             (&TyKind::Infer(_), _) | (_, &TyKind::Infer(_)) => {
                 // As the original function this is ripped off of, we don't handle these cases.
@@ -312,3 +356,329 @@ impl<'a, 'tcx> TypeRelation<'tcx> for MismatchRelation<'a, 'tcx> {
         Ok(a.rebind(self.relate(a.skip_binder(), b.skip_binder())?))
     }
 }
+
+/// Match up removed and added items that have been moved to a different module.
+///
+/// A removed and an added item are paired if they share their name and shape, and no other
+/// removed or added item does, since a wrong guess would be more confusing than reporting a
+/// removal and an addition. Paired items are taken out of `removals` and `additions`.
+pub fn match_moved_items(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    removals: &mut Vec<ModChild>,
+    additions: &mut Vec<ModChild>,
+) -> Vec<(ModChild, ModChild)> {
+    match_unique_items(id_mapping, removals, additions, |o_name, o, n_name, n| {
+        o_name == n_name && same_shape(tcx, o, n)
+    })
+}
+
+/// Match up removed and added items that have probably been renamed.
+///
+/// A removed and an added item are paired if they have the same shape and similar, but
+/// different names, and no other removed or added item is similar in the same way. Paired items
+/// are taken out of `removals` and `additions`.
+pub fn match_renamed_items(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    removals: &mut Vec<ModChild>,
+    additions: &mut Vec<ModChild>,
+) -> Vec<(ModChild, ModChild)> {
+    match_unique_items(id_mapping, removals, additions, |o_name, o, n_name, n| {
+        o_name != n_name && similar_names(o_name, n_name) && same_shape(tcx, o, n)
+    })
+}
+
+/// Match up removed and added types and traits that have been renamed and moved at once.
+///
+/// A removed and an added item are paired if they have the same structure, regardless of their
+/// names and modules, and no other removed or added item does. Paired items are taken out of
+/// `removals` and `additions`.
+pub fn match_restructured_items(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    removals: &mut Vec<ModChild>,
+    additions: &mut Vec<ModChild>,
+) -> Vec<(ModChild, ModChild)> {
+    match_unique_items(id_mapping, removals, additions, |_, o, _, n| {
+        same_structure(tcx, o, n)
+    })
+}
+
+/// Find the removed and added items that could have been matched up, but weren't.
+///
+/// This is the case for items that have the same shape and similar names, or the same structure,
+/// but also have other such candidates, so that none of them has been matched up.
+pub fn ambiguous_candidates(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    removals: &[ModChild],
+    additions: &[ModChild],
+) -> Vec<(DefId, DefId)> {
+    let old_items = removals
+        .iter()
+        .filter_map(|item| Some((item.ident.name, export_def_id(item)?)))
+        .filter(|&(_, def_id)| !id_mapping.contains_old_id(def_id));
+    let new_items: Vec<_> = additions
+        .iter()
+        .filter_map(|item| Some((item.ident.name, export_def_id(item)?)))
+        .filter(|&(_, def_id)| !id_mapping.contains_new_id(def_id))
+        .collect();
+
+    let mut candidates = Vec::new();
+
+    for (o_name, o) in old_items {
+        for &(n_name, n) in &new_items {
+            if (similar_names(o_name, n_name) && same_shape(tcx, o, n)) || same_structure(tcx, o, n)
+            {
+                candidates.push((o, n));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Pair up removed and added items for which `matches` holds for exactly one item on each side.
+fn match_unique_items(
+    id_mapping: &IdMapping,
+    removals: &mut Vec<ModChild>,
+    additions: &mut Vec<ModChild>,
+    matches: impl Fn(Symbol, DefId, Symbol, DefId) -> bool,
+) -> Vec<(ModChild, ModChild)> {
+    // items still exported at another path haven't been moved, but merely lost a re-export
+    let old_items: Vec<_> = removals
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((i, item.ident.name, export_def_id(item)?)))
+        .filter(|&(_, _, def_id)| !id_mapping.contains_old_id(def_id))
+        .collect();
+    let new_items: Vec<_> = additions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((i, item.ident.name, export_def_id(item)?)))
+        .filter(|&(_, _, def_id)| !id_mapping.contains_new_id(def_id))
+        .collect();
+
+    let mut old_indices = BTreeSet::new();
+    let mut new_indices = BTreeSet::new();
+    let mut matched = Vec::new();
+
+    for &(o, name, old_def_id) in &old_items {
+        let candidates: Vec<_> = new_items
+            .iter()
+            .filter(|&&(_, n_name, new_def_id)| matches(name, old_def_id, n_name, new_def_id))
+            .collect();
+
+        if let [&(n, new_name, new_def_id)] = candidates.as_slice() {
+            let rivals = old_items
+                .iter()
+                .filter(|&&(_, o_name, other)| matches(o_name, other, new_name, new_def_id))
+                .count();
+
+            if rivals == 1 {
+                debug!("matched: {:?} -> {:?}", old_def_id, new_def_id);
+                old_indices.insert(o);
+                new_indices.insert(n);
+                matched.push((removals[o], additions[n]));
+            }
+        }
+    }
+
+    // remove the paired items back to front to keep the remaining indices valid
+    for i in old_indices.into_iter().rev() {
+        removals.remove(i);
+    }
+
+    for i in new_indices.into_iter().rev() {
+        additions.remove(i);
+    }
+
+    matched
+}
+
+/// Check whether two item names are similar enough for one to probably be a rename of the other.
+///
+/// Ignoring case, this holds if one name contains the other and the shorter one has at least
+/// three characters, or if their edit distance is at most a third of the longer name's length.
+fn similar_names(old: Symbol, new: Symbol) -> bool {
+    let old = old.as_str().to_lowercase();
+    let new = new.as_str().to_lowercase();
+    let (shorter, longer) = if old.len() <= new.len() {
+        (&old, &new)
+    } else {
+        (&new, &old)
+    };
+
+    (shorter.chars().count() >= 3 && longer.contains(shorter.as_str()))
+        || lev_distance(&old, &new, longer.chars().count() / 3).is_some()
+}
+
+/// Get the `DefId` of an exported item, if any.
+fn export_def_id(item: &ModChild) -> Option<DefId> {
+    item.res.expect_non_local::<HirId>().opt_def_id()
+}
+
+/// Check whether two items from different crate versions have the same shape.
+///
+/// The items need to be of the same kind and declare the same number of generic parameters.
+/// Beyond that, functions need to have the same signature and other items with a type the same
+/// type, both rendered without the crate name. ADTs need to have the same variant and field
+/// names, and traits the same item names.
+fn same_shape(tcx: TyCtxt, old: DefId, new: DefId) -> bool {
+    let kind = tcx.def_kind(old);
+
+    if kind != tcx.def_kind(new) {
+        return false;
+    }
+
+    let render = |def_id: DefId, rendered: String| {
+        rendered.replace(&format!("{}::", tcx.crate_name(def_id.krate)), "")
+    };
+    let generics = |def_id: DefId| tcx.generics_of(def_id).params.len();
+
+    match kind {
+        DefKind::Fn => {
+            generics(old) == generics(new)
+                && render(old, tcx.fn_sig(old).to_string())
+                    == render(new, tcx.fn_sig(new).to_string())
+        }
+        DefKind::Const | DefKind::Static(_) | DefKind::TyAlias => {
+            generics(old) == generics(new)
+                && render(old, tcx.type_of(old).to_string())
+                    == render(new, tcx.type_of(new).to_string())
+        }
+        DefKind::Struct | DefKind::Enum | DefKind::Union => {
+            let names = |def_id: DefId| -> Vec<_> {
+                tcx.adt_def(def_id)
+                    .variants()
+                    .iter()
+                    .map(|variant| {
+                        let fields: Vec<_> = variant
+                            .fields
+                            .iter()
+                            .map(|field| field.ident(tcx).name)
+                            .collect();
+                        (variant.ident(tcx).name, fields)
+                    })
+                    .collect()
+            };
+
+            generics(old) == generics(new) && names(old) == names(new)
+        }
+        DefKind::Trait => {
+            let names = |def_id: DefId| -> BTreeSet<_> {
+                tcx.associated_items(def_id)
+                    .in_definition_order()
+                    .map(|item| item.name.to_string())
+                    .collect()
+            };
+
+            generics(old) == generics(new) && names(old) == names(new)
+        }
+        _ => false,
+    }
+}
+
+/// Check whether two ADTs or traits from different crate versions have the same structure.
+///
+/// The items need to be of the same kind and declare the same generic parameters. ADTs need to
+/// have the same variants and fields of the same types, and the same public inherent methods,
+/// and traits the same items, with methods of the same signatures. As items with little
+/// structure are easily confused, at least one field or associated item has to be declared.
+fn same_structure(tcx: TyCtxt, old: DefId, new: DefId) -> bool {
+    let kind = tcx.def_kind(old);
+
+    if kind != tcx.def_kind(new) {
+        return false;
+    }
+
+    match kind {
+        DefKind::Struct | DefKind::Enum | DefKind::Union | DefKind::Trait => {
+            let old_members = members(tcx, old);
+
+            !old_members.is_empty()
+                && old_members == members(tcx, new)
+                && generic_params(tcx, old) == generic_params(tcx, new)
+        }
+        _ => false,
+    }
+}
+
+/// Get the names and kinds of the generic parameters of an item.
+fn generic_params(tcx: TyCtxt, def_id: DefId) -> Vec<(Symbol, &'static str)> {
+    tcx.generics_of(def_id)
+        .params
+        .iter()
+        .map(|param| (param.name, param.kind.descr()))
+        .collect()
+}
+
+/// Render the fields and associated items of an ADT or trait, independently of its path.
+///
+/// Mentions of the item itself are rendered as `Self`, and paths of other items without the
+/// crate name.
+fn members(tcx: TyCtxt, def_id: DefId) -> BTreeSet<String> {
+    let self_path = tcx.def_path_str(def_id);
+    let crate_prefix = format!("{}::", tcx.crate_name(def_id.krate));
+    let render = |rendered: String| {
+        rendered
+            .replace(&self_path, "Self")
+            .replace(&crate_prefix, "")
+    };
+
+    let mut members = BTreeSet::new();
+
+    let mut add_assoc_items = |container: DefId, is_trait: bool| {
+        for item in tcx.associated_items(container).in_definition_order() {
+            if !is_trait && tcx.visibility(item.def_id) != Public {
+                continue;
+            }
+
+            members.insert(match item.kind {
+                AssocKind::Const => format!(
+                    "const {}: {}",
+                    item.name,
+                    render(tcx.type_of(item.def_id).to_string())
+                ),
+                AssocKind::Fn => format!(
+                    "fn {}: {}",
+                    item.name,
+                    render(tcx.fn_sig(item.def_id).to_string())
+                ),
+                AssocKind::Type => format!("type {}", item.name),
+            });
+        }
+    };
+
+    if tcx.def_kind(def_id) == DefKind::Trait {
+        add_assoc_items(def_id, true);
+    } else {
+        for impl_def_id in tcx.inherent_impls(def_id).iter() {
+            add_assoc_items(*impl_def_id, false);
+        }
+
+        let adt_def = tcx.adt_def(def_id);
+
+        for variant in adt_def.variants().iter() {
+            // the single variant of structs and unions shares the name of the item
+            let prefix = if adt_def.is_enum() {
+                members.insert(format!("variant {}", variant.ident(tcx).name));
+                format!("{}::", variant.ident(tcx).name)
+            } else {
+                String::new()
+            };
+
+            for field in variant.fields.iter() {
+                members.insert(format!(
+                    "{}{}: {}",
+                    prefix,
+                    field.ident(tcx).name,
+                    render(tcx.type_of(field.did).to_string())
+                ));
+            }
+        }
+    }
+
+    members
+}
@@ -0,0 +1,182 @@
+//! Recovery from panics of the compiler while analyzing single items.
+//!
+//! Some items trigger bugs in the parts of the compiler we use, which panic. Instead of aborting
+//! the whole analysis, such panics are caught, and the item is reported as not analyzed.
+//!
+//! Unwinding out of the compiler doesn't leave it in a pristine state. A query that was being
+//! computed when the panic occurred is poisoned, so that computing it again panics as well. This
+//! is recovered from the same way, and only affects items needing that query. Inference contexts
+//! are created for each comparison and dropped while unwinding, so they don't leak into the
+//! comparison of other items. Errors the compiler emitted before panicking are kept, though.
+
+use crate::budget::Timeout;
+use log::debug;
+use rustc_errors::ExplicitBug;
+use rustc_span::fatal_error::FatalErrorMarker;
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    panic::{self, AssertUnwindSafe, PanicInfo},
+    sync::Arc,
+};
+
+/// A panic hook, as installed using `std::panic::set_hook`.
+type Hook = dyn Fn(&PanicInfo<'_>) + Sync + Send + 'static;
+
+thread_local! {
+    /// Whether panics on the current thread are being recovered from.
+    static RECOVERING: Cell<bool> = Cell::new(false);
+    /// The location of the last panic recovered from on the current thread.
+    static LOCATION: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Keeps the panic hook from reporting the panics recovered from, for as long as it lives.
+///
+/// The compiler's hook reports panics as internal compiler errors, which they aren't once they
+/// are recovered from. Hooks are process-global, so the hook is replaced once per analysis pass
+/// instead of for every item, and panics outside of `recover` are still passed on to it. A hook
+/// installed by anyone else while the guard lives is replaced with the previous one on drop.
+pub struct QuietPanics {
+    /// The hook in place before the guard was created.
+    hook: Arc<Hook>,
+}
+
+impl QuietPanics {
+    /// Replace the panic hook with one staying quiet about the panics recovered from.
+    pub fn install() -> Self {
+        let hook: Arc<Hook> = Arc::from(panic::take_hook());
+        let outer = Arc::clone(&hook);
+
+        panic::set_hook(Box::new(move |info| {
+            if RECOVERING.with(Cell::get) {
+                debug!("recovering from panic: {}", info);
+                LOCATION.with(|l| *l.borrow_mut() = info.location().map(ToString::to_string));
+            } else {
+                outer(info);
+            }
+        }));
+
+        QuietPanics { hook }
+    }
+}
+
+impl Drop for QuietPanics {
+    fn drop(&mut self) {
+        // the hook can't be replaced while unwinding out of the pass, which aborts anyway
+        if !std::thread::panicking() {
+            let hook = Arc::clone(&self.hook);
+            panic::set_hook(Box::new(move |info| hook(info)));
+        }
+    }
+}
+
+/// Run a closure, recovering from panics in it.
+///
+/// If it panicked, the reason is returned, as extracted from the panic's payload.
+pub fn recover<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    let recovering = RECOVERING.with(|r| r.replace(true));
+    let res = panic::catch_unwind(AssertUnwindSafe(f));
+    RECOVERING.with(|r| r.set(recovering));
+
+    res.map_err(|payload| {
+        let location = LOCATION.with(|l| l.borrow_mut().take());
+        reason(&*payload, location)
+    })
+}
+
+/// Describe the reason for a panic, given its payload and location.
+fn reason(payload: &(dyn Any + Send), location: Option<String>) -> String {
+    let at = location.map_or_else(String::new, |l| format!(" at {}", l));
+
+    if let Some(Timeout(reason)) = payload.downcast_ref::<Timeout>() {
+        (*reason).to_owned()
+    } else if let Some(msg) = payload.downcast_ref::<&str>() {
+        (*msg).to_owned()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else if payload.is::<ExplicitBug>() {
+        // the message of the bug has been emitted as an internal compiler error already
+        format!(
+            "the compiler hit a bug{}, see the internal compiler error",
+            at
+        )
+    } else if payload.is::<FatalErrorMarker>() {
+        format!("the compiler aborted after a fatal error{}", at)
+    } else {
+        format!("the compiler panicked{}", at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recover_returns_value() {
+        assert_eq!(recover(|| 42), Ok(42));
+    }
+
+    #[test]
+    fn recover_message() {
+        let _quiet = QuietPanics::install();
+
+        assert_eq!(recover(|| panic!("abc")), Err::<(), _>("abc".to_owned()));
+        assert_eq!(recover(|| panic!("{}", 42)), Err::<(), _>("42".to_owned()));
+    }
+
+    #[test]
+    fn recover_timeout() {
+        let _quiet = QuietPanics::install();
+
+        assert_eq!(
+            recover(|| panic::panic_any(Timeout("item exceeded its time limit"))),
+            Err::<(), _>("item exceeded its time limit".to_owned())
+        );
+    }
+
+    #[test]
+    fn recover_compiler_bug() {
+        let _quiet = QuietPanics::install();
+
+        // the location is recorded by the hook, which tests running in parallel can replace
+        let reason = recover(|| panic::panic_any(ExplicitBug)).unwrap_err();
+        assert!(reason.starts_with("the compiler hit a bug"), "{}", reason);
+        assert!(
+            reason.ends_with("see the internal compiler error"),
+            "{}",
+            reason
+        );
+
+        let reason = recover(|| panic::panic_any(FatalErrorMarker)).unwrap_err();
+        assert!(reason.starts_with("the compiler aborted after a fatal error"));
+    }
+
+    #[test]
+    fn reason_location() {
+        let location = Some("src/abc.rs:1:2".to_owned());
+
+        assert_eq!(
+            reason(&ExplicitBug, location.clone()),
+            "the compiler hit a bug at src/abc.rs:1:2, see the internal compiler error"
+        );
+        assert_eq!(
+            reason(&42_u8, location),
+            "the compiler panicked at src/abc.rs:1:2"
+        );
+        assert_eq!(reason(&42_u8, None), "the compiler panicked");
+    }
+
+    #[test]
+    fn recover_nested() {
+        let _quiet = QuietPanics::install();
+
+        let res = recover(|| {
+            let inner = recover(|| panic!("abc"));
+            assert!(RECOVERING.with(Cell::get));
+            inner
+        });
+
+        assert_eq!(res, Ok(Err("abc".to_owned())));
+        assert!(!RECOVERING.with(Cell::get));
+    }
+}
@@ -0,0 +1,207 @@
+//! An owned representation of the results of an analysis.
+//!
+//! The `ChangeSet` refers to the compiler's data structures, and can't outlive the compiler
+//! session it was created in. A `Report` holds the same information in plain data, so that
+//! embedders can keep, persist and render it on their own terms. With the `report-serde`
+//! feature enabled, all types in this module implement `Serialize` and `Deserialize`.
+
+use crate::{changes::ChangeCategory, manifest::ManifestChange};
+#[cfg(feature = "report-serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A location in the source of either crate version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct SpanReport {
+    /// The path of the source file.
+    pub file: String,
+    /// The line the span starts on, counting from 1.
+    pub line_lo: usize,
+    /// The line the span ends on, counting from 1.
+    pub line_hi: usize,
+    /// The column the span starts at, counting from 0.
+    pub col_lo: usize,
+    /// The column the span ends at, counting from 0.
+    pub col_hi: usize,
+}
+
+impl fmt::Display for SpanReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line_lo, self.col_lo + 1)
+    }
+}
+
+/// The exports of an item that have been added or removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct PathChangeReport {
+    /// The name of the item.
+    pub name: String,
+    /// The definition span of the item.
+    pub def_span: SpanReport,
+    /// The spans of added exports of the item.
+    pub additions: Vec<SpanReport>,
+    /// The spans of removed exports of the item.
+    pub removals: Vec<SpanReport>,
+    /// The unstable feature the item is gated behind, if any.
+    pub unstable_feature: Option<String>,
+}
+
+/// A single change to an item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct ChangeTypeReport {
+    /// The code of the change, as accepted by `cargo semver explain`.
+    pub code: String,
+    /// The category of the change.
+    pub category: ChangeCategory,
+    /// The description of the change.
+    pub description: String,
+    /// The span the change refers to, if more specific than the item.
+    pub span: Option<SpanReport>,
+}
+
+/// The changes to an item present in both crate versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct ChangeReport {
+    /// The name of the item, or a description of it for trait impls.
+    pub name: String,
+    /// The most severe category of the changes to the item.
+    pub max_category: ChangeCategory,
+    /// The definition span of the item in the new crate version.
+    pub new_span: SpanReport,
+    /// The changes to the item.
+    pub changes: Vec<ChangeTypeReport>,
+    /// The paths of other items that changed as a result.
+    pub affected: Vec<String>,
+    /// The paths of the items whose changes caused these ones.
+    pub caused_by: Vec<String>,
+    /// The paths of the public items mentioning the item, if the impact of its breaking changes
+    /// has been recorded.
+    pub mentioned_by: Vec<String>,
+    /// The paths of items that couldn't be related to their counterpart.
+    pub unrelated: Vec<String>,
+}
+
+/// An item that couldn't be analyzed completely, so that changes to it can go unnoticed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct NotAnalyzedReport {
+    /// The name of the item, or a description of it for trait impls.
+    pub name: String,
+    /// The definition span of the item in the new crate version.
+    pub new_span: SpanReport,
+    /// The reasons the item couldn't be analyzed completely.
+    pub reasons: Vec<String>,
+}
+
+/// The results of comparing two crate versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct Report {
+    /// The version of the old crate.
+    pub old_version: String,
+    /// The version suggested for the new crate, if the old one could be parsed.
+    pub new_version: Option<String>,
+    /// The most severe category of all changes.
+    pub max_category: ChangeCategory,
+    /// The changes to the exports of items, in the order they are reported in.
+    pub path_changes: Vec<PathChangeReport>,
+    /// The changes to items, in the order they are reported in.
+    pub changes: Vec<ChangeReport>,
+    /// The items whose verdict is unknown, in the order they are reported in.
+    pub not_analyzed: Vec<NotAnalyzedReport>,
+    /// The changes to the crate manifest.
+    pub manifest_changes: Vec<ManifestChange>,
+}
+
+/// Render the report as plain text, listing each change with its location.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.new_version {
+            Some(ref new_version) => writeln!(
+                f,
+                "version bump: {} -> ({}) -> {}",
+                self.old_version, self.max_category, new_version
+            )?,
+            None => writeln!(
+                f,
+                "max change: {}, could not parse {}",
+                self.max_category, self.old_version
+            )?,
+        }
+
+        for change in &self.manifest_changes {
+            writeln!(f, "{} ({})", change, change.to_category())?;
+        }
+
+        for change in &self.path_changes {
+            writeln!(
+                f,
+                "path changes to `{}` at {}",
+                change.name, change.def_span
+            )?;
+
+            for span in &change.removals {
+                writeln!(f, "  removed path at {} (breaking)", span)?;
+            }
+
+            for span in &change.additions {
+                writeln!(f, "  added path at {} (technically breaking)", span)?;
+            }
+
+            if let Some(ref feature) = change.unstable_feature {
+                writeln!(f, "  gated behind unstable feature `{}`", feature)?;
+            }
+        }
+
+        for change in &self.changes {
+            writeln!(
+                f,
+                "{} changes in `{}` at {}",
+                change.max_category, change.name, change.new_span
+            )?;
+
+            for sub in &change.changes {
+                write!(f, "  {} ({}, {})", sub.description, sub.category, sub.code)?;
+
+                match sub.span {
+                    Some(ref span) => writeln!(f, " at {}", span)?,
+                    None => writeln!(f)?,
+                }
+            }
+
+            for cause in &change.caused_by {
+                writeln!(f, "  caused by change to `{}`", cause)?;
+            }
+
+            for path in &change.affected {
+                writeln!(f, "  `{}` changed as a result", path)?;
+            }
+
+            for path in &change.mentioned_by {
+                writeln!(f, "  mentioned by `{}`", path)?;
+            }
+
+            for path in &change.unrelated {
+                writeln!(f, "  could not relate `{}` to the other version", path)?;
+            }
+        }
+
+        if !self.not_analyzed.is_empty() {
+            writeln!(f, "not analyzed, changes can go unnoticed:")?;
+
+            for item in &self.not_analyzed {
+                writeln!(f, "  `{}` at {}", item.name, item.new_span)?;
+
+                for reason in &item.reasons {
+                    writeln!(f, "    {}", reason)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+//! Comparison of the serialized form of types implementing serde's traits.
+//!
+//! Many crates consider the data format of their types' `Serialize` and `Deserialize` impls part
+//! of their public interface. The derived impls' format is determined by the names of fields and
+//! variants and by the `#[serde(...)]` attributes on the type, which we collect into a "shape"
+//! that can be compared between crate versions.
+
+use crate::{
+    changes::{ChangeSet, ChangeType},
+    mapping::IdMapping,
+};
+use rustc_ast::{MetaItem, MetaItemKind, NestedMetaItem};
+use rustc_hir::def::{DefKind, Res::Def};
+use rustc_hir::def_id::{CrateNum, DefId};
+use rustc_metadata::creader::CStore;
+use rustc_middle::ty::{TyCtxt, TyKind};
+use rustc_span::symbol::Symbol;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Attributes that don't influence the data format.
+const IGNORED_ATTRS: &[&str] = &["bound", "crate", "expecting"];
+
+/// The serialized shape of a variant, or of a struct's fields.
+#[derive(Default)]
+struct VariantShape {
+    /// The rendered `#[serde(...)]` attributes on the variant.
+    attrs: BTreeSet<String>,
+    /// The serialized fields, by serialized name, along with their rendered attributes.
+    fields: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// The serialized shape of a struct, enum or union.
+#[derive(Default)]
+struct Shape {
+    /// The rendered `#[serde(...)]` attributes on the type.
+    attrs: BTreeSet<String>,
+    /// The serialized variants, by serialized name. Structs have a single unnamed variant.
+    variants: BTreeMap<String, VariantShape>,
+}
+
+/// Compare the serialized shapes of all matching types implementing serde's traits.
+pub fn diff_serde_shapes(changes: &mut ChangeSet, id_mapping: &IdMapping, tcx: TyCtxt) {
+    let old_adts = serde_adts(tcx, id_mapping.get_old_crate());
+    let new_adts = serde_adts(tcx, id_mapping.get_new_crate());
+
+    for (old, new) in id_mapping.items() {
+        let (old_def_id, new_def_id) = match (old, new) {
+            (Def(DefKind::Struct, o), Def(DefKind::Struct, n))
+            | (Def(DefKind::Enum, o), Def(DefKind::Enum, n))
+            | (Def(DefKind::Union, o), Def(DefKind::Union, n)) => (o, n),
+            _ => continue,
+        };
+
+        if !changes.get_output(old_def_id)
+            || !old_adts.contains(&old_def_id)
+            || !new_adts.contains(&new_def_id)
+        {
+            continue;
+        }
+
+        let old_shape = shape(tcx, old_def_id);
+        let new_shape = shape(tcx, new_def_id);
+
+        for (desc, breaking) in diff_shapes(&old_shape, &new_shape) {
+            changes.add_change(
+                ChangeType::SerdeFormatChanged { desc, breaking },
+                old_def_id,
+                None,
+            );
+        }
+    }
+}
+
+/// Collect the ADTs of a crate that implement `serde::Serialize` or `serde::Deserialize`.
+fn serde_adts(tcx: TyCtxt, krate: CrateNum) -> HashSet<DefId> {
+    let cstore = CStore::from_tcx(tcx);
+
+    cstore
+        .trait_impls_in_crate_untracked(krate)
+        .into_iter()
+        .filter(|&(trait_def_id, _, _)| {
+            let name = tcx.item_name(trait_def_id);
+
+            tcx.crate_name(trait_def_id.krate).as_str() == "serde"
+                && (name.as_str() == "Serialize" || name.as_str() == "Deserialize")
+        })
+        .filter_map(
+            |(_, impl_def_id, _)| match tcx.type_of(impl_def_id).kind() {
+                TyKind::Adt(def, _) => Some(def.did()),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// Render a meta item of a `#[serde(...)]` attribute.
+fn render_meta(item: &MetaItem) -> String {
+    let name = item
+        .path
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+
+    match item.kind {
+        MetaItemKind::Word => name,
+        MetaItemKind::List(ref items) => {
+            let items: Vec<_> = items.iter().map(render_nested).collect();
+            format!("{}({})", name, items.join(", "))
+        }
+        MetaItemKind::NameValue(_) => {
+            if let Some(value) = item.value_str() {
+                format!("{} = {:?}", name, value.as_str())
+            } else {
+                format!("{} = ..", name)
+            }
+        }
+    }
+}
+
+/// Render a nested meta item of a `#[serde(...)]` attribute.
+fn render_nested(item: &NestedMetaItem) -> String {
+    match item.meta_item() {
+        Some(meta) => render_meta(meta),
+        None => "..".to_owned(),
+    }
+}
+
+/// Get the meta items of all `#[serde(...)]` attributes of an item.
+fn serde_attrs(tcx: TyCtxt, def_id: DefId) -> Vec<MetaItem> {
+    tcx.get_attrs(def_id, Symbol::intern("serde"))
+        .filter_map(|attr| attr.meta_item_list())
+        .flatten()
+        .filter_map(|item| item.meta_item().cloned())
+        .collect()
+}
+
+/// Split the attributes of a field or variant into its serialized name and the rendered
+/// attributes relevant to the data format. Returns `None` for skipped fields or variants.
+fn name_and_attrs(tcx: TyCtxt, def_id: DefId, name: Symbol) -> Option<(String, BTreeSet<String>)> {
+    let mut serialized_name = name.to_string();
+    let mut attrs = BTreeSet::new();
+
+    for item in serde_attrs(tcx, def_id) {
+        let item_name = item.name_or_empty();
+
+        if item_name.as_str() == "skip" {
+            return None;
+        } else if item_name.as_str() == "rename" && item.value_str().is_some() {
+            serialized_name = item.value_str().unwrap().to_string();
+        } else if !IGNORED_ATTRS.contains(&item_name.as_str()) {
+            attrs.insert(render_meta(&item));
+        }
+    }
+
+    Some((serialized_name, attrs))
+}
+
+/// Compute the serialized shape of an ADT.
+fn shape(tcx: TyCtxt, def_id: DefId) -> Shape {
+    let adt_def = tcx.adt_def(def_id);
+    let mut shape = Shape::default();
+
+    for item in serde_attrs(tcx, def_id) {
+        if !IGNORED_ATTRS.contains(&item.name_or_empty().as_str()) {
+            shape.attrs.insert(render_meta(&item));
+        }
+    }
+
+    for variant in adt_def.variants() {
+        let (name, attrs) = if adt_def.is_enum() {
+            match name_and_attrs(tcx, variant.def_id, variant.ident(tcx).name) {
+                Some(res) => res,
+                None => continue,
+            }
+        } else {
+            (String::new(), BTreeSet::new())
+        };
+
+        let mut variant_shape = VariantShape {
+            attrs,
+            fields: BTreeMap::new(),
+        };
+
+        for field in &variant.fields {
+            if let Some((name, attrs)) = name_and_attrs(tcx, field.did, field.ident(tcx).name) {
+                variant_shape.fields.insert(name, attrs);
+            }
+        }
+
+        shape.variants.insert(name, variant_shape);
+    }
+
+    shape
+}
+
+/// Compare two sets of rendered attributes, describing the differences.
+fn diff_attrs(
+    res: &mut Vec<(String, bool)>,
+    subject: &str,
+    old: &BTreeSet<String>,
+    new: &BTreeSet<String>,
+) {
+    for attr in old.difference(new) {
+        res.push((
+            format!("attribute `{}` removed from {}", attr, subject),
+            true,
+        ));
+    }
+
+    for attr in new.difference(old) {
+        res.push((format!("attribute `{}` added to {}", attr, subject), true));
+    }
+}
+
+/// Compare two serialized shapes, describing the differences and whether they're breaking.
+///
+/// Removing fields or variants, or adding non-defaulted fields, prevents data produced by one
+/// version from being read by the other, which is breaking. Adding variants or defaulted fields
+/// only breaks consumers using the old version, and is considered technically breaking.
+fn diff_shapes(old: &Shape, new: &Shape) -> Vec<(String, bool)> {
+    let mut res = Vec::new();
+
+    diff_attrs(&mut res, "container", &old.attrs, &new.attrs);
+
+    for (name, old_variant) in &old.variants {
+        let new_variant = if let Some(new_variant) = new.variants.get(name) {
+            new_variant
+        } else {
+            res.push((format!("variant `{}` removed", name), true));
+            continue;
+        };
+
+        let (subject, field_prefix) = if name.is_empty() {
+            ("struct".to_owned(), String::new())
+        } else {
+            (format!("variant `{}`", name), format!("{}.", name))
+        };
+
+        diff_attrs(&mut res, &subject, &old_variant.attrs, &new_variant.attrs);
+
+        for (field, old_attrs) in &old_variant.fields {
+            if let Some(new_attrs) = new_variant.fields.get(field) {
+                let subject = format!("field `{}{}`", field_prefix, field);
+                diff_attrs(&mut res, &subject, old_attrs, new_attrs);
+            } else {
+                res.push((format!("field `{}{}` removed", field_prefix, field), true));
+            }
+        }
+
+        for (field, new_attrs) in &new_variant.fields {
+            if !old_variant.fields.contains_key(field) {
+                let defaulted = new_attrs.iter().any(|a| a.starts_with("default"));
+                res.push((
+                    format!("field `{}{}` added", field_prefix, field),
+                    !defaulted,
+                ));
+            }
+        }
+    }
+
+    for name in new.variants.keys() {
+        if !old.variants.contains_key(name) {
+            res.push((format!("variant `{}` added", name), false));
+        }
+    }
+
+    res
+}
@@ -0,0 +1,135 @@
+//! Collection of the wall time spent in the phases of the analysis.
+//!
+//! Timings are only collected when enabled using `--timings`. Phases are identified by name,
+//! and the time spent in each is accumulated over all of its occurrences. Since translation
+//! and trait solving happen while traversing the crates, their times are included in the time
+//! of the traversal as well.
+
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// The number of slowest items listed in the report.
+const SLOWEST_ITEMS: usize = 10;
+
+/// The timings collected so far.
+#[derive(Default)]
+struct Timings {
+    /// The accumulated time spent in each phase, in the order the phases were first entered.
+    phases: Vec<(&'static str, Duration)>,
+    /// The phases currently being timed, to avoid counting nested occurrences twice.
+    active: Vec<&'static str>,
+    /// The time spent comparing each item.
+    items: Vec<(String, Duration)>,
+}
+
+thread_local! {
+    static TIMINGS: RefCell<Option<Timings>> = RefCell::new(None);
+}
+
+/// Start collecting timings on the current thread.
+pub fn enable() {
+    TIMINGS.with(|t| *t.borrow_mut() = Some(Timings::default()));
+}
+
+/// Run a closure, adding the time it takes to the given phase if timings are collected.
+pub fn time<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let enter = TIMINGS.with(|t| match *t.borrow_mut() {
+        Some(ref mut timings) if !timings.active.contains(&phase) => {
+            timings.active.push(phase);
+            true
+        }
+        _ => false,
+    });
+
+    if !enter {
+        return f();
+    }
+
+    let _phase = ActivePhase {
+        phase,
+        start: Instant::now(),
+    };
+
+    f()
+}
+
+/// A phase being timed, which is left when dropped.
+///
+/// The phase is left even if the closure run in it panics, so that the analysis can recover from
+/// the panic and keep timing the phase.
+struct ActivePhase {
+    /// The name of the phase.
+    phase: &'static str,
+    /// The time the phase was entered at.
+    start: Instant,
+}
+
+impl Drop for ActivePhase {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let phase = self.phase;
+
+        TIMINGS.with(|t| {
+            if let Some(ref mut timings) = *t.borrow_mut() {
+                timings.active.retain(|&p| p != phase);
+
+                if let Some(entry) = timings.phases.iter_mut().find(|(p, _)| *p == phase) {
+                    entry.1 += elapsed;
+                } else {
+                    timings.phases.push((phase, elapsed));
+                }
+            }
+        });
+    }
+}
+
+/// Run a closure comparing an item, recording the time it takes if timings are collected.
+///
+/// The path of the item is only computed if needed.
+pub fn time_item<T>(path: impl FnOnce() -> String, f: impl FnOnce() -> T) -> T {
+    if !TIMINGS.with(|t| t.borrow().is_some()) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let res = f();
+    let elapsed = start.elapsed();
+    let path = path();
+
+    TIMINGS.with(|t| {
+        if let Some(ref mut timings) = *t.borrow_mut() {
+            timings.items.push((path, elapsed));
+        }
+    });
+
+    res
+}
+
+/// Render the timings collected so far, if any.
+pub fn report() -> Option<String> {
+    TIMINGS.with(|t| {
+        let timings = t.borrow();
+        let timings = timings.as_ref()?;
+        let mut res = String::from("timings:\n");
+
+        for (phase, elapsed) in &timings.phases {
+            let _ = writeln!(res, "  {:<16}{:>10.3}s", phase, elapsed.as_secs_f64());
+        }
+
+        let mut items: Vec<_> = timings.items.iter().collect();
+        items.sort_by(|a, b| b.1.cmp(&a.1));
+
+        if !items.is_empty() {
+            res.push_str("slowest items:\n");
+        }
+
+        for (path, elapsed) in items.into_iter().take(SLOWEST_ITEMS) {
+            let _ = writeln!(res, "  {:>10.3}s  {}", elapsed.as_secs_f64(), path);
+        }
+
+        Some(res)
+    })
+}
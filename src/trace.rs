@@ -0,0 +1,51 @@
+//! Tracing of the analysis of a single item, enabled using `--trace-item`.
+//!
+//! While the traced item is being matched up with its counterpart or compared to it, tracing
+//! is active, and the driver's logger emits all log records of the analysis, regardless of the
+//! log level configured using `RUSTC_LOG`.
+
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    static TRACED_PATH: RefCell<Option<String>> = RefCell::new(None);
+    static ACTIVE: Cell<bool> = Cell::new(false);
+}
+
+/// Start tracing the item with the given path on the current thread.
+pub fn enable(path: String) {
+    TRACED_PATH.with(|p| *p.borrow_mut() = Some(path));
+}
+
+/// Check whether an item is currently being traced.
+pub fn is_active() -> bool {
+    ACTIVE.with(Cell::get)
+}
+
+/// Run a closure processing an item, tracing it if it is the traced item.
+///
+/// The path of the item is only computed if an item is traced at all.
+pub fn item<T>(path: impl FnOnce() -> String, f: impl FnOnce() -> T) -> T {
+    let traced = TRACED_PATH.with(|p| {
+        p.borrow()
+            .as_ref()
+            .map_or(false, |traced| *traced == path())
+    });
+
+    if !traced || is_active() {
+        return f();
+    }
+
+    ACTIVE.with(|a| a.set(true));
+    let _active = Active;
+
+    f()
+}
+
+/// Ends tracing when dropped, even if the analysis of the traced item panics.
+struct Active;
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        ACTIVE.with(|a| a.set(false));
+    }
+}
@@ -1,17 +1,22 @@
 //! The translation machinery used to lift items into the context of the other crate for
 //! comparison and inference.
 
-use crate::mapping::{IdMapping, InherentEntry};
+use crate::{
+    budget,
+    mapping::{IdMapping, InherentEntry},
+    timings,
+};
 use log::{debug, info};
 use rustc_hir::def_id::DefId;
 use rustc_infer::infer::InferCtxt;
 use rustc_middle::ty::{
     fold::{BottomUpFolder, TypeFoldable, TypeFolder},
     subst::{GenericArg, InternalSubsts, SubstsRef},
-    GenericParamDefKind, ParamEnv, Predicate, Region, Term, TraitRef, Ty, TyCtxt,
-    TypeSuperFoldable, TypeVisitable, Unevaluated,
+    visit::{TypeSuperVisitable, TypeVisitor},
+    BoundRegion, BoundRegionKind, BoundVariableKind, GenericParamDefKind, List, ParamEnv,
+    Predicate, Region, Term, TraitRef, Ty, TyCtxt, TypeSuperFoldable, TypeVisitable, Unevaluated,
 };
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, ops::ControlFlow};
 
 /// The context in which `DefId` translation happens.
 pub struct TranslationContext<'a, 'tcx> {
@@ -25,6 +30,10 @@ pub struct TranslationContext<'a, 'tcx> {
     needs_translation: fn(&IdMapping, DefId) -> bool,
     /// Elementary operation to translate a `DefId`.
     translate_orig: fn(&IdMapping, DefId) -> Option<DefId>,
+    /// The items whose generics prevented a translation, in the order they were encountered.
+    failures: RefCell<Vec<DefId>>,
+    /// The items that couldn't be translated at all, in the order they were encountered.
+    unmapped: RefCell<Vec<DefId>>,
 }
 
 impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
@@ -40,6 +49,8 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
             translate_params,
             needs_translation: IdMapping::in_old_crate,
             translate_orig: IdMapping::get_new_id,
+            failures: RefCell::new(Vec::new()),
+            unmapped: RefCell::new(Vec::new()),
         }
     }
 
@@ -55,6 +66,8 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
             translate_params,
             needs_translation: IdMapping::in_new_crate,
             translate_orig: IdMapping::get_old_id,
+            failures: RefCell::new(Vec::new()),
+            unmapped: RefCell::new(Vec::new()),
         }
     }
 
@@ -91,6 +104,7 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
     fn translate_orig(&self, def_id: DefId) -> DefId {
         (self.translate_orig)(self.id_mapping, def_id).unwrap_or_else(|| {
             info!("not mapped: {:?}", def_id);
+            self.record_unmapped(def_id);
             def_id
         })
     }
@@ -149,19 +163,99 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                             self.tcx.mk_param_from_def(def)
                         }
                     }
-                    GenericParamDefKind::Const { .. } => unreachable!(),
+                    GenericParamDefKind::Const { .. } => {
+                        if !success.get() {
+                            self.tcx.mk_param_from_def(def)
+                        } else if let Some(GenericArgKind::Const(const_)) =
+                            orig_substs.get(def.index as usize).map(|k| k.unpack())
+                        {
+                            self.translate(index_map, GenericArg::from(const_))
+                        } else {
+                            success.set(false);
+                            self.tcx.mk_param_from_def(def)
+                        }
+                    }
                 });
 
             if success.get() {
                 return Some((target_def_id, target_substs));
             }
+
+            self.record_failure(orig_def_id);
+        } else {
+            self.record_unmapped(orig_def_id);
         }
 
         None
     }
 
+    /// Record an item whose generics don't match those of its counterpart.
+    fn record_failure(&self, orig_def_id: DefId) {
+        let mut failures = self.failures.borrow_mut();
+
+        if !failures.contains(&orig_def_id) {
+            failures.push(orig_def_id);
+        }
+    }
+
+    /// Record an item that has no counterpart we know of.
+    ///
+    /// Items known to be removed or added are skipped, as these changes are reported anyway.
+    fn record_unmapped(&self, orig_def_id: DefId) {
+        if self.id_mapping.is_non_mapped(orig_def_id) {
+            return;
+        }
+
+        let mut unmapped = self.unmapped.borrow_mut();
+
+        if !unmapped.contains(&orig_def_id) {
+            unmapped.push(orig_def_id);
+        }
+    }
+
+    /// Take the items that couldn't be translated since the last call.
+    ///
+    /// The untranslated items are left in place, so that types referring to them can compare
+    /// equal even if the items are incompatible.
+    pub fn take_unmapped(&self) -> Vec<DefId> {
+        self.unmapped.take()
+    }
+
+    /// Take the items whose generics prevented a translation since the last call.
+    ///
+    /// These are the points at which a translated type diverges from its counterpart, and
+    /// thus the changes causing type errors found for the translated type.
+    pub fn take_failures(&self) -> Vec<DefId> {
+        self.failures.take()
+    }
+
     /// Fold a structure, translating all `DefId`s reachable by the folder.
+    ///
+    /// Structures that don't refer to any item of the crate we translate from are returned
+    /// as-is, which spares us folding and re-interning types of the standard library and other
+    /// dependencies, which make up the bulk of most signatures and bounds.
     fn translate<T: TypeFoldable<'tcx>>(&self, index_map: &HashMap<u32, DefId>, orig: T) -> T {
+        budget::check();
+
+        timings::time("translation", || {
+            if self.in_scope(&orig) {
+                self.fold(index_map, orig)
+            } else {
+                orig
+            }
+        })
+    }
+
+    /// Check whether a structure needs to be folded to be translated.
+    ///
+    /// Type and region parameters are translated by their `DefId`s as well, so if these are to
+    /// be translated, all structures are considered in scope.
+    fn in_scope<T: TypeVisitable<'tcx>>(&self, orig: &T) -> bool {
+        self.translate_params || refers_to(orig, |did| self.needs_translation(did))
+    }
+
+    /// Perform the folding done by `translate`.
+    fn fold<T: TypeFoldable<'tcx>>(&self, index_map: &HashMap<u32, DefId>, orig: T) -> T {
         use rustc_middle::ty::ExistentialPredicate::*;
         use rustc_middle::ty::TyKind;
         use rustc_middle::ty::TypeAndMut;
@@ -177,9 +271,14 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                         if let Some(target_def_id) =
                             (self.translate_orig)(self.id_mapping, adt_def.did())
                         {
+                            if self.tcx.generics_of(target_def_id).count() != substs.len() {
+                                self.record_failure(adt_def.did());
+                            }
+
                             let target_adt = self.tcx.adt_def(target_def_id);
                             self.tcx.mk_adt(target_adt, substs)
                         } else {
+                            self.record_unmapped(adt_def.did());
                             ty
                         }
                     }
@@ -212,11 +311,16 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                         let res: Vec<_> = preds
                             .iter()
                             .map(|p| {
-                                p.map_bound(|p| {
+                                // the predicates can refer to the regions bound by their binder
+                                let bound_vars = p.bound_vars();
+                                let p = p.map_bound(|p| {
                                     match p {
                                         Trait(existential_trait_ref) => {
-                                            let trait_ref = Binder::dummy(existential_trait_ref)
-                                                .with_self_ty(self.tcx, dummy_self);
+                                            let trait_ref = Binder::bind_with_vars(
+                                                existential_trait_ref,
+                                                bound_vars,
+                                            )
+                                            .with_self_ty(self.tcx, dummy_self);
                                             let did = trait_ref.skip_binder().def_id;
                                             let substs = trait_ref.skip_binder().substs;
 
@@ -238,9 +342,11 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                                             }
                                         }
                                         Projection(existential_projection) => {
-                                            let projection_pred =
-                                                Binder::dummy(existential_projection)
-                                                    .with_self_ty(self.tcx, dummy_self);
+                                            let projection_pred = Binder::bind_with_vars(
+                                                existential_projection,
+                                                bound_vars,
+                                            )
+                                            .with_self_ty(self.tcx, dummy_self);
                                             let item_def_id = projection_pred
                                                 .skip_binder()
                                                 .projection_ty
@@ -271,7 +377,12 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                                         }
                                         AutoTrait(did) => AutoTrait(self.translate_orig(did)),
                                     }
-                                })
+                                });
+
+                                Binder::bind_with_vars(
+                                    p.skip_binder(),
+                                    self.translate_bound_vars(bound_vars),
+                                )
                             })
                             .collect();
 
@@ -282,6 +393,34 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                             ty
                         }
                     }
+                    TyKind::Closure(did, substs) if self.needs_translation(did) => {
+                        // the substs, including those of the item defining the closure, have
+                        // been folded already, so only the closure itself needs translation
+                        if let Some(target_def_id) = (self.translate_orig)(self.id_mapping, did) {
+                            self.tcx.mk_closure(target_def_id, substs)
+                        } else {
+                            self.record_unmapped(did);
+                            ty
+                        }
+                    }
+                    TyKind::Generator(did, substs, movability) if self.needs_translation(did) => {
+                        // same as for closures above
+                        if let Some(target_def_id) = (self.translate_orig)(self.id_mapping, did) {
+                            self.tcx.mk_generator(target_def_id, substs, movability)
+                        } else {
+                            self.record_unmapped(did);
+                            ty
+                        }
+                    }
+                    TyKind::Foreign(did) if self.needs_translation(did) => {
+                        // extern types have neither substs nor a structure to fold
+                        if let Some(target_def_id) = (self.translate_orig)(self.id_mapping, did) {
+                            self.tcx.mk_foreign(target_def_id)
+                        } else {
+                            self.record_unmapped(did);
+                            ty
+                        }
+                    }
                     TyKind::Projection(proj) => {
                         if let Some((target_def_id, target_substs)) =
                             self.translate_orig_substs(index_map, proj.item_def_id, proj.substs)
@@ -300,6 +439,10 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                             ty
                         }
                     }
+                    TyKind::FnPtr(sig) => self.tcx.mk_fn_ptr(Binder::bind_with_vars(
+                        sig.skip_binder(),
+                        self.translate_bound_vars(sig.bound_vars()),
+                    )),
                     TyKind::Param(param) => {
                         // FIXME: we should check `has_self` if this gets used again!
                         if param.index != 0 && self.translate_params {
@@ -333,7 +476,6 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
 
     /// Translate a region.
     fn translate_region(&self, region: Region<'tcx>) -> Region<'tcx> {
-        use rustc_middle::ty::BoundRegionKind::*;
         use rustc_middle::ty::{EarlyBoundRegion, FreeRegion};
         use rustc_type_ir::RegionKind::*;
 
@@ -356,15 +498,52 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
                 bound_region,
             }) => ReFree(FreeRegion {
                 scope: self.translate_orig(scope),
-                bound_region: match bound_region {
-                    BrNamed(def_id, name) => BrNamed(self.translate_orig(def_id), name),
-                    reg => reg,
-                },
+                bound_region: self.translate_bound_region_kind(bound_region),
             }),
+            ReLateBound(index, BoundRegion { var, kind }) => ReLateBound(
+                index,
+                BoundRegion {
+                    var,
+                    kind: self.translate_bound_region_kind(kind),
+                },
+            ),
             reg => reg,
         })
     }
 
+    /// Translate the kind of a late-bound region.
+    ///
+    /// Late-bound regions aren't part of an item's generics, so their definitions can lack a
+    /// counterpart in the mapping. These are kept as-is, since they only serve as names, while
+    /// bound regions are identified by their position in the binder.
+    fn translate_bound_region_kind(&self, kind: BoundRegionKind) -> BoundRegionKind {
+        match kind {
+            BoundRegionKind::BrNamed(def_id, name) if self.needs_translation(def_id) => {
+                let target_def_id = (self.translate_orig)(self.id_mapping, def_id);
+                BoundRegionKind::BrNamed(target_def_id.unwrap_or(def_id), name)
+            }
+            kind => kind,
+        }
+    }
+
+    /// Translate the variables bound by a binder.
+    fn translate_bound_vars(
+        &self,
+        bound_vars: &'tcx List<BoundVariableKind>,
+    ) -> &'tcx List<BoundVariableKind> {
+        if !self.translate_params {
+            return bound_vars;
+        }
+
+        self.tcx
+            .mk_bound_variable_kinds(bound_vars.iter().map(|var| match var {
+                BoundVariableKind::Region(kind) => {
+                    BoundVariableKind::Region(self.translate_bound_region_kind(kind))
+                }
+                var => var,
+            }))
+    }
+
     /// Translate an item's type.
     pub fn translate_item_type(&self, orig_def_id: DefId, orig: Ty<'tcx>) -> Ty<'tcx> {
         self.translate(&self.construct_index_map(orig_def_id), orig)
@@ -381,7 +560,8 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
             ProjectionTy, SubtypePredicate, ToPredicate, TraitPredicate, WithOptConstParam,
         };
 
-        let pred = match predicate.kind().skip_binder() {
+        let bound_pred = predicate.kind();
+        let pred = match bound_pred.skip_binder() {
             PredicateKind::Trait(pred) => PredicateKind::Trait(
                 if let Some((target_def_id, target_substs)) = self.translate_orig_substs(
                     index_map,
@@ -473,11 +653,13 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
             PredicateKind::TypeWellFormedFromEnv(_) => unimplemented!(),
         };
 
-        Some(ty::Binder::dummy(pred).to_predicate(self.tcx))
+        // keep the regions bound by the predicate bound, instead of letting them escape
+        let bound_vars = self.translate_bound_vars(bound_pred.bound_vars());
+        Some(ty::Binder::bind_with_vars(pred, bound_vars).to_predicate(self.tcx))
     }
 
     /// Translate a slice of predicates in the context of an item.
-    fn translate_predicates(
+    pub fn translate_predicates(
         &self,
         orig_def_id: DefId,
         orig_preds: &[Predicate<'tcx>],
@@ -542,6 +724,75 @@ impl<'a, 'tcx> TranslationContext<'a, 'tcx> {
     }
 }
 
+/// Check whether a structure refers to any item matching a predicate.
+///
+/// The items are looked for in types, including the predicates of trait objects, and in the
+/// predicates themselves, which covers all `DefId`s translated by `TranslationContext` apart from
+/// those of parameters.
+pub fn refers_to<'tcx, T: TypeVisitable<'tcx>>(
+    value: &T,
+    in_scope: impl Fn(DefId) -> bool,
+) -> bool {
+    value.visit_with(&mut ScopeVisitor { in_scope }).is_break()
+}
+
+/// A type visitor looking for items matching a predicate.
+struct ScopeVisitor<F> {
+    /// The predicate to match items against.
+    in_scope: F,
+}
+
+impl<'tcx, F: Fn(DefId) -> bool> TypeVisitor<'tcx> for ScopeVisitor<F> {
+    type BreakTy = ();
+
+    fn visit_ty(&mut self, ty: Ty<'tcx>) -> ControlFlow<()> {
+        use rustc_middle::ty::ExistentialPredicate::*;
+        use rustc_middle::ty::TyKind;
+
+        let in_scope = match *ty.kind() {
+            TyKind::Adt(adt_def, _) => (self.in_scope)(adt_def.did()),
+            TyKind::FnDef(did, _)
+            | TyKind::Closure(did, _)
+            | TyKind::Generator(did, _, _)
+            | TyKind::Foreign(did)
+            | TyKind::Opaque(did, _) => (self.in_scope)(did),
+            TyKind::Projection(proj) => (self.in_scope)(proj.item_def_id),
+            TyKind::Dynamic(preds, _) => preds.iter().any(|pred| match pred.skip_binder() {
+                Trait(trait_ref) => (self.in_scope)(trait_ref.def_id),
+                Projection(proj) => (self.in_scope)(proj.item_def_id),
+                AutoTrait(did) => (self.in_scope)(did),
+            }),
+            _ => false,
+        };
+
+        if in_scope {
+            ControlFlow::Break(())
+        } else {
+            ty.super_visit_with(self)
+        }
+    }
+
+    fn visit_predicate(&mut self, predicate: Predicate<'tcx>) -> ControlFlow<()> {
+        use rustc_middle::ty::PredicateKind;
+
+        let in_scope = match predicate.kind().skip_binder() {
+            PredicateKind::Trait(pred) => (self.in_scope)(pred.def_id()),
+            PredicateKind::Projection(pred) => (self.in_scope)(pred.projection_ty.item_def_id),
+            PredicateKind::ObjectSafe(did) | PredicateKind::ClosureKind(did, _, _) => {
+                (self.in_scope)(did)
+            }
+            PredicateKind::ConstEvaluatable(uv) => (self.in_scope)(uv.def.did),
+            _ => false,
+        };
+
+        if in_scope {
+            ControlFlow::Break(())
+        } else {
+            predicate.super_visit_with(self)
+        }
+    }
+}
+
 /// A type folder that removes inference artifacts.
 ///
 /// Used to lift type errors and predicates to wrap them in an error type.
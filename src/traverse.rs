@@ -6,68 +6,626 @@
 //! function signatures. The second pass then proceeds to find non-public items that are named
 //! differently, yet are compatible in their usage. The third pass now uses the information
 //! collected in the previous passes to compare the types and/or trait bounds of all item pairs
-//! that have been matched. Trait and inherent impls can't be matched by name, and are processed
-//! in a fourth pass that uses trait bounds to find matching impls.
+//! that have been matched and are reachable from the public API. Trait and inherent impls can't
+//! be matched by name, and are processed in a fourth pass that uses trait bounds to find
+//! matching impls.
 
 use crate::{
+    api::dump_item,
+    budget,
     changes::{ChangeSet, ChangeType},
-    mapping::{IdMapping, NameMapping},
-    mismatch::MismatchRelation,
+    check::{Findings, SemverCheck},
+    config::Config,
+    macro_rules::diff_macro_rules,
+    manifest::parse_rust_version,
+    mapping::{IdMapping, InherentEntry, NameMapping},
+    mismatch::{
+        ambiguous_candidates, match_moved_items, match_renamed_items, match_restructured_items,
+        MismatchRelation,
+    },
+    recover::{recover, QuietPanics},
+    serde_shape::diff_serde_shapes,
+    timings, trace,
     translate::TranslationContext,
     typeck::{BoundContext, TypeComparisonContext},
+    wasm_bindgen::diff_wasm_exports,
 };
 use log::{debug, info};
+use rustc_ast::NestedMetaItem;
+use rustc_attr::StabilityLevel;
 use rustc_hir::def::{CtorKind, CtorOf, DefKind, Res, Res::Def};
-use rustc_hir::def_id::DefId;
+use rustc_hir::def_id::{CrateNum, DefId};
 use rustc_hir::hir_id::HirId;
 use rustc_hir::lang_items::LangItem;
-use rustc_hir::Mutability;
+use rustc_hir::{Constness, Mutability};
 use rustc_infer::infer::TyCtxtInferExt;
 use rustc_metadata::creader::CStore;
 use rustc_middle::{
     metadata::ModChild,
+    mir::{
+        interpret::{ConstValue, Scalar},
+        pretty::write_mir_fn,
+        TerminatorKind,
+    },
     ty::{
-        subst::{InternalSubsts, Subst},
-        AssocItem, EarlyBinder, GenericParamDef, GenericParamDefKind, Generics, TraitRef, Ty,
-        TyCtxt, TyKind, TypeAndMut, Visibility,
+        error::TypeError,
+        subst::{GenericArg, GenericArgKind, InternalSubsts, Subst},
+        AssocItem, Binder, ConstKind, EarlyBinder, FloatTy, GenericParamDef, GenericParamDefKind,
+        Generics, ParamEnv, PolyFnSig, Predicate, PredicateKind, ScalarInt, TraitRef, Ty, TyCtxt,
+        TyKind, TypeAndMut, ValTree, Visibility,
         Visibility::Public,
     },
 };
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use rustc_span::hygiene::MacroKind;
+use rustc_span::lev_distance::find_best_match_for_name;
+use rustc_span::symbol::{sym, Symbol};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+};
 
 /// The main entry point to our analysis passes.
 ///
 /// Set up the necessary data structures and run the analysis passes and call the actual passes.
-pub fn run_analysis(tcx: TyCtxt, old: DefId, new: DefId) -> ChangeSet {
+pub fn run_analysis<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    old: DefId,
+    new: DefId,
+    config: &Config,
+) -> ChangeSet<'tcx> {
+    run_analysis_with_checks(tcx, old, new, config, &[])
+}
+
+/// Run the analysis passes, followed by the given custom checks.
+pub fn run_analysis_with_checks<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    old: DefId,
+    new: DefId,
+    config: &Config,
+    checks: &[&dyn SemverCheck],
+) -> ChangeSet<'tcx> {
     let mut changes = ChangeSet::default();
     let mut id_mapping = IdMapping::new(old.krate, new.krate);
+    let crate_name = tcx.crate_name(new.krate);
+
+    for change in &config.manifest_changes {
+        changes.add_manifest_change(change.clone());
+    }
 
-    // first pass
-    debug!("first pass started");
-    diff_structure(&mut changes, &mut id_mapping, tcx, old, new);
+    timings::time("mapping", || {
+        // first pass
+        debug!("first pass started");
+        diff_structure(
+            &mut changes,
+            &mut id_mapping,
+            tcx,
+            old,
+            new,
+            &config.renames,
+        );
 
-    // second pass
-    debug!("second pass started");
-    {
+        // second pass
+        debug!("second pass started");
         let mut mismatch = MismatchRelation::new(tcx, &mut id_mapping);
         debug!("constructed mismatch relation");
         mismatch.process();
+    });
+
+    if config.stream {
+        changes.stream_paths(tcx.sess, config, |def_id| {
+            item_path(tcx, crate_name, def_id)
+        });
+    }
+
+    timings::time("traversal", || {
+        let _quiet = QuietPanics::install();
+
+        // third pass
+        debug!("third pass started");
+        for def_id in leaked_types(&changes, &id_mapping, tcx) {
+            changes.set_output(def_id);
+        }
+
+        let reachable = reachable_items(&changes, &id_mapping, tcx);
+        for (old, new) in id_mapping.items() {
+            // private items that don't leak into the public API can't affect users, and items
+            // re-exported from the old crate are the same items
+            if reachable.contains(&old.def_id()) && !id_mapping.is_semver_trick_item(old.def_id()) {
+                let path = || item_path(tcx, crate_name, old.def_id());
+                recover_item(&mut changes, old.def_id(), |changes| {
+                    timings::time_item(path, || {
+                        trace::item(path, || diff_types(changes, &id_mapping, tcx, old, new))
+                    })
+                });
+
+                if config.stream {
+                    changes.stream_item(tcx.sess, config, old.def_id(), &path());
+                }
+            }
+        }
+
+        // fourth pass on impls
+        debug!("fourth pass started");
+        diff_inherent_impls(&mut changes, &id_mapping, tcx);
+        diff_trait_impls(&mut changes, &id_mapping, tcx);
+
+        if config.stream {
+            changes.stream_impls(tcx.sess, config, |def_id| {
+                item_path(tcx, crate_name, def_id)
+            });
+        }
+    });
+
+    // optional fifth pass on function bodies
+    if config.mir_diff || config.panic_scan {
+        debug!("fifth pass started");
+        let _quiet = QuietPanics::install();
+        for (old, new) in id_mapping.items() {
+            if id_mapping.is_semver_trick_item(old.def_id()) {
+                continue;
+            }
+
+            recover_item(&mut changes, old.def_id(), |changes| {
+                if config.mir_diff {
+                    diff_bodies(changes, tcx, old, new);
+                }
+
+                if config.panic_scan {
+                    diff_panics(changes, tcx, old, new);
+                }
+            });
+        }
+    }
+
+    // optional sixth pass on serialized formats
+    if config.serde {
+        debug!("sixth pass started");
+        diff_serde_shapes(&mut changes, &id_mapping, tcx);
+    }
+
+    // optional seventh pass on the exports of WebAssembly modules
+    if config.wasm_bindgen {
+        debug!("seventh pass started");
+        diff_wasm_exports(&mut changes, tcx, old.krate, new.krate);
+    }
+
+    if let Some(ref rust_version) = config.rust_version {
+        debug!("checking items against rust-version {}", rust_version);
+        check_rust_version(&mut changes, &id_mapping, tcx, rust_version);
+    }
+
+    for check in checks {
+        debug!("running custom check {}", check.name());
+        timings::time("custom checks", || {
+            check.check(
+                tcx,
+                &id_mapping,
+                &mut Findings::new(check.name(), &mut changes),
+            )
+        });
+    }
+
+    if let Some(ref command) = config.external_rules {
+        debug!("applying external rules {:?}", command);
+        let path = |def_id| item_path(tcx, crate_name, def_id);
+        if let Err(err) = changes.apply_external_rules(tcx.sess, command, path) {
+            tcx.sess.err(&err);
+        }
+    }
+
+    if !config.unstable_features.is_empty() {
+        debug!("excluding items gated behind unstable features");
+        let features = &config.unstable_features;
+        changes.exclude_unstable(|def_id| {
+            let new = if id_mapping.in_old_crate(def_id) {
+                id_mapping.get_new_id(def_id)
+            } else {
+                None
+            };
+
+            unstable_feature(tcx, def_id, features)
+                .or_else(|| new.and_then(|new| unstable_feature(tcx, new, features)))
+        });
+    }
+
+    if !config.only.is_empty() || !config.exclude.is_empty() {
+        debug!("filtering changes by path");
+        changes.retain(|def_id| config.path_included(&item_path(tcx, crate_name, def_id)));
     }
 
-    // third pass
-    debug!("third pass started");
-    for (old, new) in id_mapping.items() {
-        diff_types(&mut changes, &id_mapping, tcx, old, new);
+    debug!("collapsing cascaded changes");
+    collapse_cascades(&mut changes, tcx, crate_name);
+
+    if config.dot || config.impact {
+        debug!("recording the items mentioned by public items");
+        record_mentions(&mut changes, &id_mapping, tcx, crate_name);
+        changes.record_impact();
     }
 
-    // fourth pass on impls
-    debug!("fourth pass started");
-    diff_inherent_impls(&mut changes, &id_mapping, tcx);
-    diff_trait_impls(&mut changes, &id_mapping, tcx);
+    changes.add_changed_item_paths(|def_id| item_path(tcx, crate_name, def_id));
 
     changes
 }
 
+/// Record the paths of the public items, and the items mentioned in their signatures, types,
+/// fields or bounds, to report the items affected by breaking changes.
+///
+/// Besides the items themselves, the public fields of types, the public methods of inherent
+/// impls and the trait impls are recorded, as users refer to these directly.
+fn record_mentions(
+    changes: &mut ChangeSet,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt,
+    crate_name: Symbol,
+) {
+    use rustc_hir::def::DefKind::*;
+
+    // the items mentioned by each public item, along with its path
+    let mut public = Vec::new();
+
+    for (old, _) in id_mapping.items() {
+        let def_id = old.def_id();
+
+        if !changes.get_output(def_id) {
+            continue;
+        }
+
+        let path = item_path(tcx, crate_name, def_id);
+
+        if let Struct | Union | Enum = tcx.def_kind(def_id) {
+            for field in tcx.adt_def(def_id).all_fields() {
+                if field.vis == Public {
+                    let path = item_path(tcx, crate_name, field.did);
+                    public.push((field.did, path, referenced_items(tcx, field.did)));
+                }
+            }
+        }
+
+        // trait items mention their trait through their `Self: Trait` bound
+        let own_trait = id_mapping.get_trait_def(def_id);
+        let mentioned = referenced_items(tcx, def_id)
+            .into_iter()
+            .chain(bound_items(tcx, def_id))
+            .filter(|&mentioned| Some(mentioned) != own_trait)
+            .collect();
+
+        public.push((def_id, path, mentioned));
+    }
+
+    for (entry, impls) in id_mapping.inherent_impls() {
+        let parent = entry.parent_def_id;
+
+        if !id_mapping.in_old_crate(parent) || !changes.get_output(parent) {
+            continue;
+        }
+
+        for &(_, item_def_id) in impls {
+            if tcx.visibility(item_def_id) == Public {
+                let path = format!("{}::{}", item_path(tcx, crate_name, parent), entry.name);
+                let mentioned = referenced_items(tcx, item_def_id)
+                    .into_iter()
+                    .chain(bound_items(tcx, item_def_id))
+                    .filter(|&mentioned| mentioned != parent)
+                    .collect();
+
+                public.push((item_def_id, path, mentioned));
+            }
+        }
+    }
+
+    let cstore = CStore::from_tcx(tcx);
+    for (_, impl_def_id, _) in cstore.trait_impls_in_crate_untracked(id_mapping.get_old_crate()) {
+        if is_impl_trait_public(tcx, impl_def_id) && !is_doc_shim(tcx, impl_def_id) {
+            let path = tcx.def_path_str(impl_def_id);
+            let mentioned = trait_ref_items(tcx.impl_trait_ref(impl_def_id).unwrap());
+
+            public.push((impl_def_id, path, mentioned));
+        }
+    }
+
+    for (def_id, path, mentioned) in public {
+        changes.add_item_path(def_id, path);
+
+        for mentioned in mentioned {
+            if mentioned != def_id && id_mapping.in_old_crate(mentioned) {
+                changes.add_mention(mentioned, def_id);
+            }
+        }
+    }
+}
+
+/// Collapse type changes caused by breaking changes to other items into the latter.
+///
+/// An item mentioning a type or trait with breaking changes (such as a removed type parameter)
+/// in its signature usually fails to type check for that reason alone. Such items are listed
+/// with the change they are caused by, instead of being reported on their own.
+///
+/// Type aliases are expanded in signatures, so an item is considered to use a changed alias if
+/// its signature mentions the type the alias expanded to.
+fn collapse_cascades(changes: &mut ChangeSet, tcx: TyCtxt, crate_name: Symbol) {
+    let aliases: Vec<_> = changes
+        .structurally_breaking_items()
+        .into_iter()
+        .filter(|&def_id| tcx.def_kind(def_id) == DefKind::TyAlias)
+        .map(|def_id| (def_id, GenericArg::from(tcx.type_of(def_id))))
+        .collect();
+
+    for old in changes.type_changed_items() {
+        let root = referenced_items(tcx, old)
+            .into_iter()
+            .find(|&def_id| def_id != old && changes.item_structurally_breaking(def_id))
+            .or_else(|| {
+                let tys = referenced_types(tcx, old);
+                aliases
+                    .iter()
+                    .find(|&&(alias, aliased)| {
+                        alias != old && tys.iter().any(|ty| ty.walk().any(|arg| arg == aliased))
+                    })
+                    .map(|&(alias, _)| alias)
+            });
+
+        if let Some(root) = root {
+            changes.collapse(old, root, item_path(tcx, crate_name, old));
+        }
+    }
+}
+
+/// Get the types mentioned in the signature, type or fields of an item.
+fn referenced_types(tcx: TyCtxt, def_id: DefId) -> Vec<Ty> {
+    use rustc_hir::def::DefKind::*;
+
+    match tcx.def_kind(def_id) {
+        Fn | AssocFn => tcx.fn_sig(def_id).skip_binder().inputs_and_output.to_vec(),
+        Const | AssocConst | Static(_) | TyAlias | Field => vec![tcx.type_of(def_id)],
+        Struct | Union | Enum => tcx
+            .adt_def(def_id)
+            .all_fields()
+            .map(|field| tcx.type_of(field.did))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Get the ADTs and traits mentioned in the signature, type or fields of an item.
+fn referenced_items(tcx: TyCtxt, def_id: DefId) -> Vec<DefId> {
+    referenced_types(tcx, def_id)
+        .into_iter()
+        .flat_map(|ty| ty.walk())
+        .filter_map(|arg| match arg.unpack() {
+            GenericArgKind::Type(ty) => match *ty.kind() {
+                TyKind::Adt(adt_def, _) => Some(adt_def.did()),
+                TyKind::Dynamic(preds, _) => preds.principal_def_id(),
+                TyKind::Foreign(did) => Some(did),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collect the old items that are public, or reachable through the public API.
+///
+/// Private items can only be reached by users through the signatures, types, fields or bounds
+/// of other reachable items, so all remaining private items are never compared.
+fn reachable_items(changes: &ChangeSet, id_mapping: &IdMapping, tcx: TyCtxt) -> HashSet<DefId> {
+    let public = id_mapping
+        .items()
+        .map(|(old, _)| old.def_id())
+        .filter(|&def_id| changes.get_output(def_id));
+
+    let reachable = reachable_from(public, |def_id| {
+        referenced_items(tcx, def_id)
+            .into_iter()
+            .chain(bound_items(tcx, def_id))
+            .filter(|&referenced| id_mapping.in_old_crate(referenced))
+            .collect()
+    });
+
+    debug!("reachable items: {}", reachable.len());
+
+    reachable
+}
+
+/// Collect the given items, and all items transitively referenced by them.
+fn reachable_from(
+    roots: impl IntoIterator<Item = DefId>,
+    referenced: impl Fn(DefId) -> Vec<DefId>,
+) -> HashSet<DefId> {
+    let mut queue: VecDeque<_> = roots.into_iter().collect();
+    let mut reachable: HashSet<_> = queue.iter().copied().collect();
+
+    while let Some(def_id) = queue.pop_front() {
+        for referenced in referenced(def_id) {
+            if reachable.insert(referenced) {
+                queue.push_back(referenced);
+            }
+        }
+    }
+
+    reachable
+}
+
+/// Collect the old types that users can't name, but that leak into the public API.
+///
+/// Public types in private modules are part of the public API in all but name if they are
+/// mentioned in the signatures or public fields of public items, or of other leaked types.
+fn leaked_types(changes: &ChangeSet, id_mapping: &IdMapping, tcx: TyCtxt) -> HashSet<DefId> {
+    use rustc_hir::def::DefKind::*;
+
+    let exposed_types = |def_id: DefId| -> Vec<Ty> {
+        match tcx.def_kind(def_id) {
+            Struct | Union | Enum => tcx
+                .adt_def(def_id)
+                .all_fields()
+                .filter(|field| field.vis == Public)
+                .map(|field| tcx.type_of(field.did))
+                .collect(),
+            _ => referenced_types(tcx, def_id),
+        }
+    };
+
+    let mut queue: VecDeque<_> = id_mapping
+        .items()
+        .map(|(old, _)| old.def_id())
+        .filter(|&def_id| changes.get_output(def_id))
+        .collect();
+    let mut leaked = HashSet::new();
+
+    while let Some(def_id) = queue.pop_front() {
+        for arg in exposed_types(def_id).into_iter().flat_map(|ty| ty.walk()) {
+            if let GenericArgKind::Type(ty) = arg.unpack() {
+                if let TyKind::Adt(adt_def, _) = *ty.kind() {
+                    let did = adt_def.did();
+
+                    if id_mapping.in_old_crate(did)
+                        && !changes.get_output(did)
+                        && tcx.visibility(did) == Public
+                        && leaked.insert(did)
+                    {
+                        queue.push_back(did);
+                    }
+                }
+            }
+        }
+    }
+
+    debug!("leaked types: {}", leaked.len());
+
+    leaked
+}
+
+/// Check the public items present in both versions for newly used standard library items that
+/// have been stabilized after the declared minimum supported Rust version.
+///
+/// Only the items mentioned in signatures, types and bounds are found, not the ones used in
+/// function bodies, so this can't confirm that the declared version is sufficient.
+fn check_rust_version(
+    changes: &mut ChangeSet,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt,
+    rust_version: &str,
+) {
+    use rustc_hir::def::DefKind::*;
+
+    let declared = match parse_rust_version(rust_version) {
+        Some(declared) => declared,
+        None => return,
+    };
+
+    let stable_since = |def_id: DefId| match tcx.lookup_stability(def_id)?.level {
+        StabilityLevel::Stable { since, .. } => {
+            parse_rust_version(since.as_str()).map(|version| (since, version))
+        }
+        StabilityLevel::Unstable { .. } => None,
+    };
+    let used_items = |def_id: DefId| -> HashSet<DefId> {
+        referenced_items(tcx, def_id)
+            .into_iter()
+            .chain(bound_items(tcx, def_id))
+            .collect()
+    };
+
+    let pairs: Vec<_> = id_mapping
+        .items()
+        .filter_map(|(old, new)| match (old, new) {
+            (Def(kind, old), Def(_, new))
+                if matches!(
+                    kind,
+                    Fn | AssocFn | Struct | Union | Enum | Trait | TyAlias | AssocTy
+                ) && changes.get_output(old) =>
+            {
+                Some((old, new))
+            }
+            _ => None,
+        })
+        .collect();
+
+    for (old, new) in pairs {
+        let old_used = used_items(old);
+        let mut new_used: Vec<_> = used_items(new)
+            .into_iter()
+            .filter(|def_id| !old_used.contains(def_id))
+            .filter_map(|def_id| stable_since(def_id).map(|since| (def_id, since)))
+            .filter(|(_, (_, version))| *version > declared)
+            .map(|(def_id, (since, _))| (tcx.def_path_str(def_id), since))
+            .collect();
+        new_used.sort();
+
+        for (path, since) in new_used {
+            changes.add_change(ChangeType::RustVersionExceeded { path, since }, old, None);
+        }
+    }
+}
+
+/// Get the traits and ADTs mentioned in the trait bounds of an item.
+fn bound_items(tcx: TyCtxt, def_id: DefId) -> Vec<DefId> {
+    use rustc_hir::def::DefKind::*;
+
+    if !matches!(
+        tcx.def_kind(def_id),
+        Fn | AssocFn | Struct | Union | Enum | Trait | TyAlias | AssocTy
+    ) {
+        return Vec::new();
+    }
+
+    tcx.predicates_of(def_id)
+        .predicates
+        .iter()
+        .filter_map(|(pred, _)| pred.to_opt_poly_trait_pred())
+        .flat_map(|trait_pred| trait_ref_items(trait_pred.skip_binder().trait_ref))
+        .collect()
+}
+
+/// Get the trait of a trait reference, along with the ADTs mentioned in its type arguments.
+fn trait_ref_items(trait_ref: TraitRef) -> Vec<DefId> {
+    Some(trait_ref.def_id)
+        .into_iter()
+        .chain(
+            trait_ref
+                .substs
+                .types()
+                .flat_map(|ty| ty.walk())
+                .filter_map(|arg| match arg.unpack() {
+                    GenericArgKind::Type(ty) => ty.ty_adt_def().map(|adt_def| adt_def.did()),
+                    _ => None,
+                }),
+        )
+        .collect()
+}
+
+/// Render a type without regions and the names of the crates defining the ADTs mentioned.
+fn render_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> String {
+    let ty = tcx.erase_regions(ty);
+    let rendered = ty.to_string();
+
+    ty.walk()
+        .filter_map(|arg| match arg.unpack() {
+            GenericArgKind::Type(ty) => ty.ty_adt_def().map(|adt_def| adt_def.did().krate),
+            _ => None,
+        })
+        .fold(rendered, |rendered, krate| {
+            rendered.replace(&format!("{}::", tcx.crate_name(krate)), "")
+        })
+}
+
+/// Get the definition path of an item, starting with the given crate name.
+///
+/// The crate name is passed explicitly, so that items from both crate versions get the same
+/// prefix. Impls are skipped, so items in impls appear to be defined in the surrounding module.
+fn item_path(tcx: TyCtxt, crate_name: Symbol, def_id: DefId) -> String {
+    let mut path = vec![crate_name.to_string()];
+
+    path.extend(
+        tcx.def_path(def_id)
+            .data
+            .iter()
+            .filter_map(|elem| elem.data.get_opt_name())
+            .map(|name| name.to_string()),
+    );
+
+    path.join("::")
+}
+
 // Get the visibility of the inner item, given the outer item's visibility.
 fn get_vis(outer_vis: Visibility, def: ModChild) -> Visibility {
     if outer_vis == Public {
@@ -122,7 +680,9 @@ pub fn run_traversal(tcx: TyCtxt, new: DefId) {
 ///
 /// Traverse the two root modules in an interleaved manner, matching up pairs of modules
 /// from the two crate versions and compare for changes. Matching children get processed
-/// in the same fashion.
+/// in the same fashion. Removed items renamed to added ones according to `renames`, moved to
+/// another module, or probably renamed to an added item of a similar name, are matched up as
+/// well.
 #[allow(clippy::cognitive_complexity)]
 fn diff_structure<'tcx>(
     changes: &mut ChangeSet,
@@ -130,6 +690,7 @@ fn diff_structure<'tcx>(
     tcx: TyCtxt<'tcx>,
     old: DefId,
     new: DefId,
+    renames: &[(String, String)],
 ) {
     use rustc_hir::def::DefKind::*;
 
@@ -142,16 +703,31 @@ fn diff_structure<'tcx>(
     // each other and their item's definition can't be relied upon.
     let mut removals = Vec::new();
     let mut additions = Vec::new();
+    // the visibility of the modules the removed and added items have been found in
+    let mut parent_vis = HashMap::new();
+    let crate_name = tcx.crate_name(new.krate);
 
     // Start off with the root module pair.
     mod_queue.push_back((old, new, Public, Public));
 
     // Pull a matched module pair from the queue, with the modules' global visibility.
     while let Some((old_def_id, new_def_id, old_vis, new_vis)) = mod_queue.pop_front() {
-        children.add(
-            tcx.module_children(old_def_id).to_vec(), // TODO: clean up
-            tcx.module_children(new_def_id).to_vec(),
-        );
+        // documentation shims don't exist in regular builds, so they are neither compared nor
+        // allowed to stand in for the actual items
+        let module_children = |def_id| -> Vec<_> {
+            tcx.module_children(def_id)
+                .iter()
+                .filter(|child| {
+                    !child
+                        .res
+                        .opt_def_id()
+                        .map_or(false, |d| is_doc_shim(tcx, d))
+                })
+                .cloned()
+                .collect()
+        };
+
+        children.add(module_children(old_def_id), module_children(new_def_id));
 
         for items in children.drain() {
             match items {
@@ -184,116 +760,35 @@ fn diff_structure<'tcx>(
 
                             mod_queue.push_back((o_def_id, n_def_id, o_vis, n_vis));
                         }
-                    } else if id_mapping.add_export(o_res, n_res) {
-                        // struct constructors are weird/hard - let's go shopping!
-                        if let (Def(Ctor(CtorOf::Struct, _), _), Def(Ctor(CtorOf::Struct, _), _)) =
-                            (o_res, n_res)
-                        {
-                            continue;
-                        }
-
-                        let o_def_id = o_res.def_id();
-                        let n_def_id = n_res.def_id();
-                        let o_vis = get_vis(old_vis, o);
-                        let n_vis = get_vis(new_vis, n);
-
-                        let output = o_vis == Public || n_vis == Public;
-                        changes.new_change(
-                            o_def_id,
-                            n_def_id,
-                            o.ident.name,
-                            tcx.def_span(o_def_id),
-                            tcx.def_span(n_def_id),
-                            output,
-                        );
-
-                        if o_vis == Public && n_vis != Public {
-                            changes.add_change(ChangeType::ItemMadePrivate, o_def_id, None);
-                        } else if o_vis != Public && n_vis == Public {
-                            changes.add_change(ChangeType::ItemMadePublic, o_def_id, None);
-                        }
-
-                        let (o_kind, n_kind) = match (o_res, n_res) {
-                            (Res::Def(o_kind, _), Res::Def(n_kind, _)) => (o_kind, n_kind),
-                            _ => {
-                                // a non-matching item pair (seriously broken though) -
-                                // register the change and abort further analysis of it
-                                // changes.add_change(ChangeType::KindDifference, o_def_id, None);
-                                continue;
-                            }
+                    } else {
+                        let path = || {
+                            o_res
+                                .opt_def_id()
+                                .map_or_else(String::new, |did| item_path(tcx, crate_name, did))
                         };
 
-                        match (o_kind, n_kind) {
-                            // TODO: update comment
-                            // matching items we don't care about because they are either
-                            // impossible to encounter at this stage (Mod, AssocTy, PrimTy,
-                            // TyParam, SelfTy, Ctor, AssocConst, Local, Upvar,
-                            // Variant, Method, Err), whose analysis is out scope
-                            // for us (GlobalAsm), or which don't requite further
-                            // analysis at this stage (Const).
-                            (Mod, Mod)
-                            | (AssocTy, AssocTy)
-                            | (TyParam, TyParam)
-                            | (Ctor(CtorOf::Struct, _), Ctor(CtorOf::Struct, _))
-                            | (Ctor(CtorOf::Variant, _), Ctor(CtorOf::Variant, _))
-                            | (AssocConst, AssocConst)
-                            | (Variant, Variant)
-                            | (Const, Const)
-                            | (AssocFn, AssocFn)
-                            | (Macro(_), Macro(_))
-                            | (TraitAlias, TraitAlias)
-                            | (ForeignTy, ForeignTy)
-                            | (ConstParam, ConstParam) => {}
-                            // statics are subject to mutability comparison
-                            (Static(old_mut), Static(new_mut)) => {
-                                if old_mut != new_mut {
-                                    let change_type = ChangeType::StaticMutabilityChanged {
-                                        now_mut: new_mut == Mutability::Mut,
-                                    };
-
-                                    changes.add_change(change_type, o_def_id, None);
-                                }
-                            }
-                            // functions can declare generics and have structural properties
-                            // that need to be compared
-                            (Fn, Fn) => {
-                                diff_generics(changes, id_mapping, tcx, true, o_def_id, n_def_id);
-                                diff_fn(changes, tcx, o_res, n_res);
-                            }
-                            // type aliases can declare generics, too
-                            (TyAlias, TyAlias) => {
-                                diff_generics(changes, id_mapping, tcx, false, o_def_id, n_def_id);
-                            }
-                            // ADTs can declare generics and have lots of structural properties
-                            // to check, most notably the number and name of variants and/or
-                            // fields
-                            (Struct, Struct) | (Union, Union) | (Enum, Enum) => {
-                                diff_generics(changes, id_mapping, tcx, false, o_def_id, n_def_id);
-                                diff_adts(changes, id_mapping, tcx, o_res, n_res);
-                            }
-                            // trait definitions can declare generics and require us to check
-                            // for trait item addition and removal, as well as changes to their
-                            // kinds and defaultness
-                            (Trait, Trait) => {
-                                if o_vis != Public {
-                                    debug!("private trait: {:?}", o_def_id);
-                                    id_mapping.add_private_trait(o_def_id);
-                                }
+                        trace::item(path, || {
+                            debug!("matched by name: {:?} -> {:?}", o_res, n_res);
 
-                                if n_vis != Public {
-                                    debug!("private trait: {:?}", n_def_id);
-                                    id_mapping.add_private_trait(n_def_id);
-                                }
+                            if is_semver_trick_reexport(tcx, id_mapping, o_res, n_res) {
+                                debug!("re-exported from another version: {:?}", n_res);
 
-                                diff_generics(changes, id_mapping, tcx, false, o_def_id, n_def_id);
-                                traits.push((o_def_id, n_def_id, output));
-                            }
-                            // a non-matching item pair - register the change and abort further
-                            // analysis of it
-                            _ => {
-                                changes.add_change(ChangeType::KindDifference, o_def_id, None);
+                                if id_mapping.add_export(o_res, n_res) {
+                                    id_mapping.add_semver_trick_item(o_res.def_id());
+                                }
+                            } else if id_mapping.add_export(o_res, n_res) {
+                                diff_item_pair(
+                                    changes,
+                                    id_mapping,
+                                    tcx,
+                                    &mut traits,
+                                    o,
+                                    n,
+                                    old_vis,
+                                    new_vis,
+                                );
                             }
-                        }
+                        });
                     }
                 }
                 // only an old item is found
@@ -305,6 +800,7 @@ fn diff_structure<'tcx>(
 
                     if get_vis(old_vis, o) == Public {
                         // delay the handling of removals until the id mapping is complete
+                        parent_vis.insert(o.res.expect_non_local::<HirId>().def_id(), old_vis);
                         removals.push(o);
                     }
                 }
@@ -318,6 +814,7 @@ fn diff_structure<'tcx>(
                     if get_vis(new_vis, n) == Public {
                         debug!("addition: {:?} ({:?})", new_vis, n);
                         // delay the handling of additions until the id mapping is complete
+                        parent_vis.insert(n.res.expect_non_local::<HirId>().def_id(), new_vis);
                         additions.push(n);
                     }
                 }
@@ -326,6 +823,97 @@ fn diff_structure<'tcx>(
         }
     }
 
+    // pair up removed items with the added items they have been renamed to
+    if !renames.is_empty() {
+        let path = |item: &ModChild| {
+            let def_id = item.res.expect_non_local::<HirId>().def_id();
+            item_path(tcx, crate_name, def_id)
+        };
+
+        let mut renamed = Vec::new();
+
+        removals.retain(|o| {
+            let o_path = path(o);
+            let pos = renames
+                .iter()
+                .find(|(old_path, _)| *old_path == o_path)
+                .and_then(|(_, new_path)| additions.iter().position(|n| path(n) == *new_path));
+
+            if let Some(pos) = pos {
+                renamed.push((*o, additions.remove(pos)));
+                false
+            } else {
+                true
+            }
+        });
+
+        for (o, n) in renamed {
+            let (o_res, n_res) = (o.res.expect_non_local(), n.res.expect_non_local());
+            debug!("renamed: {:?} -> {:?}", o_res, n_res);
+
+            if id_mapping.add_export(o_res, n_res) {
+                let (o_vis, n_vis) = (parent_vis[&o_res.def_id()], parent_vis[&n_res.def_id()]);
+                diff_item_pair(changes, id_mapping, tcx, &mut traits, o, n, o_vis, n_vis);
+            }
+        }
+    }
+
+    // modules removed or added as a whole are reported once, unless their items just moved
+    expand_moved_modules(tcx, id_mapping, &mut removals, &mut additions);
+
+    // pair up removed items with added items of the same name and shape in other modules
+    for (o, n) in match_moved_items(tcx, id_mapping, &mut removals, &mut additions) {
+        let (o_res, n_res) = (o.res.expect_non_local(), n.res.expect_non_local());
+        debug!("moved: {:?} -> {:?}", o_res, n_res);
+
+        if id_mapping.add_export(o_res, n_res) {
+            diff_item_pair(changes, id_mapping, tcx, &mut traits, o, n, Public, Public);
+
+            let o_def_id = o_res.def_id();
+            let change_type = ChangeType::ItemMoved {
+                old_path: item_path(tcx, crate_name, o_def_id),
+                new_path: item_path(tcx, crate_name, n_res.def_id()),
+            };
+            changes.add_change(change_type, o_def_id, None);
+        }
+    }
+
+    // pair up removed items with added items of a similar name and the same shape, and then
+    // types and traits with added ones of the same structure, regardless of their name
+    let renamed = match_renamed_items(tcx, id_mapping, &mut removals, &mut additions);
+    let restructured = match_restructured_items(tcx, id_mapping, &mut removals, &mut additions);
+
+    for (o, n) in renamed.into_iter().chain(restructured) {
+        let (o_res, n_res) = (o.res.expect_non_local(), n.res.expect_non_local());
+        debug!("probably renamed: {:?} -> {:?}", o_res, n_res);
+
+        if id_mapping.add_export(o_res, n_res) {
+            diff_item_pair(changes, id_mapping, tcx, &mut traits, o, n, Public, Public);
+
+            let o_def_id = o_res.def_id();
+            let change_type = ChangeType::ItemProbablyRenamed {
+                old_path: item_path(tcx, crate_name, o_def_id),
+                new_path: item_path(tcx, crate_name, n_res.def_id()),
+            };
+            changes.add_change(change_type, o_def_id, None);
+        }
+    }
+
+    // remember the remaining pairs that could have been renamed, to ask about them if requested
+    for (o_def_id, n_def_id) in ambiguous_candidates(tcx, id_mapping, &removals, &additions) {
+        changes.add_rename_candidate(
+            item_path(tcx, crate_name, o_def_id),
+            item_path(tcx, crate_name, n_def_id),
+        );
+    }
+
+    // removed items are reported along with an added item of a similar name, if any
+    let added: Vec<_> = additions
+        .iter()
+        .map(|n| (n.ident.name, n.res.expect_non_local::<HirId>().def_id()))
+        .collect();
+    let added_names: Vec<_> = added.iter().map(|&(name, _)| name).collect();
+
     // finally, process item additions and removals
     for n in additions {
         let n_def_id = n.res.expect_non_local::<HirId>().def_id();
@@ -342,14 +930,19 @@ fn diff_structure<'tcx>(
         let o_def_id = o.res.expect_non_local::<HirId>().def_id();
 
         // reuse an already existing path change entry, if possible
-        if id_mapping.contains_old_id(o_def_id) {
-            let n_def_id = id_mapping.get_new_id(o_def_id).unwrap();
-            changes.new_path_change(n_def_id, o.ident.name, tcx.def_span(n_def_id));
-            changes.add_path_removal(n_def_id, o.span);
+        let key = if id_mapping.contains_old_id(o_def_id) {
+            id_mapping.get_new_id(o_def_id).unwrap()
         } else {
             id_mapping.add_non_mapped(o_def_id);
-            changes.new_path_change(o_def_id, o.ident.name, tcx.def_span(o_def_id));
-            changes.add_path_removal(o_def_id, o.span);
+            o_def_id
+        };
+
+        changes.new_path_change(key, o.ident.name, tcx.def_span(key));
+        changes.add_path_removal(key, o.span);
+
+        if let Some(name) = find_best_match_for_name(&added_names, o.ident.name, None) {
+            let &(_, n_def_id) = added.iter().find(|&&(n, _)| n == name).unwrap();
+            changes.add_path_suggestion(key, item_path(tcx, crate_name, n_def_id));
         }
     }
 
@@ -358,28 +951,295 @@ fn diff_structure<'tcx>(
     }
 }
 
-/// Given two fn items, perform structural checks.
-fn diff_fn<'tcx>(changes: &mut ChangeSet, tcx: TyCtxt<'tcx>, old: Res, new: Res) {
-    let old_def_id = old.def_id();
-    let new_def_id = new.def_id();
+/// Replace removed and added modules by their items, if all of these have moved between them.
+///
+/// When a module is removed, but its items are still exported at other paths, reporting the
+/// moves of the items is more useful than reporting the module's removal. Items that have
+/// probably been renamed while moving count as moved. Otherwise, the module is reported once,
+/// instead of every item it contains.
+fn expand_moved_modules(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    removals: &mut Vec<ModChild>,
+    additions: &mut Vec<ModChild>,
+) {
+    // pair every item with the items it contains, if it is a module
+    type Expanded = Vec<(ModChild, Option<Vec<ModChild>>)>;
 
-    let old_const = tcx.is_const_fn(old_def_id);
-    let new_const = tcx.is_const_fn(new_def_id);
+    fn def_id(item: &ModChild) -> DefId {
+        item.res.expect_non_local::<HirId>().def_id()
+    }
 
-    if old_const != new_const {
-        changes.add_change(
-            ChangeType::FnConstChanged {
-                now_const: new_const,
-            },
-            old_def_id,
-            None,
-        );
+    fn flatten(expanded: &Expanded) -> Vec<ModChild> {
+        expanded
+            .iter()
+            .flat_map(|(item, children)| children.clone().unwrap_or_else(|| vec![*item]))
+            .collect()
     }
-}
 
-/// Given two method items, perform structural checks.
-fn diff_method<'tcx>(changes: &mut ChangeSet, tcx: TyCtxt<'tcx>, old: AssocItem, new: AssocItem) {
-    if old.fn_has_self_parameter != new.fn_has_self_parameter {
+    fn collapse(expanded: Expanded, accounted_for: impl Fn(DefId) -> bool) -> Vec<ModChild> {
+        expanded
+            .into_iter()
+            .flat_map(|(item, children)| match children {
+                Some(children)
+                    if !children.is_empty()
+                        && children.iter().all(|child| accounted_for(def_id(child))) =>
+                {
+                    children
+                }
+                _ => vec![item],
+            })
+            .collect()
+    }
+
+    let expand = |items: &[ModChild]| -> Expanded {
+        items
+            .iter()
+            .map(|&item| match item.res.expect_non_local::<HirId>() {
+                Def(DefKind::Mod, def_id) => (item, Some(module_items(tcx, def_id))),
+                _ => (item, None),
+            })
+            .collect()
+    };
+
+    let old = expand(removals);
+    let new = expand(additions);
+
+    let mut old_items = flatten(&old);
+    let mut new_items = flatten(&new);
+    let mut moved = Vec::new();
+
+    for matcher in [
+        match_moved_items,
+        match_renamed_items,
+        match_restructured_items,
+    ] {
+        moved.extend(matcher(tcx, id_mapping, &mut old_items, &mut new_items));
+    }
+
+    if moved.is_empty() {
+        return;
+    }
+
+    let moved_old: HashSet<_> = moved.iter().map(|(o, _)| def_id(o)).collect();
+    let moved_new: HashSet<_> = moved.iter().map(|(_, n)| def_id(n)).collect();
+
+    // items still exported at another path are accounted for without having moved
+    *removals = collapse(old, |did| {
+        moved_old.contains(&did) || id_mapping.contains_old_id(did)
+    });
+    *additions = collapse(new, |did| {
+        moved_new.contains(&did) || id_mapping.contains_new_id(did)
+    });
+}
+
+/// Get the public items of a module and its public submodules, except for the submodules.
+fn module_items(tcx: TyCtxt, module: DefId) -> Vec<ModChild> {
+    use rustc_hir::def::DefKind::*;
+
+    let mut items = Vec::new();
+    let mut visited = HashSet::new();
+    let mut mod_queue = VecDeque::new();
+
+    mod_queue.push_back(module);
+
+    while let Some(def_id) = mod_queue.pop_front() {
+        for &item in tcx.module_children(def_id).iter() {
+            if item.vis != Public {
+                continue;
+            }
+
+            match item.res.expect_non_local::<HirId>() {
+                Def(Mod, child_def_id) => {
+                    if visited.insert(child_def_id) {
+                        mod_queue.push_back(child_def_id);
+                    }
+                }
+                // struct constructors are weird/hard - let's go shopping!
+                Def(Ctor(CtorOf::Struct, _), _) => {}
+                _ => items.push(item),
+            }
+        }
+    }
+
+    items
+}
+
+/// Given a pair of items matched by name, compare their visibility and structure.
+///
+/// Trait pairs are collected in `traits`, to be compared once all items have been matched.
+#[allow(clippy::too_many_arguments)]
+fn diff_item_pair(
+    changes: &mut ChangeSet,
+    id_mapping: &mut IdMapping,
+    tcx: TyCtxt,
+    traits: &mut Vec<(DefId, DefId, bool)>,
+    o: ModChild,
+    n: ModChild,
+    old_vis: Visibility,
+    new_vis: Visibility,
+) {
+    use rustc_hir::def::DefKind::*;
+
+    let (o_res, n_res): (Res, Res) = (o.res.expect_non_local(), n.res.expect_non_local());
+
+    // struct constructors are weird/hard - let's go shopping!
+    if let (Def(Ctor(CtorOf::Struct, _), _), Def(Ctor(CtorOf::Struct, _), _)) = (o_res, n_res) {
+        return;
+    }
+
+    let o_def_id = o_res.def_id();
+    let n_def_id = n_res.def_id();
+    let o_vis = get_vis(old_vis, o);
+    let n_vis = get_vis(new_vis, n);
+
+    let output = o_vis == Public || n_vis == Public;
+    changes.new_change(
+        o_def_id,
+        n_def_id,
+        o.ident.name,
+        tcx.def_span(o_def_id),
+        tcx.def_span(n_def_id),
+        output,
+    );
+
+    if o_vis == Public && n_vis != Public {
+        changes.add_change(ChangeType::ItemMadePrivate, o_def_id, None);
+    } else if o_vis != Public && n_vis == Public {
+        changes.add_change(ChangeType::ItemMadePublic, o_def_id, None);
+
+        // newly exposed items add to the public API just like newly defined ones
+        for line in dump_item(tcx, n_def_id) {
+            changes.add_change(ChangeType::PublicApiAdded { line }, o_def_id, None);
+        }
+    }
+
+    let (o_kind, n_kind) = match (o_res, n_res) {
+        (Res::Def(o_kind, _), Res::Def(n_kind, _)) => (o_kind, n_kind),
+        _ => {
+            // a non-matching item pair (seriously broken though) -
+            // register the change and abort further analysis of it
+            // changes.add_change(ChangeType::KindDifference, o_def_id, None);
+            return;
+        }
+    };
+
+    match (o_kind, n_kind) {
+        // derive macros declare helper attributes users annotate their
+        // types with
+        (Macro(MacroKind::Derive), Macro(MacroKind::Derive)) => {
+            diff_derive_helpers(changes, tcx, o_def_id, n_def_id);
+        }
+        // declarative macros are compared by the tokens of their rules
+        (Macro(MacroKind::Bang), Macro(MacroKind::Bang)) => {
+            diff_macro_rules(changes, tcx, o_def_id, n_def_id);
+        }
+        // TODO: update comment
+        // matching items we don't care about because they are either
+        // impossible to encounter at this stage (Mod, AssocTy, PrimTy,
+        // TyParam, SelfTy, Ctor, AssocConst, Local, Upvar,
+        // Variant, Method, Err), whose analysis is out scope
+        // for us (GlobalAsm), or which don't requite further
+        // analysis at this stage (Const).
+        (Mod, Mod)
+        | (AssocTy, AssocTy)
+        | (TyParam, TyParam)
+        | (Ctor(CtorOf::Struct, _), Ctor(CtorOf::Struct, _))
+        | (Ctor(CtorOf::Variant, _), Ctor(CtorOf::Variant, _))
+        | (AssocConst, AssocConst)
+        | (Variant, Variant)
+        | (Const, Const)
+        | (AssocFn, AssocFn)
+        | (Macro(_), Macro(_))
+        | (TraitAlias, TraitAlias)
+        | (ForeignTy, ForeignTy)
+        | (ConstParam, ConstParam) => {}
+        // statics are subject to mutability comparison
+        (Static(old_mut), Static(new_mut)) => {
+            if old_mut != new_mut {
+                let change_type = ChangeType::StaticMutabilityChanged {
+                    now_mut: new_mut == Mutability::Mut,
+                };
+
+                changes.add_change(change_type, o_def_id, None);
+            }
+        }
+        // functions can declare generics and have structural properties
+        // that need to be compared
+        (Fn, Fn) => {
+            diff_generics(changes, id_mapping, tcx, true, o_def_id, n_def_id);
+            diff_fn(changes, tcx, o_res, n_res);
+            diff_attributes(changes, tcx, o_def_id, n_def_id);
+        }
+        // type aliases can declare generics, too
+        (TyAlias, TyAlias) => {
+            diff_generics(changes, id_mapping, tcx, false, o_def_id, n_def_id);
+
+            // the opaque types defined by `type Foo = impl Trait;` correspond to each other
+            if let (&TyKind::Opaque(o_opaque, _), &TyKind::Opaque(n_opaque, _)) =
+                (tcx.type_of(o_def_id).kind(), tcx.type_of(n_def_id).kind())
+            {
+                if !id_mapping.contains_old_id(o_opaque) {
+                    id_mapping.add_internal_item(o_opaque, n_opaque);
+                }
+            }
+        }
+        // ADTs can declare generics and have lots of structural properties
+        // to check, most notably the number and name of variants and/or
+        // fields
+        (Struct, Struct) | (Union, Union) | (Enum, Enum) => {
+            diff_generics(changes, id_mapping, tcx, false, o_def_id, n_def_id);
+            diff_adts(changes, id_mapping, tcx, o_res, n_res);
+            diff_attributes(changes, tcx, o_def_id, n_def_id);
+        }
+        // trait definitions can declare generics and require us to check
+        // for trait item addition and removal, as well as changes to their
+        // kinds and defaultness
+        (Trait, Trait) => {
+            if o_vis != Public {
+                debug!("private trait: {:?}", o_def_id);
+                id_mapping.add_private_trait(o_def_id);
+            }
+
+            if n_vis != Public {
+                debug!("private trait: {:?}", n_def_id);
+                id_mapping.add_private_trait(n_def_id);
+            }
+
+            diff_generics(changes, id_mapping, tcx, false, o_def_id, n_def_id);
+            diff_attributes(changes, tcx, o_def_id, n_def_id);
+            traits.push((o_def_id, n_def_id, output));
+        }
+        // a non-matching item pair - register the change and abort further
+        // analysis of it
+        _ => {
+            changes.add_change(ChangeType::KindDifference, o_def_id, None);
+        }
+    }
+}
+
+/// Given two fn items, perform structural checks.
+fn diff_fn<'tcx>(changes: &mut ChangeSet, tcx: TyCtxt<'tcx>, old: Res, new: Res) {
+    let old_def_id = old.def_id();
+    let new_def_id = new.def_id();
+
+    let old_const = tcx.is_const_fn(old_def_id);
+    let new_const = tcx.is_const_fn(new_def_id);
+
+    if old_const != new_const {
+        changes.add_change(
+            ChangeType::FnConstChanged {
+                now_const: new_const,
+            },
+            old_def_id,
+            None,
+        );
+    }
+}
+
+/// Given two method items, perform structural checks.
+fn diff_method<'tcx>(changes: &mut ChangeSet, tcx: TyCtxt<'tcx>, old: AssocItem, new: AssocItem) {
+    if old.fn_has_self_parameter != new.fn_has_self_parameter {
         changes.add_change(
             ChangeType::MethodSelfChanged {
                 now_self: new.fn_has_self_parameter,
@@ -404,6 +1264,227 @@ fn diff_method<'tcx>(changes: &mut ChangeSet, tcx: TyCtxt<'tcx>, old: AssocItem,
         Def(DefKind::Fn, old.def_id),
         Def(DefKind::Fn, new.def_id),
     );
+    diff_attributes(changes, tcx, old.def_id, new.def_id);
+}
+
+/// Check whether an item carries an attribute with the given name.
+fn has_attr(tcx: TyCtxt, def_id: DefId, name: Symbol) -> bool {
+    tcx.get_attrs(def_id, name).next().is_some()
+}
+
+/// Given two items, compare the attributes affecting how user code using them is compiled.
+fn diff_attributes(changes: &mut ChangeSet, tcx: TyCtxt, old: DefId, new: DefId) {
+    if !has_attr(tcx, old, sym::must_use) && has_attr(tcx, new, sym::must_use) {
+        changes.add_change(ChangeType::MustUseAdded, old, None);
+    }
+
+    let old_tracked = has_attr(tcx, old, sym::track_caller);
+    let new_tracked = has_attr(tcx, new, sym::track_caller);
+
+    if old_tracked != new_tracked {
+        changes.add_change(
+            ChangeType::TrackCallerChanged {
+                now_tracked: new_tracked,
+            },
+            old,
+            None,
+        );
+    }
+}
+
+/// Check whether a type error is caused by a dependency used in different versions by the old
+/// and new crate.
+///
+/// This is the case if the mismatched types or traits are defined under the same path in two
+/// distinct crates of the same name, neither of which is the old or new crate.
+fn dependency_version_skew<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    id_mapping: &IdMapping,
+    err: &TypeError<'tcx>,
+) -> Option<ChangeType<'tcx>> {
+    let (old, new) = match *err {
+        TypeError::Sorts(ref ef) => match (ef.expected.kind(), ef.found.kind()) {
+            (&TyKind::Adt(old, _), &TyKind::Adt(new, _)) => (old.did(), new.did()),
+            (&TyKind::Foreign(old), &TyKind::Foreign(new)) => (old, new),
+            _ => return None,
+        },
+        TypeError::Traits(ref ef) => (ef.expected, ef.found),
+        _ => return None,
+    };
+
+    let foreign =
+        |def_id: DefId| !id_mapping.in_old_crate(def_id) && !id_mapping.in_new_crate(def_id);
+    let krate = tcx.crate_name(new.krate);
+
+    if old.krate != new.krate
+        && foreign(old)
+        && foreign(new)
+        && tcx.crate_name(old.krate) == krate
+        && tcx.def_path(old).data == tcx.def_path(new).data
+    {
+        Some(ChangeType::DependencyVersionSkew {
+            krate,
+            path: tcx.def_path_str(new),
+        })
+    } else {
+        None
+    }
+}
+
+/// Check whether a new export is the old item, re-exported from another version of the old crate.
+///
+/// Using the semver trick, a new major version depends on a previous one of itself and
+/// re-exports the items that haven't changed, so that both versions share them. The re-exported
+/// item lives in a crate of the same name as the old one, under the same definition path.
+fn is_semver_trick_reexport(tcx: TyCtxt, id_mapping: &IdMapping, old: Res, new: Res) -> bool {
+    let (old, new) = match (old.opt_def_id(), new.opt_def_id()) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return false,
+    };
+
+    !id_mapping.in_old_crate(new)
+        && !id_mapping.in_new_crate(new)
+        && tcx.crate_name(new.krate) == tcx.crate_name(id_mapping.get_old_crate())
+        && tcx.def_path(old).data == tcx.def_path(new).data
+}
+
+/// Check whether an item only exists in documentation builds.
+///
+/// Crates sometimes provide shims of platform-specific or otherwise conditional items for
+/// rustdoc, gated with `#[cfg(doc)]` or `#[cfg(docsrs)]`. An item or one of its parents carrying
+/// a `#[cfg(...)]` attribute that can't hold without either of them is such a shim. Items
+/// annotated with `#[doc(cfg(...))]` only document their requirements, and aren't shims.
+///
+/// Only rustdoc sets `cfg(doc)`, so shims are only part of the compared crates if these are
+/// built with `--cfg docsrs`. Items gated with `cfg(doc)` alone never show up here.
+fn is_doc_shim(tcx: TyCtxt, def_id: DefId) -> bool {
+    fn requires_doc(item: &NestedMetaItem) -> bool {
+        let item = match item.meta_item() {
+            Some(item) => item,
+            None => return false,
+        };
+        let list = item.meta_item_list().unwrap_or_default();
+
+        if item.has_name(sym::all) {
+            list.iter().any(requires_doc)
+        } else if item.has_name(sym::any) {
+            !list.is_empty() && list.iter().all(requires_doc)
+        } else {
+            item.is_word() && (item.has_name(sym::doc) || item.has_name(Symbol::intern("docsrs")))
+        }
+    }
+
+    let mut current = Some(def_id);
+
+    while let Some(def_id) = current {
+        if tcx
+            .get_attrs(def_id, sym::cfg)
+            .filter_map(|attr| attr.meta_item_list())
+            .any(|items| items.iter().any(requires_doc))
+        {
+            return true;
+        }
+
+        current = tcx.opt_parent(def_id);
+    }
+
+    false
+}
+
+/// Get the unstable feature an item is gated behind, if any.
+///
+/// An item is gated if it or one of its parents carries a `#[cfg(...)]` or `#[doc(cfg(...))]`
+/// attribute requiring one of the given features. Negated requirements don't gate an item.
+fn unstable_feature(tcx: TyCtxt, def_id: DefId, features: &[String]) -> Option<String> {
+    fn required(items: &[NestedMetaItem], features: &[String]) -> Option<String> {
+        items
+            .iter()
+            .filter_map(NestedMetaItem::meta_item)
+            .find_map(|item| {
+                if item.has_name(sym::not) {
+                    None
+                } else if item.has_name(sym::feature) {
+                    let feature = item.value_str()?.to_string();
+                    features.contains(&feature).then(|| feature)
+                } else {
+                    required(item.meta_item_list()?, features)
+                }
+            })
+    }
+
+    let mut current = Some(def_id);
+
+    while let Some(def_id) = current {
+        let cfgs = tcx
+            .get_attrs(def_id, sym::cfg)
+            .filter_map(|attr| attr.meta_item_list());
+        let doc_cfgs = tcx
+            .get_attrs(def_id, sym::doc)
+            .filter_map(|attr| attr.meta_item_list())
+            .flatten()
+            .filter(|item| item.has_name(sym::cfg))
+            .filter_map(|item| item.meta_item_list().map(<[_]>::to_vec));
+
+        if let Some(feature) = cfgs
+            .chain(doc_cfgs)
+            .find_map(|items| required(&items, features))
+        {
+            return Some(feature);
+        }
+
+        current = tcx.opt_parent(def_id);
+    }
+
+    None
+}
+
+/// Get the helper attributes declared by a derive macro.
+///
+/// These are given in the macro's `#[proc_macro_derive(Name, attributes(helper, ...))]`
+/// attribute.
+fn derive_helpers(tcx: TyCtxt, def_id: DefId) -> Vec<Symbol> {
+    tcx.get_attrs(def_id, sym::proc_macro_derive)
+        .filter_map(|attr| attr.meta_item_list())
+        .flatten()
+        .filter(|item| item.has_name(sym::attributes))
+        .filter_map(|item| item.meta_item_list().map(<[_]>::to_vec))
+        .flatten()
+        .filter_map(|helper| helper.ident().map(|ident| ident.name))
+        .collect()
+}
+
+/// Given two derive macros, compare the helper attributes they declare.
+///
+/// Users annotate their types with the helper attributes, so removing one breaks them. A
+/// single removed helper replaced by a single added one is reported as a rename.
+fn diff_derive_helpers(changes: &mut ChangeSet, tcx: TyCtxt, old: DefId, new: DefId) {
+    let old_helpers = derive_helpers(tcx, old);
+    let new_helpers = derive_helpers(tcx, new);
+
+    let removed: Vec<_> = old_helpers
+        .iter()
+        .filter(|helper| !new_helpers.contains(helper))
+        .collect();
+    let added: Vec<_> = new_helpers
+        .iter()
+        .filter(|helper| !old_helpers.contains(helper))
+        .collect();
+
+    let renamed_to = match (removed.len(), added.as_slice()) {
+        (1, [added]) => Some(**added),
+        _ => None,
+    };
+
+    for name in removed {
+        changes.add_change(
+            ChangeType::DeriveHelperRemoved {
+                name: *name,
+                renamed_to,
+            },
+            old,
+            None,
+        );
+    }
 }
 
 /// Given two ADT items, perform structural checks.
@@ -470,6 +1551,10 @@ fn diff_adts(changes: &mut ChangeSet, id_mapping: &mut IdMapping, tcx: TyCtxt, o
                     }
                 }
 
+                // user code can only use struct literals and exhaustive patterns if it can name
+                // all fields and the field list isn't marked `#[non_exhaustive]`.
+                let constructible = total_public && !old.is_field_list_non_exhaustive();
+
                 if old.ctor_kind != new.ctor_kind {
                     let c = ChangeType::VariantStyleChanged {
                         now_struct: new.ctor_kind == CtorKind::Fictive,
@@ -486,6 +1571,14 @@ fn diff_adts(changes: &mut ChangeSet, id_mapping: &mut IdMapping, tcx: TyCtxt, o
                         (Some(o), Some(n)) => {
                             if o.vis == Public && n.vis == Public {
                                 id_mapping.add_subitem(old_def_id, o.did, n.did);
+
+                                if !tcx.is_doc_hidden(o.did) && tcx.is_doc_hidden(n.did) {
+                                    changes.add_change(
+                                        ChangeType::FieldMadePrivate { hidden: true },
+                                        old_def_id,
+                                        Some(tcx.def_span(n.did)),
+                                    );
+                                }
                             } else if o.vis != Public && n.vis == Public {
                                 changes.add_change(
                                     ChangeType::ItemMadePublic,
@@ -494,7 +1587,7 @@ fn diff_adts(changes: &mut ChangeSet, id_mapping: &mut IdMapping, tcx: TyCtxt, o
                                 );
                             } else if o.vis == Public && n.vis != Public {
                                 changes.add_change(
-                                    ChangeType::ItemMadePrivate,
+                                    ChangeType::FieldMadePrivate { hidden: false },
                                     old_def_id,
                                     Some(tcx.def_span(n.did)),
                                 );
@@ -513,6 +1606,7 @@ fn diff_adts(changes: &mut ChangeSet, id_mapping: &mut IdMapping, tcx: TyCtxt, o
                                 public: n.vis == Public,
                                 total_public,
                                 is_enum,
+                                constructible,
                             };
                             changes.add_change(c, old_def_id, Some(tcx.def_span(n.did)));
                         }
@@ -736,21 +1830,14 @@ fn diff_generics(
     let old_count = old_gen.own_counts();
     let new_count = new_gen.own_counts();
 
-    // NEEDSWORK: proper detection of what's going on here..
-    let was_async_trait_transformed = old_count.lifetimes > 0
-        && old_gen.params[old_count.lifetimes - 1].name.as_str() == "'async_trait";
+    // only a trait's own parameters include `Self`, its items (including generic associated
+    // types and `async_trait` methods) merely inherit it from their parent
+    let own_self = |gen: &Generics| gen.has_self && gen.parent.is_none();
 
-    let self_add = if old_gen.has_self && new_gen.has_self {
-        if was_async_trait_transformed {
-            // async_trait transformation undoes the self lifetime?
-            0
-        } else {
-            1
-        }
-    } else if !old_gen.has_self && !new_gen.has_self {
-        0
-    } else {
-        unreachable!()
+    let self_add = match (own_self(old_gen), own_self(new_gen)) {
+        (true, true) => 1,
+        (false, false) => 0,
+        _ => unreachable!(),
     };
 
     // TODO: we might need to track the number of parameters in the parent.
@@ -865,14 +1952,106 @@ fn diff_generics(
         }
     }
 
+    // const parameters are matched up by their position among the const parameters
+    let const_params = |gen: &Generics| -> Vec<GenericParamDef> {
+        gen.params
+            .iter()
+            .filter(|param| matches!(param.kind, GenericParamDefKind::Const { .. }))
+            .cloned()
+            .collect()
+    };
+
+    for (old_const, new_const) in const_params(old_gen).into_iter().zip(const_params(new_gen)) {
+        let old_default = render_const_param_default(tcx, &old_const);
+        let new_default = render_const_param_default(tcx, &new_const);
+
+        if old_default != new_default {
+            found.push(ChangeType::ConstParamDefaultChanged {
+                name: new_const.name,
+                old: old_default,
+                new: new_default,
+            });
+        }
+    }
+
     for change_type in found.drain(..) {
         changes.add_change(change_type, old, None);
     }
 }
 
+/// Render the default value of a const parameter, if it has one.
+fn render_const_param_default(tcx: TyCtxt, param: &GenericParamDef) -> Option<String> {
+    if !matches!(param.kind, GenericParamDefKind::Const { has_default: true }) {
+        return None;
+    }
+
+    let default = tcx
+        .const_param_default(param.def_id)
+        .eval(tcx, ParamEnv::reveal_all());
+
+    let rendered = match default.kind() {
+        ConstKind::Value(ValTree::Leaf(int)) => render_scalar(default.ty(), int),
+        _ => None,
+    };
+
+    Some(rendered.unwrap_or_else(|| default.to_string()))
+}
+
 // Below functions constitute the third pass of analysis, in which the types and/or trait bounds
 // of matching items are compared for changes.
 
+/// Run the analysis of a single item, recovering from panics of the compiler.
+///
+/// Some items trigger bugs in the parts of the compiler we use, which panic. Instead of aborting
+/// the whole analysis, such an item is reported as not analyzed, and the analysis moves on. The
+/// same goes for items exceeding their time limit, and all items once the time budget of the
+/// analysis is exhausted. The panic hook is expected to be kept quiet by a `QuietPanics` guard
+/// installed for the whole pass.
+fn recover_item<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    old: DefId,
+    analyze: impl FnOnce(&mut ChangeSet<'tcx>),
+) {
+    if let Some(reason) = analyze_recovering(changes, analyze) {
+        changes.add_change(ChangeType::AnalysisFailed { reason }, old, None);
+    }
+}
+
+/// Run the analysis of a single impl, recovering from panics of the compiler.
+///
+/// Works like `recover_item`, but creates the entry for the impl if the analysis didn't get to
+/// it before failing.
+fn recover_impl<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    impl_def_id: DefId,
+    analyze: impl FnOnce(&mut ChangeSet<'tcx>),
+) {
+    if let Some(reason) = analyze_recovering(changes, analyze) {
+        if !changes.has_change(impl_def_id) {
+            changes.new_change_impl(
+                impl_def_id,
+                tcx.def_path_str(impl_def_id),
+                tcx.def_span(impl_def_id),
+            );
+        }
+
+        changes.add_change(ChangeType::AnalysisFailed { reason }, impl_def_id, None);
+    }
+}
+
+/// Run an analysis within the time limits, returning the reason if it failed.
+fn analyze_recovering<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    analyze: impl FnOnce(&mut ChangeSet<'tcx>),
+) -> Option<String> {
+    if budget::exhausted() {
+        return Some("time budget exhausted".to_owned());
+    }
+
+    recover(|| budget::item(|| analyze(changes))).err()
+}
+
 /// Given two items, compare their types.
 fn diff_types<'tcx>(
     changes: &mut ChangeSet<'tcx>,
@@ -896,8 +2075,28 @@ fn diff_types<'tcx>(
     }
 
     match old {
-        // type aliases, consts and statics just need their type to be checked
-        Def(TyAlias | Const | Static(_), _) => {
+        // type aliases are reported with the types they expand to, as users only see the alias
+        Def(TyAlias, _) => {
+            let (old_ty, new_ty) = (tcx.type_of(old_def_id), tcx.type_of(new_def_id));
+
+            if let (&TyKind::Opaque(o_opaque, _), &TyKind::Opaque(n_opaque, _)) =
+                (old_ty.kind(), new_ty.kind())
+            {
+                diff_opaque_types(changes, id_mapping, tcx, old_def_id, o_opaque, n_opaque);
+            } else {
+                cmp_types(
+                    changes, id_mapping, tcx, old_def_id, new_def_id, old_ty, new_ty,
+                );
+
+                let change_type = ChangeType::TypeAliasChanged {
+                    old: render_ty(tcx, old_ty),
+                    new: render_ty(tcx, new_ty),
+                };
+                changes.replace_type_changes(old_def_id, change_type);
+            }
+        }
+        // consts and statics just need their type to be checked
+        Def(Const | Static(_), _) => {
             cmp_types(
                 changes,
                 id_mapping,
@@ -907,11 +2106,32 @@ fn diff_types<'tcx>(
                 tcx.type_of(old_def_id),
                 tcx.type_of(new_def_id),
             );
+
+            if matches!(old, Def(Const, _)) {
+                diff_const_values(changes, tcx, old_def_id, new_def_id);
+            }
         }
         // functions and methods require us to compare their signatures, not types
         Def(Fn | AssocFn, _) => {
-            let old_fn_sig = tcx.type_of(old_def_id).fn_sig(tcx);
-            let new_fn_sig = tcx.type_of(new_def_id).fn_sig(tcx);
+            let mut old_fn_sig = tcx.type_of(old_def_id).fn_sig(tcx);
+            let mut new_fn_sig = tcx.type_of(new_def_id).fn_sig(tcx);
+
+            let old_opaque = is_opaque_return(old_fn_sig);
+            let new_opaque = is_opaque_return(new_fn_sig);
+
+            // a named type and an opaque one can't be compared, so only the arguments are
+            if old_opaque != new_opaque {
+                changes.add_change(
+                    ChangeType::ReturnTypeOpacityChanged {
+                        now_opaque: new_opaque,
+                    },
+                    old_def_id,
+                    None,
+                );
+
+                old_fn_sig = without_output(tcx, old_fn_sig);
+                new_fn_sig = without_output(tcx, new_fn_sig);
+            }
 
             cmp_types(
                 changes,
@@ -922,6 +2142,8 @@ fn diff_types<'tcx>(
                 tcx.mk_fn_ptr(old_fn_sig),
                 tcx.mk_fn_ptr(new_fn_sig),
             );
+
+            diff_lifetime_bounds(changes, id_mapping, tcx, old_def_id, new_def_id);
         }
         // ADTs' types are compared field-wise
         Def(Struct | Enum | Union, _) => {
@@ -933,15 +2155,254 @@ fn diff_types<'tcx>(
                     cmp_types(changes, id_mapping, tcx, old_def_id, new_def_id, o_ty, n_ty);
                 }
             }
+
+            diff_layouts(changes, tcx, old_def_id, new_def_id);
+            diff_lifetime_bounds(changes, id_mapping, tcx, old_def_id, new_def_id);
         }
         // a trait definition has no type, so only it's trait bounds are compared
         Def(Trait, _) => {
             cmp_bounds(changes, id_mapping, tcx, old_def_id, new_def_id);
+            diff_lifetime_bounds(changes, id_mapping, tcx, old_def_id, new_def_id);
+            diff_upcasting(changes, id_mapping, tcx, old_def_id, new_def_id);
+        }
+        // generic associated types have where clauses of their own, besides their bounds
+        Def(AssocTy, _)
+            if id_mapping.get_trait_def(old_def_id).is_some()
+                && !tcx.generics_of(old_def_id).params.is_empty() =>
+        {
+            cmp_bounds(changes, id_mapping, tcx, old_def_id, new_def_id);
+            diff_gat_bounds(changes, id_mapping, tcx, old_def_id, new_def_id);
         }
         _ => (),
     }
 }
 
+/// Compare the lifetime bounds declared on two generic items.
+///
+/// Outlives obligations are deferred to region checking by the trait solver, so added bounds
+/// like `T: 'static` never show up when checking bounds, and are compared as written instead.
+/// The bounds inferred for the fields of ADTs already held for the old version, so only the
+/// explicit bounds of the new version are checked against them.
+fn diff_lifetime_bounds<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt<'tcx>,
+    old_def_id: DefId,
+    new_def_id: DefId,
+) {
+    info!(
+        "comparing lifetime bounds of {:?} / {:?}",
+        old_def_id, new_def_id
+    );
+
+    let is_outlives = |pred: &Predicate| {
+        matches!(
+            pred.kind().skip_binder(),
+            PredicateKind::TypeOutlives(_) | PredicateKind::RegionOutlives(_)
+        )
+    };
+
+    let trans = TranslationContext::target_new(tcx, id_mapping, true);
+    let orig_bounds: Vec<_> = tcx
+        .predicates_of(old_def_id)
+        .predicates
+        .iter()
+        .map(|&(pred, _)| pred)
+        .filter(is_outlives)
+        .collect();
+
+    if let Some(orig_bounds) = trans.translate_predicates(old_def_id, &orig_bounds) {
+        let target_bounds = tcx.explicit_predicates_of(new_def_id).predicates;
+
+        for &(pred, span) in target_bounds.iter().filter(|(p, _)| is_outlives(p)) {
+            if !orig_bounds.contains(&pred) {
+                changes.add_change(
+                    ChangeType::LifetimeBoundAdded { pred },
+                    old_def_id,
+                    Some(span),
+                );
+            }
+        }
+    }
+}
+
+/// Compare the bounds declared on a generic associated type of a trait.
+///
+/// Implementors have to satisfy added bounds, and users can no longer rely on removed ones, so
+/// both are breaking, like changes to the bounds of the trait itself.
+fn diff_gat_bounds<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt<'tcx>,
+    old_def_id: DefId,
+    new_def_id: DefId,
+) {
+    info!(
+        "comparing item bounds of {:?} / {:?}",
+        old_def_id, new_def_id
+    );
+
+    let trans = TranslationContext::target_new(tcx, id_mapping, true);
+    let orig_bounds: Vec<_> = tcx
+        .explicit_item_bounds(old_def_id)
+        .iter()
+        .map(|&(pred, _)| pred)
+        .collect();
+
+    if let Some(orig_bounds) = trans.translate_predicates(old_def_id, &orig_bounds) {
+        let target_bounds: Vec<_> = tcx
+            .explicit_item_bounds(new_def_id)
+            .iter()
+            .map(|&(pred, _)| pred)
+            .collect();
+
+        for pred in orig_bounds.iter().filter(|p| !target_bounds.contains(p)) {
+            let change_type = ChangeType::BoundsLoosened {
+                pred: *pred,
+                trait_def: true,
+            };
+            changes.add_change(change_type, old_def_id, None);
+        }
+
+        for pred in target_bounds.iter().filter(|p| !orig_bounds.contains(p)) {
+            let change_type = ChangeType::BoundsTightened {
+                pred: *pred,
+                trait_def: true,
+            };
+            changes.add_change(change_type, old_def_id, None);
+        }
+    }
+
+    add_unrelated(changes, tcx, old_def_id, trans.take_unmapped());
+}
+
+/// Compare the alignment and packing of ADTs whose layout is guaranteed by their `repr`.
+///
+/// Only types with a `repr(C)`, `repr(packed)` or `repr(align)` attribute in the old version
+/// have a layout users may rely on, and only non-generic ones have a layout to compute.
+fn diff_layouts(changes: &mut ChangeSet, tcx: TyCtxt, old_def_id: DefId, new_def_id: DefId) {
+    let old_repr = tcx.adt_def(old_def_id).repr();
+    let new_repr = tcx.adt_def(new_def_id).repr();
+
+    if !old_repr.c() && old_repr.pack.is_none() && old_repr.align.is_none() {
+        return;
+    }
+
+    let old_pack = old_repr.pack.map(|pack| pack.bytes());
+    let new_pack = new_repr.pack.map(|pack| pack.bytes());
+
+    if old_pack != new_pack {
+        let change_type = ChangeType::PackingChanged {
+            old: old_pack,
+            new: new_pack,
+        };
+        changes.add_change(change_type, old_def_id, None);
+    }
+
+    let align = |def_id| {
+        let ty = tcx.type_of(def_id);
+        tcx.layout_of(ParamEnv::reveal_all().and(ty))
+            .ok()
+            .map(|layout| layout.align.abi.bytes())
+    };
+
+    if let (Some(old_align), Some(new_align)) = (align(old_def_id), align(new_def_id)) {
+        if new_align < old_align {
+            let change_type = ChangeType::AlignmentReduced {
+                old: old_align,
+                new: new_align,
+            };
+            changes.add_change(change_type, old_def_id, None);
+        }
+    }
+}
+
+/// Given two constants of unchanged type, compare their values.
+///
+/// Values are only compared if both of them can be rendered.
+fn diff_const_values(changes: &mut ChangeSet, tcx: TyCtxt, old: DefId, new: DefId) {
+    if changes.item_breaking(old) {
+        return;
+    }
+
+    if let (Some(old_value), Some(new_value)) = (render_const(tcx, old), render_const(tcx, new)) {
+        if old_value != new_value {
+            let c = ChangeType::ConstValueChanged {
+                old: old_value,
+                new: new_value,
+            };
+            changes.add_change(c, old, None);
+        }
+    }
+}
+
+/// Evaluate a constant and render it's value, if it is of a primitive or string type.
+fn render_const(tcx: TyCtxt, def_id: DefId) -> Option<String> {
+    let ty = tcx.type_of(def_id);
+
+    match (tcx.const_eval_poly(def_id).ok()?, ty.kind()) {
+        (ConstValue::Scalar(Scalar::Int(int)), _) => render_scalar(ty, int),
+        (ConstValue::Slice { data, start, end }, TyKind::Ref(_, inner, _)) if inner.is_str() => {
+            let bytes = data
+                .inner()
+                .inspect_with_uninit_and_ptr_outside_interpreter(start..end);
+
+            Some(format!("{:?}", String::from_utf8_lossy(bytes)))
+        }
+        _ => None,
+    }
+}
+
+/// Render a scalar value of primitive type.
+fn render_scalar(ty: Ty, int: ScalarInt) -> Option<String> {
+    let bits = int.assert_bits(int.size());
+
+    match ty.kind() {
+        TyKind::Bool => Some(format!("{}", bits != 0)),
+        TyKind::Char => char::from_u32(bits as u32).map(|c| format!("{:?}", c)),
+        TyKind::Int(_) => Some(format!("{}", int.size().sign_extend(bits) as i128)),
+        TyKind::Uint(_) => Some(format!("{}", bits)),
+        TyKind::Float(FloatTy::F32) => Some(format!("{}", f32::from_bits(bits as u32))),
+        TyKind::Float(FloatTy::F64) => Some(format!("{}", f64::from_bits(bits as u64))),
+        _ => None,
+    }
+}
+
+/// Attribute a type change of an item to changes of the items whose translation failed.
+fn add_causes(changes: &mut ChangeSet, tcx: TyCtxt, orig_def_id: DefId, causes: Vec<DefId>) {
+    for cause in causes {
+        if cause != orig_def_id {
+            changes.add_cause(orig_def_id, tcx.def_path_str(cause));
+        }
+    }
+}
+
+/// Flag the verdict on an item as unreliable, as some of the items it refers to couldn't be
+/// related to their counterparts.
+fn add_unrelated(changes: &mut ChangeSet, tcx: TyCtxt, orig_def_id: DefId, unrelated: Vec<DefId>) {
+    for def_id in unrelated {
+        changes.add_unrelated(orig_def_id, tcx.def_path_str(def_id));
+    }
+}
+
+/// Check whether a function returns an opaque `impl Trait` type.
+fn is_opaque_return(sig: PolyFnSig) -> bool {
+    matches!(sig.skip_binder().output().kind(), TyKind::Opaque(..))
+}
+
+/// Replace the return type of a function signature with `()`.
+fn without_output<'tcx>(tcx: TyCtxt<'tcx>, sig: PolyFnSig<'tcx>) -> PolyFnSig<'tcx> {
+    sig.map_bound(|sig| {
+        tcx.mk_fn_sig(
+            sig.inputs().iter().copied(),
+            tcx.mk_unit(),
+            sig.c_variadic,
+            sig.unsafety,
+            sig.abi,
+        )
+    })
+}
+
 /// Compare two types and their trait bounds, possibly registering the resulting change.
 fn cmp_types<'tcx>(
     changes: &mut ChangeSet<'tcx>,
@@ -957,11 +2418,24 @@ fn cmp_types<'tcx>(
         orig_def_id, target_def_id, orig, target
     );
 
+    let array_lengths = changed_array_lengths(tcx, orig, target);
+
+    for &(old, new) in &array_lengths {
+        changes.add_change(
+            ChangeType::ArrayLengthChanged { old, new },
+            orig_def_id,
+            None,
+        );
+    }
+
     tcx.infer_ctxt().enter(|infcx| {
         let compcx = TypeComparisonContext::target_new(&infcx, id_mapping, false);
 
         let orig_substs = InternalSubsts::identity_for_item(infcx.tcx, target_def_id);
         let orig = compcx.forward_trans.translate_item_type(orig_def_id, orig);
+        let causes = compcx.forward_trans.take_failures();
+        let unrelated = compcx.forward_trans.take_unmapped();
+        add_unrelated(changes, tcx, orig_def_id, unrelated);
         // let orig = orig.subst(infcx.tcx, orig_substs);
 
         // functions need inference, other items can declare defaulted type parameters
@@ -975,52 +2449,410 @@ fn cmp_types<'tcx>(
         let target_param_env =
             EarlyBinder(infcx.tcx.param_env(target_def_id)).subst(infcx.tcx, target_substs);
 
-        if let Some(err) =
-            compcx.check_type_error(tcx, target_def_id, target_param_env, orig, target)
-        {
-            changes.add_change(ChangeType::TypeChanged { error: err }, orig_def_id, None);
-        } else {
-            // check the bounds if no type error has been found
-            compcx.check_bounds_bidirectional(
-                changes,
-                tcx,
-                orig_def_id,
-                target_def_id,
-                orig_substs,
-                target_substs,
-            );
+        if let Some(err) =
+            compcx.check_type_error(tcx, target_def_id, target_param_env, orig, target)
+        {
+            // changed array lengths have been reported along with their values already
+            let length_error = matches!(
+                err,
+                TypeError::FixedArraySize(_) | TypeError::ConstMismatch(_)
+            );
+
+            if array_lengths.is_empty() || !length_error {
+                let change = dependency_version_skew(tcx, id_mapping, &err)
+                    .unwrap_or(ChangeType::TypeChanged { error: err });
+                changes.add_change(change, orig_def_id, None);
+                add_causes(changes, tcx, orig_def_id, causes);
+            }
+        } else {
+            // check the bounds if no type error has been found
+            compcx.check_bounds_bidirectional(
+                changes,
+                tcx,
+                orig_def_id,
+                target_def_id,
+                orig_substs,
+                target_substs,
+            );
+        }
+    });
+}
+
+/// Find the arrays whose length differs between two types, along with their old and new length.
+///
+/// Lengths given as const expressions or named consts are evaluated. Arrays are matched up by
+/// their position in the types, so lengths are only compared if both types contain the same
+/// number of arrays.
+fn changed_array_lengths<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    orig: Ty<'tcx>,
+    target: Ty<'tcx>,
+) -> Vec<(u64, u64)> {
+    let lengths = |ty: Ty<'tcx>| -> Vec<Option<u64>> {
+        ty.walk()
+            .filter_map(|arg| match arg.unpack() {
+                GenericArgKind::Type(ty) => match *ty.kind() {
+                    TyKind::Array(_, len) => Some(len.try_eval_usize(tcx, ParamEnv::reveal_all())),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    };
+
+    let (orig_lengths, target_lengths) = (lengths(orig), lengths(target));
+
+    if orig_lengths.len() != target_lengths.len() {
+        return Vec::new();
+    }
+
+    orig_lengths
+        .into_iter()
+        .zip(target_lengths)
+        .filter_map(|lengths| match lengths {
+            (Some(old), Some(new)) if old != new => Some((old, new)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Compare the opaque types defined by two type aliases of the form `type Foo = impl Trait;`.
+///
+/// As the hidden types can't be named by users, only the bounds of the opaque types and the
+/// auto traits leaking through them are relevant.
+fn diff_opaque_types<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt<'tcx>,
+    alias_def_id: DefId,
+    o_opaque: DefId,
+    n_opaque: DefId,
+) {
+    info!("comparing opaque types {:?} / {:?}", o_opaque, n_opaque);
+
+    let trans = TranslationContext::target_new(tcx, id_mapping, false);
+    let orig_bounds: Vec<_> = tcx
+        .explicit_item_bounds(o_opaque)
+        .iter()
+        .map(|&(pred, _)| pred)
+        .collect();
+
+    if let Some(orig_bounds) = trans.translate_predicates(o_opaque, &orig_bounds) {
+        let target_bounds: Vec<_> = tcx
+            .explicit_item_bounds(n_opaque)
+            .iter()
+            .map(|&(pred, _)| pred)
+            .collect();
+
+        for pred in orig_bounds.iter().filter(|p| !target_bounds.contains(p)) {
+            let change_type = ChangeType::OpaqueBoundChanged {
+                bound: render_bound(tcx, *pred),
+                removed: true,
+            };
+            changes.add_change(change_type, alias_def_id, None);
+        }
+
+        for pred in target_bounds.iter().filter(|p| !orig_bounds.contains(p)) {
+            let change_type = ChangeType::OpaqueBoundChanged {
+                bound: render_bound(tcx, *pred),
+                removed: false,
+            };
+            changes.add_change(change_type, alias_def_id, None);
+        }
+    }
+
+    add_unrelated(changes, tcx, alias_def_id, trans.take_unmapped());
+
+    // auto traits are implemented by opaque types whenever their hidden types implement them
+    let auto_traits = [
+        tcx.get_diagnostic_item(sym::Send),
+        tcx.lang_items().sync_trait(),
+        tcx.lang_items().unpin_trait(),
+    ];
+
+    for trait_def_id in auto_traits.into_iter().flatten() {
+        let old_impl = opaque_implements(tcx, o_opaque, trait_def_id);
+        let new_impl = opaque_implements(tcx, n_opaque, trait_def_id);
+
+        if old_impl != new_impl {
+            let change_type = ChangeType::AutoTraitChanged {
+                name: tcx.item_name(trait_def_id),
+                now_impl: new_impl,
+            };
+            changes.add_change(change_type, alias_def_id, None);
+        }
+    }
+}
+
+/// Render a bound of an opaque type without mentioning the opaque type itself.
+fn render_bound<'tcx>(tcx: TyCtxt<'tcx>, pred: Predicate<'tcx>) -> String {
+    match pred.kind().skip_binder() {
+        PredicateKind::Trait(trait_pred) => tcx.def_path_str(trait_pred.def_id()),
+        PredicateKind::Projection(proj_pred) => format!(
+            "{} = {}",
+            tcx.def_path_str(proj_pred.projection_ty.item_def_id),
+            proj_pred.term
+        ),
+        _ => pred.to_string(),
+    }
+}
+
+/// Check whether an opaque type implements a trait without any further parameters.
+fn opaque_implements(tcx: TyCtxt<'_>, opaque_def_id: DefId, trait_def_id: DefId) -> bool {
+    tcx.infer_ctxt().enter(|infcx| {
+        let substs = InternalSubsts::identity_for_item(tcx, opaque_def_id);
+        let ty = tcx.mk_opaque(opaque_def_id, substs);
+
+        let mut bound_cx = BoundContext::new(&infcx, tcx.param_env(opaque_def_id));
+        bound_cx.register_trait_ref(TraitRef {
+            def_id: trait_def_id,
+            substs: tcx.mk_substs_trait(ty, &[]),
+        });
+        bound_cx.get_errors().is_none()
+    })
+}
+
+/// Compare the supertraits trait objects of two trait definitions can be upcast to.
+///
+/// Removing a supertrait is already reported as a loosened bound, but user code upcasting
+/// `dyn Trait` to `dyn Supertrait` is affected even if the supertrait is only removed
+/// further up the hierarchy, so the transitive supertraits are compared separately.
+fn diff_upcasting(
+    changes: &mut ChangeSet,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt,
+    old_def_id: DefId,
+    new_def_id: DefId,
+) {
+    // trait objects only exist for object safe traits
+    if !tcx.is_object_safe(old_def_id) || !tcx.is_object_safe(new_def_id) {
+        return;
+    }
+
+    let new_supertraits = supertraits(tcx, new_def_id);
+
+    for old_supertrait in supertraits(tcx, old_def_id) {
+        // removed supertraits are reported on their own
+        if let Some(new_supertrait) = id_mapping.get_new_id(old_supertrait) {
+            if !new_supertraits.contains(&new_supertrait) {
+                let change_type = ChangeType::UpcastingRemoved {
+                    supertrait: tcx.def_path_str(new_supertrait),
+                };
+                changes.add_change(change_type, old_def_id, None);
+            }
+        }
+    }
+}
+
+/// Collect the transitive supertraits of a trait that trait objects can be upcast to.
+fn supertraits(tcx: TyCtxt, def_id: DefId) -> Vec<DefId> {
+    let mut res = Vec::new();
+    let mut queue = VecDeque::from([def_id]);
+
+    while let Some(def_id) = queue.pop_front() {
+        for &(pred, _) in tcx.super_predicates_of(def_id).predicates {
+            if let Some(trait_pred) = pred.to_opt_poly_trait_pred() {
+                let supertrait = trait_pred.def_id();
+
+                if !tcx.trait_is_auto(supertrait) && !res.contains(&supertrait) {
+                    res.push(supertrait);
+                    queue.push_back(supertrait);
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Compare the trait bounds of two items, possibly registering the resulting change.
+fn cmp_bounds<'tcx>(
+    changes: &mut ChangeSet<'tcx>,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt<'tcx>,
+    orig_def_id: DefId,
+    target_def_id: DefId,
+) {
+    info!(
+        "comparing bounds of {:?} / {:?}",
+        orig_def_id, target_def_id
+    );
+
+    tcx.infer_ctxt().enter(|infcx| {
+        let compcx = TypeComparisonContext::target_new(&infcx, id_mapping, true);
+
+        let orig_substs = InternalSubsts::identity_for_item(infcx.tcx, target_def_id);
+        let target_substs = compcx.compute_target_default_substs(target_def_id);
+
+        compcx.check_bounds_bidirectional(
+            changes,
+            tcx,
+            orig_def_id,
+            target_def_id,
+            orig_substs,
+            target_substs,
+        );
+    })
+}
+
+// Below functions constitute the optional fifth pass of analysis, in which the bodies of matching
+// functions are compared.
+
+/// Given two functions, compare their bodies if their signatures are unchanged.
+fn diff_bodies(changes: &mut ChangeSet, tcx: TyCtxt, old: Res, new: Res) {
+    use rustc_hir::def::DefKind::*;
+
+    let (old_def_id, new_def_id) = match (old, new) {
+        (Def(Fn, o), Def(Fn, n)) | (Def(AssocFn, o), Def(AssocFn, n)) => (o, n),
+        _ => return,
+    };
+
+    if changes.item_breaking(old_def_id) || !changes.get_output(old_def_id) {
+        return;
+    }
+
+    if let (Some(old_hash), Some(new_hash)) =
+        (hash_body(tcx, old_def_id), hash_body(tcx, new_def_id))
+    {
+        if old_hash != new_hash {
+            changes.add_change(ChangeType::FnBodyChanged, old_def_id, None);
+        }
+    }
+}
+
+/// Hash the MIR of a function, if it is available.
+///
+/// The hash is computed from the pretty-printed MIR, with span comments and the name of the
+/// crate defining the function removed, so that it only changes if the body does.
+fn hash_body(tcx: TyCtxt, def_id: DefId) -> Option<u64> {
+    if !tcx.is_mir_available(def_id) {
+        return None;
+    }
+
+    let mut mir = Vec::new();
+    write_mir_fn(tcx, tcx.optimized_mir(def_id), &mut |_, _| Ok(()), &mut mir).ok()?;
+    let mir = String::from_utf8(mir).ok()?;
+
+    let crate_name = tcx.crate_name(def_id.krate);
+
+    let mut hasher = DefaultHasher::new();
+    for line in mir.lines() {
+        let line = line.split("//").next().unwrap_or("").trim_end();
+        strip_crate_prefix(line, crate_name.as_str()).hash(&mut hasher);
+    }
+
+    Some(hasher.finish())
+}
+
+/// Remove the leading segment naming the given crate from all paths in a line of MIR.
+///
+/// Only occurrences at the start of a path are removed, not ones that end a longer identifier,
+/// or follow another path segment, such as `other::old::Abc` for a crate `old`.
+fn strip_crate_prefix(line: &str, crate_name: &str) -> String {
+    let prefix = format!("{}::", crate_name);
+    let mut stripped = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(pos) = rest.find(&prefix) {
+        let starts_path = !rest[..pos]
+            .chars()
+            .last()
+            .or_else(|| stripped.chars().last())
+            .map_or(false, |c| c.is_alphanumeric() || c == '_' || c == ':');
+
+        stripped.push_str(&rest[..pos]);
+        if !starts_path {
+            stripped.push_str(&prefix);
+        }
+        rest = &rest[pos + prefix.len()..];
+    }
+
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Given two functions, check whether the new one can panic while the old one can't.
+fn diff_panics(changes: &mut ChangeSet, tcx: TyCtxt, old: Res, new: Res) {
+    use rustc_hir::def::DefKind::*;
+
+    let (old_def_id, new_def_id) = match (old, new) {
+        (Def(Fn, o), Def(Fn, n)) | (Def(AssocFn, o), Def(AssocFn, n)) => (o, n),
+        _ => return,
+    };
+
+    if changes.item_breaking(old_def_id) || !changes.get_output(old_def_id) {
+        return;
+    }
+
+    if !tcx.is_mir_available(old_def_id) || !tcx.is_mir_available(new_def_id) {
+        return;
+    }
+
+    if !may_panic(tcx, old_def_id) && may_panic(tcx, new_def_id) {
+        changes.add_change(ChangeType::PanicIntroduced, old_def_id, None);
+    }
+}
+
+/// Check whether a function calls a panicking function, directly or through other functions
+/// of the same crate.
+///
+/// This is a heuristic: explicit panics and calls to `unwrap` and `expect` on `Option` and
+/// `Result` are found, while implicit panics like overflow and bounds checks, as well as calls
+/// through trait methods, function pointers, or into other crates, are not.
+fn may_panic(tcx: TyCtxt, def_id: DefId) -> bool {
+    let lang_items = tcx.lang_items();
+    let panic_fns: Vec<DefId> = [
+        lang_items.panic_fn(),
+        lang_items.panic_fmt(),
+        lang_items.panic_display(),
+        lang_items.panic_str(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let mut visited = HashSet::new();
+    let mut queue = vec![def_id];
+
+    while let Some(fn_def_id) = queue.pop() {
+        if !visited.insert(fn_def_id) || !tcx.is_mir_available(fn_def_id) {
+            continue;
         }
-    });
-}
 
-/// Compare the trait bounds of two items, possibly registering the resulting change.
-fn cmp_bounds<'tcx>(
-    changes: &mut ChangeSet<'tcx>,
-    id_mapping: &IdMapping,
-    tcx: TyCtxt<'tcx>,
-    orig_def_id: DefId,
-    target_def_id: DefId,
-) {
-    info!(
-        "comparing bounds of {:?} / {:?}",
-        orig_def_id, target_def_id
-    );
+        for block in tcx.optimized_mir(fn_def_id).basic_blocks() {
+            if let TerminatorKind::Call { ref func, .. } = block.terminator().kind {
+                if let Some((callee, _)) = func.const_fn_def() {
+                    if panic_fns.contains(&callee) || is_unwrap(tcx, callee) {
+                        debug!("{:?} panics through {:?}", fn_def_id, callee);
+                        return true;
+                    }
 
-    tcx.infer_ctxt().enter(|infcx| {
-        let compcx = TypeComparisonContext::target_new(&infcx, id_mapping, true);
+                    if callee.krate == def_id.krate {
+                        queue.push(callee);
+                    }
+                }
+            }
+        }
+    }
 
-        let orig_substs = InternalSubsts::identity_for_item(infcx.tcx, target_def_id);
-        let target_substs = compcx.compute_target_default_substs(target_def_id);
+    false
+}
 
-        compcx.check_bounds_bidirectional(
-            changes,
-            tcx,
-            orig_def_id,
-            target_def_id,
-            orig_substs,
-            target_substs,
-        );
-    })
+/// Check whether a function is one of the panicking accessors of `Option` or `Result`.
+fn is_unwrap(tcx: TyCtxt, def_id: DefId) -> bool {
+    if !matches!(
+        tcx.opt_item_name(def_id).as_ref().map(Symbol::as_str),
+        Some("unwrap" | "expect" | "unwrap_err" | "expect_err")
+    ) {
+        return false;
+    }
+
+    tcx.impl_of_method(def_id)
+        .and_then(|impl_def_id| tcx.type_of(impl_def_id).ty_adt_def())
+        .map_or(false, |adt_def| {
+            tcx.is_diagnostic_item(sym::Option, adt_def.did())
+                || tcx.is_diagnostic_item(sym::Result, adt_def.did())
+        })
 }
 
 // Below functions constitute the fourth pass of analysis, in which impls are matched up based on
@@ -1037,6 +2869,9 @@ fn diff_inherent_impls<'tcx>(
     let to_new = TranslationContext::target_new(tcx, id_mapping, false);
     let to_old = TranslationContext::target_old(tcx, id_mapping, false);
 
+    // old impls whose bounds have been tightened, along with the added bounds
+    let mut tightened_impls = BTreeMap::new();
+
     for (orig_item, orig_impls) in id_mapping.inherent_impls() {
         // determine where the item comes from
         let (forward_trans, err_type) = if id_mapping.in_old_crate(orig_item.parent_def_id) {
@@ -1064,47 +2899,114 @@ fn diff_inherent_impls<'tcx>(
                 parent_output && orig_assoc_item.visibility(tcx) == Public,
             );
 
-            // ... determine the set of target impls that serve as candidates
-            let target_impls = if let Some(impls) = forward_trans
-                .translate_inherent_entry(orig_item)
-                .and_then(|item| id_mapping.get_inherent_impls(&item))
-            {
-                impls
-            } else {
-                changes.add_change(err_type.clone(), orig_item_def_id, None);
-                continue;
-            };
+            recover_item(changes, orig_item_def_id, |changes| {
+                // ... determine the set of target impls that serve as candidates
+                let target_impls = if let Some(impls) = forward_trans
+                    .translate_inherent_entry(orig_item)
+                    .and_then(|item| id_mapping.get_inherent_impls(&item))
+                {
+                    impls
+                } else {
+                    changes.add_change(err_type.clone(), orig_item_def_id, None);
+                    add_shadowed_traits(changes, id_mapping, tcx, orig_item, orig_item_def_id);
+                    return;
+                };
 
-            // if any of the candidates matches, the item is compatible across versions
-            let match_found =
-                target_impls
-                    .iter()
-                    .any(|&(target_impl_def_id, target_item_def_id)| {
-                        let target_assoc_item = tcx.associated_item(target_item_def_id);
+                // if any of the candidates matches, the item is compatible across versions
+                let match_found =
+                    target_impls
+                        .iter()
+                        .any(|&(target_impl_def_id, target_item_def_id)| {
+                            let target_assoc_item = tcx.associated_item(target_item_def_id);
 
-                        if parent_output && target_assoc_item.visibility(tcx) == Public {
-                            changes.set_output(orig_item.parent_def_id);
-                        }
+                            if parent_output && target_assoc_item.visibility(tcx) == Public {
+                                changes.set_output(orig_item.parent_def_id);
+                            }
 
-                        match_inherent_impl(
-                            changes,
-                            id_mapping,
-                            tcx,
-                            orig_impl_def_id,
-                            target_impl_def_id,
-                            *orig_assoc_item,
-                            *target_assoc_item,
-                        )
-                    });
-
-            // otherwise, it has been essentially added/removed
-            if !match_found {
-                changes.add_change(err_type.clone(), orig_item_def_id, None);
-            }
+                            match_inherent_impl(
+                                changes,
+                                id_mapping,
+                                tcx,
+                                orig_impl_def_id,
+                                target_impl_def_id,
+                                *orig_assoc_item,
+                                *target_assoc_item,
+                                &mut tightened_impls,
+                            )
+                        });
+
+                // otherwise, it has been essentially added/removed
+                if !match_found {
+                    changes.add_change(err_type.clone(), orig_item_def_id, None);
+                    add_shadowed_traits(changes, id_mapping, tcx, orig_item, orig_item_def_id);
+                }
+            });
+        }
+    }
+
+    // tightened bounds affect all items in an impl alike, so they are reported for the impl
+    for (old_impl_def_id, preds) in tightened_impls {
+        changes.new_change_impl(
+            old_impl_def_id,
+            tcx.def_path_str(old_impl_def_id),
+            tcx.def_span(old_impl_def_id),
+        );
+
+        for pred in preds {
+            changes.add_change(
+                ChangeType::InherentImplTightened { pred },
+                old_impl_def_id,
+                None,
+            );
         }
     }
 }
 
+/// Record the traits whose methods an added inherent method takes precedence over.
+///
+/// Only the traits implemented for the type in its own crate, including derived ones, are
+/// considered, as these are the ones users are most likely to call methods of.
+fn add_shadowed_traits(
+    changes: &mut ChangeSet,
+    id_mapping: &IdMapping,
+    tcx: TyCtxt,
+    item: &InherentEntry,
+    item_def_id: DefId,
+) {
+    use rustc_middle::ty::AssocKind;
+
+    if item.kind != AssocKind::Fn || !id_mapping.in_new_crate(item.parent_def_id) {
+        return;
+    }
+
+    let implemented_for_type = |impl_def_id: DefId| match *tcx.type_of(impl_def_id).kind() {
+        TyKind::Adt(adt_def, _) => adt_def.did() == item.parent_def_id,
+        _ => false,
+    };
+
+    let cstore = CStore::from_tcx(tcx);
+    let mut traits: Vec<_> = cstore
+        .trait_impls_in_crate_untracked(id_mapping.get_new_crate())
+        .filter(|&(_, impl_def_id, _)| implemented_for_type(impl_def_id))
+        .map(|(trait_def_id, _, _)| trait_def_id)
+        .filter(|&trait_def_id| {
+            tcx.associated_items(trait_def_id)
+                .filter_by_name_unhygienic(item.name)
+                .any(|trait_item| trait_item.kind == AssocKind::Fn)
+        })
+        .collect();
+
+    traits.sort();
+    traits.dedup();
+
+    for trait_def_id in traits {
+        let change_type = ChangeType::TraitMethodShadowed {
+            trait_path: tcx.def_path_str(trait_def_id),
+        };
+        changes.add_change(change_type, item_def_id, None);
+    }
+}
+
 // There doesn't seem to be a way to get the visibility of impl traits from rustc
 // (CC rust-lang/rust#61464), so we implement the logic here.  Note that this implementation is far
 // from perfect and will cause false positives in some cases (see comment in the inner function).
@@ -1151,6 +3053,102 @@ fn is_impl_trait_public<'tcx>(tcx: TyCtxt<'tcx>, impl_def_id: DefId) -> bool {
     is_public
 }
 
+/// Check whether a trait impl could overlap with impls downstream crates are allowed to write.
+///
+/// The orphan rules allow downstream crates to implement a trait for (or with) their own types,
+/// so an impl with an input type that is a type parameter not covered by a non-fundamental type
+/// (like `T`, `&T` or `Box<T>`) can apply to the same types as a downstream impl.
+///
+/// Impls of foreign traits need one of the crate's own types as an input type, and the orphan
+/// rules only allow uncovered type parameters after the first one, so only those are considered.
+/// An impl like `impl From<Local> for Vec<u8>` can't overlap with downstream impls, while
+/// `impl<T> PartialEq<T> for Local` can.
+fn is_impl_possibly_conflicting(tcx: TyCtxt, impl_def_id: DefId) -> bool {
+    fn is_uncovered_param(ty: Ty) -> bool {
+        match ty.kind() {
+            TyKind::Param(_) => true,
+            TyKind::Ref(_, t, _) => is_uncovered_param(*t),
+            TyKind::Adt(def, substs) if def.is_fundamental() => {
+                substs.types().any(is_uncovered_param)
+            }
+            _ => false,
+        }
+    }
+
+    fn is_local(ty: Ty, krate: CrateNum) -> bool {
+        match ty.kind() {
+            TyKind::Ref(_, t, _) => is_local(*t, krate),
+            TyKind::Adt(def, substs) if def.is_fundamental() => {
+                substs.types().any(|t| is_local(t, krate))
+            }
+            TyKind::Adt(def, _) => def.did().krate == krate,
+            TyKind::Foreign(def_id) => def_id.krate == krate,
+            _ => false,
+        }
+    }
+
+    let trait_ref = match tcx.impl_trait_ref(impl_def_id) {
+        Some(trait_ref) => trait_ref,
+        None => return false,
+    };
+
+    if trait_ref.def_id.krate == impl_def_id.krate {
+        trait_ref.substs.types().any(is_uncovered_param)
+    } else {
+        trait_ref
+            .substs
+            .types()
+            .skip_while(|&t| !is_local(t, impl_def_id.krate))
+            .any(is_uncovered_param)
+    }
+}
+
+/// Check whether a new impl is for a type that already existed in the old crate version.
+fn is_impl_for_existing_type(tcx: TyCtxt, id_mapping: &IdMapping, impl_def_id: DefId) -> bool {
+    match *tcx.type_of(impl_def_id).kind() {
+        TyKind::Adt(adt_def, _) => id_mapping.get_old_id(adt_def.did()).is_some(),
+        _ => false,
+    }
+}
+
+/// Check whether a new trait impl can break type inference in user code.
+///
+/// User code relying on a single impl of a trait to infer a type stops compiling once a second
+/// one appears. This is the case for blanket impls, and for impls of the standard conversion and
+/// comparison traits for types not defined in the crate, which user code commonly converts to.
+/// Returns the kind of calls affected, if any.
+fn impl_inference_breakage(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    trait_def_id: DefId,
+    impl_def_id: DefId,
+) -> Option<&'static str> {
+    const INFERRED_TRAITS: [(Symbol, &str); 5] = [
+        (sym::From, "`.into()` calls and the `?` operator"),
+        (sym::TryFrom, "`.try_into()` calls"),
+        (sym::FromIterator, "`.collect()` calls"),
+        (sym::AsRef, "`.as_ref()` calls"),
+        (sym::AsMut, "`.as_mut()` calls"),
+    ];
+
+    match *tcx.type_of(impl_def_id).kind() {
+        TyKind::Param(_) => return Some("calls of generic functions bounded by the trait"),
+        TyKind::Adt(adt_def, _) if adt_def.did().krate == id_mapping.get_new_crate() => {
+            return None;
+        }
+        _ => (),
+    }
+
+    if Some(trait_def_id) == tcx.lang_items().eq_trait() {
+        return Some("comparisons using `==`");
+    }
+
+    INFERRED_TRAITS
+        .iter()
+        .find(|(name, _)| tcx.is_diagnostic_item(*name, trait_def_id))
+        .map(|(_, calls)| *calls)
+}
+
 /// Compare the implementations of all matching traits.
 fn diff_trait_impls<'tcx>(
     changes: &mut ChangeSet<'tcx>,
@@ -1168,6 +3166,15 @@ fn diff_trait_impls<'tcx>(
     let structural_teq_def_id = tcx.require_lang_item(LangItem::StructuralTeq, None);
     let structural_peq_def_id = tcx.require_lang_item(LangItem::StructuralPeq, None);
     let structural_trait_def_ids = [structural_peq_def_id, structural_teq_def_id];
+    let lang_items = tcx.lang_items();
+    let call_trait_def_ids = [
+        lang_items.fn_trait(),
+        lang_items.fn_mut_trait(),
+        lang_items.fn_once_trait(),
+    ];
+
+    // impls of the `Fn*` traits whose call signature changed are reported with the old ones
+    let mut changed_call_impls = HashSet::new();
 
     let cstore = CStore::from_tcx(tcx);
     for (old_trait_def_id, old_impl_def_id, _) in
@@ -1177,18 +3184,83 @@ fn diff_trait_impls<'tcx>(
             continue;
         }
 
-        if !to_new.can_translate(old_trait_def_id) || !is_impl_trait_public(tcx, old_impl_def_id) {
+        if !to_new.can_translate(old_trait_def_id)
+            || !is_impl_trait_public(tcx, old_impl_def_id)
+            || is_doc_shim(tcx, old_impl_def_id)
+        {
             continue;
         }
 
-        if !match_trait_impl(tcx, &to_new, old_impl_def_id) {
-            changes.new_change_impl(
-                old_impl_def_id,
-                tcx.def_path_str(old_impl_def_id),
-                tcx.def_span(old_impl_def_id),
-            );
-            changes.add_change(ChangeType::TraitImplTightened, old_impl_def_id, None);
-        }
+        recover_impl(changes, tcx, old_impl_def_id, |changes| {
+            if !match_trait_impl(tcx, &to_new, old_impl_def_id) {
+                changes.new_change_impl(
+                    old_impl_def_id,
+                    tcx.def_path_str(old_impl_def_id),
+                    tcx.def_span(old_impl_def_id),
+                );
+
+                // the unsizing traits are never named in user code, only relied on by coercions
+                let change_type = if Some(old_trait_def_id) == lang_items.coerce_unsized_trait() {
+                    ChangeType::UnsizingImplRemoved { dispatch: false }
+                } else if Some(old_trait_def_id) == lang_items.dispatch_from_dyn_trait() {
+                    ChangeType::UnsizingImplRemoved { dispatch: true }
+                } else if call_trait_def_ids.contains(&Some(old_trait_def_id)) {
+                    let trait_name = tcx.item_name(old_trait_def_id);
+
+                    match find_call_impl(tcx, id_mapping, old_trait_def_id, old_impl_def_id) {
+                        Some(new_impl_def_id) => {
+                            changed_call_impls.insert(new_impl_def_id);
+
+                            let old_trait_ref = tcx.impl_trait_ref(old_impl_def_id).unwrap();
+                            let old_trait_ref =
+                                to_new.translate_trait_ref(old_impl_def_id, &old_trait_ref);
+                            let new_trait_ref = tcx.impl_trait_ref(new_impl_def_id).unwrap();
+
+                            ChangeType::CallSignatureChanged {
+                                trait_name,
+                                old: old_trait_ref.substs.type_at(1),
+                                new: new_trait_ref.substs.type_at(1),
+                            }
+                        }
+                        None => ChangeType::CallImplRemoved { trait_name },
+                    }
+                } else {
+                    ChangeType::TraitImplTightened
+                };
+                changes.add_change(change_type, old_impl_def_id, None);
+            } else {
+                let mut impl_changes = diff_impl_assoc_types(tcx, &to_new, old_impl_def_id);
+
+                if let Some(new_impl_def_id) = select_trait_impl(tcx, &to_new, old_impl_def_id) {
+                    let old_const = tcx.impl_constness(old_impl_def_id) == Constness::Const;
+                    let new_const = tcx.impl_constness(new_impl_def_id) == Constness::Const;
+
+                    if old_const != new_const {
+                        impl_changes.push(ChangeType::TraitImplConstChanged {
+                            now_const: new_const,
+                        });
+                    }
+
+                    impl_changes.extend(diff_impl_defaultness(
+                        tcx,
+                        old_impl_def_id,
+                        new_impl_def_id,
+                    ));
+                }
+
+                if !impl_changes.is_empty() {
+                    changes.new_change_impl(
+                        old_impl_def_id,
+                        tcx.def_path_str(old_impl_def_id),
+                        tcx.def_span(old_impl_def_id),
+                    );
+
+                    for change_type in impl_changes {
+                        changes.add_change(change_type, old_impl_def_id, None);
+                    }
+                }
+            }
+        });
     }
 
     for (new_trait_def_id, new_impl_def_id, _) in
@@ -1198,23 +3270,203 @@ fn diff_trait_impls<'tcx>(
             continue;
         }
 
-        if !to_old.can_translate(new_trait_def_id) || !is_impl_trait_public(tcx, new_impl_def_id) {
+        if !to_old.can_translate(new_trait_def_id)
+            || !is_impl_trait_public(tcx, new_impl_def_id)
+            || is_doc_shim(tcx, new_impl_def_id)
+            || changed_call_impls.contains(&new_impl_def_id)
+        {
             continue;
         }
 
-        if !match_trait_impl(tcx, &to_old, new_impl_def_id) {
-            changes.new_change_impl(
-                new_impl_def_id,
-                tcx.def_path_str(new_impl_def_id),
-                tcx.def_span(new_impl_def_id),
-            );
-            changes.add_change(ChangeType::TraitImplLoosened, new_impl_def_id, None);
+        recover_impl(changes, tcx, new_impl_def_id, |changes| {
+            if !match_trait_impl(tcx, &to_old, new_impl_def_id) {
+                changes.new_change_impl(
+                    new_impl_def_id,
+                    tcx.def_path_str(new_impl_def_id),
+                    tcx.def_span(new_impl_def_id),
+                );
+                changes.add_change(ChangeType::TraitImplLoosened, new_impl_def_id, None);
+
+                if is_impl_possibly_conflicting(tcx, new_impl_def_id) {
+                    changes.add_change(
+                        ChangeType::TraitImplPossiblyConflicting,
+                        new_impl_def_id,
+                        None,
+                    );
+                }
+
+                // autoderef makes the methods of the target type callable on existing types
+                let deref_mutable = if Some(new_trait_def_id) == lang_items.deref_trait() {
+                    Some(false)
+                } else if Some(new_trait_def_id) == lang_items.deref_mut_trait() {
+                    Some(true)
+                } else {
+                    None
+                };
+
+                if let Some(mutable) = deref_mutable {
+                    if is_impl_for_existing_type(tcx, id_mapping, new_impl_def_id) {
+                        let change_type = ChangeType::DerefImplAdded { mutable };
+                        changes.add_change(change_type, new_impl_def_id, None);
+                    }
+                }
+
+                if let Some(calls) =
+                    impl_inference_breakage(tcx, id_mapping, new_trait_def_id, new_impl_def_id)
+                {
+                    let change_type = ChangeType::TraitImplBreaksInference { calls };
+                    changes.add_change(change_type, new_impl_def_id, None);
+                }
+            }
+        });
+    }
+}
+
+/// Compare the `default` markers of the items of two matching trait impls.
+///
+/// Items of a `default impl` are specializable as if they were marked themselves.
+fn diff_impl_defaultness<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    old_impl_def_id: DefId,
+    new_impl_def_id: DefId,
+) -> Vec<ChangeType<'tcx>> {
+    let is_default = |impl_def_id, item_def_id| {
+        tcx.impl_defaultness(impl_def_id).is_default()
+            || tcx.impl_defaultness(item_def_id).is_default()
+    };
+
+    let mut res = Vec::new();
+
+    for old_item in tcx.associated_items(old_impl_def_id).in_definition_order() {
+        let new_item = tcx
+            .associated_items(new_impl_def_id)
+            .filter_by_name_unhygienic(old_item.name)
+            .find(|i| i.kind == old_item.kind);
+
+        if let Some(new_item) = new_item {
+            let old_default = is_default(old_impl_def_id, old_item.def_id);
+            let new_default = is_default(new_impl_def_id, new_item.def_id);
+
+            if old_default != new_default {
+                res.push(ChangeType::DefaultnessChanged {
+                    name: old_item.name,
+                    now_default: new_default,
+                });
+            }
         }
     }
+
+    res
+}
+
+/// Find the impl of a `Fn*` trait for the same type in the new crate as an old impl.
+///
+/// Only used for old impls that don't match in the new crate, so that an impl found has a
+/// different call signature.
+fn find_call_impl(
+    tcx: TyCtxt,
+    id_mapping: &IdMapping,
+    trait_def_id: DefId,
+    old_impl_def_id: DefId,
+) -> Option<DefId> {
+    let self_adt = |impl_def_id| tcx.type_of(impl_def_id).ty_adt_def().map(|adt| adt.did());
+    let new_adt = id_mapping.get_new_id(self_adt(old_impl_def_id)?)?;
+
+    CStore::from_tcx(tcx)
+        .trait_impls_in_crate_untracked(id_mapping.get_new_crate())
+        .into_iter()
+        .find(|&(t, impl_def_id, _)| t == trait_def_id && self_adt(impl_def_id) == Some(new_adt))
+        .map(|(_, impl_def_id, _)| impl_def_id)
+}
+
+/// Compare the associated types of an original trait impl to the ones the target crate provides
+/// for the same trait reference.
+///
+/// NB: The trait impl is expected to have been matched in the target crate already.
+fn diff_impl_assoc_types<'a, 'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trans: &TranslationContext<'a, 'tcx>,
+    orig_def_id: DefId,
+) -> Vec<ChangeType<'tcx>> {
+    use rustc_middle::ty::{AssocKind, TypeVisitable};
+
+    tcx.infer_ctxt().enter(|infcx| {
+        let mut res = Vec::new();
+
+        let param_env =
+            if let Some(env) = trans.translate_param_env(orig_def_id, tcx.param_env(orig_def_id)) {
+                env
+            } else {
+                return res;
+            };
+
+        let orig = tcx.impl_trait_ref(orig_def_id).unwrap();
+        let target = trans.translate_trait_ref(orig_def_id, &orig);
+
+        for item_def_id in tcx.associated_item_def_ids(orig_def_id).iter() {
+            let item = tcx.associated_item(*item_def_id);
+
+            if item.kind != AssocKind::Type {
+                continue;
+            }
+
+            let target_item = tcx
+                .associated_items(target.def_id)
+                .filter_by_name_unhygienic(item.name)
+                .find(|i| i.kind == AssocKind::Type);
+
+            let target_item = if let Some(target_item) = target_item {
+                target_item
+            } else {
+                continue;
+            };
+
+            // generic associated types are projected with their own parameters appended to the
+            // trait's, which are the impl item's as the parameters themselves aren't translated
+            let own_substs = &InternalSubsts::identity_for_item(tcx, item.def_id)
+                [tcx.generics_of(item.def_id).parent_count..];
+
+            if own_substs.len() != tcx.generics_of(target_item.def_id).params.len() {
+                continue;
+            }
+
+            let orig_ty = trans.translate_item_type(item.def_id, tcx.type_of(item.def_id));
+
+            let mut bound_cx = BoundContext::new(&infcx, param_env);
+            let target_ty = bound_cx.normalize(tcx.mk_projection(
+                target_item.def_id,
+                tcx.mk_substs(target.substs.iter().chain(own_substs.iter().copied())),
+            ));
+
+            if bound_cx.get_errors().is_some() {
+                continue;
+            }
+
+            let target_ty = infcx.resolve_vars_if_possible(target_ty);
+
+            if target_ty.needs_infer() || orig_ty.needs_infer() {
+                continue;
+            }
+
+            if infcx.can_eq(param_env, orig_ty, target_ty).is_err() {
+                res.push(ChangeType::TraitImplAssocTypeChanged {
+                    name: item.name,
+                    old: orig_ty,
+                    new: target_ty,
+                });
+            }
+        }
+
+        res
+    })
 }
 
 /// Compare an item pair in two inherent implementations and indicate whether the target one is
 /// compatible with the original one.
+///
+/// Impls whose bounds have been tightened still match, and are recorded in `tightened_impls`
+/// along with the added bounds.
+#[allow(clippy::too_many_arguments)]
 fn match_inherent_impl<'tcx>(
     changes: &mut ChangeSet<'tcx>,
     id_mapping: &IdMapping,
@@ -1223,6 +3475,7 @@ fn match_inherent_impl<'tcx>(
     target_impl_def_id: DefId,
     orig_item: AssocItem,
     target_item: AssocItem,
+    tightened_impls: &mut BTreeMap<DefId, Vec<Predicate<'tcx>>>,
 ) -> bool {
     use rustc_middle::ty::AssocKind;
 
@@ -1278,9 +3531,15 @@ fn match_inherent_impl<'tcx>(
         if let Some(orig_param_env) = orig_param_env {
             let errors =
                 compcx.check_bounds_error(tcx, orig_param_env, target_impl_def_id, target_substs);
-            if errors.is_some() {
-                // The bounds on the impls have been tightened - no impl match.
-                return false;
+            if let Some(errors) = errors {
+                if !register_errors {
+                    // The bounds on the impls have been loosened - no impl match.
+                    return false;
+                }
+
+                // The bounds on the impls have been tightened, which is reported for the impl
+                // as a whole, while its items are still compared.
+                tightened_impls.entry(orig_impl_def_id).or_insert(errors);
             }
         } else {
             // The bounds could not have been translated - no impl match.
@@ -1302,28 +3561,54 @@ fn match_inherent_impl<'tcx>(
             ),
             (AssocKind::Fn, AssocKind::Fn) => {
                 diff_method(changes, tcx, orig_item, target_item);
-                let orig_sig = infcx.tcx.type_of(orig_item_def_id).fn_sig(tcx);
-                let target_sig = infcx.tcx.type_of(target_item_def_id).fn_sig(tcx);
+                let mut orig_sig = infcx.tcx.type_of(orig_item_def_id).fn_sig(tcx);
+                let mut target_sig = infcx.tcx.type_of(target_item_def_id).fn_sig(tcx);
+
+                // as for toplevel functions, only the arguments are compared in this case
+                let target_opaque = is_opaque_return(target_sig);
+                if is_opaque_return(orig_sig) != target_opaque {
+                    changes.add_change(
+                        ChangeType::ReturnTypeOpacityChanged {
+                            now_opaque: target_opaque,
+                        },
+                        orig_item_def_id,
+                        None,
+                    );
+
+                    orig_sig = without_output(tcx, orig_sig);
+                    target_sig = without_output(tcx, target_sig);
+                }
+
                 (tcx.mk_fn_ptr(orig_sig), tcx.mk_fn_ptr(target_sig))
             }
             _ => unreachable!(),
         };
 
+        // only attribute failures in translating the item type itself
+        compcx.forward_trans.take_failures();
+        compcx.forward_trans.take_unmapped();
         let orig = compcx
             .forward_trans
             .translate_item_type(orig_item_def_id, orig);
+        let causes = compcx.forward_trans.take_failures();
+        let unrelated = compcx.forward_trans.take_unmapped();
+        add_unrelated(changes, tcx, orig_item_def_id, unrelated);
         let target = EarlyBinder(target).subst(infcx.tcx, target_substs);
 
         let error =
             compcx.check_type_error(tcx, target_item_def_id, target_param_env, orig, target);
 
         if let Some(err) = error {
-            changes.add_change(
-                ChangeType::TypeChanged { error: err },
-                orig_item_def_id,
-                None,
-            );
+            let change = dependency_version_skew(tcx, id_mapping, &err)
+                .unwrap_or(ChangeType::TypeChanged { error: err });
+            changes.add_change(change, orig_item_def_id, None);
+            add_causes(changes, tcx, orig_item_def_id, causes);
         } else {
+            // like toplevel consts, associated consts can still change their value
+            if orig_item.kind == AssocKind::Const {
+                diff_const_values(changes, tcx, orig_item_def_id, target_item_def_id);
+            }
+
             // check the bounds if no type error has been found
             compcx.check_bounds_bidirectional(
                 changes,
@@ -1339,6 +3624,37 @@ fn match_inherent_impl<'tcx>(
     })
 }
 
+/// Find the impl in the target crate providing the trait reference of an original impl.
+///
+/// Returns `None` if the trait reference isn't provided by a single impl, such as when it's
+/// implied by the impl's bounds.
+fn select_trait_impl<'a, 'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trans: &TranslationContext<'a, 'tcx>,
+    orig_def_id: DefId,
+) -> Option<DefId> {
+    use rustc_infer::traits::Obligation;
+    use rustc_middle::traits::{ImplSource, ObligationCause};
+    use rustc_trait_selection::traits::SelectionContext;
+
+    tcx.infer_ctxt().enter(|infcx| {
+        let param_env = trans.translate_param_env(orig_def_id, tcx.param_env(orig_def_id))?;
+        let orig = tcx.impl_trait_ref(orig_def_id).unwrap();
+        let target = trans.translate_trait_ref(orig_def_id, &orig);
+
+        let obligation = Obligation::new(
+            ObligationCause::dummy(),
+            param_env,
+            Binder::dummy(target).to_poly_trait_predicate(),
+        );
+
+        match SelectionContext::new(&infcx).select(&obligation) {
+            Ok(Some(ImplSource::UserDefined(data))) => Some(data.impl_def_id),
+            _ => None,
+        }
+    })
+}
+
 /// Compare two implementations and indicate whether the target one is compatible with the
 /// original one.
 fn match_trait_impl<'a, 'tcx>(
@@ -1370,3 +3686,37 @@ fn match_trait_impl<'a, 'tcx>(
         bound_cx.get_errors().is_none()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hir::def_id::{DefIndex, LOCAL_CRATE};
+
+    fn def_id(index: u32) -> DefId {
+        DefId {
+            krate: LOCAL_CRATE,
+            index: DefIndex::from_u32(index),
+        }
+    }
+
+    #[test]
+    fn reachable_through_references() {
+        // 1 -> 2 -> 3 -> 2, while 4 -> 5 is only referenced by an unreachable item
+        let referenced = |d: DefId| match d.index.as_u32() {
+            1 => vec![def_id(2)],
+            2 => vec![def_id(3)],
+            3 => vec![def_id(2)],
+            4 => vec![def_id(5)],
+            _ => Vec::new(),
+        };
+
+        let reachable = reachable_from(vec![def_id(1)], referenced);
+        let expected: HashSet<_> = [1, 2, 3].iter().map(|&i| def_id(i)).collect();
+        assert_eq!(reachable, expected);
+
+        let reachable = reachable_from(vec![def_id(1), def_id(4)], referenced);
+        assert_eq!(reachable.len(), 5);
+
+        assert!(reachable_from(Vec::new(), referenced).is_empty());
+    }
+}
@@ -4,9 +4,11 @@
 //! for code reuse across analysis steps.
 
 use crate::{
+    budget,
     changes::ChangeSet,
     mapping::IdMapping,
-    translate::{InferenceCleanupFolder, TranslationContext},
+    timings,
+    translate::{refers_to, InferenceCleanupFolder, TranslationContext},
 };
 use log::debug;
 use rustc_hir::def_id::DefId;
@@ -19,7 +21,7 @@ use rustc_middle::{
         fold::TypeFoldable,
         subst::{GenericArg, InternalSubsts, SubstsRef},
         GenericParamDefKind, ImplPolarity, ParamEnv, Predicate, PredicateKind, TraitRef, Ty,
-        TyCtxt,
+        TyCtxt, TyKind,
     },
 };
 use rustc_trait_selection::traits::FulfillmentContext;
@@ -45,7 +47,16 @@ impl<'a, 'tcx> BoundContext<'a, 'tcx> {
     }
 
     /// Register the bounds of an item.
-    pub fn register(&mut self, checked_def_id: DefId, substs: SubstsRef<'tcx>) {
+    ///
+    /// Bounds that neither depend on the item's parameters nor refer to items of the compared
+    /// crates hold in both crate versions alike, and are skipped to spare the trait solver from
+    /// proving facts about the standard library and other dependencies.
+    pub fn register(
+        &mut self,
+        id_mapping: &IdMapping,
+        checked_def_id: DefId,
+        substs: SubstsRef<'tcx>,
+    ) {
         use rustc_infer::traits::Normalized;
         use rustc_trait_selection::traits::{normalize, SelectionContext};
 
@@ -64,7 +75,14 @@ impl<'a, 'tcx> BoundContext<'a, 'tcx> {
                 .register_predicate_obligation(self.infcx, obligation);
         }
 
-        for predicate in value.predicates {
+        let in_scope = |predicate: &Predicate<'tcx>| {
+            !predicate.is_global()
+                || refers_to(predicate, |did| {
+                    id_mapping.in_old_crate(did) || id_mapping.in_new_crate(did)
+                })
+        };
+
+        for predicate in value.predicates.into_iter().filter(in_scope) {
             let obligation = Obligation::new(cause.clone(), self.given_param_env, predicate);
             self.fulfill_cx
                 .register_predicate_obligation(self.infcx, obligation);
@@ -86,9 +104,36 @@ impl<'a, 'tcx> BoundContext<'a, 'tcx> {
             .register_predicate_obligation(self.infcx, obligation);
     }
 
+    /// Normalize a value, registering the obligations needed to do so.
+    pub fn normalize<T: TypeFoldable<'tcx>>(&mut self, value: T) -> T {
+        use rustc_infer::traits::Normalized;
+        use rustc_trait_selection::traits::{normalize, SelectionContext};
+
+        let mut selcx = SelectionContext::new(self.infcx);
+        let Normalized { value, obligations } = normalize(
+            &mut selcx,
+            self.given_param_env,
+            ObligationCause::dummy(),
+            value,
+        );
+
+        for obligation in obligations {
+            self.fulfill_cx
+                .register_predicate_obligation(self.infcx, obligation);
+        }
+
+        value
+    }
+
     /// Return inference errors, if any.
     pub fn get_errors(&mut self) -> Option<Vec<FulfillmentError<'tcx>>> {
-        let errors = self.fulfill_cx.select_all_or_error(self.infcx);
+        budget::check();
+
+        let errors = timings::time("trait solving", || {
+            self.fulfill_cx.select_all_or_error(self.infcx)
+        });
+        budget::check();
+
         if !errors.is_empty() {
             debug!("err: {:?}", errors);
             Some(errors)
@@ -221,16 +266,26 @@ impl<'a, 'tcx> TypeComparisonContext<'a, 'tcx> {
         use rustc_infer::infer::InferOk;
         use rustc_middle::ty::Lift;
 
+        budget::check();
+
         let error = self.infcx.commit_if_ok(|snapshot| {
+            // projections are normalized on both sides, so that they compare equal to the types
+            // they're defined as, instead of only to the same unnormalized projection
+            let mut bound_cx = BoundContext::new(self.infcx, target_param_env);
+            let orig = bound_cx.normalize(orig);
+            let target = bound_cx.normalize(target);
+
             let select = self
                 .infcx
                 .at(&ObligationCause::dummy(), target_param_env)
                 .eq(orig, target)
                 .map(|InferOk { obligations: o, .. }| {
                     assert_eq!(o, vec![]);
-                });
+                })
+                .and_then(|()| projection_error(bound_cx.get_errors()));
             // check for unresolvable lifetime constraints
             let leak_check = self.infcx.leak_check(false, snapshot);
+            budget::check();
             select.and(leak_check)
         });
 
@@ -281,7 +336,7 @@ impl<'a, 'tcx> TypeComparisonContext<'a, 'tcx> {
         );
 
         let mut bound_cx = BoundContext::new(self.infcx, orig_param_env);
-        bound_cx.register(target_def_id, target_substs);
+        bound_cx.register(self.id_mapping, target_def_id, target_substs);
 
         bound_cx.get_errors().map(|errors| {
             errors
@@ -307,7 +362,7 @@ impl<'a, 'tcx> TypeComparisonContext<'a, 'tcx> {
         orig_substs: SubstsRef<'tcx>,
         target_substs: SubstsRef<'tcx>,
     ) {
-        use crate::changes::ChangeType::{BoundsLoosened, BoundsTightened};
+        use crate::changes::ChangeType::{BoundsLoosened, BoundsTightened, SizedRelaxationChanged};
 
         let tcx = self.infcx.tcx;
 
@@ -325,7 +380,17 @@ impl<'a, 'tcx> TypeComparisonContext<'a, 'tcx> {
             self.check_bounds_error(lift_tcx, orig_param_env, target_def_id, target_substs)
         {
             for err in errors {
-                let err_type = BoundsTightened { pred: err };
+                let err_type = match sized_param(lift_tcx, err) {
+                    Some(param) => SizedRelaxationChanged {
+                        param,
+                        relaxed: false,
+                        trait_def: self.checking_trait_def,
+                    },
+                    None => BoundsTightened {
+                        pred: err,
+                        trait_def: self.checking_trait_def,
+                    },
+                };
 
                 changes.add_change(err_type, orig_def_id, None);
             }
@@ -345,9 +410,16 @@ impl<'a, 'tcx> TypeComparisonContext<'a, 'tcx> {
             self.check_bounds_error(lift_tcx, target_param_env, orig_def_id, orig_substs)
         {
             for err in errors {
-                let err_type = BoundsLoosened {
-                    pred: err,
-                    trait_def: self.checking_trait_def,
+                let err_type = match sized_param(lift_tcx, err) {
+                    Some(param) => SizedRelaxationChanged {
+                        param,
+                        relaxed: true,
+                        trait_def: self.checking_trait_def,
+                    },
+                    None => BoundsLoosened {
+                        pred: err,
+                        trait_def: self.checking_trait_def,
+                    },
                 };
 
                 changes.add_change(err_type, orig_def_id, None);
@@ -355,3 +427,44 @@ impl<'a, 'tcx> TypeComparisonContext<'a, 'tcx> {
         }
     }
 }
+
+/// Get the type error of the first projection that failed to normalize to the type it was
+/// equated with, among the errors of fulfilling the obligations of a normalization.
+///
+/// Other obligations stem from the bounds of the compared items, which are checked separately.
+fn projection_error<'tcx>(
+    errors: Option<Vec<FulfillmentError<'tcx>>>,
+) -> Result<(), TypeError<'tcx>> {
+    use rustc_infer::traits::{FulfillmentErrorCode, MismatchedProjectionTypes};
+
+    let error = errors.into_iter().flatten().find_map(|err| match err.code {
+        FulfillmentErrorCode::CodeProjectionError(MismatchedProjectionTypes { err }) => Some(err),
+        _ => None,
+    });
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Get the name of the type parameter a predicate requires to be `Sized`, if it's such a bound.
+///
+/// Type parameters are `Sized` unless relaxed using `?Sized`, so these bounds change whenever
+/// a relaxation is added or removed. `Self` in trait definitions is excluded, as it's only
+/// bounded by an explicit `Sized` supertrait.
+fn sized_param<'tcx>(tcx: TyCtxt<'tcx>, pred: Predicate<'tcx>) -> Option<String> {
+    use rustc_span::symbol::kw;
+
+    match pred.kind().skip_binder() {
+        PredicateKind::Trait(trait_pred)
+            if Some(trait_pred.def_id()) == tcx.lang_items().sized_trait() =>
+        {
+            match *trait_pred.self_ty().kind() {
+                TyKind::Param(param) if param.name != kw::SelfUpper => Some(param.name.to_string()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,147 @@
+//! Comparison of the functions exported to JavaScript using `#[wasm_bindgen]`.
+//!
+//! For crates compiled to WebAssembly and used from JavaScript, the public surface is the set of
+//! functions exported from the module, which the bindings generated by `wasm-bindgen` call by
+//! name. `#[wasm_bindgen]` expands every exported function, class and method into a wrapper with
+//! an `#[export_name]` attribute, named after the function or its `js_name` argument, and a
+//! signature in terms of the ABI types passed across the boundary. The wrappers are matched up
+//! by their export names, and compared by the Rust types they convert from and to, which
+//! determine the JavaScript values exchanged.
+//!
+//! The wrappers are only generated when compiling for a WebAssembly target, so the analysis
+//! needs to be run with `--target wasm32-unknown-unknown` for this to find anything.
+
+use crate::changes::{ChangeSet, ChangeType, WasmExportChange};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{CrateNum, DefId};
+use rustc_middle::{
+    middle::exported_symbols::ExportedSymbol,
+    ty::{self, Ty, TyCtxt},
+};
+use std::collections::BTreeMap;
+
+/// Prefix of the symbols `wasm-bindgen` uses to describe exports, which aren't callable.
+const DESCRIPTOR_PREFIX: &str = "__wbindgen_";
+
+/// Compare the functions exported from the WebAssembly modules of both crate versions.
+pub fn diff_wasm_exports(changes: &mut ChangeSet, tcx: TyCtxt, old: CrateNum, new: CrateNum) {
+    let old_exports = wasm_exports(tcx, old);
+    let mut new_exports = wasm_exports(tcx, new);
+
+    for (name, old_def_id) in old_exports {
+        let (kind, desc) = match new_exports.remove(&name) {
+            None => (
+                WasmExportChange::Removed,
+                format!("export `{}` removed", name),
+            ),
+            Some(new_def_id) => {
+                let old_sig = render_signature(tcx, old_def_id);
+                let new_sig = render_signature(tcx, new_def_id);
+
+                if old_sig == new_sig {
+                    continue;
+                }
+
+                (
+                    WasmExportChange::SignatureChanged,
+                    format!(
+                        "signature of export `{}` changed from `{}` to `{}`",
+                        name, old_sig, new_sig
+                    ),
+                )
+            }
+        };
+
+        changes.new_change_impl(old_def_id, name, tcx.def_span(old_def_id));
+        changes.add_change(
+            ChangeType::WasmExportChanged { desc, kind },
+            old_def_id,
+            None,
+        );
+    }
+
+    for (name, new_def_id) in new_exports {
+        let desc = format!("export `{}` added", name);
+
+        changes.new_change_impl(new_def_id, name, tcx.def_span(new_def_id));
+        changes.add_change(
+            ChangeType::WasmExportChanged {
+                desc,
+                kind: WasmExportChange::Added,
+            },
+            new_def_id,
+            None,
+        );
+    }
+}
+
+/// Collect the functions a crate exports under an unmangled name, by their exported name.
+fn wasm_exports(tcx: TyCtxt, krate: CrateNum) -> BTreeMap<String, DefId> {
+    tcx.exported_symbols(krate)
+        .iter()
+        .filter_map(|&(symbol, _)| match symbol {
+            ExportedSymbol::NonGeneric(def_id) if tcx.def_kind(def_id) == DefKind::Fn => {
+                Some(def_id)
+            }
+            _ => None,
+        })
+        .filter_map(|def_id| {
+            let attrs = tcx.codegen_fn_attrs(def_id);
+
+            if !attrs.contains_extern_indicator() {
+                return None;
+            }
+
+            let name = attrs.export_name.unwrap_or_else(|| tcx.item_name(def_id));
+
+            Some((name.to_string(), def_id))
+        })
+        .filter(|(name, _)| !name.starts_with(DESCRIPTOR_PREFIX))
+        .collect()
+}
+
+/// Render the signature of an exported function, as seen from JavaScript.
+///
+/// The wrappers take and return ABI types given as projections like
+/// `<String as ReturnWasmAbi>::Abi`. As different Rust types share ABI types while converting to
+/// different JavaScript values, such as `u32` and `char`, the projections aren't normalized, and
+/// the types they project from are rendered instead.
+fn render_signature(tcx: TyCtxt, def_id: DefId) -> String {
+    let sig = tcx.erase_late_bound_regions(tcx.fn_sig(def_id));
+
+    let inputs: Vec<_> = sig
+        .inputs()
+        .iter()
+        .map(|&ty| render_js_type(tcx, ty))
+        .collect();
+    let output = sig.output();
+
+    if output.is_unit() {
+        format!("fn({})", inputs.join(", "))
+    } else {
+        format!(
+            "fn({}) -> {}",
+            inputs.join(", "),
+            render_js_type(tcx, output)
+        )
+    }
+}
+
+/// Render the Rust type an ABI type passed across the boundary is converted from or to.
+///
+/// Arguments passed by reference are converted using `RefFromWasmAbi` or `RefMutFromWasmAbi`
+/// on the referenced type. Types other than projections are rendered as they are.
+fn render_js_type<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> String {
+    match ty.kind() {
+        ty::Projection(proj) => {
+            let self_ty = render_js_type(tcx, proj.self_ty());
+
+            match tcx.item_name(proj.trait_def_id(tcx)).as_str() {
+                "RefFromWasmAbi" => format!("&{}", self_ty),
+                "RefMutFromWasmAbi" => format!("&mut {}", self_ty),
+                _ => self_ty,
+            }
+        }
+        _ => ty.to_string(),
+    }
+}
@@ -0,0 +1,26 @@
+#[repr(C, align(4))]
+pub struct Abc {
+    pub field: u8,
+}
+
+#[repr(C, packed(2))]
+pub struct Bcd {
+    pub a: u8,
+    pub b: u64,
+}
+
+#[repr(C, packed)]
+pub struct Cde {
+    pub a: u32,
+    pub b: u32,
+}
+
+#[repr(C, align(16))]
+pub struct Def {
+    pub field: u8,
+}
+
+#[repr(packed)]
+pub struct Efg {
+    pub field: u64,
+}
@@ -0,0 +1,25 @@
+#[repr(C, align(8))]
+pub struct Abc {
+    pub field: u8,
+}
+
+#[repr(C, packed(4))]
+pub struct Bcd {
+    pub a: u8,
+    pub b: u64,
+}
+
+#[repr(C)]
+pub struct Cde {
+    pub a: u32,
+    pub b: u32,
+}
+
+#[repr(C, align(4))]
+pub struct Def {
+    pub field: u8,
+}
+
+pub struct Efg {
+    pub field: u64,
+}
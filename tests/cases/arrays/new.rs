@@ -0,0 +1,9 @@
+const LEN: usize = 32;
+
+pub fn abc(_: [u8; 32]) {}
+
+pub fn bcd(_: [u8; LEN]) {}
+
+pub fn cde(_: [u8; 4 * 8]) {}
+
+pub fn def(_: [u8; 2 * 8]) {}
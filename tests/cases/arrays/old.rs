@@ -0,0 +1,9 @@
+const LEN: usize = 16;
+
+pub fn abc(_: [u8; 16]) {}
+
+pub fn bcd(_: [u8; LEN]) {}
+
+pub fn cde(_: [u8; 4 * 4]) {}
+
+pub fn def(_: [u8; 16]) {}
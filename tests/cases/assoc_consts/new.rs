@@ -0,0 +1,7 @@
+pub struct Abc;
+
+impl Abc {
+    pub const MAX: u8 = 20;
+    pub const NAME: &'static str = "bcd";
+    pub const UNCHANGED: bool = true;
+}
@@ -0,0 +1,7 @@
+pub struct Abc;
+
+impl Abc {
+    pub const MAX: u8 = 10;
+    pub const NAME: &'static str = "abc";
+    pub const UNCHANGED: bool = true;
+}
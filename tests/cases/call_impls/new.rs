@@ -0,0 +1,13 @@
+#![feature(fn_traits, unboxed_closures)]
+
+pub struct Abc;
+
+impl FnOnce<(u16,)> for Abc {
+    type Output = u16;
+
+    extern "rust-call" fn call_once(self, args: (u16,)) -> u16 {
+        args.0
+    }
+}
+
+pub struct Bcd;
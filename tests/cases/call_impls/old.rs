@@ -0,0 +1,21 @@
+#![feature(fn_traits, unboxed_closures)]
+
+pub struct Abc;
+
+impl FnOnce<(u8,)> for Abc {
+    type Output = u8;
+
+    extern "rust-call" fn call_once(self, args: (u8,)) -> u8 {
+        args.0
+    }
+}
+
+pub struct Bcd;
+
+impl FnOnce<(u8,)> for Bcd {
+    type Output = u8;
+
+    extern "rust-call" fn call_once(self, args: (u8,)) -> u8 {
+        args.0
+    }
+}
@@ -0,0 +1,5 @@
+pub trait Abc<A> {}
+
+pub fn abc(_: &dyn Abc<u8>) {}
+
+pub fn bcd(_: Box<dyn Abc<u8>>) {}
@@ -0,0 +1,5 @@
+pub trait Abc {}
+
+pub fn abc(_: &dyn Abc) {}
+
+pub fn bcd(_: Box<dyn Abc>) {}
@@ -0,0 +1,22 @@
+#![feature(generators, generator_trait)]
+
+use std::ops::Generator;
+
+pub fn adder(n: u16) -> impl Fn(u8) -> u8 {
+    move |x| x + n as u8
+}
+
+pub fn counter() -> impl FnMut() -> u8 {
+    let mut count = 0;
+    move || {
+        count += 2;
+        count
+    }
+}
+
+pub fn numbers() -> impl Generator<Yield = u8, Return = ()> {
+    || {
+        yield 1;
+        yield 2;
+    }
+}
@@ -0,0 +1,21 @@
+#![feature(generators, generator_trait)]
+
+use std::ops::Generator;
+
+pub fn adder(n: u8) -> impl Fn(u8) -> u8 {
+    move |x| x + n
+}
+
+pub fn counter() -> impl FnMut() -> u8 {
+    let mut count = 0;
+    move || {
+        count += 1;
+        count
+    }
+}
+
+pub fn numbers() -> impl Generator<Yield = u8, Return = ()> {
+    || {
+        yield 1;
+    }
+}
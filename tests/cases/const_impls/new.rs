@@ -0,0 +1,17 @@
+#![feature(const_trait_impl)]
+
+pub struct Abc;
+
+impl Default for Abc {
+    fn default() -> Self {
+        Abc
+    }
+}
+
+pub struct Bcd;
+
+impl const Default for Bcd {
+    fn default() -> Self {
+        Bcd
+    }
+}
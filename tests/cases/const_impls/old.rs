@@ -0,0 +1,17 @@
+#![feature(const_trait_impl)]
+
+pub struct Abc;
+
+impl const Default for Abc {
+    fn default() -> Self {
+        Abc
+    }
+}
+
+pub struct Bcd;
+
+impl Default for Bcd {
+    fn default() -> Self {
+        Bcd
+    }
+}
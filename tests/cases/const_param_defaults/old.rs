@@ -0,0 +1,13 @@
+pub struct Abc<const N: usize = 4> {
+    pub field: [u8; N],
+}
+
+pub struct Bcd<const N: usize> {
+    pub field: [u8; N],
+}
+
+pub struct Cde<const N: usize = 4> {
+    pub field: [u8; N],
+}
+
+pub struct Def<const B: bool = true> {}
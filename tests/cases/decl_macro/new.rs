@@ -0,0 +1,6 @@
+#![feature(decl_macro)]
+pub macro abc($x:ident) { $x }
+
+pub macro bcd {
+    () => { 0 },
+}
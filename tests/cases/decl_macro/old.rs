@@ -0,0 +1,7 @@
+#![feature(decl_macro)]
+pub macro abc($x:expr) { $x }
+
+pub macro bcd {
+    () => { 0 },
+    ($x:expr) => { $x },
+}
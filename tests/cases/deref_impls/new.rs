@@ -0,0 +1,17 @@
+use std::ops::{Deref, DerefMut};
+
+pub struct Abc(pub String);
+
+impl Deref for Abc {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.0
+    }
+}
+
+impl DerefMut for Abc {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
@@ -0,0 +1 @@
+pub struct Abc(pub String);
@@ -0,0 +1,20 @@
+#![crate_type = "proc-macro"]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(Builder, attributes(builder, setter))]
+pub fn derive_builder(_: TokenStream) -> TokenStream {
+    TokenStream::new()
+}
+
+#[proc_macro_derive(Display, attributes(display))]
+pub fn derive_display(_: TokenStream) -> TokenStream {
+    TokenStream::new()
+}
+
+#[proc_macro_derive(Default, attributes(default))]
+pub fn derive_default(_: TokenStream) -> TokenStream {
+    TokenStream::new()
+}
@@ -0,0 +1,9 @@
+// only built with `--cfg docsrs`, so it doesn't make up for the removal of the actual item
+#[cfg(docsrs)]
+pub fn handle() {}
+
+pub struct Abc;
+
+// the shim module is ignored, so the removal of its contents goes unreported
+#[cfg(docsrs)]
+pub mod platform {}
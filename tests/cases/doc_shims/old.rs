@@ -0,0 +1,15 @@
+pub fn handle() {}
+
+pub struct Abc;
+
+#[cfg(docsrs)]
+impl Clone for Abc {
+    fn clone(&self) -> Abc {
+        Abc
+    }
+}
+
+#[cfg(any(doc, docsrs))]
+pub mod platform {
+    pub fn shim() {}
+}
@@ -0,0 +1,11 @@
+#![feature(extern_types)]
+
+extern "C" {
+    #[allow(dead_code)]
+    type Bcd;
+    pub type Cde;
+}
+
+pub fn abc(_: *const Cde) {}
+
+pub struct Def(*mut Cde);
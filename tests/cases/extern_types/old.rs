@@ -0,0 +1,11 @@
+#![feature(extern_types)]
+
+extern "C" {
+    pub type Abc;
+    pub type Bcd;
+    pub type Cde;
+}
+
+pub fn abc(_: *const Cde) {}
+
+pub struct Def(*mut Cde);
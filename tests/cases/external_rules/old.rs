@@ -0,0 +1,7 @@
+pub struct Abc {
+    pub a: u8,
+}
+
+pub struct Def;
+
+pub struct Ghi;
@@ -0,0 +1,5 @@
+#!/bin/sh
+# Downgrade the first change and drop the third one.
+cat > /dev/null
+echo '{"id":0,"category":"NonBreaking"}'
+echo '{"id":2,"remove":true}'
@@ -0,0 +1,8 @@
+pub struct Abc {
+    field: u8,
+}
+
+pub struct Def {
+    #[doc(hidden)]
+    pub field: u8,
+}
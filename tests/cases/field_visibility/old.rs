@@ -0,0 +1,7 @@
+pub struct Abc {
+    pub field: u8,
+}
+
+pub struct Def {
+    pub field: u8,
+}
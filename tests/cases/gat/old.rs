@@ -0,0 +1,23 @@
+#![feature(generic_associated_types)]
+
+pub trait Abc {
+    type Item<'a>
+    where
+        Self: 'a;
+}
+
+pub trait Bcd {
+    type Item<T>;
+}
+
+pub trait Cde {
+    type Item<'a>: Clone
+    where
+        Self: 'a;
+}
+
+pub struct Def;
+
+impl Abc for Def {
+    type Item<'a> = &'a u8;
+}
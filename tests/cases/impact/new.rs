@@ -0,0 +1,14 @@
+pub trait Abc {
+    fn abc(&self);
+    fn bcd(&self);
+}
+
+pub struct Bcd<T: Abc> {
+    pub inner: T,
+}
+
+pub fn cde(_: &dyn Abc) {}
+
+pub fn def<T: Abc>(_: Bcd<T>) {}
+
+pub fn efg() {}
@@ -0,0 +1,13 @@
+pub trait Abc {
+    fn abc(&self);
+}
+
+pub struct Bcd<T: Abc> {
+    pub inner: T,
+}
+
+pub fn cde(_: &dyn Abc) {}
+
+pub fn def<T: Abc>(_: Bcd<T>) {}
+
+pub fn efg() {}
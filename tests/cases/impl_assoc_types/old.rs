@@ -0,0 +1,19 @@
+pub struct Abc;
+
+impl Iterator for Abc {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        None
+    }
+}
+
+pub struct Def;
+
+impl Iterator for Def {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        None
+    }
+}
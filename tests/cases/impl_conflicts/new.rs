@@ -0,0 +1,9 @@
+pub struct Local;
+
+impl From<Local> for Vec<u8> {
+    fn from(_: Local) -> Vec<u8> { Vec::new() }
+}
+
+impl<T> PartialEq<T> for Local {
+    fn eq(&self, _: &T) -> bool { false }
+}
@@ -0,0 +1,16 @@
+#[derive(Clone)]
+pub struct Abc;
+
+pub fn abc() -> impl Clone {
+    Abc
+}
+
+pub fn bcd() -> Abc {
+    Abc
+}
+
+impl Abc {
+    pub fn cde(&self) -> impl Clone {
+        Abc
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Clone)]
+pub struct Abc;
+
+pub fn abc() -> Abc {
+    Abc
+}
+
+pub fn bcd() -> impl Clone {
+    Abc
+}
+
+impl Abc {
+    pub fn cde(&self) -> Abc {
+        Abc
+    }
+}
@@ -0,0 +1,23 @@
+pub struct Abc;
+
+impl From<Abc> for String {
+    fn from(_: Abc) -> String {
+        String::new()
+    }
+}
+
+impl PartialEq<Abc> for u8 {
+    fn eq(&self, _: &Abc) -> bool {
+        false
+    }
+}
+
+pub trait Bcd {}
+
+impl<T> Bcd for T {}
+
+impl From<u8> for Abc {
+    fn from(_: u8) -> Abc {
+        Abc
+    }
+}
@@ -0,0 +1,9 @@
+pub struct Abc<T>(pub T);
+
+impl<T: Clone + Send> Abc<T> {
+    pub fn abc(&self) -> T {
+        self.0.clone()
+    }
+
+    pub fn bcd(&self) {}
+}
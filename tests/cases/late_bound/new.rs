@@ -0,0 +1,11 @@
+pub fn abc<F: for<'a> Fn(&'a u8) -> &'a u8>(_: F) {}
+
+pub fn bcd(_: &dyn for<'a> Fn(&'a u8) -> &'a u8) {}
+
+pub fn cde(_: for<'a> fn(&'a u8) -> &'a u8) {}
+
+pub trait Def
+where
+    for<'a> &'a Self: IntoIterator,
+{
+}
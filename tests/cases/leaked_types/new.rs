@@ -0,0 +1,26 @@
+mod inner {
+    pub struct Abc {
+        pub field: u16,
+        pub bcd: Bcd,
+    }
+
+    pub struct Bcd {
+        pub field: u16,
+    }
+
+    pub struct Cde {
+        pub field: u16,
+    }
+}
+
+pub struct Def {
+    #[allow(dead_code)]
+    cde: inner::Cde,
+}
+
+pub fn abc() -> inner::Abc {
+    inner::Abc {
+        field: 0,
+        bcd: inner::Bcd { field: 0 },
+    }
+}
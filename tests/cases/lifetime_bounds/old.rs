@@ -0,0 +1,5 @@
+pub fn abc<T>(_: T) {}
+
+pub fn bcd<T: 'static>(_: T) {}
+
+pub struct Cde<'a, T>(pub &'a T);
@@ -0,0 +1,11 @@
+#[macro_export]
+macro_rules! abc {
+    ($x:ident) => { $x + 1 };
+    () => { 1 };
+    ($x:expr, $y:expr) => { $x + $y };
+}
+
+#[macro_export]
+macro_rules! bcd {
+    ($x:expr) => { $x };
+}
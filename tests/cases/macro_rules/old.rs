@@ -0,0 +1,10 @@
+#[macro_export]
+macro_rules! abc {
+    ($x:expr) => { $x + 1 };
+    () => { 0 };
+}
+
+#[macro_export]
+macro_rules! bcd {
+    ($x:expr) => { $x };
+}
@@ -0,0 +1,9 @@
+#[inline]
+pub fn abc(a: u8) -> u8 {
+    a + 2
+}
+
+#[inline]
+pub fn def(a: u8) -> u8 {
+    a + 1
+}
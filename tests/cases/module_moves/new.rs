@@ -0,0 +1,7 @@
+pub mod c {
+    pub struct Abc;
+
+    pub fn bcd() {}
+}
+
+pub struct Cde;
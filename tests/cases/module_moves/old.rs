@@ -0,0 +1,11 @@
+pub mod a {
+    pub struct Abc;
+
+    pub fn bcd() {}
+}
+
+pub mod b {
+    pub struct Cde;
+
+    pub struct Def;
+}
@@ -0,0 +1,7 @@
+pub mod a {
+
+}
+
+pub mod b {
+    pub struct Abc;
+}
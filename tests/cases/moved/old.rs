@@ -0,0 +1,7 @@
+pub mod a {
+    pub struct Abc;
+}
+
+pub mod b {
+
+}
@@ -0,0 +1,20 @@
+#[must_use]
+pub struct Abc;
+
+#[must_use]
+pub fn bcd() -> u8 {
+    0
+}
+
+pub fn cde() -> u8 {
+    0
+}
+
+pub struct Def;
+
+impl Def {
+    #[must_use]
+    pub fn efg(&self) -> u8 {
+        0
+    }
+}
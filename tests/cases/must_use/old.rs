@@ -0,0 +1,18 @@
+pub struct Abc;
+
+pub fn bcd() -> u8 {
+    0
+}
+
+#[must_use]
+pub fn cde() -> u8 {
+    0
+}
+
+pub struct Def;
+
+impl Def {
+    pub fn efg(&self) -> u8 {
+        0
+    }
+}
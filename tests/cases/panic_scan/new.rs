@@ -0,0 +1,23 @@
+#[inline]
+pub fn abc(a: Option<u8>) -> u8 {
+    a.unwrap()
+}
+
+#[inline]
+pub fn bcd(a: u8) -> u8 {
+    check(a)
+}
+
+#[inline]
+pub fn cde(a: Option<u8>) -> u8 {
+    a.unwrap()
+}
+
+#[inline]
+fn check(a: u8) -> u8 {
+    if a == 0 {
+        panic!("zero");
+    }
+
+    a
+}
@@ -0,0 +1,19 @@
+#[inline]
+pub fn abc(a: Option<u8>) -> u8 {
+    a.unwrap_or(0)
+}
+
+#[inline]
+pub fn bcd(a: u8) -> u8 {
+    check(a)
+}
+
+#[inline]
+pub fn cde(a: Option<u8>) -> u8 {
+    a.expect("missing value")
+}
+
+#[inline]
+fn check(a: u8) -> u8 {
+    a
+}
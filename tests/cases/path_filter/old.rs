@@ -0,0 +1,11 @@
+pub mod net {
+    pub struct Socket;
+
+    pub fn connect() {}
+}
+
+pub mod internal {
+    pub struct Buffer;
+
+    pub fn flush() {}
+}
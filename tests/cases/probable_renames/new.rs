@@ -0,0 +1,7 @@
+pub struct Configuration {
+    pub verbose: bool,
+}
+
+pub fn load_config() -> bool {
+    true
+}
@@ -0,0 +1,7 @@
+pub struct Config {
+    pub verbose: bool,
+}
+
+pub fn load_cfg() -> bool {
+    true
+}
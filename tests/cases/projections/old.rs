@@ -0,0 +1,21 @@
+pub trait Abc {
+    type Out;
+}
+
+pub struct Def;
+
+impl Abc for Def {
+    type Out = u8;
+}
+
+pub fn abc() -> <Def as Abc>::Out {
+    0
+}
+
+pub fn bcd<T: Iterator<Item = u8>>(mut iter: T) -> T::Item {
+    iter.next().unwrap_or(0)
+}
+
+pub struct Efg {
+    pub field: <Def as Abc>::Out,
+}
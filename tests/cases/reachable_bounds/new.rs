@@ -0,0 +1,19 @@
+mod private {
+    // only reachable through bounds, and private, so changes to it aren't reported
+    pub trait Sealed {
+        fn seal(&self) -> u16;
+    }
+
+    #[allow(dead_code)]
+    pub struct Unreachable(u8);
+}
+
+pub trait Public: private::Sealed {
+    type Item: private::Sealed;
+}
+
+pub struct Wrapper<T: private::Sealed>(pub T);
+
+pub fn bounded<T: private::Sealed>(_: T) {}
+
+pub fn changed(_: u16) {}
@@ -0,0 +1,18 @@
+mod private {
+    pub trait Sealed {
+        fn seal(&self) -> u8;
+    }
+
+    #[allow(dead_code)]
+    pub struct Unreachable;
+}
+
+pub trait Public: private::Sealed {
+    type Item: private::Sealed;
+}
+
+pub struct Wrapper<T: private::Sealed>(pub T);
+
+pub fn bounded<T: private::Sealed>(_: T) {}
+
+pub fn changed(_: u8) {}
@@ -0,0 +1,3 @@
+pub fn frobnicates(a: u16) -> u16 {
+    a
+}
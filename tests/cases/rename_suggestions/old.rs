@@ -0,0 +1,3 @@
+pub fn frobnicate(a: u8) -> u8 {
+    a
+}
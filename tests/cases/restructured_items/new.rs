@@ -0,0 +1,12 @@
+pub mod settings {
+    pub struct Settings {
+        pub verbose: bool,
+        pub level: u8,
+    }
+
+    impl Settings {
+        pub fn quiet(&self) -> bool {
+            !self.verbose
+        }
+    }
+}
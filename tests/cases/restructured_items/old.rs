@@ -0,0 +1,12 @@
+pub mod config {
+    pub struct Config {
+        pub verbose: bool,
+        pub level: u8,
+    }
+
+    impl Config {
+        pub fn quiet(&self) -> bool {
+            !self.verbose
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub struct Abc {
+    a: u8,
+    b: Option<std::num::NonZeroU8>,
+}
@@ -0,0 +1,3 @@
+pub struct Abc {
+    a: u8,
+}
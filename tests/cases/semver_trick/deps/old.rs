@@ -0,0 +1,9 @@
+// a later release of the old version, which the new version re-exports its items from
+pub struct Abc {
+    pub a: u8,
+}
+
+#[inline]
+pub fn answer() -> u8 {
+    42
+}
@@ -0,0 +1,5 @@
+extern crate old;
+
+pub use old::{answer, Abc};
+
+pub fn bcd(_: u16) {}
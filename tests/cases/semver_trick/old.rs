@@ -0,0 +1,10 @@
+pub struct Abc {
+    pub a: u8,
+}
+
+#[inline]
+pub fn answer() -> u8 {
+    41
+}
+
+pub fn bcd(_: u8) {}
@@ -0,0 +1,23 @@
+#![feature(register_attr)]
+#![register_attr(serde)]
+
+extern crate serde;
+
+pub fn removed() {}
+
+pub struct Config {
+    pub name: String,
+    pub verbose: bool,
+    pub level: u8,
+}
+
+impl serde::Serialize for Config {}
+impl serde::Deserialize for Config {}
+
+#[serde(tag = "type")]
+pub enum Event {
+    Start { at: u64 },
+    Stop,
+}
+
+impl serde::Serialize for Event {}
@@ -0,0 +1,26 @@
+#[derive(Clone)]
+pub struct Abc;
+
+impl Abc {
+    pub fn abc(&self) {}
+
+    pub fn clone(&self) -> Abc {
+        Abc
+    }
+
+    pub fn name(&self) -> &'static str {
+        "Abc"
+    }
+
+    pub fn bcd(&self) {}
+}
+
+pub trait Named {
+    fn name(&self) -> String;
+}
+
+impl Named for Abc {
+    fn name(&self) -> String {
+        "Abc".to_owned()
+    }
+}
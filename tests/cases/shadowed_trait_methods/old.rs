@@ -0,0 +1,16 @@
+#[derive(Clone)]
+pub struct Abc;
+
+impl Abc {
+    pub fn abc(&self) {}
+}
+
+pub trait Named {
+    fn name(&self) -> String;
+}
+
+impl Named for Abc {
+    fn name(&self) -> String {
+        "Abc".to_owned()
+    }
+}
@@ -0,0 +1,5 @@
+pub fn abc<T>(_: &T) {}
+
+pub fn bcd<T: ?Sized>(_: &T) {}
+
+pub struct Cde<T>(pub Box<T>);
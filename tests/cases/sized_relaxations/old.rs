@@ -0,0 +1,5 @@
+pub fn abc<T: ?Sized>(_: &T) {}
+
+pub fn bcd<T>(_: &T) {}
+
+pub struct Cde<T: ?Sized>(pub Box<T>);
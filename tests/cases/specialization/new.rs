@@ -0,0 +1,13 @@
+#![feature(min_specialization)]
+
+pub trait Abc {
+    fn abc(&self);
+    fn bcd(&self);
+}
+
+pub struct Def;
+
+impl Abc for Def {
+    fn abc(&self) {}
+    default fn bcd(&self) {}
+}
@@ -0,0 +1,15 @@
+pub struct Abc {
+    pub field: u8,
+    pub field2: u8,
+}
+
+#[non_exhaustive]
+pub struct Def {
+    pub field: u8,
+    pub field2: u8,
+}
+
+pub enum Efg {
+    #[non_exhaustive]
+    A { field: u8, field2: u8 },
+}
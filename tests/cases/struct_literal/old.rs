@@ -0,0 +1,13 @@
+pub struct Abc {
+    pub field: u8,
+}
+
+#[non_exhaustive]
+pub struct Def {
+    pub field: u8,
+}
+
+pub enum Efg {
+    #[non_exhaustive]
+    A { field: u8 },
+}
@@ -0,0 +1,13 @@
+#![feature(type_alias_impl_trait)]
+
+pub type Abc = impl Iterator<Item = u8>;
+
+pub fn abc() -> Abc {
+    std::iter::empty()
+}
+
+pub type Bcd = impl Sized;
+
+pub fn bcd() -> Bcd {
+    std::rc::Rc::new(0u8)
+}
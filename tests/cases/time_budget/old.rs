@@ -0,0 +1,4 @@
+pub struct Abc {
+    pub a: u8,
+    pub b: u8,
+}
@@ -0,0 +1,7 @@
+#[track_caller]
+pub fn abc() {}
+
+pub fn bcd() {}
+
+#[track_caller]
+pub fn cde() {}
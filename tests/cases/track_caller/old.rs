@@ -0,0 +1,7 @@
+pub fn abc() {}
+
+#[track_caller]
+pub fn bcd() {}
+
+#[track_caller]
+pub fn cde() {}
@@ -0,0 +1,9 @@
+pub trait Abc: Sized { }
+
+pub trait Def where Self::Item: Clone {
+    type Item;
+}
+
+pub trait Efg {
+    fn efg<A: Clone>(a: A);
+}
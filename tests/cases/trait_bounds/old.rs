@@ -0,0 +1,9 @@
+pub trait Abc { }
+
+pub trait Def {
+    type Item;
+}
+
+pub trait Efg {
+    fn efg<A>(a: A);
+}
@@ -0,0 +1,9 @@
+pub trait Abc {}
+
+pub trait Bcd {}
+
+pub trait Cde: Bcd {}
+
+pub trait Def {}
+
+pub trait Efg: Def {}
@@ -0,0 +1,9 @@
+pub trait Abc {}
+
+pub trait Bcd: Abc {}
+
+pub trait Cde: Bcd {}
+
+pub trait Def: Clone {}
+
+pub trait Efg: Def {}
@@ -0,0 +1,7 @@
+pub type Id = u64;
+
+pub fn abc(_: Id) {}
+
+pub fn bcd() -> Id {
+    0
+}
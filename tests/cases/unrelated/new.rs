@@ -0,0 +1,11 @@
+#![feature(type_alias_impl_trait)]
+
+mod a {
+    pub trait Secret {}
+
+    impl Secret for () {}
+}
+
+pub type Foo = impl a::Secret;
+
+pub fn foo() -> Foo {}
@@ -0,0 +1,11 @@
+#![feature(type_alias_impl_trait)]
+
+mod a {
+    pub trait Hidden {}
+
+    impl Hidden for () {}
+}
+
+pub type Foo = impl a::Hidden;
+
+pub fn foo() -> Foo {}
@@ -0,0 +1,10 @@
+#![feature(coerce_unsized, unsize)]
+
+use std::marker::Unsize;
+use std::ops::CoerceUnsized;
+
+pub struct Abc<T: ?Sized>(Box<T>);
+
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Abc<U>> for Abc<T> {}
+
+pub struct Bcd<T: ?Sized>(Box<T>);
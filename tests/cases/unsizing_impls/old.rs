@@ -0,0 +1,14 @@
+#![feature(coerce_unsized, dispatch_from_dyn, unsize)]
+
+use std::marker::Unsize;
+use std::ops::{CoerceUnsized, DispatchFromDyn};
+
+pub struct Abc<T: ?Sized>(Box<T>);
+
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Abc<U>> for Abc<T> {}
+
+impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<Abc<U>> for Abc<T> {}
+
+pub struct Bcd<T: ?Sized>(Box<T>);
+
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Bcd<U>> for Bcd<T> {}
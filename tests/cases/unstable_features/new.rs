@@ -0,0 +1,10 @@
+#![feature(doc_cfg)]
+
+pub mod stable {
+    pub fn abc(_: u16) {}
+}
+
+#[doc(cfg(feature = "unstable"))]
+pub mod unstable {
+    pub fn def(_: u16) {}
+}
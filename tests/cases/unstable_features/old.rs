@@ -0,0 +1,12 @@
+#![feature(doc_cfg)]
+
+pub mod stable {
+    pub fn abc(_: u8) {}
+}
+
+#[doc(cfg(feature = "unstable"))]
+pub mod unstable {
+    pub fn def(_: u8) {}
+
+    pub struct Ghi;
+}
@@ -0,0 +1,15 @@
+#![allow(dead_code)]
+
+pub fn abc(a: u8) -> u16 {
+    a.into()
+}
+
+pub struct Bcd {
+    pub field: u8,
+}
+
+impl Bcd {
+    pub fn new() -> Bcd {
+        Bcd { field: 0 }
+    }
+}
@@ -0,0 +1,86 @@
+// the expansion of `wasm_bindgen::convert`, reduced to the conversions used below
+mod wasm_bindgen {
+    pub mod convert {
+        pub trait FromWasmAbi {
+            type Abi;
+            unsafe fn from_abi(abi: Self::Abi) -> Self;
+        }
+
+        pub trait RefFromWasmAbi {
+            type Abi;
+            unsafe fn ref_from_abi(abi: Self::Abi) -> &'static Self;
+        }
+
+        pub trait ReturnWasmAbi {
+            type Abi;
+            fn return_abi(self) -> Self::Abi;
+        }
+
+        impl FromWasmAbi for bool {
+            type Abi = u32;
+            unsafe fn from_abi(abi: u32) -> bool { abi != 0 }
+        }
+
+        impl RefFromWasmAbi for str {
+            type Abi = u64;
+            unsafe fn ref_from_abi(_: u64) -> &'static str { "" }
+        }
+
+        impl ReturnWasmAbi for String {
+            type Abi = u64;
+            fn return_abi(self) -> u64 { 0 }
+        }
+
+        impl ReturnWasmAbi for char {
+            type Abi = u32;
+            fn return_abi(self) -> u32 { self as u32 }
+        }
+    }
+}
+
+// #[wasm_bindgen]
+// fn greet(name: &str, loud: bool) -> String
+fn greet(name: &str, loud: bool) -> String { if loud { name.to_uppercase() } else { name.to_owned() } }
+
+#[allow(non_upper_case_globals)]
+const __wasm_bindgen_generated_greet__const: () = {
+    #[export_name = "greet"]
+    pub unsafe extern "C" fn __wasm_bindgen_generated_greet(arg0: <str as wasm_bindgen::convert::RefFromWasmAbi>::Abi, arg1: <bool as wasm_bindgen::convert::FromWasmAbi>::Abi) -> <String as wasm_bindgen::convert::ReturnWasmAbi>::Abi {
+        let arg0 = <str as wasm_bindgen::convert::RefFromWasmAbi>::ref_from_abi(arg0);
+        let arg1 = <bool as wasm_bindgen::convert::FromWasmAbi>::from_abi(arg1);
+        <String as wasm_bindgen::convert::ReturnWasmAbi>::return_abi(greet(arg0, arg1))
+    }
+
+    #[no_mangle]
+    pub extern "C" fn __wbindgen_describe_greet() {}
+};
+
+// #[wasm_bindgen]
+// fn count() -> char
+fn count() -> char { '0' }
+
+#[allow(non_upper_case_globals)]
+const __wasm_bindgen_generated_count__const: () = {
+    #[export_name = "count"]
+    pub unsafe extern "C" fn __wasm_bindgen_generated_count() -> <char as wasm_bindgen::convert::ReturnWasmAbi>::Abi {
+        <char as wasm_bindgen::convert::ReturnWasmAbi>::return_abi(count())
+    }
+
+    #[no_mangle]
+    pub extern "C" fn __wbindgen_describe_count() {}
+};
+
+// #[wasm_bindgen(js_name = wave)]
+// fn farewell()
+fn farewell() {}
+
+#[allow(non_upper_case_globals)]
+const __wasm_bindgen_generated_farewell__const: () = {
+    #[export_name = "wave"]
+    pub unsafe extern "C" fn __wasm_bindgen_generated_farewell() {
+        farewell()
+    }
+
+    #[no_mangle]
+    pub extern "C" fn __wbindgen_describe_wave() {}
+};
@@ -0,0 +1,77 @@
+pub fn hello() {}
+
+// the expansion of `wasm_bindgen::convert`, reduced to the conversions used below
+mod wasm_bindgen {
+    pub mod convert {
+        pub trait RefFromWasmAbi {
+            type Abi;
+            unsafe fn ref_from_abi(abi: Self::Abi) -> &'static Self;
+        }
+
+        pub trait ReturnWasmAbi {
+            type Abi;
+            fn return_abi(self) -> Self::Abi;
+        }
+
+        impl RefFromWasmAbi for str {
+            type Abi = u64;
+            unsafe fn ref_from_abi(_: u64) -> &'static str { "" }
+        }
+
+        impl ReturnWasmAbi for String {
+            type Abi = u64;
+            fn return_abi(self) -> u64 { 0 }
+        }
+
+        impl ReturnWasmAbi for u32 {
+            type Abi = u32;
+            fn return_abi(self) -> u32 { self }
+        }
+    }
+}
+
+// #[wasm_bindgen]
+// fn greet(name: &str) -> String
+fn greet(name: &str) -> String { name.to_owned() }
+
+#[allow(non_upper_case_globals)]
+const __wasm_bindgen_generated_greet__const: () = {
+    #[export_name = "greet"]
+    pub unsafe extern "C" fn __wasm_bindgen_generated_greet(arg0: <str as wasm_bindgen::convert::RefFromWasmAbi>::Abi) -> <String as wasm_bindgen::convert::ReturnWasmAbi>::Abi {
+        let arg0 = <str as wasm_bindgen::convert::RefFromWasmAbi>::ref_from_abi(arg0);
+        <String as wasm_bindgen::convert::ReturnWasmAbi>::return_abi(greet(arg0))
+    }
+
+    #[no_mangle]
+    pub extern "C" fn __wbindgen_describe_greet() {}
+};
+
+// #[wasm_bindgen]
+// fn count() -> u32
+fn count() -> u32 { 0 }
+
+#[allow(non_upper_case_globals)]
+const __wasm_bindgen_generated_count__const: () = {
+    #[export_name = "count"]
+    pub unsafe extern "C" fn __wasm_bindgen_generated_count() -> <u32 as wasm_bindgen::convert::ReturnWasmAbi>::Abi {
+        <u32 as wasm_bindgen::convert::ReturnWasmAbi>::return_abi(count())
+    }
+
+    #[no_mangle]
+    pub extern "C" fn __wbindgen_describe_count() {}
+};
+
+// #[wasm_bindgen]
+// fn farewell()
+fn farewell() {}
+
+#[allow(non_upper_case_globals)]
+const __wasm_bindgen_generated_farewell__const: () = {
+    #[export_name = "farewell"]
+    pub unsafe extern "C" fn __wasm_bindgen_generated_farewell() {
+        farewell()
+    }
+
+    #[no_mangle]
+    pub extern "C" fn __wbindgen_describe_farewell() {}
+};
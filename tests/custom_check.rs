@@ -0,0 +1,67 @@
+mod custom_check {
+    use std::{
+        fs::read_to_string,
+        path::Path,
+        process::{Command, Stdio},
+        str,
+    };
+
+    /// Compile a crate version of the test case into an rlib next to its source.
+    fn compile(dir: &Path, name: &str) -> String {
+        let rlib = dir.join(format!("lib{}.rlib", name));
+
+        let status = Command::new("rustc")
+            .args(&["--crate-type=lib", "-o"])
+            .arg(&rlib)
+            .arg(dir.join(format!("{}.rs", name)))
+            .stdin(Stdio::null())
+            .status()
+            .expect("could not run rustc");
+        assert!(status.success(), "couldn't compile {}", name);
+
+        rlib.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn deprecations() {
+        let dir = Path::new("tests").join("custom_check");
+        let old_rlib = compile(&dir, "old");
+        let new_rlib = compile(&dir, "new");
+
+        let output = Command::new(
+            Path::new("target")
+                .join("debug")
+                .join("examples")
+                .join("custom_check"),
+        )
+        .args(&[
+            "--crate-type=lib",
+            "--extern",
+            &format!("old={}", old_rlib),
+            "--extern",
+            &format!("new={}", new_rlib),
+            Path::new("tests")
+                .join("helper")
+                .join("test.rs")
+                .to_str()
+                .unwrap(),
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .expect("could not run the custom_check example");
+
+        std::fs::remove_file(old_rlib).expect("could not remove rlib");
+        std::fs::remove_file(new_rlib).expect("could not remove rlib");
+
+        let actual: String = str::from_utf8(&output.stdout)
+            .unwrap()
+            .lines()
+            .chain(str::from_utf8(&output.stderr).unwrap().lines())
+            .map(|line| line.trim_end().replace('\\', "/") + "\n")
+            .collect();
+        let expected = read_to_string(dir.join("stdout")).expect("could not read expected output");
+
+        assert!(output.status.success());
+        assert_eq!(actual.trim_end(), expected.trim_end());
+    }
+}
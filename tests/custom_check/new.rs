@@ -0,0 +1,4 @@
+#[deprecated]
+pub struct Abc;
+
+pub struct Bcd;
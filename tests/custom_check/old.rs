@@ -0,0 +1,3 @@
+pub struct Abc;
+
+pub struct Bcd;
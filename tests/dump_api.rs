@@ -0,0 +1,47 @@
+mod dump_api {
+    use std::{
+        fs::read_to_string,
+        path::Path,
+        process::{Command, Stdio},
+        str,
+    };
+
+    #[test]
+    fn public_api() {
+        let dir = Path::new("tests").join("dump_api");
+        let rlib = dir.join("libnew.rlib");
+
+        let status = Command::new("rustc")
+            .args(&["--crate-type=lib", "-o"])
+            .arg(&rlib)
+            .arg(dir.join("new.rs"))
+            .stdin(Stdio::null())
+            .status()
+            .expect("could not run rustc");
+        assert!(status.success(), "couldn't compile new");
+
+        let output = Command::new(Path::new("target").join("debug").join("rust-semver-public"))
+            .args(&[
+                "--crate-type=lib",
+                "--extern",
+                &format!("new={}", rlib.to_str().unwrap()),
+                dir.join("test.rs").to_str().unwrap(),
+            ])
+            .env("RUST_SEMVER_DUMP_API", "true")
+            .stdin(Stdio::null())
+            .output()
+            .expect("could not run rust-semver-public");
+
+        std::fs::remove_file(rlib).expect("could not remove rlib");
+
+        let actual: String = str::from_utf8(&output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| line.trim_end().to_owned() + "\n")
+            .collect();
+        let expected = read_to_string(dir.join("stdout")).expect("could not read expected output");
+
+        assert!(output.status.success());
+        assert_eq!(actual.trim_end(), expected.trim_end());
+    }
+}
@@ -0,0 +1,9 @@
+pub struct Inner;
+
+pub mod new {
+    pub struct Inner;
+}
+
+pub fn convert(_: Inner) -> new::Inner {
+    new::Inner
+}
@@ -0,0 +1,2 @@
+#[allow(unused_extern_crates)]
+extern crate new;
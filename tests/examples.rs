@@ -1,21 +1,134 @@
 mod features {
     use std::{
         env,
-        fs::{read_to_string, File},
+        fs::{read_dir, read_to_string, File},
         io::Write,
         path::Path,
         process::{Command, Stdio},
         str,
     };
 
+    /// Alternative expected outputs of a test case, along with the environment variable to set
+    /// when running `rust-semverver` to obtain them.
+    const VARIANTS: &[(&str, &str, &str)] = &[
+        (
+            "stdout_api_guidelines",
+            "RUST_SEMVER_API_GUIDELINES",
+            "true",
+        ),
+        (
+            "stdout_technically_breaking_deny",
+            "RUST_SEMVER_SEVERITIES",
+            "technically-breaking=deny",
+        ),
+        (
+            "stdout_technically_breaking_allow",
+            "RUST_SEMVER_SEVERITIES",
+            "technically-breaking=allow",
+        ),
+        ("stdout_mir_diff", "RUST_SEMVER_MIR_DIFF", "true"),
+        ("stdout_dot", "RUST_SEMVER_DOT", "true"),
+        ("stdout_impact", "RUST_SEMVER_IMPACT", "true"),
+        (
+            "stdout_manifest",
+            "RUST_SEMVER_MANIFEST_CHANGES",
+            r#"[{"FeatureRemoved":{"name":"std","renamed_to":null}},{"FeatureRemoved":{"name":"serde","renamed_to":"serde1"}},{"OptionalDepFeatureRemoved":{"name":"rand","namespaced":true}},{"OptionalDepFeatureRemoved":{"name":"log","namespaced":false}},{"DefaultFeatureRemoved":{"name":"std"}},{"DefaultFeatureAdded":{"name":"alloc"}},{"EditionChanged":{"old":"2018","new":"2021"}}]"#,
+        ),
+        ("stdout_panic_scan", "RUST_SEMVER_PANIC_SCAN", "true"),
+        ("stdout_quiet", "RUST_SEMVER_QUIET", "true"),
+        ("stdout_rust_version", "RUST_SEMVER_RUST_VERSION", "1.20"),
+        (
+            "stdout_path_filter",
+            "RUST_SEMVER_EXCLUDE",
+            "new::internal::**",
+        ),
+        ("stdout_renames", "RUST_SEMVER_RENAMES", "new::Abc=new::Bcd"),
+        ("stdout_time_budget", "RUST_SEMVER_TIME_BUDGET", "0"),
+        ("stdout_stream", "RUST_SEMVER_STREAM", "true"),
+        (
+            "stdout_external_rules",
+            "RUST_SEMVER_EXTERNAL_RULES",
+            r#"["sh","tests/cases/external_rules/rules.sh"]"#,
+        ),
+        (
+            "stdout_unstable_features",
+            "RUST_SEMVER_UNSTABLE_FEATURES",
+            "unstable",
+        ),
+        ("stdout_wasm_bindgen", "RUST_SEMVER_WASM_BINDGEN", "true"),
+        ("stdout_serde", "RUST_SEMVER_SERDE", "true"),
+    ];
+
     fn test_example2(name: &str, path: &Path, expected_path: &Path, expected_result: bool) {
-        let old_rlib = path.join("libold.rlib").to_str().unwrap().to_owned();
-        let new_rlib = path.join("libnew.rlib").to_str().unwrap().to_owned();
+        // cases declaring a proc macro crate are built as such, into a dynamic library
+        let proc_macro = read_to_string(path.join("old.rs"))
+            .map_or(false, |s| s.contains("#![crate_type = \"proc-macro\"]"));
+        let (crate_type, suffix) = if proc_macro {
+            ("--crate-type=proc-macro", env::consts::DLL_SUFFIX)
+        } else {
+            ("--crate-type=lib", ".rlib")
+        };
+
+        let old_rlib = path
+            .join(format!("libold{}", suffix))
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let new_rlib = path
+            .join(format!("libnew{}", suffix))
+            .to_str()
+            .unwrap()
+            .to_owned();
 
         let target_args = std::env::var("TEST_TARGET").map(|t| ["--target".to_string(), t]);
 
+        // cases can provide external crates in `deps`, which both versions are linked against
+        let deps_dir = path.join("deps");
+        let deps: Vec<_> = read_dir(&deps_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|dep| dep.extension() == Some("rs".as_ref()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let lib_args = ["-L".to_string(), deps_dir.to_str().unwrap().to_owned()];
+
+        // cases can pass extra flags when building both versions, e.g. to set `--cfg` options
+        let rustc_flags: Vec<String> = read_to_string(path.join("rustc_flags"))
+            .map(|flags| flags.split_whitespace().map(str::to_owned).collect())
+            .unwrap_or_default();
+
+        let mut dep_rlibs = Vec::new();
+
+        for dep in &deps {
+            let name = dep.file_stem().unwrap().to_str().unwrap();
+            let dep_rlib = deps_dir.join(format!("lib{}.rlib", name));
+
+            let mut cmd = Command::new("rustc");
+            cmd.args(&["--crate-type=lib", "-o"])
+                .arg(&dep_rlib)
+                .arg(dep)
+                .env("RUST_BACKTRACE", "full")
+                .stdin(Stdio::null());
+
+            if let Ok(target_args) = &target_args {
+                cmd.args(target_args);
+            }
+
+            let rustc_dep_result = cmd
+                .status()
+                .unwrap_or_else(|_| panic!("could not run rustc on {}", name))
+                .success();
+            assert!(rustc_dep_result, "couldn't compile {}", name);
+
+            dep_rlibs.push(dep_rlib);
+        }
+
         let mut cmd = Command::new("rustc");
-        cmd.args(&["--crate-type=lib", "-o", &old_rlib])
+        cmd.args(&[crate_type, "-o", &old_rlib])
+            .args(&lib_args)
+            .args(&rustc_flags)
             .arg(path.join("old.rs"))
             .env("RUST_BACKTRACE", "full")
             .stdin(Stdio::null());
@@ -28,7 +141,9 @@ mod features {
         assert!(rustc_old_result, "couldn't compile old");
 
         let mut cmd = Command::new("rustc");
-        cmd.args(&["--crate-type=lib", "-o", &new_rlib])
+        cmd.args(&[crate_type, "-o", &new_rlib])
+            .args(&lib_args)
+            .args(&rustc_flags)
             .arg(path.join("new.rs"))
             .env("RUST_BACKTRACE", "full")
             .stdin(Stdio::null());
@@ -62,6 +177,7 @@ mod features {
                 .unwrap(),
         ])
         .env("RUST_BACKTRACE", "full")
+        .args(&lib_args)
         .env("RUST_SEMVER_CRATE_VERSION", "1.0.0")
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
@@ -71,12 +187,10 @@ mod features {
             cmd.args(target_args);
         }
 
-        if expected_path
-            .to_str()
-            .unwrap()
-            .contains("stdout_api_guidelines")
-        {
-            cmd.env("RUST_SEMVER_API_GUIDELINES", "true");
+        for &(file, var, value) in VARIANTS {
+            if expected_path.file_name() == Some(file.as_ref()) {
+                cmd.env(var, value);
+            }
         }
 
         let expected_output = read_to_string(&expected_path)
@@ -170,6 +284,10 @@ mod features {
         let rm_old = std::fs::remove_file(old_rlib);
         let rm_new = std::fs::remove_file(new_rlib);
         rm_old.and(rm_new).expect("could not remove rlib files");
+
+        for dep_rlib in dep_rlibs {
+            std::fs::remove_file(dep_rlib).expect("could not remove rlib files");
+        }
     }
 
     macro_rules! test {
@@ -179,9 +297,11 @@ mod features {
                 let path = Path::new("tests").join("cases").join(stringify!($name));
                 test_example2(stringify!($name), &path, &path.join("stdout"), $result);
 
-                if path.join("stdout_api_guidelines").exists() {
-                    eprintln!("api-guidelines");
-                    test_example2(stringify!($name), &path, &path.join("stdout_api_guidelines"), $result);
+                for &(file, _, _) in VARIANTS {
+                    if path.join(file).exists() {
+                        eprintln!("{}", file);
+                        test_example2(stringify!($name), &path, &path.join(file), $result);
+                    }
                 }
             }
         };
@@ -197,33 +317,90 @@ mod features {
         addition => true,
         addition_path => true,
         addition_use => false,
+        alignment => false,
+        arrays => false,
+        assoc_consts => true,
         bounds => false,
+        call_impls => false,
+        cascade => false,
         circular => true,
+        closures => false,
+        const_impls => false,
+        const_param_defaults => false,
         consts => false,
+        decl_macro => false,
+        deref_impls => true,
+        derive_helpers => false,
+        doc_shims => false,
         enums => false,
+        extern_types => false,
+        external_rules => false,
+        field_visibility => false,
         func => false,
         func_local_items => true,
+        gat => false,
+        impact => false,
+        impl_assoc_types => false,
+        impl_conflicts => true,
+        impl_trait_return => false,
         infer => true,
         infer_regress => false,
+        inference_impls => true,
+        inherent_impl_bounds => false,
         inherent_impls => false,
         issue_34 => true,
         issue_50 => true,
         kind_change => false,
+        late_bound => true,
+        leaked_types => false,
+        lifetime_bounds => false,
+        macro_rules => false,
         macros => false,
+        manifest => false,
         max_priv => true,
+        mir_diff => true,
         mix => false,
+        module_moves => false,
+        moved => false,
+        must_use => true,
+        panic_scan => true,
+        path_filter => false,
         pathologic_paths => true,
+        probable_renames => false,
+        projections => true,
         pub_use => true,
+        reachable_bounds => false,
         regions => false,
         removal => false,
         removal_path => false,
         removal_use => false,
+        rename_suggestions => false,
+        renames => false,
+        restructured_items => false,
+        rust_version => true,
         sealed_traits => true,
+        semver_trick => false,
+        serde_shape => false,
+        shadowed_trait_methods => true,
+        sized_relaxations => false,
+        specialization => false,
         structs => false,
+        struct_literal => false,
         swap => true,
+        tait => false,
+        time_budget => false,
         traits => false,
+        trait_bounds => false,
         trait_impls => false,
         trait_objects => true,
+        trait_upcasting => false,
+        track_caller => true,
         ty_alias => false,
+        ty_alias_use => false,
+        unrelated => true,
+        unsizing_impls => false,
+        unstable_features => false,
+        visibility_widening => true,
+        wasm_bindgen => false,
     }
 }
@@ -0,0 +1,31 @@
+mod testing {
+    use semverver_testing::{check_compatible, Baseline};
+    use std::{env, path::Path};
+
+    fn check(name: &str) -> Result<(), String> {
+        // Add target dir to PATH so the check will call the right cargo-semver
+        if let Some(path) = env::var_os("PATH") {
+            let mut paths = env::split_paths(&path).collect::<Vec<_>>();
+            let current_dir = env::current_dir().expect("could not determine current dir");
+            paths.insert(0, current_dir.join("target/debug"));
+            let new_path = env::join_paths(paths).unwrap();
+            env::set_var("PATH", &new_path);
+        }
+
+        let dir = Path::new("tests/testing_crates");
+        let baseline = env::current_dir().unwrap().join(dir.join("base"));
+
+        check_compatible(dir.join(name).to_str().unwrap(), Baseline::Path(&baseline))
+    }
+
+    #[test]
+    fn compatible() {
+        assert_eq!(check("compatible"), Ok(()));
+    }
+
+    #[test]
+    fn breaking() {
+        let report = check("breaking").unwrap_err();
+        assert!(report.contains("breaking changes in `abc`"), "{}", report);
+    }
+}
@@ -0,0 +1,5 @@
+pub fn abc() -> u8 {
+    0
+}
+
+pub fn def() {}
@@ -0,0 +1,3 @@
+fn main() {
+    usage_helper::extra(usage_fixture::abc());
+}
@@ -0,0 +1,3 @@
+pub fn abc() -> u8 {
+    0
+}
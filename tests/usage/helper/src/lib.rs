@@ -0,0 +1,2 @@
+#[cfg(feature = "extra")]
+pub fn extra(_: u8) {}